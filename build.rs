@@ -3,4 +3,27 @@ fn main() {
     let gmsh_lib_dir = std::env::var("GMSH_LIB_DIR").expect("GMSH_LIB_DIR is not set");
     println!("cargo:rustc-link-search=native={}", gmsh_lib_dir);
     println!("cargo:rustc-link-lib=gmsh");
+
+    // Build metadata for provenance records. An explicit CORE_ENGINE_GIT_HASH
+    // wins, e.g. for builds from a source archive without a repository.
+    // Declaring these inputs replaces Cargo's default of rerunning on any
+    // package change, so the checked-out commit is watched explicitly.
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=GMSH_LIB_DIR");
+    println!("cargo:rerun-if-env-changed=CORE_ENGINE_GIT_HASH");
+    for git_path in [".git/HEAD", ".git/refs", ".git/packed-refs"] {
+        if std::path::Path::new(git_path).exists() {
+            println!("cargo:rerun-if-changed={}", git_path);
+        }
+    }
+    let git_hash = std::env::var("CORE_ENGINE_GIT_HASH").ok().or_else(|| {
+        let output = std::process::Command::new("git").args(["rev-parse", "HEAD"]).output().ok()?;
+        output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    });
+    if let Some(git_hash) = git_hash.filter(|hash| !hash.is_empty()) {
+        println!("cargo:rustc-env=CORE_ENGINE_GIT_HASH={}", git_hash);
+    }
+    if let Ok(profile) = std::env::var("PROFILE") {
+        println!("cargo:rustc-env=CORE_ENGINE_BUILD_PROFILE={}", profile);
+    }
 }
@@ -8,6 +8,7 @@ fn main() {
     let geo_def = GeometryDefinition::Primitive(GeometricPrimitive {
         shape: "cube".to_string(),
         dimensions: vec![1.0, 1.0, 1.0],
+        transform: None,
     });
 
     match generate_mesh_from_geo(&geo_def) {
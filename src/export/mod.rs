@@ -0,0 +1,396 @@
+// src/export/mod.rs
+
+//! Writes meshes and solutions to file formats read by visualization tools.
+
+use crate::{EngineError, Mesh, Solution};
+use std::io::Write;
+
+/// How node coordinates are written, so a file matches the units and axes a
+/// downstream tool expects. The in-memory [`Mesh`] is left untouched.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ExportOptions {
+    /// Factor applied to every coordinate, e.g. `1000.0` to write a model in
+    /// meters as millimeters. Field values keep their own units.
+    #[serde(default = "ExportOptions::unit_scale")]
+    pub scale: f64,
+    /// Two axes, `0` to `2` for x to z, whose coordinates trade places, e.g.
+    /// `[1, 2]` to turn a z-up model y-up. Three-component point arrays are
+    /// swapped alike. A swap mirrors the model, which reverses element orientation.
+    #[serde(default)]
+    pub swap_axes: Option<[usize; 2]>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions { scale: Self::unit_scale(), swap_axes: None }
+    }
+}
+
+impl ExportOptions {
+    fn unit_scale() -> f64 {
+        1.0
+    }
+
+    /// Rejects a non-finite or zero scale and axes outside x, y and z.
+    fn validate(&self) -> Result<(), EngineError> {
+        if !self.scale.is_finite() || self.scale == 0.0 {
+            return Err(EngineError::ExportFailed(format!("Export scale must be finite and nonzero, got {}", self.scale)));
+        }
+        if let Some(axes) = self.swap_axes.filter(|axes| axes.iter().any(|&axis| axis > 2)) {
+            return Err(EngineError::ExportFailed(format!("Swapped axes must be 0, 1 or 2, got {:?}", axes)));
+        }
+        Ok(())
+    }
+
+    /// Swaps the components of a vector as configured, without scaling.
+    fn orient(&self, mut vector: [f64; 3]) -> [f64; 3] {
+        if let Some([a, b]) = self.swap_axes {
+            vector.swap(a, b);
+        }
+        vector
+    }
+
+    /// Maps a node position to the written coordinates.
+    pub fn apply(&self, point: [f64; 3]) -> [f64; 3] {
+        self.orient(point).map(|x| x * self.scale)
+    }
+}
+
+/// Returns the VTK cell type of an element with `num_nodes` nodes, if known.
+fn vtk_cell_type(num_nodes: usize) -> Option<u8> {
+    match num_nodes {
+        2 => Some(3),  // VTK_LINE
+        3 => Some(5),  // VTK_TRIANGLE
+        4 => Some(10), // VTK_TETRA
+        8 => Some(12), // VTK_HEXAHEDRON
+        _ => None,
+    }
+}
+
+/// Writes the mesh geometry as a legacy ASCII VTK unstructured grid, without point data.
+///
+/// Non-empty `times` are written as a `TIME` field data array, one value per
+/// array of point data, which ParaView reads as the time of each step.
+fn write_vtk_mesh<W: Write>(mesh: &Mesh, title: &str, times: &[f64], options: &ExportOptions, writer: &mut W) -> std::io::Result<()> {
+    writeln!(writer, "# vtk DataFile Version 3.0")?;
+    writeln!(writer, "{}", title.lines().next().unwrap_or(""))?;
+    writeln!(writer, "ASCII")?;
+    writeln!(writer, "DATASET UNSTRUCTURED_GRID")?;
+    if !times.is_empty() {
+        let values: Vec<String> = times.iter().map(f64::to_string).collect();
+        writeln!(writer, "FIELD FieldData 1\nTIME 1 {} double\n{}", times.len(), values.join(" "))?;
+    }
+
+    writeln!(writer, "POINTS {} double", mesh.num_nodes())?;
+    for [x, y, z] in mesh.nodes.iter().map(|&node| options.apply(node)) {
+        writeln!(writer, "{} {} {}", x, y, z)?;
+    }
+
+    let size: usize = mesh.elements.iter().map(|e| e.len() + 1).sum();
+    writeln!(writer, "CELLS {} {}", mesh.num_elements(), size)?;
+    for element in &mesh.elements {
+        let ids: Vec<String> = element.iter().map(usize::to_string).collect();
+        writeln!(writer, "{} {}", element.len(), ids.join(" "))?;
+    }
+
+    writeln!(writer, "CELL_TYPES {}", mesh.num_elements())?;
+    for element in &mesh.elements {
+        // Unknown elements are written as VTK_EMPTY_CELL so the file stays valid.
+        writeln!(writer, "{}", vtk_cell_type(element.len()).unwrap_or(0))?;
+    }
+    Ok(())
+}
+
+/// Formats one point data array of `components` values per node, with
+/// three-component values oriented like the coordinates.
+fn vtk_point_array(name: &str, values: &[f64], components: usize, num_nodes: usize, options: &ExportOptions) -> String {
+    let mut body = match components {
+        1 => format!("SCALARS {} double 1\nLOOKUP_TABLE default\n", name),
+        3 => format!("VECTORS {} double\n", name),
+        _ => format!("FIELD FieldData 1\n{} {} {} double\n", name, components, num_nodes),
+    };
+    for values in values.chunks(components) {
+        let oriented = <[f64; 3]>::try_from(values).map(|vector| options.orient(vector));
+        let values = oriented.as_ref().map_or(values, |vector| vector.as_slice());
+        let row: Vec<String> = values.iter().map(f64::to_string).collect();
+        body.push_str(&row.join(" "));
+        body.push('\n');
+    }
+    body
+}
+
+/// Writes a solution as a legacy ASCII VTK unstructured grid.
+///
+/// The nodal data is split into `data.len() / num_nodes` components per node and
+/// written as `SCALARS` for one component, `VECTORS` for three and a generic
+/// `FIELD` array otherwise. A transient solution gets one array per time step,
+/// named `solution_0`, `solution_1` and so on, with the step times in `TIME`.
+/// The named `fields` follow as further point arrays under their own names.
+pub fn write_vtk<W: Write>(solution: &Solution, writer: W) -> Result<(), EngineError> {
+    write_vtk_with_options(solution, &ExportOptions::default(), writer)
+}
+
+/// Writes a solution like [`write_vtk`], with the coordinates scaled and
+/// oriented by `options`.
+pub fn write_vtk_with_options<W: Write>(solution: &Solution, options: &ExportOptions, mut writer: W) -> Result<(), EngineError> {
+    options.validate()?;
+    let mesh = &solution.mesh;
+    if mesh.elements.iter().flatten().any(|&n| n >= mesh.num_nodes()) {
+        return Err(EngineError::ExportFailed("Mesh contains out-of-bounds node indices".to_string()));
+    }
+    let num_nodes = mesh.num_nodes();
+    let steps = solution.steps();
+    let values = steps[0].1.len();
+    if num_nodes > 0 && !values.is_multiple_of(num_nodes) {
+        return Err(EngineError::ExportFailed(format!("Solution has {} values, which is not a multiple of {} nodes", values, num_nodes)));
+    }
+    if let Some((t, field)) = steps.iter().find(|(_, field)| field.len() != values) {
+        return Err(EngineError::ExportFailed(format!("Time step at {} has {} values, expected {}", t, field.len(), values)));
+    }
+
+    let io_error = |e: std::io::Error| EngineError::ExportFailed(format!("Failed to write VTK output: {}", e));
+    let times: Vec<f64> = if solution.is_transient() { steps.iter().map(|(t, _)| *t).collect() } else { Vec::new() };
+    write_vtk_mesh(mesh, &solution.id, &times, options, &mut writer).map_err(io_error)?;
+
+    let mut fields: Vec<(&String, &Vec<f64>)> = solution.fields.iter().filter(|(_, field)| !field.is_empty()).collect();
+    fields.sort_by(|a, b| a.0.cmp(b.0));
+    if let Some((name, field)) = fields.iter().find(|(_, field)| num_nodes == 0 || !field.len().is_multiple_of(num_nodes)) {
+        return Err(EngineError::ExportFailed(format!("Field {} has {} values, which is not a multiple of {} nodes", name, field.len(), num_nodes)));
+    }
+
+    if num_nodes > 0 && (values > 0 || !fields.is_empty()) {
+        let components = values / num_nodes;
+        let mut body = format!("POINT_DATA {}\n", num_nodes);
+        if values > 0 && solution.is_transient() {
+            for (index, (_, field)) in steps.iter().enumerate() {
+                body.push_str(&vtk_point_array(&format!("solution_{}", index), field, components, num_nodes, options));
+            }
+        } else if values > 0 {
+            body.push_str(&vtk_point_array("solution", &solution.data, components, num_nodes, options));
+        }
+        for (name, field) in fields {
+            body.push_str(&vtk_point_array(name, field, field.len() / num_nodes, num_nodes, options));
+        }
+        writer.write_all(body.as_bytes()).map_err(io_error)?;
+    }
+    Ok(())
+}
+
+/// Writes a solution to an HDF5 file at `path`, replacing any existing file.
+///
+/// The file holds `mesh/nodes` (`num_nodes x 3`), `mesh/connectivity`
+/// (`num_elements x nodes_per_element`, zero-based) and `fields/solution`
+/// (`num_nodes x components`), so it can be read directly with h5py or MATLAB.
+/// A transient solution also holds `fields/times` and `fields/steps`
+/// (`num_steps x num_nodes x components`), with `fields/solution` the last step.
+#[cfg(feature = "hdf5")]
+pub fn write_hdf5<P: AsRef<std::path::Path>>(solution: &Solution, path: P) -> Result<(), EngineError> {
+    let mesh = &solution.mesh;
+    let num_nodes = mesh.num_nodes();
+    let nodes_per_element = mesh.elements.first().map_or(0, Vec::len);
+    if mesh.elements.iter().any(|e| e.len() != nodes_per_element) {
+        return Err(EngineError::ExportFailed("HDF5 export requires elements with a uniform number of nodes".to_string()));
+    }
+    if mesh.elements.iter().flatten().any(|&n| n >= num_nodes) {
+        return Err(EngineError::ExportFailed("Mesh contains out-of-bounds node indices".to_string()));
+    }
+    if num_nodes == 0 || !solution.data.len().is_multiple_of(num_nodes) {
+        return Err(EngineError::ExportFailed(format!("Solution has {} values, which is not a multiple of {} nodes", solution.data.len(), num_nodes)));
+    }
+    let components = solution.data.len() / num_nodes;
+
+    let shape_error = |e: ndarray::ShapeError| EngineError::ExportFailed(format!("Failed to shape HDF5 dataset: {}", e));
+    let nodes = ndarray::Array2::from_shape_vec((num_nodes, 3), mesh.nodes.iter().flatten().copied().collect()).map_err(shape_error)?;
+    let connectivity = ndarray::Array2::from_shape_vec(
+        (mesh.num_elements(), nodes_per_element),
+        mesh.elements.iter().flatten().map(|&n| n as u64).collect(),
+    )
+    .map_err(shape_error)?;
+    let fields = ndarray::Array2::from_shape_vec((num_nodes, components), solution.data.clone()).map_err(shape_error)?;
+    if solution.time_steps.iter().any(|(_, field)| field.len() != solution.data.len()) {
+        return Err(EngineError::ExportFailed("Every time step must have as many values as the solution".to_string()));
+    }
+    let times = ndarray::Array1::from_iter(solution.time_steps.iter().map(|(t, _)| *t));
+    let steps = ndarray::Array3::from_shape_vec(
+        (solution.time_steps.len(), num_nodes, components),
+        solution.time_steps.iter().flat_map(|(_, field)| field.iter().copied()).collect(),
+    )
+    .map_err(shape_error)?;
+
+    let write = || -> hdf5::Result<()> {
+        let file = hdf5::File::create(path.as_ref())?;
+        let mesh_group = file.create_group("mesh")?;
+        mesh_group.new_dataset_builder().with_data(&nodes).create("nodes")?;
+        mesh_group.new_dataset_builder().with_data(&connectivity).create("connectivity")?;
+        let fields_group = file.create_group("fields")?;
+        fields_group.new_dataset_builder().with_data(&fields).create("solution")?;
+        if solution.is_transient() {
+            fields_group.new_dataset_builder().with_data(&times).create("times")?;
+            fields_group.new_dataset_builder().with_data(&steps).create("steps")?;
+        }
+        Ok(())
+    };
+    write().map_err(|e| EngineError::ExportFailed(format!("Failed to write HDF5 output: {}", e)))
+}
+
+/// Writes a matrix, or a vector as a single column, in Matrix Market coordinate format.
+///
+/// Only nonzero entries are listed, with one-based indices, so the file loads
+/// directly with e.g. `scipy.io.mmread` or MATLAB's `mmread`.
+pub fn write_matrix_market<R, C, S, W>(matrix: &nalgebra::Matrix<f64, R, C, S>, mut writer: W) -> Result<(), EngineError>
+where
+    R: nalgebra::Dim,
+    C: nalgebra::Dim,
+    S: nalgebra::RawStorage<f64, R, C>,
+    W: Write,
+{
+    let entries: Vec<(usize, usize, f64)> = (0..matrix.ncols())
+        .flat_map(|col| (0..matrix.nrows()).map(move |row| (row, col)))
+        .map(|(row, col)| (row, col, matrix[(row, col)]))
+        .filter(|&(_, _, value)| value != 0.0)
+        .collect();
+
+    let mut body = format!("%%MatrixMarket matrix coordinate real general\n{} {} {}\n", matrix.nrows(), matrix.ncols(), entries.len());
+    for (row, col, value) in entries {
+        body.push_str(&format!("{} {} {}\n", row + 1, col + 1, value));
+    }
+    writer.write_all(body.as_bytes()).and_then(|_| writer.flush()).map_err(|e| EngineError::ExportFailed(format!("Failed to write Matrix Market output: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provenance::ProvenanceChain;
+
+    #[test]
+    fn test_deserialized_solution_exports_to_vtk() {
+        let mut chain = ProvenanceChain::new();
+        chain.add_record("solver_run".to_string(), b"displacements", "v1.0.0".to_string(), serde_json::json!({"solver": "fem"})).unwrap();
+
+        let mesh = crate::meshing::structured_cube_mesh(1, 1.0);
+        let data: Vec<f64> = (0..mesh.nodes.len() * 3).map(|i| i as f64 * 0.5).collect();
+        let mut solution = Solution::from_mesh("saved_run".to_string(), mesh, data);
+        solution.provenance_chain = chain.take_records();
+
+        let json = serde_json::to_string(&solution).unwrap();
+        let loaded: Solution = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.provenance_chain.len(), 1);
+        assert_eq!(loaded.provenance_chain[0].calculate_record_hash(), solution.provenance_chain[0].calculate_record_hash());
+
+        let mut buffer = Vec::new();
+        write_vtk(&loaded, &mut buffer).unwrap();
+        let vtk = String::from_utf8(buffer).unwrap();
+
+        assert!(vtk.starts_with("# vtk DataFile Version 3.0\nsaved_run\nASCII\nDATASET UNSTRUCTURED_GRID\n"));
+        assert!(vtk.contains("POINTS 8 double\n0 0 0\n"));
+        assert!(vtk.contains("CELLS 6 30\n"));
+        assert_eq!(vtk.lines().filter(|l| *l == "10").count(), 6);
+        assert!(vtk.contains("POINT_DATA 8\nVECTORS solution double\n0 0.5 1\n"));
+    }
+
+    #[test]
+    fn test_mismatched_data_is_rejected() {
+        let mesh = crate::meshing::structured_cube_mesh(1, 1.0);
+        let solution = Solution::from_mesh("bad".to_string(), mesh, vec![1.0; 5]);
+        assert!(write_vtk(&solution, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_named_fields_are_written_without_displacements() {
+        let mesh = crate::meshing::structured_cube_mesh(1, 1.0);
+        let mut solution = Solution::from_mesh("stress_only".to_string(), mesh, Vec::new());
+        solution.fields.insert("von_mises".to_string(), vec![3.0; 8]);
+
+        let mut buffer = Vec::new();
+        write_vtk(&solution, &mut buffer).unwrap();
+        let vtk = String::from_utf8(buffer).unwrap();
+        assert!(vtk.contains("POINT_DATA 8\nSCALARS von_mises double 1\nLOOKUP_TABLE default\n3\n"));
+        assert!(!vtk.contains("solution double"));
+
+        solution.fields.insert("stress".to_string(), vec![0.0; 7]);
+        assert!(write_vtk(&solution, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_transient_solution_writes_one_array_per_step() {
+        let mesh = crate::meshing::structured_cube_mesh(1, 1.0);
+        let mut solution = Solution::from_mesh("transient".to_string(), mesh, vec![2.0; 8]);
+        solution.time_steps = vec![(0.0, vec![0.0; 8]), (0.5, vec![1.0; 8]), (1.0, vec![2.0; 8])];
+
+        let mut buffer = Vec::new();
+        write_vtk(&solution, &mut buffer).unwrap();
+        let vtk = String::from_utf8(buffer).unwrap();
+
+        assert!(vtk.contains("DATASET UNSTRUCTURED_GRID\nFIELD FieldData 1\nTIME 1 3 double\n0 0.5 1\nPOINTS 8 double\n"));
+        assert!(vtk.contains("POINT_DATA 8\nSCALARS solution_0 double 1\nLOOKUP_TABLE default\n0\n"));
+        assert!(vtk.contains("SCALARS solution_1 double 1\nLOOKUP_TABLE default\n1\n"));
+        assert!(vtk.contains("SCALARS solution_2 double 1\nLOOKUP_TABLE default\n2\n"));
+        assert!(!vtk.contains("SCALARS solution double"));
+
+        solution.time_steps[1].1.pop();
+        assert!(write_vtk(&solution, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_millimeter_export_scales_coordinates() {
+        let points = |vtk: &str| -> Vec<[f64; 3]> {
+            vtk.lines()
+                .skip_while(|line| !line.starts_with("POINTS"))
+                .skip(1)
+                .take(8)
+                .map(|line| {
+                    let values: Vec<f64> = line.split(' ').map(|v| v.parse().unwrap()).collect();
+                    [values[0], values[1], values[2]]
+                })
+                .collect()
+        };
+        let mesh = crate::meshing::structured_cube_mesh(1, 1.0);
+        let nodes = mesh.nodes.clone();
+        let data: Vec<f64> = (0..mesh.nodes.len()).flat_map(|_| [1.0, 2.0, 3.0]).collect();
+        let solution = Solution::from_mesh("cube".to_string(), mesh, data);
+
+        let mut meters = Vec::new();
+        write_vtk(&solution, &mut meters).unwrap();
+        let mut millimeters = Vec::new();
+        let options = ExportOptions { scale: 1000.0, ..ExportOptions::default() };
+        write_vtk_with_options(&solution, &options, &mut millimeters).unwrap();
+        let (meters, millimeters) = (String::from_utf8(meters).unwrap(), String::from_utf8(millimeters).unwrap());
+
+        assert_eq!(points(&meters), nodes);
+        for (m, mm) in points(&meters).iter().zip(points(&millimeters)) {
+            assert_eq!(mm, m.map(|x| x * 1000.0));
+        }
+        assert_eq!(solution.mesh.nodes, nodes);
+        assert!(millimeters.contains("VECTORS solution double\n1 2 3\n"));
+
+        let y_up = ExportOptions { swap_axes: Some([1, 2]), ..options };
+        let mut buffer = Vec::new();
+        write_vtk_with_options(&solution, &y_up, &mut buffer).unwrap();
+        let vtk = String::from_utf8(buffer).unwrap();
+        assert_eq!(points(&vtk), nodes.iter().map(|&[x, y, z]| [x * 1000.0, z * 1000.0, y * 1000.0]).collect::<Vec<_>>());
+        assert!(vtk.contains("VECTORS solution double\n1 3 2\n"));
+
+        let invalid = ExportOptions { swap_axes: Some([0, 3]), ..ExportOptions::default() };
+        assert!(write_vtk_with_options(&solution, &invalid, Vec::new()).is_err());
+    }
+
+    #[cfg(feature = "hdf5")]
+    #[test]
+    fn test_hdf5_datasets_have_expected_shapes() {
+        let mesh = crate::meshing::structured_cube_mesh(1, 1.0);
+        let data: Vec<f64> = (0..mesh.nodes.len() * 3).map(|i| i as f64).collect();
+        let solution = Solution::from_mesh("hdf5_run".to_string(), mesh, data);
+
+        let path = std::env::temp_dir().join(format!("core_engine_export_{}.h5", std::process::id()));
+        solution.to_hdf5(&path).unwrap();
+
+        let file = hdf5::File::open(&path).unwrap();
+        assert_eq!(file.dataset("mesh/nodes").unwrap().shape(), vec![8, 3]);
+        assert_eq!(file.dataset("mesh/connectivity").unwrap().shape(), vec![6, 4]);
+        let fields = file.dataset("fields/solution").unwrap();
+        assert_eq!(fields.shape(), vec![8, 3]);
+        assert_eq!(fields.read_raw::<f64>().unwrap(), solution.data);
+        drop(file);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
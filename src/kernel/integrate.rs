@@ -0,0 +1,226 @@
+// src/kernel/integrate.rs
+
+//! Time integration of semi-discrete systems `M du/dt + K u = f(t)`.
+//!
+//! Solvers describe their system through a callback returning the matrices at a
+//! given time, and delegate the time marching to [`step`] or [`march`].
+//! Second-order systems `M a + K u = f(t)` are marched by
+//! [`march_central_difference`] instead.
+
+use super::{Matrix, Vector};
+use crate::EngineError;
+
+/// The available time-stepping schemes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum TimeScheme {
+    /// Explicit, first order.
+    ForwardEuler,
+    /// Explicit, classical fourth-order Runge-Kutta.
+    RungeKutta4,
+    /// Implicit, first order and unconditionally stable.
+    BackwardEuler,
+    /// Implicit trapezoidal rule, second order.
+    CrankNicolson,
+    /// Explicit central differences, second order, for second-order systems
+    /// only; see [`march_central_difference`].
+    CentralDifference,
+}
+
+/// The matrices of `M du/dt + K u = f` evaluated at one instant.
+#[derive(Debug, Clone)]
+pub struct SystemMatrices {
+    pub mass: Matrix,
+    pub stiffness: Matrix,
+    pub load: Vector,
+}
+
+/// Solves `a x = b`, reporting a singular system as a solver failure.
+fn solve_linear(a: Matrix, b: &Vector) -> Result<Vector, EngineError> {
+    a.lu().solve(b).ok_or_else(|| EngineError::SolverFailed("Time integration system is singular.".to_string()))
+}
+
+/// Evaluates `du/dt = M^-1 (f - K u)`.
+fn rate(system: &SystemMatrices, u: &Vector) -> Result<Vector, EngineError> {
+    solve_linear(system.mass.clone(), &(&system.load - &system.stiffness * u))
+}
+
+/// Advances the state `u` at time `t` by one step of size `dt`.
+pub fn step<F>(scheme: TimeScheme, system: &mut F, t: f64, dt: f64, u: &Vector) -> Result<Vector, EngineError>
+where
+    F: FnMut(f64) -> SystemMatrices,
+{
+    match scheme {
+        TimeScheme::ForwardEuler => {
+            let s0 = system(t);
+            Ok(u + rate(&s0, u)? * dt)
+        }
+        TimeScheme::RungeKutta4 => {
+            let s0 = system(t);
+            let s_half = system(t + 0.5 * dt);
+            let s1 = system(t + dt);
+            let k1 = rate(&s0, u)?;
+            let k2 = rate(&s_half, &(u + &k1 * (0.5 * dt)))?;
+            let k3 = rate(&s_half, &(u + &k2 * (0.5 * dt)))?;
+            let k4 = rate(&s1, &(u + &k3 * dt))?;
+            Ok(u + (k1 + k2 * 2.0 + k3 * 2.0 + k4) * (dt / 6.0))
+        }
+        TimeScheme::BackwardEuler => {
+            // (M + dt K1) u1 = M u0 + dt f1
+            let s1 = system(t + dt);
+            let rhs = &s1.mass * u + &s1.load * dt;
+            solve_linear(&s1.mass + &s1.stiffness * dt, &rhs)
+        }
+        TimeScheme::CrankNicolson => {
+            // (M + dt/2 K1) u1 = (M - dt/2 K0) u0 + dt/2 (f0 + f1)
+            let s0 = system(t);
+            let s1 = system(t + dt);
+            let rhs = (&s1.mass - &s0.stiffness * (0.5 * dt)) * u + (&s0.load + &s1.load) * (0.5 * dt);
+            solve_linear(&s1.mass + &s1.stiffness * (0.5 * dt), &rhs)
+        }
+        TimeScheme::CentralDifference => Err(EngineError::SolverFailed(
+            "CentralDifference integrates second-order systems M a + K u = f; use march_central_difference".to_string(),
+        )),
+    }
+}
+
+/// Marches `num_steps` steps of size `dt` from `(t0, u0)`.
+///
+/// Returns the full history, starting with the initial state, as `(time, state)` pairs.
+pub fn march<F>(
+    scheme: TimeScheme,
+    mut system: F,
+    u0: Vector,
+    t0: f64,
+    dt: f64,
+    num_steps: usize,
+) -> Result<Vec<(f64, Vector)>, EngineError>
+where
+    F: FnMut(f64) -> SystemMatrices,
+{
+    let mut history = Vec::with_capacity(num_steps + 1);
+    let mut t = t0;
+    let mut u = u0;
+    for i in 0..num_steps {
+        let next = step(scheme, &mut system, t, dt, &u)?;
+        history.push((t, u));
+        u = next;
+        t = t0 + (i + 1) as f64 * dt;
+    }
+    history.push((t, u));
+    Ok(history)
+}
+
+/// Marches `u'' = acceleration(t, u)` from `(t0, u0)` with initial velocity `v0`
+/// by central differences, `u[n+1] = 2 u[n] - u[n-1] + dt^2 a[n]`.
+///
+/// The scheme is explicit and only stable for `dt` below `2 / omega_max`,
+/// where `omega_max` is the largest natural frequency of the system; checking
+/// that is left to the caller. Returns the history of displacements as in [`march`].
+pub fn march_central_difference<F>(
+    mut acceleration: F,
+    u0: Vector,
+    v0: Vector,
+    t0: f64,
+    dt: f64,
+    num_steps: usize,
+) -> Result<Vec<(f64, Vector)>, EngineError>
+where
+    F: FnMut(f64, &Vector) -> Result<Vector, EngineError>,
+{
+    let mut history = Vec::with_capacity(num_steps + 1);
+    // The fictitious state one step before the start, from a Taylor expansion.
+    let a0 = acceleration(t0, &u0)?;
+    let mut previous = &u0 - &v0 * dt + a0 * (0.5 * dt * dt);
+    let mut u = u0;
+    for i in 0..num_steps {
+        let t = t0 + i as f64 * dt;
+        let next = &u * 2.0 - &previous + acceleration(t, &u)? * (dt * dt);
+        previous = std::mem::replace(&mut u, next);
+        history.push((t, previous.clone()));
+    }
+    history.push((t0 + num_steps as f64 * dt, u));
+    Ok(history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DECAY_RATE: f64 = 2.0;
+
+    /// du/dt = -λ u, u(0) = 1, so u(t) = exp(-λ t).
+    fn decay(_t: f64) -> SystemMatrices {
+        SystemMatrices {
+            mass: Matrix::identity(1, 1),
+            stiffness: Matrix::from_element(1, 1, DECAY_RATE),
+            load: Vector::zeros(1),
+        }
+    }
+
+    fn error_at_one_second(scheme: TimeScheme, num_steps: usize) -> f64 {
+        let dt = 1.0 / num_steps as f64;
+        let history = march(scheme, decay, Vector::from_element(1, 1.0), 0.0, dt, num_steps).unwrap();
+        let (t_end, u_end) = history.last().unwrap();
+        assert!((t_end - 1.0).abs() < 1e-12);
+        (u_end[0] - (-DECAY_RATE).exp()).abs()
+    }
+
+    fn observed_order(scheme: TimeScheme) -> f64 {
+        let coarse = error_at_one_second(scheme, 40);
+        let fine = error_at_one_second(scheme, 80);
+        (coarse / fine).log2()
+    }
+
+    #[test]
+    fn test_forward_euler_is_first_order() {
+        let order = observed_order(TimeScheme::ForwardEuler);
+        assert!((order - 1.0).abs() < 0.1, "Observed order {}", order);
+    }
+
+    #[test]
+    fn test_backward_euler_is_first_order() {
+        let order = observed_order(TimeScheme::BackwardEuler);
+        assert!((order - 1.0).abs() < 0.1, "Observed order {}", order);
+    }
+
+    #[test]
+    fn test_crank_nicolson_is_second_order() {
+        let order = observed_order(TimeScheme::CrankNicolson);
+        assert!((order - 2.0).abs() < 0.1, "Observed order {}", order);
+    }
+
+    #[test]
+    fn test_runge_kutta4_is_fourth_order() {
+        let order = observed_order(TimeScheme::RungeKutta4);
+        assert!((order - 4.0).abs() < 0.2, "Observed order {}", order);
+        assert!(error_at_one_second(TimeScheme::RungeKutta4, 40) < 1e-7);
+    }
+
+    #[test]
+    fn test_central_difference_is_second_order() {
+        // u'' = -omega^2 u from rest at u = 1, so u(t) = cos(omega t).
+        let omega = 3.0;
+        let error_at_one_second = |num_steps: usize| {
+            let dt = 1.0 / num_steps as f64;
+            let oscillator = |_t: f64, u: &Vector| Ok(u * -(omega * omega));
+            let history = march_central_difference(oscillator, Vector::from_element(1, 1.0), Vector::zeros(1), 0.0, dt, num_steps).unwrap();
+            let (t_end, u_end) = history.last().unwrap();
+            assert_eq!(history.len(), num_steps + 1);
+            assert!((t_end - 1.0).abs() < 1e-12);
+            (u_end[0] - omega.cos()).abs()
+        };
+        let order = (error_at_one_second(40) / error_at_one_second(80)).log2();
+        assert!((order - 2.0).abs() < 0.1, "Observed order {}", order);
+
+        let mut system = decay;
+        assert!(step(TimeScheme::CentralDifference, &mut system, 0.0, 0.1, &Vector::zeros(1)).is_err());
+    }
+
+    #[test]
+    fn test_march_records_every_step() {
+        let history = march(TimeScheme::BackwardEuler, decay, Vector::from_element(1, 1.0), 0.0, 0.1, 5).unwrap();
+        assert_eq!(history.len(), 6);
+        assert_eq!(history[0].1[0], 1.0);
+        assert!(history.windows(2).all(|w| w[1].1[0] < w[0].1[0]));
+    }
+}
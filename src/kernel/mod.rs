@@ -4,7 +4,12 @@
 //! The numerical kernel of the simulation engine.
 //! This module provides fundamental mathematical operations and data structures.
 
-use nalgebra::{DMatrix, DVector};
+pub mod integrate;
+pub mod quadrature;
+
+use nalgebra::{DMatrix, DVector, RealField};
+use crate::EngineError;
+use std::time::{Duration, Instant};
 
 // Type aliases for clarity throughout the engine.
 pub type Matrix = DMatrix<f64>;
@@ -18,6 +23,321 @@ pub fn add_vectors(a: &Vector, b: &Vector) -> Option<Vector> {
     Some(a + b)
 }
 
+/// Solves the nonlinear system `residual(x) = 0` with Newton-Raphson iteration.
+///
+/// Each iteration solves `jacobian(x) dx = -residual(x)` and updates `x += dx`.
+/// Iteration stops once the residual norm drops to `tolerance`, returning the
+/// converged state, or fails with `EngineError::NotConverged` after
+/// `max_iterations` updates.
+pub fn newton_raphson<R, J>(
+    residual: R,
+    jacobian: J,
+    x0: Vector,
+    tolerance: f64,
+    max_iterations: u32,
+) -> Result<Vector, EngineError>
+where
+    R: Fn(&Vector) -> Vector,
+    J: Fn(&Vector) -> Matrix,
+{
+    let mut x = x0;
+    let mut r = residual(&x);
+    for _ in 0..max_iterations {
+        if r.norm() <= tolerance {
+            return Ok(x);
+        }
+        let dx = jacobian(&x).lu().solve(&(-&r))
+            .ok_or_else(|| EngineError::SolverFailed("Newton-Raphson Jacobian is singular.".to_string()))?;
+        x += dx;
+        r = residual(&x);
+    }
+
+    if r.norm() <= tolerance {
+        return Ok(x);
+    }
+    Err(EngineError::NotConverged(format!(
+        "Newton-Raphson residual {:e} above tolerance {:e} after {} iterations",
+        r.norm(), tolerance, max_iterations
+    )))
+}
+
+/// The outcome of an iterative linear solve, which may stop before converging.
+#[derive(Debug, Clone)]
+pub struct IterativeSolution<T: nalgebra::Scalar = f64> {
+    /// The latest iterate, the best available estimate if the solve stopped early.
+    pub x: DVector<T>,
+    pub iterations: u32,
+    pub residual_norm: f64,
+    /// The residual norm after each iteration, in order.
+    pub residual_history: Vec<f64>,
+    pub converged: bool,
+    /// Why the iteration stopped before converging.
+    pub termination_reason: Option<String>,
+}
+
+/// Solves the symmetric positive definite system `a x = b` with conjugate gradients.
+///
+/// Iteration stops once the residual norm drops to `tolerance`. Unlike
+/// [`newton_raphson`], running out of `max_iterations` or of the wall-clock
+/// `time_budget` is not an error: the latest iterate is returned with
+/// `converged` unset and the reason recorded.
+///
+/// The system may be in any real precision, e.g. `f32` to halve its memory;
+/// the residual norms are reported as `f64` either way.
+pub fn conjugate_gradient<T: RealField + Copy>(
+    a: &DMatrix<T>,
+    b: &DVector<T>,
+    x0: DVector<T>,
+    tolerance: f64,
+    max_iterations: u32,
+    time_budget: Option<Duration>,
+) -> IterativeSolution<T> {
+    conjugate_gradient_with(|p| a * p, b, x0, tolerance, max_iterations, time_budget)
+}
+
+/// Solves `a x = b` like [`conjugate_gradient`], with `a` given only through
+/// the product `apply(p) = a p`.
+///
+/// Operators such as finite difference stencils can so be applied without
+/// ever storing their matrix.
+pub fn conjugate_gradient_with<T: RealField + Copy>(
+    apply: impl Fn(&DVector<T>) -> DVector<T>,
+    b: &DVector<T>,
+    x0: DVector<T>,
+    tolerance: f64,
+    max_iterations: u32,
+    time_budget: Option<Duration>,
+) -> IterativeSolution<T> {
+    let start = Instant::now();
+    let mut x = x0;
+    let mut r = b - apply(&x);
+    let mut p = r.clone();
+    let mut rr = r.dot(&r);
+    let mut iterations = 0;
+    let mut residual_history = Vec::new();
+    let norm = |rr: T| rr.sqrt().to_subset_unchecked();
+
+    let termination_reason = loop {
+        if norm(rr) <= tolerance {
+            break None;
+        }
+        if iterations >= max_iterations {
+            break Some(format!("reached the limit of {} iterations", max_iterations));
+        }
+        if let Some(budget) = time_budget {
+            if start.elapsed() >= budget {
+                break Some(format!("time budget of {:?} exhausted after {} iterations", budget, iterations));
+            }
+        }
+
+        let ap = apply(&p);
+        let pap = p.dot(&ap);
+        if pap <= T::zero() {
+            break Some("matrix is not positive definite".to_string());
+        }
+        let alpha = rr / pap;
+        x.axpy(alpha, &p, T::one());
+        r.axpy(-alpha, &ap, T::one());
+        let rr_next = r.dot(&r);
+        p = &r + &p * (rr_next / rr);
+        rr = rr_next;
+        iterations += 1;
+        residual_history.push(norm(rr));
+    };
+
+    IterativeSolution {
+        x,
+        iterations,
+        residual_norm: norm(rr),
+        residual_history,
+        converged: termination_reason.is_none(),
+        termination_reason,
+    }
+}
+
+/// Solves the tridiagonal system `a x = rhs` with the Thomas algorithm in O(n).
+///
+/// `diag` holds the `n` diagonal entries, `lower[i] = a[(i + 1, i)]` and
+/// `upper[i] = a[(i, i + 1)]` the `n - 1` off-diagonal ones. There is no
+/// pivoting, which is stable for diagonally dominant matrices such as
+/// finite difference stencils; a zero pivot fails with `EngineError::SolverFailed`.
+pub fn solve_tridiagonal(lower: &[f64], diag: &[f64], upper: &[f64], rhs: &[f64]) -> Result<Vector, EngineError> {
+    let n = diag.len();
+    if rhs.len() != n || lower.len() + 1 != n.max(1) || upper.len() + 1 != n.max(1) {
+        return Err(EngineError::SolverFailed(format!(
+            "Tridiagonal system of size {} needs {} off-diagonal entries and {} right-hand sides, got {}, {} and {}",
+            n, n.saturating_sub(1), n, lower.len(), upper.len(), rhs.len()
+        )));
+    }
+
+    // Forward sweep: eliminate the lower band, keeping the modified upper band and right-hand side.
+    let mut c = vec![0.0; n];
+    let mut d = vec![0.0; n];
+    for i in 0..n {
+        let (sub, prev_c, prev_d) = if i == 0 { (0.0, 0.0, 0.0) } else { (lower[i - 1], c[i - 1], d[i - 1]) };
+        let pivot = diag[i] - sub * prev_c;
+        if pivot == 0.0 || !pivot.is_finite() {
+            return Err(EngineError::SolverFailed(format!("Tridiagonal system is singular at row {}", i)));
+        }
+        c[i] = if i + 1 < n { upper[i] / pivot } else { 0.0 };
+        d[i] = (rhs[i] - sub * prev_d) / pivot;
+    }
+
+    // Back substitution.
+    let mut x = Vector::zeros(n);
+    for i in (0..n).rev() {
+        x[i] = d[i] - if i + 1 < n { c[i] * x[i + 1] } else { 0.0 };
+    }
+    Ok(x)
+}
+
+/// Which end of the spectrum [`lanczos`] extracts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EigenTarget {
+    /// The algebraically largest eigenvalues, in descending order.
+    Largest,
+    /// The smallest eigenvalues in magnitude, in ascending order, found by
+    /// iterating on the inverse. The matrix must be nonsingular, as the
+    /// stiffness matrix of a constrained structure is.
+    Smallest,
+}
+
+/// An eigenvalue and its unit-norm eigenvector.
+#[derive(Debug, Clone)]
+pub struct EigenPair {
+    pub value: f64,
+    pub vector: Vector,
+}
+
+/// Extracts `count` eigenpairs of the symmetric matrix `a` with the Lanczos method.
+///
+/// The Krylov basis is grown one vector per iteration and fully
+/// reorthogonalized, and the Ritz pairs of the projected tridiagonal matrix
+/// are accepted once each residual norm `||a x - lambda x||` is within
+/// `tolerance` relative to its eigenvalue. For [`EigenTarget::Smallest`] the
+/// iteration runs on `a^-1` from a single LU factorization (shift-invert about
+/// zero), so the lowest modes converge as fast as the highest do. The start
+/// vector is fixed, so results are reproducible. When neither the residuals
+/// drop nor the basis spans the whole space within `max_iterations`, this
+/// fails with `EngineError::NotConverged`.
+pub fn lanczos(a: &Matrix, count: usize, target: EigenTarget, tolerance: f64, max_iterations: u32) -> Result<Vec<EigenPair>, EngineError> {
+    let n = a.nrows();
+    if a.ncols() != n {
+        return Err(EngineError::SolverFailed(format!("Lanczos needs a square matrix, got {}x{}", n, a.ncols())));
+    }
+
+    match target {
+        EigenTarget::Largest => lanczos_operator(n, count, |v| Ok(a * v), tolerance, max_iterations),
+        EigenTarget::Smallest => {
+            let lu = a.clone().lu();
+            let apply = |v: &Vector| lu.solve(v).ok_or_else(|| EngineError::SolverFailed("Lanczos shift-invert matrix is singular.".to_string()));
+            let mut pairs = lanczos_operator(n, count, apply, tolerance, max_iterations)?;
+            for pair in &mut pairs {
+                pair.value = 1.0 / pair.value;
+            }
+            pairs.sort_by(|x, y| x.value.abs().total_cmp(&y.value.abs()));
+            Ok(pairs)
+        }
+    }
+}
+
+/// Extracts the `count` algebraically largest eigenpairs, in descending order,
+/// of the symmetric linear operator `apply` on vectors of length `n`.
+///
+/// This is the iteration behind [`lanczos`] for operators that are cheaper to
+/// apply than to form, such as a factorized generalized eigenproblem. Errors
+/// returned by `apply` abort the iteration.
+pub fn lanczos_operator<F>(n: usize, count: usize, apply: F, tolerance: f64, max_iterations: u32) -> Result<Vec<EigenPair>, EngineError>
+where
+    F: Fn(&Vector) -> Result<Vector, EngineError>,
+{
+    if count == 0 || count > n {
+        return Err(EngineError::SolverFailed(format!("Cannot extract {} eigenpairs of a {}x{} matrix", count, n, n)));
+    }
+
+    let steps = n.min(max_iterations as usize);
+    let mut basis: Vec<Vector> = Vec::with_capacity(steps);
+    let mut alpha = Vec::with_capacity(steps);
+    let mut beta = Vec::with_capacity(steps);
+    // A fixed, dense start vector that is unlikely to miss any eigenvector.
+    let mut v = Vector::from_fn(n, |i, _| 1.0 + ((i + 1) as f64 * 0.618_033_988_749_895).fract()).normalize();
+
+    for j in 0..steps {
+        basis.push(v);
+        let q = &basis[j];
+        let mut w = apply(q)?;
+        let applied_norm = w.norm();
+        alpha.push(w.dot(q));
+        // Two Gram-Schmidt passes keep the basis orthonormal in floating point.
+        for _ in 0..2 {
+            for q in &basis {
+                let c = q.dot(&w);
+                w.axpy(-c, q, 1.0);
+            }
+        }
+        let residual = w.norm();
+
+        if j + 1 >= count {
+            let size = j + 1;
+            let t = Matrix::from_fn(size, size, |r, c| match r.abs_diff(c) {
+                0 => alpha[r],
+                1 => beta[r.min(c)],
+                _ => 0.0,
+            });
+            let eigen = t.symmetric_eigen();
+            let mut order: Vec<usize> = (0..size).collect();
+            order.sort_by(|&x, &y| eigen.eigenvalues[y].total_cmp(&eigen.eigenvalues[x]));
+            order.truncate(count);
+
+            // The Ritz residual is the last basis coefficient times the next off-diagonal.
+            let converged = order.iter().all(|&i| (residual * eigen.eigenvectors[(j, i)]).abs() <= tolerance * eigen.eigenvalues[i].abs());
+            if converged || size == n {
+                let pairs = order
+                    .iter()
+                    .map(|&i| {
+                        let mut vector = Vector::zeros(n);
+                        for (k, q) in basis.iter().enumerate() {
+                            vector.axpy(eigen.eigenvectors[(k, i)], q, 1.0);
+                        }
+                        EigenPair { value: eigen.eigenvalues[i], vector: vector.normalize() }
+                    })
+                    .collect();
+                return Ok(pairs);
+            }
+        }
+
+        if j + 1 == steps {
+            break;
+        }
+        if residual > 1e-10 * applied_norm {
+            beta.push(residual);
+            v = w / residual;
+        } else {
+            // The basis spans an invariant subspace: continue from the unit
+            // vector farthest from it, which decouples the tridiagonal matrix.
+            beta.push(0.0);
+            v = (0..n)
+                .map(|k| {
+                    let mut e = Vector::zeros(n);
+                    e[k] = 1.0;
+                    for q in &basis {
+                        let c = q[k];
+                        e.axpy(-c, q, 1.0);
+                    }
+                    e
+                })
+                .max_by(|x, y| x.norm_squared().total_cmp(&y.norm_squared()))
+                .expect("the matrix is not empty")
+                .normalize();
+        }
+    }
+
+    Err(EngineError::NotConverged(format!(
+        "Lanczos did not converge {} eigenpairs to tolerance {:e} within {} iterations",
+        count, tolerance, max_iterations
+    )))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -38,5 +358,107 @@ mod tests {
         let result = add_vectors(&a, &b);
         assert!(result.is_none());
     }
-}
 
+    #[test]
+    fn test_newton_raphson_cube_root() {
+        let root = newton_raphson(
+            |x| Vector::from_element(1, x[0].powi(3) - 2.0),
+            |x| Matrix::from_element(1, 1, 3.0 * x[0].powi(2)),
+            Vector::from_element(1, 1.0),
+            1e-12,
+            50,
+        ).unwrap();
+        assert!((root[0] - 2.0f64.cbrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_newton_raphson_not_converged() {
+        // x^2 + 1 = 0 has no real root, so the iteration can never converge.
+        let result = newton_raphson(
+            |x| Vector::from_element(1, x[0].powi(2) + 1.0),
+            |x| Matrix::from_element(1, 1, 2.0 * x[0]),
+            Vector::from_element(1, 0.5),
+            1e-10,
+            25,
+        );
+        assert!(matches!(result, Err(EngineError::NotConverged(_))), "Unexpected result: {:?}", result);
+    }
+
+    /// The tridiagonal 1D Laplacian, whose condition number grows as `n^2`.
+    fn laplacian(n: usize) -> Matrix {
+        Matrix::from_fn(n, n, |i, j| match i.abs_diff(j) {
+            0 => 2.0,
+            1 => -1.0,
+            _ => 0.0,
+        })
+    }
+
+    #[test]
+    fn test_lanczos_extracts_laplacian_eigenvalues() {
+        // The 1D Laplacian has eigenvalues 4 sin^2(k pi / (2 (n + 1))), k = 1..n.
+        let n = 60;
+        let a = laplacian(n);
+        let exact = |k: usize| 4.0 * (k as f64 * std::f64::consts::PI / (2.0 * (n + 1) as f64)).sin().powi(2);
+
+        let lowest = lanczos(&a, 3, EigenTarget::Smallest, 1e-10, 100).unwrap();
+        assert_eq!(lowest.len(), 3);
+        for (k, pair) in lowest.iter().enumerate() {
+            assert!((pair.value - exact(k + 1)).abs() <= 1e-9 * exact(k + 1), "lambda_{} = {} vs {}", k + 1, pair.value, exact(k + 1));
+            assert!((&a * &pair.vector - &pair.vector * pair.value).norm() <= 1e-8);
+        }
+
+        let highest = lanczos(&a, 1, EigenTarget::Largest, 1e-10, n as u32).unwrap();
+        assert!((highest[0].value - exact(n)).abs() <= 1e-8);
+
+        // Too few iterations to resolve the clustered top of the spectrum.
+        assert!(matches!(lanczos(&a, 1, EigenTarget::Largest, 1e-12, 5), Err(EngineError::NotConverged(_))));
+        assert!(lanczos(&Matrix::zeros(2, 2), 1, EigenTarget::Smallest, 1e-10, 10).is_err());
+        // A spectrum with repeated eigenvalues exercises the restart on an invariant subspace.
+        let identity = lanczos(&Matrix::identity(4, 4), 2, EigenTarget::Largest, 1e-10, 10).unwrap();
+        assert!(identity.iter().all(|pair| (pair.value - 1.0).abs() < 1e-12));
+        assert!(identity[0].vector.dot(&identity[1].vector).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_solve_tridiagonal_matches_dense_solve() {
+        let a = laplacian(30);
+        let b = Vector::from_fn(30, |i, _| (i as f64).sin());
+        let lower: Vec<f64> = (0..29).map(|i| a[(i + 1, i)]).collect();
+        let upper: Vec<f64> = (0..29).map(|i| a[(i, i + 1)]).collect();
+        let x = solve_tridiagonal(&lower, a.diagonal().as_slice(), &upper, b.as_slice()).unwrap();
+        let dense = a.lu().solve(&b).unwrap();
+        assert!((&x - &dense).norm() < 1e-10 * dense.norm());
+
+        assert!(solve_tridiagonal(&[1.0], &[1.0, 1.0], &[1.0], &[1.0, 1.0]).is_err());
+        assert!(solve_tridiagonal(&[], &[1.0, 1.0], &[1.0], &[1.0, 1.0]).is_err());
+    }
+
+    #[test]
+    fn test_conjugate_gradient_converges() {
+        let a = laplacian(20);
+        let b = Vector::from_element(20, 1.0);
+        let solution = conjugate_gradient(&a, &b, Vector::zeros(20), 1e-10, 100, None);
+        assert!(solution.converged, "{:?}", solution.termination_reason);
+        assert!(solution.iterations <= 20);
+        assert!((&a * &solution.x - &b).norm() <= 1e-10);
+    }
+
+    #[test]
+    fn test_conjugate_gradient_stops_at_time_budget() {
+        let n = 400;
+        let a = laplacian(n);
+        let b = Vector::from_element(n, 1.0);
+        let solution = conjugate_gradient(&a, &b, Vector::zeros(n), 1e-14, 10_000, Some(Duration::ZERO));
+
+        assert!(!solution.converged);
+        assert!(solution.termination_reason.as_deref().unwrap().contains("time budget"));
+        assert!(solution.iterations < n as u32);
+        assert_eq!(solution.x.len(), n);
+
+        let limited = conjugate_gradient(&a, &b, Vector::zeros(n), 1e-14, 5, None);
+        assert!(!limited.converged && limited.iterations == 5);
+        assert_eq!(limited.residual_history.len(), 5);
+        assert_eq!(limited.residual_history[4], limited.residual_norm);
+        assert!(limited.x.norm() > 0.0);
+    }
+}
@@ -0,0 +1,249 @@
+// src/kernel/quadrature.rs
+
+//! Gauss quadrature rules for reference elements.
+//!
+//! Rules are selected by the polynomial degree they must integrate exactly.
+//! The reference tetrahedron has vertices `(0,0,0)`, `(1,0,0)`, `(0,1,0)` and
+//! `(0,0,1)`; the reference hexahedron is the cube `[-1, 1]^3` with nodes in
+//! Gmsh order.
+
+use crate::EngineError;
+
+/// The element shapes with quadrature rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementShape {
+    Tetrahedron,
+    Hexahedron,
+}
+
+/// A quadrature point in reference coordinates with its weight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuadraturePoint {
+    pub point: [f64; 3],
+    pub weight: f64,
+}
+
+/// Reference coordinates of the hexahedron nodes in Gmsh order.
+const HEXAHEDRON_CORNERS: [[f64; 3]; 8] = [
+    [-1.0, -1.0, -1.0], [1.0, -1.0, -1.0], [1.0, 1.0, -1.0], [-1.0, 1.0, -1.0],
+    [-1.0, -1.0, 1.0], [1.0, -1.0, 1.0], [1.0, 1.0, 1.0], [-1.0, 1.0, 1.0],
+];
+
+/// Returns the Gauss-Legendre points and weights on `[-1, 1]` for `n` points.
+///
+/// The rule integrates polynomials up to degree `2n - 1` exactly.
+pub fn gauss_legendre(n: usize) -> Vec<(f64, f64)> {
+    (0..n)
+        .map(|i| {
+            // Newton iteration on P_n, starting from the Chebyshev-like initial guess.
+            let mut x = (std::f64::consts::PI * (i as f64 + 0.75) / (n as f64 + 0.5)).cos();
+            let mut derivative = 1.0;
+            for _ in 0..100 {
+                let (p, dp) = legendre(n, x);
+                derivative = dp;
+                let dx = p / dp;
+                x -= dx;
+                if dx.abs() < 1e-15 {
+                    break;
+                }
+            }
+            (x, 2.0 / ((1.0 - x * x) * derivative * derivative))
+        })
+        .collect()
+}
+
+/// Evaluates the Legendre polynomial `P_n` and its derivative at `x`.
+fn legendre(n: usize, x: f64) -> (f64, f64) {
+    let (mut p0, mut p1) = (1.0, x);
+    if n == 0 {
+        return (1.0, 0.0);
+    }
+    for k in 2..=n {
+        let p2 = ((2 * k - 1) as f64 * x * p1 - (k - 1) as f64 * p0) / k as f64;
+        p0 = p1;
+        p1 = p2;
+    }
+    (p1, n as f64 * (x * p1 - p0) / (x * x - 1.0))
+}
+
+/// Returns a rule on the reference `shape` that integrates polynomials of total
+/// degree up to `order` exactly.
+///
+/// Hexahedra use tensor-product Gauss-Legendre rules. Tetrahedra use the
+/// classical 1- and 4-point rules for orders 1 and 2, and a collapsed
+/// (Duffy-transformed) tensor rule for higher orders.
+pub fn gauss_points(shape: ElementShape, order: usize) -> Vec<QuadraturePoint> {
+    match shape {
+        ElementShape::Hexahedron => {
+            let line = gauss_legendre(order / 2 + 1);
+            let mut points = Vec::with_capacity(line.len().pow(3));
+            for &(z, wz) in &line {
+                for &(y, wy) in &line {
+                    for &(x, wx) in &line {
+                        points.push(QuadraturePoint { point: [x, y, z], weight: wx * wy * wz });
+                    }
+                }
+            }
+            points
+        }
+        ElementShape::Tetrahedron if order <= 1 => vec![QuadraturePoint { point: [0.25; 3], weight: 1.0 / 6.0 }],
+        ElementShape::Tetrahedron if order == 2 => {
+            let a = 0.585_410_196_624_968_5;
+            let b = 0.138_196_601_125_010_5;
+            [[b, b, b], [a, b, b], [b, a, b], [b, b, a]]
+                .into_iter()
+                .map(|point| QuadraturePoint { point, weight: 1.0 / 24.0 })
+                .collect()
+        }
+        ElementShape::Tetrahedron => {
+            // The Jacobian (1-v)(1-w)^2 of the collapse map raises the degree by up to 2.
+            let line: Vec<(f64, f64)> = gauss_legendre((order + 2) / 2 + 1)
+                .into_iter()
+                .map(|(x, w)| (0.5 * (x + 1.0), 0.5 * w))
+                .collect();
+            let mut points = Vec::with_capacity(line.len().pow(3));
+            for &(w, ww) in &line {
+                for &(v, wv) in &line {
+                    for &(u, wu) in &line {
+                        points.push(QuadraturePoint {
+                            point: [u * (1.0 - v) * (1.0 - w), v * (1.0 - w), w],
+                            weight: wu * wv * ww * (1.0 - v) * (1.0 - w).powi(2),
+                        });
+                    }
+                }
+            }
+            points
+        }
+    }
+}
+
+/// Evaluates the shape functions and their reference derivatives at `xi`.
+fn shape_functions(shape: ElementShape, xi: [f64; 3]) -> (Vec<f64>, Vec<[f64; 3]>) {
+    match shape {
+        ElementShape::Tetrahedron => (
+            vec![1.0 - xi[0] - xi[1] - xi[2], xi[0], xi[1], xi[2]],
+            vec![[-1.0, -1.0, -1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        ),
+        ElementShape::Hexahedron => HEXAHEDRON_CORNERS
+            .iter()
+            .map(|c| {
+                let f = [(1.0 + c[0] * xi[0]) / 2.0, (1.0 + c[1] * xi[1]) / 2.0, (1.0 + c[2] * xi[2]) / 2.0];
+                let value = f[0] * f[1] * f[2];
+                let gradient = [c[0] / 2.0 * f[1] * f[2], f[0] * c[1] / 2.0 * f[2], f[0] * f[1] * c[2] / 2.0];
+                (value, gradient)
+            })
+            .unzip(),
+    }
+}
+
+/// Integrates `f` over a physical element given by its corner `nodes`.
+///
+/// The element is mapped from the reference shape with linear (tetrahedron) or
+/// trilinear (hexahedron) shape functions, and `f` is evaluated at physical
+/// coordinates with a rule of the given polynomial `order`.
+pub fn integrate_over_element<F>(shape: ElementShape, order: usize, nodes: &[[f64; 3]], f: F) -> Result<f64, EngineError>
+where
+    F: Fn([f64; 3]) -> f64,
+{
+    let expected = match shape {
+        ElementShape::Tetrahedron => 4,
+        ElementShape::Hexahedron => 8,
+    };
+    if nodes.len() != expected {
+        return Err(EngineError::SolverFailed(format!("{:?} needs {} nodes, got {}", shape, expected, nodes.len())));
+    }
+
+    let mut total = 0.0;
+    for qp in gauss_points(shape, order) {
+        let (values, gradients) = shape_functions(shape, qp.point);
+        let mut x = [0.0; 3];
+        let mut jacobian = nalgebra::Matrix3::<f64>::zeros();
+        for (node, (value, gradient)) in nodes.iter().zip(values.iter().zip(&gradients)) {
+            for i in 0..3 {
+                x[i] += value * node[i];
+                for j in 0..3 {
+                    jacobian[(i, j)] += node[i] * gradient[j];
+                }
+            }
+        }
+        let det = jacobian.determinant();
+        if det.abs() <= f64::EPSILON {
+            return Err(EngineError::SolverFailed(format!("Degenerate {:?} element", shape)));
+        }
+        total += qp.weight * det.abs() * f(x);
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn factorial(n: u32) -> f64 {
+        (1..=n).map(f64::from).product()
+    }
+
+    /// Exponent triples `(a, b, c)` with `a + b + c <= degree`.
+    fn monomials(degree: u32) -> Vec<(u32, u32, u32)> {
+        let mut result = Vec::new();
+        for a in 0..=degree {
+            for b in 0..=degree - a {
+                for c in 0..=degree - a - b {
+                    result.push((a, b, c));
+                }
+            }
+        }
+        result
+    }
+
+    fn integrate_reference(shape: ElementShape, order: usize, (a, b, c): (u32, u32, u32)) -> f64 {
+        gauss_points(shape, order)
+            .iter()
+            .map(|qp| qp.weight * qp.point[0].powi(a as i32) * qp.point[1].powi(b as i32) * qp.point[2].powi(c as i32))
+            .sum()
+    }
+
+    #[test]
+    fn test_tetrahedron_rules_are_exact_for_their_order() {
+        for order in 1..=6 {
+            for (a, b, c) in monomials(order) {
+                let exact = factorial(a) * factorial(b) * factorial(c) / factorial(a + b + c + 3);
+                let approx = integrate_reference(ElementShape::Tetrahedron, order as usize, (a, b, c));
+                assert!((approx - exact).abs() < 1e-14, "Order {}, x^{} y^{} z^{}: {} vs {}", order, a, b, c, approx, exact);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hexahedron_rules_are_exact_for_their_order() {
+        let line = |k: u32| if k.is_multiple_of(2) { 2.0 / (k + 1) as f64 } else { 0.0 };
+        for order in 1..=7 {
+            for (a, b, c) in monomials(order) {
+                let exact = line(a) * line(b) * line(c);
+                let approx = integrate_reference(ElementShape::Hexahedron, order as usize, (a, b, c));
+                assert!((approx - exact).abs() < 1e-13, "Order {}, x^{} y^{} z^{}: {} vs {}", order, a, b, c, approx, exact);
+            }
+        }
+        assert_eq!(gauss_points(ElementShape::Hexahedron, 3).len(), 8);
+    }
+
+    #[test]
+    fn test_integrate_over_physical_elements() {
+        // A tetrahedron with legs 2, 3 and 4: volume 4 and centroid (0.5, 0.75, 1).
+        let tet = [[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [0.0, 3.0, 0.0], [0.0, 0.0, 4.0]];
+        let volume = integrate_over_element(ElementShape::Tetrahedron, 1, &tet, |_| 1.0).unwrap();
+        assert!((volume - 4.0).abs() < 1e-12);
+        let moment = integrate_over_element(ElementShape::Tetrahedron, 1, &tet, |x| x[1]).unwrap();
+        assert!((moment - 4.0 * 0.75).abs() < 1e-12);
+
+        // The box [0, 2] x [0, 1] x [0, 3]: the integral of x^2 z is (8/3) * 1 * (9/2).
+        let hex: Vec<[f64; 3]> = HEXAHEDRON_CORNERS
+            .iter()
+            .map(|c| [c[0] + 1.0, (c[1] + 1.0) / 2.0, 1.5 * (c[2] + 1.0)])
+            .collect();
+        let integral = integrate_over_element(ElementShape::Hexahedron, 3, &hex, |x| x[0] * x[0] * x[2]).unwrap();
+        assert!((integral - 12.0).abs() < 1e-12);
+
+        assert!(integrate_over_element(ElementShape::Hexahedron, 1, &tet, |_| 1.0).is_err());
+    }
+}
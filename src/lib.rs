@@ -4,19 +4,280 @@ pub mod symbolic;
 pub mod solver;
 pub mod sandbox;
 pub mod provenance;
+pub mod verification;
+pub mod export;
 
 // Re-exporting core numerical types for easier access by other modules.
 pub use kernel::{Matrix, Vector};
 
 // --- Return Types and Errors ---
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct Solution {
     pub id: String,
     pub mesh: Mesh,
     pub processed_equations: Option<symbolic::ProcessedEquations>,
-    pub data: Vec<f64>, // Raw solution data
+    /// The nodal field of a steady solve, or the last step of a transient one.
+    pub data: Vec<f64>,
+    /// Every step of a transient solve as `(time, field)` pairs, empty for steady solves.
+    #[serde(default)]
+    pub time_steps: Vec<(f64, Vec<f64>)>,
+    /// Further named nodal fields, such as the `"von_mises"` stress selected by `SolverSettings::outputs`.
+    #[serde(default)]
+    pub fields: std::collections::HashMap<String, Vec<f64>>,
     pub provenance_chain: Vec<provenance::ProvenanceRecord>,
+    /// Solver diagnostics such as `residual_norm` and `total_reaction`.
+    #[serde(default)]
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+    /// Non-fatal issues from meshing and solving, in the order they were found.
+    #[serde(default)]
+    pub warnings: Vec<EngineWarning>,
+    /// Wall-clock time spent in each stage of the run, keyed `"meshing"`,
+    /// `"symbolic"` and `"solving"`. Stages that did not run are absent.
+    #[serde(default)]
+    pub timings: std::collections::HashMap<String, std::time::Duration>,
+}
+
+/// One line of the NDJSON stream written by [`CoreEngine::run_batch_ndjson`].
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub enum BatchRecord {
+    /// A problem that solved.
+    Solution(Box<Solution>),
+    /// A problem that failed, with the problem id and the error message.
+    Error { id: String, message: String },
+}
+
+impl Solution {
+    /// Builds a solution from a mesh and nodal data, e.g. to export results
+    /// produced or stored outside of [`CoreEngine::run_simulation`].
+    ///
+    /// Provenance, metadata and processed equations start empty. A persisted
+    /// solution with its provenance chain can instead be loaded with serde.
+    pub fn from_mesh(id: String, mesh: Mesh, data: Vec<f64>) -> Self {
+        Solution {
+            id,
+            mesh,
+            processed_equations: None,
+            data,
+            time_steps: Vec::new(),
+            fields: std::collections::HashMap::new(),
+            provenance_chain: Vec::new(),
+            metadata: serde_json::Map::new(),
+            warnings: Vec::new(),
+            timings: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Returns whether the solution holds the steps of a transient solve.
+    pub fn is_transient(&self) -> bool {
+        !self.time_steps.is_empty()
+    }
+
+    /// Returns the fields to visualize as `(time, field)` pairs: every time step
+    /// of a transient solution, or `data` at time zero for a steady one.
+    pub fn steps(&self) -> Vec<(f64, &[f64])> {
+        if self.is_transient() {
+            self.time_steps.iter().map(|(t, field)| (*t, field.as_slice())).collect()
+        } else {
+            vec![(0.0, self.data.as_slice())]
+        }
+    }
+
+    /// Serializes the solution to JSON and gzip-compresses it.
+    pub fn to_json_gz(&self) -> Result<Vec<u8>, String> {
+        use std::io::Write;
+
+        let json = serde_json::to_vec(self).map_err(|e| format!("Failed to serialize solution: {}", e))?;
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&json)
+            .and_then(|_| encoder.finish())
+            .map_err(|e| format!("Failed to compress solution: {}", e))
+    }
+
+    /// Deserializes a solution from gzip-compressed JSON.
+    pub fn from_json_gz(bytes: &[u8]) -> Result<Self, String> {
+        let decoder = flate2::read::GzDecoder::new(bytes);
+        serde_json::from_reader(decoder).map_err(|e| format!("Failed to deserialize solution: {}", e))
+    }
+
+    /// Writes the mesh and nodal data to an HDF5 file; see [`export::write_hdf5`].
+    #[cfg(feature = "hdf5")]
+    pub fn to_hdf5<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), EngineError> {
+        export::write_hdf5(self, path)
+    }
+
+    /// Interpolates the nodal solution at an arbitrary point.
+    ///
+    /// The point is located in a tetrahedron of the mesh and each solution
+    /// component is interpolated with barycentric weights, so the result holds
+    /// `data.len() / num_nodes` values. Returns `None` if the point lies outside
+    /// every tetrahedron or the data does not match the node count.
+    pub fn sample(&self, point: [f64; 3]) -> Option<Vec<f64>> {
+        self.interpolate(&self.data, point)
+    }
+
+    /// Interpolates the named nodal field at an arbitrary point, like
+    /// [`Solution::sample`] does for `data`. Returns `None` if there is no such field.
+    pub fn sample_field(&self, name: &str, point: [f64; 3]) -> Option<Vec<f64>> {
+        self.interpolate(self.fields.get(name)?, point)
+    }
+
+    /// Interpolates nodal `values` at `point` in the tetrahedron containing it.
+    fn interpolate(&self, values: &[f64], point: [f64; 3]) -> Option<Vec<f64>> {
+        let num_nodes = self.mesh.num_nodes();
+        if num_nodes == 0 || !values.len().is_multiple_of(num_nodes) {
+            return None;
+        }
+        let components = values.len() / num_nodes;
+
+        self.mesh.elements.iter().filter(|element| element.len() == 4).find_map(|element| {
+            let corners: Vec<[f64; 3]> = element.iter().filter_map(|&n| self.mesh.node(n)).collect();
+            let weights = barycentric_coordinates(corners.as_slice().try_into().ok()?, point)?;
+            Some((0..components)
+                .map(|c| element.iter().zip(weights).map(|(&n, w)| w * values[n * components + c]).sum())
+                .collect())
+        })
+    }
+
+    /// Splits the stress along a classification line into membrane and bending
+    /// parts, as for an ASME Section VIII Division 2 assessment.
+    ///
+    /// The nodal `"stress"` field, selected with [`OutputField::Stress`], is
+    /// sampled at `num_points` Gauss-Legendre points of the segment from `start`
+    /// to `end`, which usually spans the wall thickness `t`. With `x` the distance
+    /// from `start`, each Voigt component is linearized as
+    ///
+    /// - membrane: `sigma_m = 1/t * integral(sigma dx)`,
+    /// - bending: `sigma_b = 6/t^2 * integral(sigma (t/2 - x) dx)`,
+    ///
+    /// so the linearized stress is `sigma_m + sigma_b` at `start` and
+    /// `sigma_m - sigma_b` at `end`. Fails if the field is missing, the segment
+    /// is degenerate or a point lies outside the mesh.
+    pub fn linearize_stress(&self, start: [f64; 3], end: [f64; 3], num_points: usize) -> Result<LinearizedStress, EngineError> {
+        let thickness = (0..3).map(|i| (end[i] - start[i]).powi(2)).sum::<f64>().sqrt();
+        if !(thickness > 0.0 && thickness.is_finite()) {
+            return Err(EngineError::InvalidInput(format!("Stress classification line from {:?} to {:?} has no length", start, end)));
+        }
+        if num_points == 0 {
+            return Err(EngineError::InvalidInput("Stress linearization needs at least one sample point".to_string()));
+        }
+        if !self.fields.contains_key("stress") {
+            return Err(EngineError::InvalidInput("Stress linearization needs the \"stress\" output field".to_string()));
+        }
+
+        let mut linearized = LinearizedStress { membrane: [0.0; 6], bending: [0.0; 6], thickness };
+        for (xi, weight) in kernel::quadrature::gauss_legendre(num_points) {
+            let fraction = 0.5 * (xi + 1.0);
+            let point = std::array::from_fn(|i| start[i] + fraction * (end[i] - start[i]));
+            let stress = self.sample_field("stress", point).filter(|stress| stress.len() == 6).ok_or_else(|| {
+                EngineError::InvalidInput(format!("Stress classification point {:?} lies outside the mesh", point))
+            })?;
+            // dx = t/2 dxi, so the integrals divided by t carry weight / 2.
+            let x = fraction * thickness;
+            for (c, sigma) in stress.iter().enumerate() {
+                linearized.membrane[c] += 0.5 * weight * sigma;
+                linearized.bending[c] += 3.0 * weight * sigma * (0.5 * thickness - x) / thickness;
+            }
+        }
+        Ok(linearized)
+    }
+
+    /// Returns the range and mean of `data` over every node of the model.
+    ///
+    /// A node's value is its component for a scalar field and the magnitude of
+    /// its components otherwise, so a displacement field reports how far nodes
+    /// moved. Returns `None` for empty data or data that does not match the node count.
+    pub fn field_statistics(&self) -> Option<FieldStats> {
+        FieldStats::over(&self.nodal_magnitudes()?, 0..self.mesh.num_nodes())
+    }
+
+    /// Returns the statistics of [`Solution::field_statistics`] over the nodes of
+    /// each boundary region, keyed by region name. Regions without a valid node
+    /// are left out, and so is every region when the data does not match the mesh.
+    pub fn field_statistics_by_region(&self) -> std::collections::HashMap<String, FieldStats> {
+        let Some(values) = self.nodal_magnitudes() else {
+            return std::collections::HashMap::new();
+        };
+        self.mesh
+            .boundary_regions
+            .iter()
+            .filter_map(|(name, nodes)| Some((name.clone(), FieldStats::over(&values, nodes.iter().copied())?)))
+            .collect()
+    }
+
+    /// Returns one value per node: the component of a scalar field or the
+    /// magnitude of a vector one.
+    fn nodal_magnitudes(&self) -> Option<Vec<f64>> {
+        let num_nodes = self.mesh.num_nodes();
+        if num_nodes == 0 || self.data.is_empty() || !self.data.len().is_multiple_of(num_nodes) {
+            return None;
+        }
+        let components = self.data.len() / num_nodes;
+        Some(match components {
+            1 => self.data.clone(),
+            _ => self.data.chunks(components).map(|values| values.iter().map(|v| v * v).sum::<f64>().sqrt()).collect(),
+        })
+    }
+}
+
+/// The membrane and bending stresses along a stress classification line, from
+/// [`Solution::linearize_stress`], in Voigt order [xx, yy, zz, xy, yz, zx].
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct LinearizedStress {
+    /// The average stress through the thickness.
+    pub membrane: [f64; 6],
+    /// The linear part of the stress at the start of the line, zero at the middle.
+    pub bending: [f64; 6],
+    /// The length of the line.
+    pub thickness: f64,
+}
+
+/// The range and mean of a nodal field over a set of nodes, from
+/// [`Solution::field_statistics`].
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct FieldStats {
+    pub min: f64,
+    pub max: f64,
+    /// The plain average over the nodes, not weighted by volume.
+    pub mean: f64,
+    /// The node holding `min`, the first one on ties.
+    pub min_node: usize,
+    /// The node holding `max`, the first one on ties.
+    pub max_node: usize,
+}
+
+impl FieldStats {
+    /// Gathers the statistics of `values` at `nodes`, skipping indices out of
+    /// range. Returns `None` if no node remains.
+    fn over(values: &[f64], nodes: impl IntoIterator<Item = usize>) -> Option<Self> {
+        let mut stats: Option<FieldStats> = None;
+        let mut count = 0;
+        for node in nodes.into_iter().filter(|&n| n < values.len()) {
+            let value = values[node];
+            let current = stats.get_or_insert(FieldStats { min: value, max: value, mean: 0.0, min_node: node, max_node: node });
+            if value < current.min {
+                (current.min, current.min_node) = (value, node);
+            }
+            if value > current.max {
+                (current.max, current.max_node) = (value, node);
+            }
+            current.mean += value;
+            count += 1;
+        }
+        stats.map(|stats| FieldStats { mean: stats.mean / count as f64, ..stats })
+    }
+}
+
+/// Returns the barycentric coordinates of `point` in a tetrahedron if it lies inside.
+///
+/// Points on the boundary, up to a small tolerance, count as inside.
+fn barycentric_coordinates(corners: &[[f64; 3]; 4], point: [f64; 3]) -> Option<[f64; 4]> {
+    let [p0, p1, p2, p3] = corners;
+    let edges = nalgebra::Matrix3::from_fn(|i, j| [p1, p2, p3][j][i] - p0[i]);
+    let offset = nalgebra::Vector3::from_fn(|i, _| point[i] - p0[i]);
+    let local = edges.lu().solve(&offset)?;
+    let weights = [1.0 - local.sum(), local[0], local[1], local[2]];
+    weights.iter().all(|&w| w >= -1e-10).then_some(weights)
 }
 
 #[derive(Debug)]
@@ -26,6 +287,10 @@ pub enum EngineError {
     SolverFailed(String),
     PluginNotFound(String),
     ProvenanceFailed(String),
+    RuntimeFailed(String),
+    NotConverged(String),
+    ExportFailed(String),
+    InvalidInput(String),
 }
 
 impl std::fmt::Display for EngineError {
@@ -36,12 +301,60 @@ impl std::fmt::Display for EngineError {
             EngineError::SolverFailed(s) => write!(f, "Solver failed: {}", s),
             EngineError::PluginNotFound(s) => write!(f, "Plugin not found: {}", s),
             EngineError::ProvenanceFailed(s) => write!(f, "Provenance failed: {}", s),
+            EngineError::RuntimeFailed(s) => write!(f, "Async runtime failed: {}", s),
+            EngineError::NotConverged(s) => write!(f, "Iteration did not converge: {}", s),
+            EngineError::ExportFailed(s) => write!(f, "Export failed: {}", s),
+            EngineError::InvalidInput(s) => write!(f, "Invalid input: {}", s),
         }
     }
 }
 
 impl std::error::Error for EngineError {}
 
+/// The kind of a non-fatal issue, for frontends to match on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum WarningCode {
+    /// A boundary condition names a region the mesh does not have, so it was not applied.
+    UnmatchedRegion,
+    /// Inverted elements were reordered because of `flip_inverted_elements`.
+    InvertedElements,
+    /// Free rigid body modes were pinned because of `auto_stabilize`.
+    RigidBodyModes,
+    /// An iterative solve stopped before reaching its tolerance.
+    NotConverged,
+    /// The equilibrium residual of the solution exceeds the solver tolerance.
+    LargeResidual,
+    /// Gmsh reported a defect in the geometry.
+    GeometryDefect,
+    /// A Poisson's ratio close to 0.5 makes the elements lock volumetrically.
+    NearlyIncompressible,
+}
+
+/// A non-fatal issue a stage of the engine ran into, collected in `Solution::warnings`.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct EngineWarning {
+    pub code: WarningCode,
+    pub message: String,
+}
+
+impl EngineWarning {
+    /// Prints the warning and returns it, for stages that report as they go.
+    pub fn emit(code: WarningCode, message: String) -> Self {
+        println!("Warning: {}", message);
+        EngineWarning { code, message }
+    }
+}
+
+/// Provenance metadata for a solver run: everything in the problem that
+/// changes the numbers, so the run can be reproduced from the chain.
+fn solver_run_metadata(problem: &ProblemDefinition) -> serde_json::Value {
+    serde_json::json!({
+        "solver_name": problem.solver_settings.solver_name,
+        "solver_settings": problem.solver_settings,
+        "material": problem.physics.material,
+    })
+}
+
 // --- Solver Manager ---
 
 struct SolverManager {
@@ -51,10 +364,24 @@ struct SolverManager {
 impl SolverManager {
     fn new() -> Self {
         SolverManager {
-            solvers: vec![Box::new(solver::DummySolver), Box::new(solver::fem_solver::FemSolver), Box::new(solver::fdm_solver::FdmSolver)],
+            solvers: vec![Box::new(solver::DummySolver), Box::new(solver::fem_solver::FemSolver::new()), Box::new(solver::fem_solver::CachedFemSolver::new()), Box::new(solver::fem_solver::HarmonicFemSolver), Box::new(solver::fem_solver::BucklingSolver), Box::new(solver::fem_solver::WaveFemSolver), Box::new(solver::stokes_solver::StokesSolver), Box::new(solver::fdm_solver::FdmSolver), Box::new(solver::thermal_solver::ThermalFemSolver)],
         }
     }
 
+    fn register(&mut self, solver: Box<dyn solver::Solver>) {
+        self.solvers.retain(|s| s.name() != solver.name());
+        self.solvers.push(solver);
+    }
+
+    /// Returns the first registered solver whose capabilities cover `descriptor`
+    /// and that accepts the problem.
+    fn select(&self, descriptor: &solver::PhysicsDescriptor, problem: &ProblemDefinition) -> Option<&dyn solver::Solver> {
+        self.solvers
+            .iter()
+            .map(|s| s.as_ref())
+            .find(|s| s.capabilities().supports(descriptor) && s.check_compatibility(problem).is_ok())
+    }
+
     fn get_solver(&self, name: &str) -> Result<&dyn solver::Solver, EngineError> {
         self.solvers
             .iter()
@@ -66,9 +393,29 @@ impl SolverManager {
 
 // --- Core Engine Facade ---
 
+/// Receives `(stage, fraction)` updates while a long-running stage executes.
+///
+/// `stage` uses the names of [`Solution::timings`], e.g. `"meshing"`, and
+/// `fraction` rises from 0 to 1 within each stage. It may be called from a
+/// helper thread.
+pub type ProgressCallback = std::sync::Arc<dyn Fn(&str, f64) + Send + Sync>;
+
 pub struct CoreEngine {
     solver_manager: SolverManager,
     provenance_chain: provenance::ProvenanceChain,
+    meshing_config: meshing::MeshingConfig,
+    mesh_cache: meshing::MeshCache,
+    sandbox_config: sandbox::SandboxConfig,
+    provenance_enabled: bool,
+    /// Stage durations of the current run, moved into its solution.
+    stage_timings: std::collections::HashMap<String, std::time::Duration>,
+    progress_callback: Option<ProgressCallback>,
+}
+
+impl Default for CoreEngine {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CoreEngine {
@@ -76,75 +423,411 @@ impl CoreEngine {
         CoreEngine {
             solver_manager: SolverManager::new(),
             provenance_chain: provenance::ProvenanceChain::new(),
+            meshing_config: meshing::MeshingConfig::default(),
+            mesh_cache: meshing::MeshCache::default(),
+            sandbox_config: sandbox::SandboxConfig::default(),
+            provenance_enabled: true,
+            stage_timings: std::collections::HashMap::new(),
+            progress_callback: None,
         }
     }
 
+    /// Sets the options used for every subsequent meshing stage.
+    pub fn set_meshing_config(&mut self, config: meshing::MeshingConfig) {
+        self.meshing_config = config;
+    }
+
+    /// Reports the progress of subsequent runs to `callback`, or stops reporting with `None`.
+    pub fn set_progress_callback(&mut self, callback: Option<ProgressCallback>) {
+        self.progress_callback = callback;
+    }
+
+    /// Bounds how many generated meshes are kept for reuse; zero disables the cache.
+    pub fn set_mesh_cache_capacity(&mut self, capacity: usize) {
+        self.mesh_cache.set_capacity(capacity);
+    }
+
+    /// Drops all cached meshes, e.g. after editing a geometry file in place.
+    pub fn clear_mesh_cache(&mut self) {
+        self.mesh_cache.clear();
+    }
+
+    /// Adds a solver, e.g. a [`solver::wasm_solver::WasmSolver`] plugin, replacing
+    /// any registered solver with the same name.
+    pub fn register_solver(&mut self, solver: Box<dyn solver::Solver>) {
+        self.solver_manager.register(solver);
+    }
+
+    /// Sets the Docker sandbox options used for symbolic processing.
+    pub fn set_sandbox_config(&mut self, config: sandbox::SandboxConfig) {
+        self.sandbox_config = config;
+    }
+
+    /// Turns provenance recording on or off; it is on by default.
+    ///
+    /// While disabled no artifact is serialized or hashed, and solutions carry an
+    /// empty provenance chain.
+    pub fn set_provenance_enabled(&mut self, enabled: bool) {
+        self.provenance_enabled = enabled;
+    }
+
+    /// Sets how numbers in recorded artifacts are canonicalized before hashing.
+    pub fn set_provenance_float_precision(&mut self, precision: provenance::FloatPrecision) {
+        self.provenance_chain.set_float_precision(precision);
+    }
+
     /// The main entry point for running a simulation.
+    ///
+    /// A problem that already carries a mesh is solved on it as is; Gmsh only
+    /// runs when `problem.mesh` is `None`.
     pub async fn run_simulation(&mut self, mut problem: ProblemDefinition) -> Result<Solution, EngineError> {
         println!("Received simulation task: {}", problem.id);
 
+        let warnings = self.prepare_problem(&mut problem).await?;
+        self.solve_problem(problem, warnings)
+    }
+
+    /// Runs a thermal solve and feeds its temperatures into a structural solve.
+    ///
+    /// The thermal stage runs the `ThermalFemSolver` with the problem's
+    /// `thermal_boundary_conditions` on the problem's mesh, and its nodal
+    /// temperatures are passed to the structural stage as the `"temperature"` aux
+    /// field, where they yield thermal strains through the material's
+    /// `thermal_expansion`. The structural stage uses the named solver, or the
+    /// `FemSolver` when none is given. Both stages are recorded in the provenance
+    /// chain.
+    pub async fn run_coupled(&mut self, mut problem: ProblemDefinition) -> Result<Solution, EngineError> {
+        println!("Received coupled thermal-structural task: {}", problem.id);
+
+        let mut warnings = self.prepare_problem(&mut problem).await?;
+
+        // Thermal stage on the same mesh.
+        let thermal = ProblemDefinition {
+            id: format!("{}_thermal", problem.id),
+            geometry: problem.geometry.clone(),
+            physics: PhysicsDefinition {
+                equations: vec![],
+                boundary_conditions: problem.physics.thermal_boundary_conditions.clone(),
+                thermal_boundary_conditions: vec![],
+                material: problem.physics.material.clone(),
+                boundary_conditions_file: None,
+                constraints: vec![],
+                processed_equations: None,
+            },
+            solver_settings: SolverSettings {
+                solver_name: "ThermalFemSolver".to_string(),
+                ..problem.solver_settings.clone()
+            },
+            mesh: problem.mesh.clone(),
+            aux_fields: std::collections::HashMap::new(),
+        };
+        let start = std::time::Instant::now();
+        let thermal_solver = self.solver_manager.get_solver("ThermalFemSolver")?;
+        thermal_solver.check_compatibility(&thermal)?;
+        let thermal_data = thermal_solver.solve(&thermal)?;
+        let elapsed = self.time_stage("thermal_solving", start);
+        let mut thermal_metadata = solver_run_metadata(&thermal);
+        thermal_metadata["duration_secs"] = serde_json::json!(elapsed.as_secs_f64());
+        self.record("thermal_solver_run", &thermal_data, thermal_metadata)?;
+        warnings.extend(thermal_data.warnings);
+        problem.aux_fields.insert("temperature".to_string(), thermal_data.data);
+
+        // Structural stage.
+        if problem.solver_settings.solver_name.is_empty() {
+            problem.solver_settings.solver_name = "FemSolver".to_string();
+        }
+        self.solve_problem(problem, warnings)
+    }
+
+    /// Records the problem, generates its mesh and processes its equations.
+    ///
+    /// Returns the warnings of these stages, such as the geometry defects
+    /// Gmsh reported for the mesh.
+    async fn prepare_problem(&mut self, problem: &mut ProblemDefinition) -> Result<Vec<EngineWarning>, EngineError> {
+        self.stage_timings.clear();
         // Record initial problem definition
-        let problem_json = serde_json::to_string(&problem).map_err(|e| EngineError::ProvenanceFailed(e.to_string()))?;
-        self.provenance_chain.add_record(
-            "problem_definition".to_string(),
-            problem_json.as_bytes(),
-            env!("CARGO_PKG_VERSION").to_string(),
-            serde_json::json!({"problem_id": problem.id}),
-        ).map_err(|e| EngineError::ProvenanceFailed(e.to_string()))?;
+        self.record("problem_definition", problem, serde_json::json!({"problem_id": problem.id}))?;
 
-        // 1. Generate mesh from geometry
-        let mesh = self.generate_mesh(&problem.geometry)?;
-        problem.mesh = Some(mesh);
-        let mesh_json = serde_json::to_string(&problem.mesh).map_err(|e| EngineError::ProvenanceFailed(e.to_string()))?;
-        self.provenance_chain.add_record(
-            "mesh_generation".to_string(),
-            mesh_json.as_bytes(),
-            env!("CARGO_PKG_VERSION").to_string(),
-            serde_json::json!({"geometry_type": format!("{:?}", problem.geometry)}),
-        ).map_err(|e| EngineError::ProvenanceFailed(e.to_string()))?;
+        // 1. Generate mesh from geometry, unless the caller supplied one
+        let start = std::time::Instant::now();
+        if let Some(mesh) = &problem.mesh {
+            meshing::validate_mesh(mesh)?;
+            println!("Using the supplied mesh with {} nodes, skipping mesh generation", mesh.num_nodes());
+            let (num_nodes, num_elements) = (mesh.num_nodes(), mesh.num_elements());
+            let elapsed = self.time_stage("meshing", start);
+            self.record("external_mesh", &problem.mesh, serde_json::json!({
+                "num_nodes": num_nodes,
+                "num_elements": num_elements,
+                "duration_secs": elapsed.as_secs_f64(),
+            }))?;
+        } else {
+            let mesh = self.generate_mesh(&problem.geometry)?;
+            problem.mesh = Some(mesh);
+            let elapsed = self.time_stage("meshing", start);
+            self.record("mesh_generation", &problem.mesh, serde_json::json!({
+                "geometry_type": format!("{:?}", problem.geometry),
+                "duration_secs": elapsed.as_secs_f64(),
+            }))?;
+        }
 
         // 2. Process physics equations (symbolic engine)
         if !problem.physics.equations.is_empty() {
+            let start = std::time::Instant::now();
             let processed_equations = self.process_equations(&problem.physics.equations).await?;
             problem.physics.processed_equations = Some(processed_equations);
-            let processed_equations_json = serde_json::to_string(&problem.physics.processed_equations).map_err(|e| EngineError::ProvenanceFailed(e.to_string()))?;
-            self.provenance_chain.add_record(
-                "symbolic_processing".to_string(),
-                processed_equations_json.as_bytes(),
-                env!("CARGO_PKG_VERSION").to_string(),
-                serde_json::json!({"equations": problem.physics.equations}),
-            ).map_err(|e| EngineError::ProvenanceFailed(e.to_string()))?;
+            let elapsed = self.time_stage("symbolic", start);
+            self.record("symbolic_processing", &problem.physics.processed_equations, serde_json::json!({
+                "equations": problem.physics.equations,
+                "duration_secs": elapsed.as_secs_f64(),
+            }))?;
         }
 
-        // 3. Select and run solver
-        let solver = self.solver_manager.get_solver(&problem.solver_settings.solver_name)?;
-        let solution_data = solver.solve(&mut problem)?;
-        let solution_data_json = serde_json::to_string(&solution_data).map_err(|e| EngineError::ProvenanceFailed(e.to_string()))?;
+        // Let recognized equations pick the solver when none was named.
+        if problem.solver_settings.solver_name.is_empty() {
+            if let Some(route) = symbolic::weak_form::route_physics(&problem.physics) {
+                println!("Routing equations to {}", route.solver_name);
+                problem.solver_settings.solver_name = route.solver_name.to_string();
+            }
+        }
+
+        // Otherwise pick the first registered solver able to handle the physics.
+        if problem.solver_settings.solver_name.is_empty() {
+            let descriptor = solver::PhysicsDescriptor::infer(problem);
+            if let Some(solver) = descriptor.and_then(|d| self.solver_manager.select(&d, problem)) {
+                println!("Selecting {} for {:?}", solver.name(), descriptor.unwrap());
+                problem.solver_settings.solver_name = solver.name().to_string();
+            }
+        }
+
+        let geometry_warnings = problem.mesh.iter().flat_map(|mesh| &mesh.geometry_warnings);
+        Ok(geometry_warnings
+            .map(|warning| EngineWarning { code: WarningCode::GeometryDefect, message: warning.message.clone() })
+            .collect())
+    }
+
+    /// Adds the time since `start` to `stage`, so repeated stages accumulate, and returns it.
+    fn time_stage(&mut self, stage: &str, start: std::time::Instant) -> std::time::Duration {
+        let elapsed = start.elapsed();
+        *self.stage_timings.entry(stage.to_string()).or_default() += elapsed;
+        elapsed
+    }
+
+    /// Appends a provenance record for `artifact`, unless recording is disabled.
+    fn record<T: serde::Serialize + ?Sized>(&mut self, event_type: &str, artifact: &T, metadata: serde_json::Value) -> Result<(), EngineError> {
+        if !self.provenance_enabled {
+            return Ok(());
+        }
+        let artifact_json = serde_json::to_string(artifact).map_err(|e| EngineError::ProvenanceFailed(e.to_string()))?;
         self.provenance_chain.add_record(
-            "solver_run".to_string(),
-            solution_data_json.as_bytes(),
+            event_type.to_string(),
+            artifact_json.as_bytes(),
             env!("CARGO_PKG_VERSION").to_string(),
-            serde_json::json!({"solver_name": problem.solver_settings.solver_name}),
-        ).map_err(|e| EngineError::ProvenanceFailed(e.to_string()))?;
+            metadata,
+        ).map_err(|e| EngineError::ProvenanceFailed(e.to_string()))
+    }
+
+    /// Runs the problem's solver and assembles the solution with its provenance,
+    /// appending the solver's warnings to those of the earlier stages.
+    fn solve_problem(&mut self, problem: ProblemDefinition, warnings: Vec<EngineWarning>) -> Result<Solution, EngineError> {
+        let solution_data = self.run_solver(&problem)?;
+        Ok(self.assemble_solution(problem, solution_data, warnings))
+    }
+
+    /// Selects and runs the problem's solver, recording the run.
+    fn run_solver(&mut self, problem: &ProblemDefinition) -> Result<solver::SolverSolutionData, EngineError> {
+        // 3. Select and run solver
+        let start = std::time::Instant::now();
+        let solver = self.solver_manager.get_solver(&problem.solver_settings.solver_name)?;
+        solver.check_compatibility(problem)?;
+        let solution_data = solver.solve(problem)?;
+        let solver_name = solver.name();
+        let elapsed = self.time_stage("solving", start);
+        solver::check_finite(solver_name, &solution_data)?;
+        for iteration in &solution_data.iterations {
+            self.record("solver_iteration", iteration, serde_json::json!({
+                "solver_name": solver_name,
+                "iteration": iteration.iteration,
+                "residual_norm": iteration.residual_norm,
+            }))?;
+        }
+        let mut run_metadata = solver_run_metadata(problem);
+        run_metadata["duration_secs"] = serde_json::json!(elapsed.as_secs_f64());
+        // The precision the system was actually solved in; solvers that ignore the setting use f64.
+        run_metadata["precision"] = solution_data.metadata.get("precision").cloned().unwrap_or_else(|| serde_json::json!(Precision::F64));
+        if !solution_data.warnings.is_empty() {
+            run_metadata["warnings"] = serde_json::json!(solution_data.warnings);
+        }
+        self.record("solver_run", &solution_data, run_metadata)?;
+        Ok(solution_data)
+    }
 
-        // Return solution
-        Ok(Solution {
+    /// Builds the solution of a solver run, taking the provenance recorded so far.
+    fn assemble_solution(&mut self, mut problem: ProblemDefinition, solution_data: solver::SolverSolutionData, mut warnings: Vec<EngineWarning>) -> Solution {
+        warnings.extend(solution_data.warnings);
+        Solution {
             id: problem.id.clone(),
             mesh: problem.mesh.take().unwrap(),
             processed_equations: problem.physics.processed_equations.take(),
             data: solution_data.data,
+            time_steps: solution_data.time_steps,
+            fields: solution_data.fields,
             provenance_chain: self.provenance_chain.drain_records(),
-        })
+            metadata: solution_data.metadata,
+            warnings,
+            timings: std::mem::take(&mut self.stage_timings),
+        }
+    }
+
+    /// Solves a static FEM problem, refining the mesh until the estimated error meets a target.
+    ///
+    /// Each cycle solves on the current mesh and estimates the relative stress
+    /// error with [`solver::fem_solver::FemSolver::estimate_error`]. Once the
+    /// estimate is at most `target_error`, or after `max_cycles` solves, the latest
+    /// solution is returned. Otherwise the elements whose indicator is at least
+    /// half the largest are bisected with [`meshing::refine_tetrahedra`] and the
+    /// next cycle starts. Every cycle records its solver run and an
+    /// `adaptive_cycle` event with the element indicators, and the solution
+    /// metadata lists the `estimated_errors` and `num_elements` of every cycle.
+    /// The solver defaults to the `FemSolver`.
+    pub async fn run_adaptive(&mut self, mut problem: ProblemDefinition, target_error: f64, max_cycles: usize) -> Result<Solution, EngineError> {
+        println!("Received adaptive simulation task: {}", problem.id);
+        if max_cycles == 0 {
+            return Err(EngineError::InvalidInput("Adaptive refinement needs at least one cycle".to_string()));
+        }
+        if !problem.aux_fields.is_empty() {
+            return Err(EngineError::InvalidInput("Adaptive refinement cannot carry nodal aux fields over to refined meshes".to_string()));
+        }
+        let outputs = &problem.solver_settings.outputs;
+        if !outputs.is_empty() && !outputs.contains(&OutputField::Displacement) {
+            return Err(EngineError::InvalidInput("Adaptive refinement needs the displacement output".to_string()));
+        }
+
+        let mut warnings = self.prepare_problem(&mut problem).await?;
+        if problem.solver_settings.solver_name.is_empty() {
+            problem.solver_settings.solver_name = "FemSolver".to_string();
+        }
+        if !matches!(problem.solver_settings.solver_name.as_str(), "FemSolver" | "CachedFemSolver") {
+            return Err(EngineError::InvalidInput(format!(
+                "Adaptive refinement needs a static FEM solver, got {}", problem.solver_settings.solver_name
+            )));
+        }
+
+        let mut estimated_errors = Vec::new();
+        let mut num_elements = Vec::new();
+        loop {
+            let solution_data = self.run_solver(&problem)?;
+            let estimate = solver::fem_solver::FemSolver::new().estimate_error(&problem, &solution_data.data)?;
+            let mesh = problem.mesh.as_ref().unwrap();
+            estimated_errors.push(estimate.relative_error);
+            num_elements.push(mesh.num_elements());
+            let cycle = estimated_errors.len();
+            println!("Adaptive cycle {}: {} elements, estimated error {:e}", cycle, mesh.num_elements(), estimate.relative_error);
+            self.record("adaptive_cycle", &estimate.element_errors, serde_json::json!({
+                "cycle": cycle,
+                "num_elements": mesh.num_elements(),
+                "estimated_error": estimate.relative_error,
+            }))?;
+
+            if estimate.relative_error <= target_error || cycle == max_cycles {
+                if estimate.relative_error > target_error {
+                    warnings.push(EngineWarning::emit(WarningCode::NotConverged, format!(
+                        "Adaptive refinement stopped after {} cycles at estimated error {:e}, above the target {:e}",
+                        cycle, estimate.relative_error, target_error
+                    )));
+                }
+                let mut solution = self.assemble_solution(problem, solution_data, warnings);
+                solution.metadata.insert("estimated_errors".to_string(), serde_json::json!(estimated_errors));
+                solution.metadata.insert("num_elements".to_string(), serde_json::json!(num_elements));
+                return Ok(solution);
+            }
+
+            let largest = estimate.element_errors.iter().cloned().fold(0.0, f64::max);
+            let marked: Vec<usize> = (0..estimate.element_errors.len()).filter(|&e| estimate.element_errors[e] >= 0.5 * largest).collect();
+            problem.mesh = Some(meshing::refine_tetrahedra(mesh, &marked)?);
+        }
+    }
+
+    /// Runs the problems one after another and writes each outcome to `writer`
+    /// as a line of NDJSON, a [`BatchRecord`] per problem in input order.
+    ///
+    /// A line is written and flushed as soon as its problem finishes, so only
+    /// one solution is held in memory and a partial stream stays readable. A
+    /// failing problem becomes an `Error` record and the batch moves on; only a
+    /// failure to write aborts it. Returns how many problems failed.
+    pub async fn run_batch_ndjson<W: std::io::Write>(&mut self, problems: impl IntoIterator<Item = ProblemDefinition>, mut writer: W) -> Result<usize, EngineError> {
+        let mut failures = 0;
+        for problem in problems {
+            let id = problem.id.clone();
+            let record = match self.run_simulation(problem).await {
+                Ok(solution) => BatchRecord::Solution(Box::new(solution)),
+                Err(e) => {
+                    println!("Warning: batch problem {} failed: {}", id, e);
+                    // The failed run's records must not leak into the next solution.
+                    self.provenance_chain.drain_records();
+                    failures += 1;
+                    BatchRecord::Error { id, message: e.to_string() }
+                }
+            };
+            serde_json::to_writer(&mut writer, &record)
+                .map_err(|e| EngineError::ExportFailed(format!("Failed to write batch record: {}", e)))?;
+            writer.write_all(b"\n")
+                .and_then(|_| writer.flush())
+                .map_err(|e| EngineError::ExportFailed(format!("Failed to write batch record: {}", e)))?;
+        }
+        Ok(failures)
+    }
+
+    /// Runs a simulation to completion on a private single-threaded runtime.
+    ///
+    /// This is a convenience for command-line tools and scripts that have no
+    /// executor of their own. It must not be called from within an async context;
+    /// use [`CoreEngine::run_simulation`] there instead.
+    pub fn run_simulation_blocking(&mut self, problem: ProblemDefinition) -> Result<Solution, EngineError> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| EngineError::RuntimeFailed(e.to_string()))?;
+        runtime.block_on(self.run_simulation(problem))
     }
 
     /// Generates a mesh from a given geometry definition.
+    ///
+    /// Identical geometry and meshing options reuse the cached mesh of an earlier call.
     pub fn generate_mesh(&mut self, geo_def: &GeometryDefinition) -> Result<Mesh, EngineError> {
-        meshing::generate_mesh_from_geo(geo_def)
+        let callback = self.progress_callback.clone();
+        let progress = callback.map(|callback| move |fraction| callback("meshing", fraction));
+        self.mesh_cache.generate_with_progress(geo_def, &self.meshing_config, progress.as_ref().map(|p| p as &(dyn Fn(f64) + Sync)))
+    }
+
+    /// Runs only the meshing stage, returning the mesh with its provenance.
+    ///
+    /// Records the geometry as a `geometry_definition` event and the mesh as a
+    /// `mesh_generation` event linked to it, as [`CoreEngine::run_simulation`]
+    /// would. The records are taken out of the engine, so they do not leak into
+    /// the next solution, and are empty while provenance is disabled.
+    pub fn mesh_only(&mut self, geo: &GeometryDefinition) -> Result<(Mesh, Vec<provenance::ProvenanceRecord>), EngineError> {
+        let result = self.record_mesh_only(geo);
+        let records = self.provenance_chain.drain_records();
+        result.map(|mesh| (mesh, records))
+    }
+
+    /// Records the geometry, meshes it and records the mesh, for [`CoreEngine::mesh_only`].
+    fn record_mesh_only(&mut self, geo: &GeometryDefinition) -> Result<Mesh, EngineError> {
+        self.record("geometry_definition", geo, serde_json::json!({"geometry_type": format!("{:?}", geo)}))?;
+        let start = std::time::Instant::now();
+        let mesh = self.generate_mesh(geo)?;
+        self.record("mesh_generation", &mesh, serde_json::json!({
+            "geometry_type": format!("{:?}", geo),
+            "num_nodes": mesh.num_nodes(),
+            "num_elements": mesh.num_elements(),
+            "duration_secs": start.elapsed().as_secs_f64(),
+        }))?;
+        Ok(mesh)
     }
 
     /// Processes physics equations using the symbolic engine.
     pub async fn process_equations(&mut self, equations: &[String]) -> Result<symbolic::ProcessedEquations, EngineError> {
-        symbolic::process_equations_with_sympy(equations)
+        symbolic::process_equations_with_sympy_config(equations, &self.sandbox_config)
             .await
             .map_err(|e| EngineError::SymbolicFailed(e.to_string()))
     }
@@ -156,21 +839,103 @@ pub struct ProblemDefinition {
     pub geometry: GeometryDefinition,
     pub physics: PhysicsDefinition,
     pub solver_settings: SolverSettings,
+    /// A prebuilt mesh to solve on. When set, mesh generation is skipped.
     pub mesh: Option<Mesh>,
+    /// Named nodal fields supplied by earlier stages, e.g. `"temperature"` from a thermal solve.
+    #[serde(default)]
+    pub aux_fields: std::collections::HashMap<String, Vec<f64>>,
+}
+
+impl ProblemDefinition {
+    /// Reads a problem from a JSON file.
+    ///
+    /// Boundary conditions in the file named by `physics.boundary_conditions_file`,
+    /// relative to the problem file, are appended to the inline ones and the
+    /// reference is cleared. When the problem carries a mesh, every loaded
+    /// condition must name one of its regions; point loads are exempt.
+    pub fn from_json_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self, EngineError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| EngineError::InvalidInput(format!("Failed to read problem file {}: {}", path.display(), e)))?;
+        let mut problem: ProblemDefinition = serde_json::from_str(&text)
+            .map_err(|e| EngineError::InvalidInput(format!("Failed to parse problem file {}: {}", path.display(), e)))?;
+
+        if let Some(bc_file) = problem.physics.boundary_conditions_file.take() {
+            let bc_path = path.parent().map_or_else(|| bc_file.clone(), |dir| dir.join(&bc_file));
+            let loaded = BoundaryCondition::load_many(&bc_path)?;
+            if let Some(mesh) = &problem.mesh {
+                let unknown: Vec<&str> = loaded
+                    .iter()
+                    .filter(|bc| !matches!(bc.value, BcValue::PointLoad { .. }) && !mesh.boundary_regions.contains_key(&bc.region))
+                    .map(|bc| bc.region.as_str())
+                    .collect();
+                if !unknown.is_empty() {
+                    return Err(EngineError::InvalidInput(format!(
+                        "Boundary conditions in {} name regions the mesh does not have: {:?}", bc_path.display(), unknown
+                    )));
+                }
+            }
+            problem.physics.boundary_conditions.extend(loaded);
+        }
+        Ok(problem)
+    }
 }
 
 /// Defines the geometry for the simulation.
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub enum GeometryDefinition {
     File(String), // Path to a CAD file (e.g., STEP, IGES)
     Primitive(GeometricPrimitive), // A basic, built-in shape
 }
 
 /// Describes a simple geometric primitive.
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct GeometricPrimitive {
     pub shape: String, // e.g., "cube", "sphere"
+    /// `[lx, ly, lz]` for a `"cube"`; `[x1, y1, ..., xn, yn, height]` for an
+    /// `"extruded_polygon"`, whose profile in the xy plane is extruded along z.
     pub dimensions: Vec<f64>,
+    /// Placement applied to the generated mesh nodes.
+    #[serde(default)]
+    pub transform: Option<Transform>,
+}
+
+/// An affine placement: scale, then rotate, then translate.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Transform {
+    /// Offset added last.
+    #[serde(default)]
+    pub translation: [f64; 3],
+    /// Rotation angles in radians about the x, y and z axes, applied in that order.
+    #[serde(default)]
+    pub rotation: [f64; 3],
+    /// Per-axis scale factors, applied first.
+    #[serde(default = "Transform::unit_scale")]
+    pub scale: [f64; 3],
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform {
+            translation: [0.0; 3],
+            rotation: [0.0; 3],
+            scale: Self::unit_scale(),
+        }
+    }
+}
+
+impl Transform {
+    fn unit_scale() -> [f64; 3] {
+        [1.0; 3]
+    }
+
+    /// Maps a point through the transform.
+    pub fn apply(&self, point: [f64; 3]) -> [f64; 3] {
+        let scaled = nalgebra::Vector3::new(point[0] * self.scale[0], point[1] * self.scale[1], point[2] * self.scale[2]);
+        let rotation = nalgebra::Rotation3::from_euler_angles(self.rotation[0], self.rotation[1], self.rotation[2]);
+        let moved = rotation * scaled;
+        [moved[0] + self.translation[0], moved[1] + self.translation[1], moved[2] + self.translation[2]]
+    }
 }
 
 /// Contains the physical equations and boundary conditions.
@@ -178,72 +943,1391 @@ pub struct GeometricPrimitive {
 pub struct PhysicsDefinition {
     pub equations: Vec<String>, // e.g., "div(grad(T)) = 0"
     pub boundary_conditions: Vec<BoundaryCondition>,
+    /// Temperature conditions for the thermal stage of [`CoreEngine::run_coupled`].
+    #[serde(default)]
+    pub thermal_boundary_conditions: Vec<BoundaryCondition>,
+    /// A JSON file with further boundary conditions, merged into
+    /// `boundary_conditions` by [`ProblemDefinition::from_json_file`].
+    #[serde(default)]
+    pub boundary_conditions_file: Option<std::path::PathBuf>,
+    /// Linear constraints between nodal displacements, imposed by the static FEM solvers.
+    #[serde(default)]
+    pub constraints: Vec<MultiPointConstraint>,
     pub material: Material,
     pub processed_equations: Option<symbolic::ProcessedEquations>,
 }
 
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct BoundaryCondition {
     pub region: String, // Name of the geometric region
-    pub condition_type: String, // e.g., "Dirichlet", "Neumann", "Force"
-    pub value: Vec<f64>, // For Dirichlet: [ux, uy, uz], For Force: [fx, fy, fz]
+    pub condition_type: String, // e.g., "Dirichlet", "Neumann", "Force", "PointForce", "Convective", "Symmetry"
+    /// The condition's value, whose [`BcValue`] variant depends on the type.
+    ///
+    /// Elasticity takes a `Vector3` for `Dirichlet` displacements (NaN leaves a
+    /// component free) and `Force`, a `MaskedVector3` for partial `Dirichlet`
+    /// conditions, a `Scalar` axis or `Vector3` normal for `Symmetry` and a
+    /// `PointLoad` for `PointForce`. Heat conduction takes a `Scalar`
+    /// temperature for `Dirichlet` and a `Convective` value for `Convective`.
+    pub value: BcValue,
 }
 
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+/// A linear multi-point constraint `sum(coefficient * u) = value` between
+/// nodal displacement components, such as a rigid connector tying nodes together.
+///
+/// The static FEM solvers impose it with a penalty stiffness, so it holds to
+/// about eight significant digits of the displacements rather than exactly and
+/// needs `Precision::F64`. The harmonic, wave and buckling solvers reject it.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct MultiPointConstraint {
+    pub terms: Vec<ConstraintTerm>,
+    /// The right-hand side, zero when not given.
+    #[serde(default)]
+    pub value: f64,
+}
+
+/// One `coefficient * u` term of a [`MultiPointConstraint`].
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ConstraintTerm {
+    /// Location of the node, which snaps to the nearest mesh node like a point load.
+    pub position: [f64; 3],
+    /// The displacement component: 0 = x, 1 = y, 2 = z.
+    pub component: usize,
+    pub coefficient: f64,
+}
+
+impl MultiPointConstraint {
+    /// Makes component `component` of the node at `follower` move with the one at `leader`.
+    pub fn tie(follower: [f64; 3], leader: [f64; 3], component: usize) -> Self {
+        MultiPointConstraint {
+            terms: vec![
+                ConstraintTerm { position: follower, component, coefficient: 1.0 },
+                ConstraintTerm { position: leader, component, coefficient: -1.0 },
+            ],
+            value: 0.0,
+        }
+    }
+}
+
+impl BoundaryCondition {
+    /// Reads a JSON array of boundary conditions from a file.
+    pub fn load_many<P: AsRef<std::path::Path>>(path: P) -> Result<Vec<BoundaryCondition>, EngineError> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path)
+            .map_err(|e| EngineError::InvalidInput(format!("Failed to open boundary condition file {}: {}", path.display(), e)))?;
+        serde_json::from_reader(std::io::BufReader::new(file))
+            .map_err(|e| EngineError::InvalidInput(format!("Failed to parse boundary condition file {}: {}", path.display(), e)))
+    }
+}
+
+/// The typed value carried by a boundary condition.
+///
+/// For compatibility with the original `Vec<f64>` format, a JSON array
+/// deserializes by length: one entry is a `Scalar`, two are `[h, t_inf]` for
+/// `Convective` and three are a `Vector3`. A bare number and objects with `h`
+/// and `t_inf`, `value` and `constrained`, or `position` and `force` keys are
+/// also accepted; the last is the only form of a `PointLoad`, so no array
+/// takes a new meaning.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(untagged)]
+pub enum BcValue {
+    Scalar(f64),
+    Vector3([f64; 3]),
+    Convective { h: f64, t_inf: f64 },
+    /// A concentrated force applied at the mesh node nearest to `position`.
+    PointLoad { position: [f64; 3], force: [f64; 3] },
+    /// Prescribes only the components of `value` whose `constrained` flag is set.
+    MaskedVector3 { value: [f64; 3], constrained: [bool; 3] },
+}
+
+impl<'de> serde::Deserialize<'de> for BcValue {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum RawBcValue {
+            Number(f64),
+            List(Vec<f64>),
+            Convective { h: f64, t_inf: f64 },
+            PointLoad { position: [f64; 3], force: [f64; 3] },
+            MaskedVector3 { value: [f64; 3], constrained: [bool; 3] },
+        }
+
+        match RawBcValue::deserialize(deserializer)? {
+            RawBcValue::Number(v) => Ok(BcValue::Scalar(v)),
+            RawBcValue::Convective { h, t_inf } => Ok(BcValue::Convective { h, t_inf }),
+            RawBcValue::PointLoad { position, force } => Ok(BcValue::PointLoad { position, force }),
+            RawBcValue::MaskedVector3 { value, constrained } => Ok(BcValue::MaskedVector3 { value, constrained }),
+            RawBcValue::List(list) => match list.as_slice() {
+                [v] => Ok(BcValue::Scalar(*v)),
+                [h, t_inf] => Ok(BcValue::Convective { h: *h, t_inf: *t_inf }),
+                [x, y, z] => Ok(BcValue::Vector3([*x, *y, *z])),
+                _ => Err(serde::de::Error::custom(format!(
+                    "boundary condition value must have 1, 2 or 3 entries, got {}; point loads are written as {{\"position\": [x, y, z], \"force\": [fx, fy, fz]}}",
+                    list.len()
+                ))),
+            },
+        }
+    }
+}
+
+/// A linear elastic material.
+///
+/// In JSON a material is an object of its properties, or the name of a
+/// [`Material::named`] library entry such as `"steel_304"`. An object with a
+/// `"name"` starts from that entry and overrides the properties it lists.
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct Material {
-    pub youngs_modulus: f64,
-    pub poissons_ratio: f64,
+    pub youngs_modulus: MaterialProperty,
+    pub poissons_ratio: MaterialProperty,
+    /// Mass density, needed only by analyses that assemble a mass matrix.
+    pub density: Option<MaterialProperty>,
+    /// Linear coefficient of thermal expansion. With a temperature field, the
+    /// FEM solver adds the thermal strain `alpha * (T - reference_temperature)`.
+    pub thermal_expansion: Option<MaterialProperty>,
+    /// Temperature at which the material is free of thermal strain.
+    pub reference_temperature: f64,
+    /// Conductivity of heat conduction analyses, isotropic and unit when not given.
+    pub thermal_conductivity: Option<ThermalConductivity>,
+    /// Dynamic viscosity, needed only by fluid analyses.
+    pub viscosity: Option<MaterialProperty>,
+}
+
+/// The built-in material library as `(name, E [Pa], nu, density [kg/m^3],
+/// expansion [1/K], conductivity [W/(m K)])`, room-temperature values.
+const MATERIAL_LIBRARY: [(&str, f64, f64, f64, f64, f64); 5] = [
+    ("structural_steel", 200e9, 0.30, 7850.0, 12.0e-6, 50.0),
+    ("steel_304", 193e9, 0.29, 8000.0, 17.3e-6, 16.2),
+    ("aluminum_6061", 68.9e9, 0.33, 2700.0, 23.6e-6, 167.0),
+    ("titanium_ti6al4v", 113.8e9, 0.342, 4430.0, 8.6e-6, 6.7),
+    ("copper_c110", 115e9, 0.31, 8890.0, 17.0e-6, 388.0),
+];
+
+impl Material {
+    /// Looks up a material of the built-in library by name.
+    ///
+    /// Properties are constant, in SI units, and strain-free at a reference
+    /// temperature of 20 degrees Celsius.
+    pub fn named(name: &str) -> Result<Material, EngineError> {
+        let &(_, youngs_modulus, poissons_ratio, density, expansion, conductivity) = MATERIAL_LIBRARY
+            .iter()
+            .find(|entry| entry.0 == name)
+            .ok_or_else(|| EngineError::InvalidInput(format!(
+                "Unknown material '{}'; the library has {}",
+                name,
+                MATERIAL_LIBRARY.map(|entry| entry.0).join(", ")
+            )))?;
+        Ok(Material {
+            youngs_modulus: MaterialProperty::Constant(youngs_modulus),
+            poissons_ratio: MaterialProperty::Constant(poissons_ratio),
+            density: Some(MaterialProperty::Constant(density)),
+            thermal_expansion: Some(MaterialProperty::Constant(expansion)),
+            reference_temperature: 20.0,
+            thermal_conductivity: Some(ThermalConductivity::Isotropic(conductivity)),
+            viscosity: None,
+        })
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Material {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct MaterialFields {
+            name: Option<String>,
+            youngs_modulus: Option<MaterialProperty>,
+            poissons_ratio: Option<MaterialProperty>,
+            density: Option<MaterialProperty>,
+            thermal_expansion: Option<MaterialProperty>,
+            reference_temperature: Option<f64>,
+            thermal_conductivity: Option<ThermalConductivity>,
+            viscosity: Option<MaterialProperty>,
+        }
+
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum RawMaterial {
+            Name(String),
+            Fields(Box<MaterialFields>),
+        }
+
+        let fields = match RawMaterial::deserialize(deserializer)? {
+            RawMaterial::Name(name) => return Material::named(&name).map_err(serde::de::Error::custom),
+            RawMaterial::Fields(fields) => *fields,
+        };
+        let base = fields.name.as_deref().map(Material::named).transpose().map_err(serde::de::Error::custom)?;
+        let missing = |property: &str| serde::de::Error::custom(format!("material needs a name or a {}", property));
+        let youngs_modulus = match (fields.youngs_modulus, &base) {
+            (Some(value), _) => value,
+            (None, Some(base)) => base.youngs_modulus.clone(),
+            (None, None) => return Err(missing("youngs_modulus")),
+        };
+        let poissons_ratio = match (fields.poissons_ratio, &base) {
+            (Some(value), _) => value,
+            (None, Some(base)) => base.poissons_ratio.clone(),
+            (None, None) => return Err(missing("poissons_ratio")),
+        };
+        Ok(Material {
+            youngs_modulus,
+            poissons_ratio,
+            density: fields.density.or_else(|| base.as_ref().and_then(|b| b.density.clone())),
+            thermal_expansion: fields.thermal_expansion.or_else(|| base.as_ref().and_then(|b| b.thermal_expansion.clone())),
+            reference_temperature: fields.reference_temperature.or(base.as_ref().map(|b| b.reference_temperature)).unwrap_or_default(),
+            thermal_conductivity: fields.thermal_conductivity.or_else(|| base.as_ref().and_then(|b| b.thermal_conductivity.clone())),
+            viscosity: fields.viscosity.or_else(|| base.as_ref().and_then(|b| b.viscosity.clone())),
+        })
+    }
+}
+
+/// A thermal conductivity that is either isotropic or a full tensor.
+///
+/// In JSON an isotropic conductivity is a plain number and a tensor a 3x3
+/// nested array of rows, which must be symmetric and positive definite.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(untagged)]
+pub enum ThermalConductivity {
+    Isotropic(f64),
+    Tensor([[f64; 3]; 3]),
+}
+
+impl ThermalConductivity {
+    /// Returns the conductivity tensor, checking that it is symmetric and positive definite.
+    pub fn tensor(&self) -> Result<nalgebra::Matrix3<f64>, EngineError> {
+        let k = match self {
+            ThermalConductivity::Isotropic(k) => nalgebra::Matrix3::from_diagonal_element(*k),
+            ThermalConductivity::Tensor(rows) => nalgebra::Matrix3::from_fn(|i, j| rows[i][j]),
+        };
+        let scale = k.amax();
+        if !k.iter().all(|v| v.is_finite()) || (k - k.transpose()).amax() > 1e-12 * scale {
+            return Err(EngineError::SolverFailed(format!("Thermal conductivity {:?} must be finite and symmetric", self)));
+        }
+        if k.cholesky().is_none() {
+            return Err(EngineError::SolverFailed(format!("Thermal conductivity {:?} must be positive definite", self)));
+        }
+        Ok(k)
+    }
+}
+
+/// A material property that is either constant or varies with temperature.
+///
+/// In JSON a constant is a plain number and a table is a list of
+/// `[temperature, value]` pairs sorted by temperature.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(untagged)]
+pub enum MaterialProperty {
+    Constant(f64),
+    Table(Vec<[f64; 2]>),
+}
+
+impl MaterialProperty {
+    /// Evaluates the property, interpolating tables linearly at `temperature`.
+    ///
+    /// Temperatures outside the table are clamped to its first or last entry.
+    /// Tables require a temperature; constants ignore it.
+    pub fn value(&self, temperature: Option<f64>) -> Result<f64, EngineError> {
+        let table = match self {
+            MaterialProperty::Constant(value) => return Ok(*value),
+            MaterialProperty::Table(table) => table,
+        };
+
+        let t = temperature.ok_or_else(|| EngineError::SolverFailed("Temperature-dependent material property requires a temperature field".to_string()))?;
+        if table.is_empty() {
+            return Err(EngineError::SolverFailed("Material property table is empty".to_string()));
+        }
+        if table.windows(2).any(|w| w[1][0] <= w[0][0]) {
+            return Err(EngineError::SolverFailed("Material property table must be sorted by strictly increasing temperature".to_string()));
+        }
+
+        let last = table[table.len() - 1];
+        if t <= table[0][0] {
+            return Ok(table[0][1]);
+        }
+        if t >= last[0] {
+            return Ok(last[1]);
+        }
+        let upper = table.iter().position(|entry| entry[0] >= t).unwrap();
+        let ([t0, v0], [t1, v1]) = (table[upper - 1], table[upper]);
+        Ok(v0 + (v1 - v0) * (t - t0) / (t1 - t0))
+    }
+}
+
+impl From<f64> for MaterialProperty {
+    fn from(value: f64) -> Self {
+        MaterialProperty::Constant(value)
+    }
 }
 
 /// Specifies which solver to use and its parameters.
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct SolverSettings {
     pub solver_name: String, // e.g., "FEM_LinearStatic", "PINN_FluidFlow"
     pub tolerance: f64,
     pub max_iterations: u32,
+    /// Worker threads for parallel assembly; `None` uses all available cores.
+    #[serde(default)]
+    pub num_threads: Option<usize>,
+    /// Nodes per axis of a structured grid, e.g. `[nx, ny]`, for grid-based solvers.
+    #[serde(default)]
+    pub grid_dimensions: Option<Vec<usize>>,
+    /// Number of nodes of a 1D grid, as a shorthand for a single grid dimension.
+    #[serde(default)]
+    pub grid_resolution: Option<usize>,
+    /// Mass matrix formulation for dynamic analyses.
+    #[serde(default)]
+    pub mass_matrix: MassMatrixType,
+    /// Reorders the nodes of inverted tetrahedra instead of rejecting the mesh.
+    #[serde(default)]
+    pub flip_inverted_elements: bool,
+    /// How solvers that support both approaches solve their linear systems.
+    #[serde(default)]
+    pub linear_solver: LinearSolver,
+    /// Wall-clock limit for iterative solves. When it runs out, the solver
+    /// returns its latest iterate with `converged: false` in the metadata.
+    #[serde(default)]
+    pub time_budget: Option<std::time::Duration>,
+    /// Marches a transient problem in time instead of solving for the steady state.
+    #[serde(default)]
+    pub time_stepping: Option<TimeStepping>,
+    /// Excitation frequencies and damping of frequency-domain solvers.
+    #[serde(default)]
+    pub harmonic: Option<HarmonicExcitation>,
+    /// Result fields the FEM solvers compute. Empty returns only the displacements.
+    #[serde(default)]
+    pub outputs: Vec<OutputField>,
+    /// Debugging aid: the static FEM solvers write the constrained system they are
+    /// about to solve to `<id>_stiffness.mtx` and `<id>_force.mtx` in this directory.
+    #[serde(default)]
+    pub dump_system: Option<std::path::PathBuf>,
+    /// Lets the static FEM solvers pin rigid body modes the boundary conditions
+    /// leave free with weak springs, instead of failing on a singular system.
+    /// The pinned degrees of freedom are reported as a warning.
+    #[serde(default)]
+    pub auto_stabilize: bool,
+    /// Records every Nth iteration of iterative solves in provenance as a
+    /// `solver_iteration` event carrying only the residual norm.
+    #[serde(default)]
+    pub record_iterations: Option<u32>,
+    /// How many eigenpairs an eigenvalue solver such as the `BucklingSolver`
+    /// extracts. Defaults to one.
+    #[serde(default)]
+    pub num_modes: Option<usize>,
+    /// Makes the FEM solvers reject materials whose Poisson's ratio exceeds
+    /// this limit. Without it, nearly incompressible materials only warn.
+    #[serde(default)]
+    pub max_poissons_ratio: Option<f64>,
+    /// The floating-point type the static FEM solvers assemble and solve the
+    /// system in. Other solvers always use `f64`.
+    #[serde(default)]
+    pub precision: Precision,
+}
+
+impl Default for SolverSettings {
+    /// Settings that leave the solver to be picked by capability, with a
+    /// tolerance of `1e-8`, at most 1000 iterations and every optional setting
+    /// at its serde default.
+    fn default() -> Self {
+        SolverSettings {
+            solver_name: String::new(),
+            tolerance: 1e-8,
+            max_iterations: 1000,
+            num_threads: None,
+            grid_dimensions: None,
+            grid_resolution: None,
+            mass_matrix: MassMatrixType::default(),
+            flip_inverted_elements: false,
+            linear_solver: LinearSolver::default(),
+            time_budget: None,
+            time_stepping: None,
+            harmonic: None,
+            outputs: Vec::new(),
+            dump_system: None,
+            auto_stabilize: false,
+            record_iterations: None,
+            num_modes: None,
+            max_poissons_ratio: None,
+            precision: Precision::default(),
+        }
+    }
+}
+
+/// The excitation of a frequency-domain (harmonic) solve.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct HarmonicExcitation {
+    /// Excitation frequencies in Hz; several frequencies make a sweep.
+    pub frequencies: Vec<f64>,
+    #[serde(default)]
+    pub damping: RayleighDamping,
+}
+
+impl HarmonicExcitation {
+    /// Sweeps `num_points` evenly spaced frequencies from `start` to `end` Hz.
+    pub fn linear_sweep(start: f64, end: f64, num_points: usize, damping: RayleighDamping) -> Self {
+        let step = if num_points > 1 { (end - start) / (num_points - 1) as f64 } else { 0.0 };
+        HarmonicExcitation {
+            frequencies: (0..num_points).map(|i| start + i as f64 * step).collect(),
+            damping,
+        }
+    }
+}
+
+/// Viscous damping proportional to mass and stiffness, `C = alpha M + beta K`.
+///
+/// A mode of angular frequency `omega` gets the damping ratio
+/// `alpha / (2 omega) + beta omega / 2`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Deserialize, serde::Serialize)]
+pub struct RayleighDamping {
+    #[serde(default)]
+    pub alpha: f64,
+    #[serde(default)]
+    pub beta: f64,
+}
+
+/// Time stepping parameters of a transient solve.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct TimeStepping {
+    pub scheme: kernel::integrate::TimeScheme,
+    pub time_step: f64,
+    pub num_steps: usize,
+    /// Uniform initial value of the field, overridden where boundary conditions fix it.
+    pub initial_value: f64,
+}
+
+/// A result field an FEM solve can return.
+///
+/// Displacements are the solution `data`; the others are nodal fields in
+/// `Solution::fields` under the names given below.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum OutputField {
+    /// Three components per node.
+    Displacement,
+    /// `"strain"`: six Voigt components per node, with engineering shear strains.
+    Strain,
+    /// `"stress"`: six Voigt components per node.
+    Stress,
+    /// `"von_mises"`: the equivalent stress, one value per node.
+    VonMises,
+    /// `"reaction_forces"`: three components per node, zero where nothing is constrained.
+    ReactionForces,
+}
+
+/// The method used to solve a linear system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub enum LinearSolver {
+    /// LU factorization, exact up to rounding.
+    #[default]
+    Direct,
+    /// Conjugate gradients, bounded by `max_iterations` and `time_budget`.
+    ConjugateGradient,
+}
+
+/// The floating-point type of an assembled linear system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub enum Precision {
+    /// Double precision.
+    #[default]
+    F64,
+    /// Single precision, which halves the memory of the system matrix at the
+    /// cost of about seven significant digits in the result.
+    F32,
+}
+
+/// How element mass is distributed over the nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub enum MassMatrixType {
+    /// The full matrix from the shape functions, more accurate.
+    #[default]
+    Consistent,
+    /// Row sums of the consistent matrix on the diagonal, cheap to invert.
+    Lumped,
 }
 
 /// Represents a discretized simulation domain (the mesh).
 /// This is a key data structure passed between the meshing engine and solvers.
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct Mesh {
     pub nodes: Vec<[f64; 3]>,
     pub elements: Vec<Vec<usize>>,
     pub element_type: String, // e.g., "Tetrahedron", "Hexahedron"
     pub boundary_regions: std::collections::HashMap<String, Vec<usize>>,
+    /// The geometry the mesh was generated from, if it came from the meshing engine.
+    #[serde(default)]
+    pub source_geometry: Option<GeometryDefinition>,
+    /// The `(min, max)` corners of the node coordinates, computed after meshing.
+    #[serde(default)]
+    pub bounding_box: Option<([f64; 3], [f64; 3])>,
+    /// Defects found in imported CAD geometry, which meshing worked around.
+    #[serde(default)]
+    pub geometry_warnings: Vec<meshing::GeometryWarning>,
+}
+
+impl Mesh {
+    /// Returns the number of nodes in the mesh.
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the number of elements in the mesh.
+    pub fn num_elements(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// Returns the coordinates of node `i`, or `None` if it is out of range.
+    pub fn node(&self, i: usize) -> Option<[f64; 3]> {
+        self.nodes.get(i).copied()
+    }
+
+    /// Iterates over the node coordinates of element `i`.
+    ///
+    /// Yields nothing if the element does not exist, and skips any connectivity
+    /// entry that does not refer to a valid node.
+    pub fn element_nodes(&self, i: usize) -> impl Iterator<Item = [f64; 3]> + '_ {
+        self.elements
+            .get(i)
+            .into_iter()
+            .flatten()
+            .filter_map(|&n| self.node(n))
+    }
+
+    /// Extracts the listed elements and the nodes they touch as a new mesh.
+    ///
+    /// Local nodes keep the relative order of their global indices, and each
+    /// boundary region keeps only its nodes inside the submesh; regions left
+    /// empty are dropped. Repeated indices are taken once, and out-of-range
+    /// elements or elements referring to missing nodes are skipped.
+    pub fn subdomain(&self, element_indices: &[usize]) -> Mesh {
+        let mut seen = std::collections::HashSet::new();
+        let elements: Vec<&Vec<usize>> = element_indices
+            .iter()
+            .filter(|&&e| seen.insert(e))
+            .filter_map(|&e| self.elements.get(e))
+            .filter(|element| element.iter().all(|&n| n < self.nodes.len()))
+            .collect();
+
+        let mut global_nodes: Vec<usize> = elements.iter().flat_map(|element| element.iter().copied()).collect();
+        global_nodes.sort_unstable();
+        global_nodes.dedup();
+        let local: std::collections::HashMap<usize, usize> = global_nodes.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+
+        let boundary_regions = self
+            .boundary_regions
+            .iter()
+            .filter_map(|(name, nodes)| {
+                let kept: Vec<usize> = nodes.iter().filter_map(|n| local.get(n).copied()).collect();
+                (!kept.is_empty()).then(|| (name.clone(), kept))
+            })
+            .collect();
+
+        let mut submesh = Mesh {
+            nodes: global_nodes.iter().map(|&n| self.nodes[n]).collect(),
+            elements: elements.iter().map(|element| element.iter().map(|n| local[n]).collect()).collect(),
+            element_type: self.element_type.clone(),
+            boundary_regions,
+            source_geometry: None,
+            bounding_box: None,
+            geometry_warnings: Vec::new(),
+        };
+        if self.bounding_box.is_some() {
+            submesh.bounding_box = Some(meshing::bounding_box(&submesh));
+        }
+        submesh
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
 
-    #[actix_rt::test]
-    async fn test_e2e_simulation_run_with_dummy_solver() {
-        let mut engine = CoreEngine::new();
-
-        let problem = ProblemDefinition {
-            id: "e2e_test_dummy_01".to_string(),
+    /// Builds a problem on the unit cube, solved on `mesh` when given and on
+    /// the meshed cube otherwise, with every other setting left empty.
+    fn test_problem(mesh: Option<Mesh>, material: Material, boundary_conditions: Vec<BoundaryCondition>) -> ProblemDefinition {
+        ProblemDefinition {
+            id: "test_problem".to_string(),
             geometry: GeometryDefinition::Primitive(GeometricPrimitive {
                 shape: "cube".to_string(),
                 dimensions: vec![1.0, 1.0, 1.0],
+                transform: None,
             }),
             physics: PhysicsDefinition {
-                equations: vec!["2*x=y".to_string()],
-                boundary_conditions: vec![],
-                material: Material {
-                    youngs_modulus: 1.0,
-                    poissons_ratio: 0.0,
-                },
+                equations: vec![],
+                boundary_conditions,
+                thermal_boundary_conditions: vec![],
+                material,
+                boundary_conditions_file: None,
+                constraints: vec![],
                 processed_equations: None,
             },
+            solver_settings: SolverSettings { tolerance: 1e-5, max_iterations: 10, ..Default::default() },
+            mesh,
+            aux_fields: HashMap::new(),
+        }
+    }
+
+    /// Returns an isotropic elastic material without any optional property.
+    fn test_material(youngs_modulus: f64, poissons_ratio: f64) -> Material {
+        Material {
+            youngs_modulus: MaterialProperty::Constant(youngs_modulus),
+            poissons_ratio: MaterialProperty::Constant(poissons_ratio),
+            density: None,
+            thermal_expansion: None,
+            reference_temperature: 0.0,
+            thermal_conductivity: None,
+            viscosity: None,
+        }
+    }
+
+    /// Returns a boundary condition of `condition_type` on `region`.
+    fn bc(region: &str, condition_type: &str, value: BcValue) -> BoundaryCondition {
+        BoundaryCondition { region: region.to_string(), condition_type: condition_type.to_string(), value }
+    }
+
+    #[test]
+    fn test_solution_gzip_round_trip() {
+        let solution = Solution {
+            id: "gzip_test".to_string(),
+            mesh: meshing::structured_cube_mesh(2, 1.0),
+            processed_equations: None,
+            data: vec![0.5; 27],
+            time_steps: vec![],
+            fields: HashMap::new(),
+            provenance_chain: vec![],
+            metadata: serde_json::Map::new(),
+            warnings: Vec::new(),
+            timings: HashMap::new(),
+        };
+
+        let json = serde_json::to_vec(&solution).unwrap();
+        let compressed = solution.to_json_gz().unwrap();
+        assert!(compressed.len() < json.len());
+
+        let restored = Solution::from_json_gz(&compressed).unwrap();
+        assert_eq!(restored.id, solution.id);
+        assert_eq!(restored.mesh.elements, solution.mesh.elements);
+        assert_eq!(restored.data, solution.data);
+    }
+
+    #[test]
+    fn test_sample_interpolates_linear_field() {
+        let mesh = meshing::structured_cube_mesh(2, 1.0);
+        let field = |p: [f64; 3]| [1.0 + 2.0 * p[0] - p[1] + 0.5 * p[2], -3.0 * p[2]];
+        let data = mesh.nodes.iter().flat_map(|&p| field(p)).collect();
+        let solution = Solution {
+            id: "sample".to_string(),
+            mesh,
+            processed_equations: None,
+            data,
+            time_steps: vec![],
+            fields: HashMap::new(),
+            provenance_chain: vec![],
+            metadata: serde_json::Map::new(),
+            warnings: Vec::new(),
+            timings: HashMap::new(),
+        };
+
+        for point in [[0.1, 0.2, 0.3], [0.5, 0.5, 0.5], [0.9, 0.05, 0.77], [1.0, 1.0, 1.0]] {
+            let sampled = solution.sample(point).unwrap();
+            let expected = field(point);
+            assert_eq!(sampled.len(), 2);
+            for (s, e) in sampled.iter().zip(expected) {
+                assert!((s - e).abs() < 1e-12, "At {:?}: {} vs {}", point, s, e);
+            }
+        }
+
+        assert_eq!(solution.sample([1.5, 0.5, 0.5]), None);
+        assert_eq!(solution.sample([0.5, -0.01, 0.5]), None);
+    }
+
+    #[test]
+    fn test_field_statistics_of_linear_temperature() {
+        // T = 100 at x = 0 rising to 150 at x = 1.
+        let mut mesh = meshing::structured_cube_mesh(2, 1.0);
+        meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let data = mesh.nodes.iter().map(|p| 100.0 + 50.0 * p[0]).collect();
+        let mut solution = Solution::from_mesh("linear_temperature".to_string(), mesh, data);
+
+        let stats = solution.field_statistics().unwrap();
+        assert_eq!((stats.min, stats.max), (100.0, 150.0));
+        assert!((stats.mean - 125.0).abs() < 1e-12);
+        assert_eq!(solution.mesh.nodes[stats.min_node][0], 0.0);
+        assert_eq!(solution.mesh.nodes[stats.max_node][0], 1.0);
+
+        let regions = solution.field_statistics_by_region();
+        assert_eq!(regions.len(), 6);
+        let (cold, hot) = (regions["face_x_neg"], regions["face_x_pos"]);
+        assert_eq!((cold.min, cold.max, cold.mean), (100.0, 100.0, 100.0));
+        assert_eq!((hot.min, hot.max, hot.mean), (150.0, 150.0, 150.0));
+        assert_eq!(regions["face_y_neg"].min, 100.0);
+        assert_eq!(regions["face_y_neg"].max, 150.0);
+
+        // Vector fields are summarized by magnitude.
+        solution.data = solution.mesh.nodes.iter().flat_map(|p| [3.0 * p[0], 4.0 * p[0], 0.0]).collect();
+        assert_eq!(solution.field_statistics().unwrap().max, 5.0);
+        solution.data.pop();
+        assert_eq!(solution.field_statistics(), None);
+        assert!(solution.field_statistics_by_region().is_empty());
+    }
+
+    #[test]
+    fn test_mesh_accessors() {
+        let mesh = meshing::structured_cube_mesh(1, 2.0);
+
+        assert_eq!(mesh.num_nodes(), 8);
+        assert_eq!(mesh.num_elements(), 6);
+        assert_eq!(mesh.node(0), Some([0.0, 0.0, 0.0]));
+        assert_eq!(mesh.node(7), Some([2.0, 2.0, 2.0]));
+        assert_eq!(mesh.node(8), None);
+
+        let corners: Vec<[f64; 3]> = mesh.element_nodes(0).collect();
+        assert_eq!(corners.len(), 4);
+        assert_eq!(corners[0], [0.0, 0.0, 0.0]);
+        assert!(corners.contains(&[2.0, 2.0, 2.0]));
+        assert_eq!(mesh.element_nodes(6).count(), 0);
+    }
+
+    #[test]
+    fn test_element_nodes_skips_invalid_connectivity() {
+        let mesh = Mesh {
+            nodes: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0]],
+            elements: vec![vec![0, 1, 5]],
+            element_type: "Triangle".to_string(),
+            boundary_regions: std::collections::HashMap::new(),
+            source_geometry: None,
+            bounding_box: None,
+            geometry_warnings: Vec::new(),
+        };
+        let nodes: Vec<[f64; 3]> = mesh.element_nodes(0).collect();
+        assert_eq!(nodes, vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0]]);
+    }
+
+    #[test]
+    fn test_subdomain_of_half_cube() {
+        let mut mesh = meshing::structured_cube_mesh(2, 1.0);
+        meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let left: Vec<usize> = (0..mesh.num_elements())
+            .filter(|&e| mesh.element_nodes(e).map(|p| p[0]).sum::<f64>() < 2.0)
+            .collect();
+
+        let half = mesh.subdomain(&left);
+        assert_eq!(half.num_elements(), mesh.num_elements() / 2);
+        assert_eq!(half.num_nodes(), 18);
+        for (local, &global) in left.iter().enumerate() {
+            assert!(half.elements[local].iter().all(|&n| n < half.num_nodes()));
+            assert_eq!(half.element_nodes(local).collect::<Vec<_>>(), mesh.element_nodes(global).collect::<Vec<_>>());
+        }
+        assert!(half.nodes.iter().all(|p| p[0] <= 0.5));
+        assert_eq!(half.boundary_regions["face_x_neg"].len(), 9);
+        assert_eq!(half.boundary_regions["face_y_neg"].len(), 6);
+        assert!(!half.boundary_regions.contains_key("face_x_pos"));
+    }
+
+    #[test]
+    fn test_material_property_table_interpolation() {
+        let youngs_modulus = MaterialProperty::Table(vec![[20.0, 200e9], [520.0, 150e9]]);
+
+        assert_eq!(youngs_modulus.value(Some(270.0)).unwrap(), 175e9);
+        assert_eq!(youngs_modulus.value(Some(0.0)).unwrap(), 200e9);
+        assert_eq!(youngs_modulus.value(Some(1000.0)).unwrap(), 150e9);
+        assert!(youngs_modulus.value(None).is_err());
+        assert_eq!(MaterialProperty::Constant(1.0).value(None).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_material_property_deserialization() {
+        let material: Material = serde_json::from_str(
+            r#"{"youngs_modulus": [[20.0, 200e9], [520.0, 150e9]], "poissons_ratio": 0.3}"#
+        ).unwrap();
+        assert!(matches!(material.youngs_modulus, MaterialProperty::Table(ref t) if t.len() == 2));
+        assert_eq!(material.poissons_ratio, MaterialProperty::Constant(0.3));
+        assert_eq!(material.thermal_conductivity, None);
+
+        let conductivity = |json: &str| serde_json::from_str::<ThermalConductivity>(json).unwrap();
+        assert_eq!(conductivity("45.0"), ThermalConductivity::Isotropic(45.0));
+        let layered = conductivity("[[10.0, 0.0, 0.0], [0.0, 10.0, 0.0], [0.0, 0.0, 0.5]]");
+        assert_eq!(layered.tensor().unwrap()[(2, 2)], 0.5);
+        assert!(conductivity("[[1.0, 2.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]").tensor().is_err());
+        assert!(conductivity("[[1.0, 2.0, 0.0], [2.0, 1.0, 0.0], [0.0, 0.0, 1.0]]").tensor().is_err());
+        assert!(ThermalConductivity::Isotropic(0.0).tensor().is_err());
+    }
+
+    #[test]
+    fn test_named_material_resolves_from_library() {
+        let aluminum: Material = serde_json::from_str(r#""aluminum_6061""#).unwrap();
+        assert_eq!(aluminum.youngs_modulus, MaterialProperty::Constant(68.9e9));
+        assert_eq!(aluminum.density, Some(MaterialProperty::Constant(2700.0)));
+
+        // Listed properties override those of the named entry.
+        let hot: Material = serde_json::from_str(r#"{"name": "aluminum_6061", "youngs_modulus": 60e9, "reference_temperature": 25.0}"#).unwrap();
+        assert_eq!(hot.youngs_modulus, MaterialProperty::Constant(60e9));
+        assert_eq!(hot.poissons_ratio, MaterialProperty::Constant(0.33));
+        assert_eq!(hot.reference_temperature, 25.0);
+        assert_eq!(hot.thermal_conductivity, Some(ThermalConductivity::Isotropic(167.0)));
+
+        // Resolved materials serialize with their values and read back unchanged.
+        let round_trip: Material = serde_json::from_value(serde_json::to_value(&aluminum).unwrap()).unwrap();
+        assert_eq!(round_trip.poissons_ratio, aluminum.poissons_ratio);
+
+        let error = serde_json::from_str::<Material>(r#""unobtainium""#).unwrap_err().to_string();
+        assert!(error.contains("Unknown material 'unobtainium'") && error.contains("steel_304"), "{}", error);
+        let error = serde_json::from_str::<Material>(r#"{"poissons_ratio": 0.3}"#).unwrap_err().to_string();
+        assert!(error.contains("youngs_modulus"), "{}", error);
+    }
+
+    #[test]
+    fn test_bc_value_deserialization() {
+        let parse = |json: &str| serde_json::from_str::<BcValue>(json).unwrap();
+
+        assert_eq!(parse("[0.0, 0.0, 0.0]"), BcValue::Vector3([0.0, 0.0, 0.0]));
+        assert_eq!(parse("[100.0]"), BcValue::Scalar(100.0));
+        assert_eq!(parse("25.0"), BcValue::Scalar(25.0));
+        assert_eq!(parse("[10.0, 20.0]"), BcValue::Convective { h: 10.0, t_inf: 20.0 });
+        assert_eq!(parse(r#"{"h": 10.0, "t_inf": 20.0}"#), BcValue::Convective { h: 10.0, t_inf: 20.0 });
+        assert!(serde_json::from_str::<BcValue>("[1.0, 2.0, 3.0, 4.0]").is_err());
+
+        let bc: BoundaryCondition = serde_json::from_str(
+            r#"{"region": "face_x_pos", "condition_type": "Force", "value": [100.0, 0.0, 0.0]}"#
+        ).unwrap();
+        assert_eq!(bc.value, BcValue::Vector3([100.0, 0.0, 0.0]));
+
+        assert_eq!(
+            parse(r#"{"value": [0.0, 0.0, 0.0], "constrained": [false, true, false]}"#),
+            BcValue::MaskedVector3 { value: [0.0; 3], constrained: [false, true, false] }
+        );
+        assert_eq!(
+            parse(r#"{"position": [0.5, 0.5, 1.0], "force": [0.0, 0.0, -10.0]}"#),
+            BcValue::PointLoad { position: [0.5, 0.5, 1.0], force: [0.0, 0.0, -10.0] }
+        );
+        // Point loads have no array form, so six numbers stay as unsupported as before.
+        assert!(serde_json::from_str::<BcValue>("[0.5, 0.5, 1.0, 0.0, 0.0, -10.0]").is_err());
+
+        for value in [
+            BcValue::Scalar(1.5),
+            BcValue::Vector3([1.0, 2.0, 3.0]),
+            BcValue::Convective { h: 5.0, t_inf: 300.0 },
+            BcValue::PointLoad { position: [1.0, 2.0, 3.0], force: [4.0, 5.0, 6.0] },
+            BcValue::MaskedVector3 { value: [0.0, 0.1, 0.0], constrained: [false, true, false] },
+        ] {
+            assert_eq!(parse(&serde_json::to_string(&value).unwrap()), value);
+        }
+    }
+
+    #[test]
+    fn test_boundary_conditions_file_merges_with_inline_ones() {
+        let dir = meshing::test_dir("bc_file");
+        let mut mesh = meshing::structured_cube_mesh(1, 1.0);
+        meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let problem = |bc_file: &str| serde_json::json!({
+            "id": "bc_file",
+            "geometry": {"File": "never_read.step"},
+            "physics": {
+                "equations": [],
+                "boundary_conditions": [{"region": "face_x_neg", "condition_type": "Dirichlet", "value": [0.0, 0.0, 0.0]}],
+                "boundary_conditions_file": bc_file,
+                "material": {"youngs_modulus": 200e9, "poissons_ratio": 0.3},
+                "processed_equations": null,
+            },
+            "solver_settings": {"solver_name": "FemSolver", "tolerance": 1e-5, "max_iterations": 10},
+            "mesh": mesh,
+        });
+        std::fs::write(dir.join("bcs.json"), r#"[
+            {"region": "face_x_pos", "condition_type": "Force", "value": [100.0, 0.0, 0.0]},
+            {"region": "", "condition_type": "PointForce", "value": {"position": [1.0, 1.0, 1.0], "force": [0.0, 0.0, -5.0]}}
+        ]"#).unwrap();
+        std::fs::write(dir.join("typo.json"), r#"[{"region": "face_x_poss", "condition_type": "Force", "value": [1.0, 0.0, 0.0]}]"#).unwrap();
+        std::fs::write(dir.join("problem.json"), problem("bcs.json").to_string()).unwrap();
+        std::fs::write(dir.join("typo_problem.json"), problem("typo.json").to_string()).unwrap();
+
+        let loaded = ProblemDefinition::from_json_file(dir.join("problem.json")).unwrap();
+        let regions: Vec<&str> = loaded.physics.boundary_conditions.iter().map(|bc| bc.region.as_str()).collect();
+        assert_eq!(regions, ["face_x_neg", "face_x_pos", ""]);
+        assert_eq!(loaded.physics.boundary_conditions[1].value, BcValue::Vector3([100.0, 0.0, 0.0]));
+        assert!(loaded.physics.boundary_conditions_file.is_none());
+
+        match ProblemDefinition::from_json_file(dir.join("typo_problem.json")) {
+            Err(EngineError::InvalidInput(msg)) => assert!(msg.contains("face_x_poss"), "Unexpected message: {}", msg),
+            other => panic!("Expected an unknown region error, got {:?}", other),
+        }
+        assert!(BoundaryCondition::load_many(dir.join("missing.json")).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn test_coupled_run_of_heated_constrained_bar() {
+        let dir = meshing::test_dir("coupled_run");
+        let mut engine = CoreEngine::new();
+        engine.set_meshing_config(meshing::MeshingConfig {
+            working_dir: Some(dir.clone()),
+            gmsh_executable: Some(meshing::fake_gmsh(&dir)),
+            ..Default::default()
+        });
+
+        let (alpha, delta_t) = (1.2e-5, 80.0);
+        let material = Material {
+            thermal_expansion: Some(MaterialProperty::Constant(alpha)),
+            reference_temperature: 20.0,
+            ..test_material(200e9, 0.3)
+        };
+        let mut problem = test_problem(None, material, vec![
+            bc("face_x_neg", "Symmetry", BcValue::Scalar(0.0)),
+            bc("face_x_pos", "Symmetry", BcValue::Scalar(0.0)),
+            bc("face_y_neg", "Symmetry", BcValue::Scalar(1.0)),
+            bc("face_z_neg", "Symmetry", BcValue::Scalar(2.0)),
+        ]);
+        problem.physics.thermal_boundary_conditions = vec![
+            bc("face_x_neg", "Dirichlet", BcValue::Scalar(20.0 + delta_t)),
+            bc("face_x_pos", "Dirichlet", BcValue::Scalar(20.0 + delta_t)),
+        ];
+        problem.solver_settings.flip_inverted_elements = true;
+
+        let solution = engine.run_coupled(problem).await.unwrap();
+
+        // The walls carry the thermal stress -E alpha dT over the unit cross-section.
+        let expected = -200e9 * alpha * delta_t;
+        let reaction = solution.metadata["region_reactions"]["face_x_pos"][0].as_f64().unwrap();
+        assert!((reaction - expected).abs() < 1e-6 * expected.abs(), "Reaction {} vs {}", reaction, expected);
+
+        let events: Vec<&str> = solution.provenance_chain.iter().map(|r| r.event_type.as_str()).collect();
+        assert_eq!(events, ["problem_definition", "mesh_generation", "thermal_solver_run", "solver_run"]);
+        assert_eq!(solution.provenance_chain[3].metadata["solver_name"], "FemSolver");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn test_solver_run_record_contains_settings_and_material() {
+        let dir = meshing::test_dir("solver_run_metadata");
+        let mut engine = CoreEngine::new();
+        engine.set_meshing_config(meshing::MeshingConfig {
+            working_dir: Some(dir.clone()),
+            gmsh_executable: Some(meshing::fake_gmsh(&dir)),
+            ..Default::default()
+        });
+
+        let mut problem = test_problem(None, test_material(70e9, 0.33), vec![]);
+        problem.solver_settings = SolverSettings {
+            solver_name: "DummySolver".to_string(),
+            tolerance: 2.5e-7,
+            max_iterations: 321,
+            ..Default::default()
+        };
+
+        let solution = engine.run_simulation(problem).await.unwrap();
+        let record = solution.provenance_chain.iter().rev().find(|r| r.event_type == "solver_run").unwrap();
+        let metadata = &record.metadata;
+        assert_eq!(metadata["solver_name"], "DummySolver");
+        assert_eq!(metadata["solver_settings"]["tolerance"], 2.5e-7);
+        assert_eq!(metadata["solver_settings"]["max_iterations"], 321);
+        assert_eq!(metadata["precision"], "F64");
+        let material: Material = serde_json::from_value(metadata["material"].clone()).unwrap();
+        assert!(matches!(material.youngs_modulus, MaterialProperty::Constant(e) if e == 70e9));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn test_batch_streams_one_ndjson_line_per_problem() {
+        let mut mesh = meshing::structured_cube_mesh(1, 1.0);
+        meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let problem = |id: &str, solver_name: &str| -> ProblemDefinition {
+            serde_json::from_value(serde_json::json!({
+                "id": id,
+                "geometry": {"File": "never_read.step"},
+                "physics": {
+                    "equations": [],
+                    "boundary_conditions": [],
+                    "material": "structural_steel",
+                    "processed_equations": null,
+                },
+                "solver_settings": {"solver_name": solver_name, "tolerance": 1e-5, "max_iterations": 10},
+                "mesh": mesh,
+            })).unwrap()
+        };
+        let problems = vec![problem("first", "DummySolver"), problem("broken", "NoSuchSolver"), problem("last", "DummySolver")];
+
+        let mut engine = CoreEngine::new();
+        let mut output = Vec::new();
+        let failures = engine.run_batch_ndjson(problems, &mut output).await.unwrap();
+        assert_eq!(failures, 1);
+
+        let records: Vec<BatchRecord> = std::str::from_utf8(&output).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 3);
+        assert!(matches!(&records[0], BatchRecord::Solution(solution) if solution.id == "first"));
+        match &records[1] {
+            BatchRecord::Error { id, message } => assert!(id == "broken" && message.contains("NoSuchSolver"), "{}: {}", id, message),
+            other => panic!("Expected an error record, got {:?}", other),
+        }
+        let BatchRecord::Solution(last) = &records[2] else { panic!("Expected a solution, got {:?}", records[2]) };
+        // Each solution carries only its own provenance.
+        assert_eq!(last.provenance_chain.iter().filter(|r| r.event_type == "problem_definition").count(), 1);
+        assert_eq!(last.provenance_chain[0].metadata["problem_id"], "last");
+    }
+
+    #[actix_rt::test]
+    async fn test_solution_reports_stage_timings() {
+        let dir = meshing::test_dir("stage_timings");
+        let mut engine = CoreEngine::new();
+        engine.set_meshing_config(meshing::MeshingConfig {
+            working_dir: Some(dir.clone()),
+            gmsh_executable: Some(meshing::fake_gmsh(&dir)),
+            ..Default::default()
+        });
+
+        let mut problem = test_problem(None, test_material(70e9, 0.33), vec![]);
+        problem.solver_settings.solver_name = "DummySolver".to_string();
+
+        let solution = engine.run_simulation(problem).await.unwrap();
+        assert!(solution.timings["meshing"] > std::time::Duration::ZERO);
+        assert!(solution.timings["solving"] > std::time::Duration::ZERO);
+        // No equations were given, so the symbolic stage never ran.
+        assert!(!solution.timings.contains_key("symbolic"));
+
+        let recorded = |event: &str| solution.provenance_chain.iter().find(|r| r.event_type == event).unwrap().metadata["duration_secs"].as_f64().unwrap();
+        assert_eq!(recorded("mesh_generation"), solution.timings["meshing"].as_secs_f64());
+        assert_eq!(recorded("solver_run"), solution.timings["solving"].as_secs_f64());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn test_transient_run_populates_time_steps() {
+        let dir = meshing::test_dir("transient_fdm");
+        let mut engine = CoreEngine::new();
+        engine.set_meshing_config(meshing::MeshingConfig {
+            working_dir: Some(dir.clone()),
+            gmsh_executable: Some(meshing::fake_gmsh(&dir)),
+            ..Default::default()
+        });
+
+        let mut problem = test_problem(None, test_material(1.0, 0.0), vec![]);
+        problem.solver_settings.solver_name = "FdmSolver".to_string();
+        problem.solver_settings.time_stepping = Some(TimeStepping {
+            scheme: kernel::integrate::TimeScheme::BackwardEuler,
+            time_step: 0.01,
+            num_steps: 5,
+            initial_value: 0.0,
+        });
+
+        let solution = engine.run_simulation(problem).await.unwrap();
+        assert!(solution.is_transient());
+        assert_eq!(solution.time_steps.len(), 6);
+        assert_eq!(solution.time_steps[0].0, 0.0);
+        assert!((solution.time_steps[5].0 - 0.05).abs() < 1e-12);
+        assert_eq!(solution.data, solution.time_steps[5].1);
+        // Heat from the 100 degree left end spreads into the initially cold bar.
+        assert_eq!(solution.time_steps[0].1[1], 0.0);
+        assert!(solution.time_steps[1].1[1] < solution.time_steps[5].1[1]);
+        assert_eq!(solution.steps().len(), 6);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_identical_geometry_is_meshed_once() {
+        let dir = meshing::test_dir("mesh_cache");
+        let count = dir.join("runs");
+        let fake = meshing::fake_gmsh(&dir);
+        let gmsh = meshing::gmsh_script(&dir, &format!("echo run >> '{}'\nexec '{}' \"$@\"\n", count.display(), fake.display()));
+        let runs = || std::fs::read_to_string(&count).map_or(0, |text| text.lines().count());
+        let config = meshing::MeshingConfig { working_dir: Some(dir.clone()), gmsh_executable: Some(gmsh), ..Default::default() };
+        let cube = GeometryDefinition::Primitive(GeometricPrimitive { shape: "cube".to_string(), dimensions: vec![1.0, 1.0, 1.0], transform: None });
+
+        let mut engine = CoreEngine::new();
+        engine.set_meshing_config(config.clone());
+        let first = engine.generate_mesh(&cube).unwrap();
+        let second = engine.generate_mesh(&cube).unwrap();
+        assert_eq!(runs(), 1);
+        assert_eq!(first.nodes, second.nodes);
+        assert_eq!(first.elements, second.elements);
+
+        // Other meshing options are another cache entry.
+        engine.set_meshing_config(meshing::MeshingConfig { optimize: true, ..config.clone() });
+        engine.generate_mesh(&cube).unwrap();
+        assert_eq!(runs(), 2);
+        engine.set_meshing_config(config);
+        engine.generate_mesh(&cube).unwrap();
+        assert_eq!(runs(), 2);
+
+        engine.clear_mesh_cache();
+        engine.generate_mesh(&cube).unwrap();
+        assert_eq!(runs(), 3);
+        engine.set_mesh_cache_capacity(0);
+        engine.generate_mesh(&cube).unwrap();
+        assert_eq!(runs(), 4);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_meshing_progress_is_reported_while_gmsh_runs() {
+        let dir = meshing::test_dir("meshing_progress");
+        let ack = dir.join("ack");
+        let fake = meshing::fake_gmsh(&dir);
+        // Waits for the first callback to acknowledge progress before finishing.
+        let script = format!(
+            "echo 'Info    : Meshing 1D...' >&2\necho 'Info    : [ 50%] Meshing curve 2 (Line)' >&2\n\
+             i=0\nwhile [ ! -e '{ack}' ] && [ $i -lt 100 ]; do sleep 0.05; i=$((i + 1)); done\n\
+             [ -e '{ack}' ] && echo acknowledged > '{ack}.seen'\n\
+             echo 'Info    : Done meshing 1D (Wall 0.001s, CPU 0s)' >&2\necho 'Info    : Meshing 2D...' >&2\n\
+             echo 'Info    : [ 20%] Meshing surface 1 (Plane, Frontal-Delaunay)' >&2\necho 'Info    : [ 10%] Meshing surface 2 (Plane, Frontal-Delaunay)' >&2\n\
+             echo 'Info    : Meshing 3D...' >&2\nexec '{fake}' \"$@\"\n",
+            ack = ack.display(),
+            fake = fake.display()
+        );
+        let gmsh = meshing::gmsh_script(&dir, &script);
+        let updates = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = updates.clone();
+        let ack_path = ack.clone();
+        let mut engine = CoreEngine::new();
+        engine.set_meshing_config(meshing::MeshingConfig { working_dir: Some(dir.clone()), gmsh_executable: Some(gmsh), ..Default::default() });
+        engine.set_progress_callback(Some(std::sync::Arc::new(move |stage: &str, fraction| {
+            recorded.lock().unwrap().push((stage.to_string(), fraction));
+            let _ = std::fs::write(&ack_path, "");
+        })));
+        let cube = GeometryDefinition::Primitive(GeometricPrimitive { shape: "cube".to_string(), dimensions: vec![1.0, 1.0, 1.0], transform: None });
+
+        engine.generate_mesh(&cube).unwrap();
+        assert!(dir.join("ack.seen").exists(), "no progress reported before Gmsh finished");
+        let fractions: Vec<f64> = updates.lock().unwrap().iter().map(|(stage, fraction)| {
+            assert_eq!(stage, "meshing");
+            *fraction
+        }).collect();
+        // The 10% surface line would move backwards and is dropped.
+        let expected = [0.5 / 3.0, 1.0 / 3.0, 1.2 / 3.0, 2.0 / 3.0, 1.0];
+        assert_eq!(fractions.len(), expected.len(), "{:?}", fractions);
+        for (fraction, expected) in fractions.iter().zip(expected) {
+            assert!((fraction - expected).abs() < 1e-12, "{:?}", fractions);
+        }
+
+        // A cached mesh completes at once.
+        updates.lock().unwrap().clear();
+        engine.generate_mesh(&cube).unwrap();
+        assert_eq!(*updates.lock().unwrap(), vec![("meshing".to_string(), 1.0)]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_mesh_only_records_geometry_and_mesh() {
+        let dir = meshing::test_dir("mesh_only");
+        let mut engine = CoreEngine::new();
+        engine.set_meshing_config(meshing::MeshingConfig {
+            working_dir: Some(dir.clone()),
+            gmsh_executable: Some(meshing::fake_gmsh(&dir)),
+            ..Default::default()
+        });
+        let cube = GeometryDefinition::Primitive(GeometricPrimitive { shape: "cube".to_string(), dimensions: vec![1.0, 1.0, 1.0], transform: None });
+
+        let (mesh, records) = engine.mesh_only(&cube).unwrap();
+        assert!(mesh.num_nodes() > 0);
+        let events: Vec<&str> = records.iter().map(|r| r.event_type.as_str()).collect();
+        assert_eq!(events, ["geometry_definition", "mesh_generation"]);
+        assert_eq!(records[0].previous_record_hash, None);
+        assert_eq!(records[1].previous_record_hash, Some(records[0].calculate_record_hash()));
+        assert_eq!(records[1].metadata["num_nodes"], mesh.num_nodes());
+
+        // Nothing is left behind for the next run.
+        let (_, again) = engine.mesh_only(&cube).unwrap();
+        assert_eq!(again.len(), 2);
+        assert_eq!(again[0].previous_record_hash, None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn test_supplied_mesh_skips_gmsh() {
+        let dir = meshing::test_dir("supplied_mesh");
+        let marker = dir.join("gmsh_ran");
+        let gmsh = meshing::gmsh_script(&dir, &format!("touch '{}'\nexit 1\n", marker.display()));
+        let mut engine = CoreEngine::new();
+        engine.set_meshing_config(meshing::MeshingConfig {
+            working_dir: Some(dir.clone()),
+            gmsh_executable: Some(gmsh),
+            ..Default::default()
+        });
+
+        let mut problem = test_problem(Some(meshing::structured_cube_mesh(2, 1.0)), test_material(1.0, 0.0), vec![]);
+        problem.geometry = GeometryDefinition::File("never_read.step".to_string());
+        problem.solver_settings.solver_name = "DummySolver".to_string();
+
+        let solution = engine.run_simulation(problem).await.unwrap();
+        assert!(!marker.exists(), "Gmsh was invoked");
+        assert_eq!(solution.data.len(), 27);
+        assert_eq!(solution.mesh.num_elements(), 48);
+        let events: Vec<&str> = solution.provenance_chain.iter().map(|r| r.event_type.as_str()).collect();
+        assert_eq!(events, vec!["problem_definition", "external_mesh", "solver_run"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn test_elasticity_problem_without_solver_name_selects_fem_solver() {
+        let mut mesh = meshing::structured_cube_mesh(1, 1.0);
+        meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let mut problem = test_problem(Some(mesh), test_material(200e9, 0.3), vec![
+            bc("face_x_neg", "Dirichlet", BcValue::Vector3([0.0, 0.0, 0.0])),
+            bc("face_x_pos", "Force", BcValue::Vector3([100.0, 0.0, 0.0])),
+        ]);
+        let descriptor = solver::PhysicsDescriptor::infer(&problem).unwrap();
+        assert_eq!(descriptor, solver::PhysicsDescriptor { kind: solver::PhysicsKind::LinearElasticity, dimension: 3 });
+
+        let mut engine = CoreEngine::new();
+        let thermal = test_problem(problem.mesh.clone(), test_material(200e9, 0.3), vec![bc("face_x_neg", "Dirichlet", BcValue::Scalar(100.0))]);
+        let descriptor = solver::PhysicsDescriptor::infer(&thermal).unwrap();
+        assert_eq!(descriptor.kind, solver::PhysicsKind::HeatConduction);
+        assert_eq!(engine.solver_manager.select(&descriptor, &thermal).unwrap().name(), "FdmSolver");
+
+        let solution = engine.run_simulation(problem).await.unwrap();
+        let run = solution.provenance_chain.iter().find(|r| r.event_type == "solver_run").unwrap();
+        assert_eq!(run.metadata["solver_name"], "FemSolver");
+        assert_eq!(solution.data.len(), 8 * 3);
+
+        // An explicit name still wins.
+        problem = ProblemDefinition { mesh: Some(solution.mesh), ..thermal };
+        problem.solver_settings.solver_name = "DummySolver".to_string();
+        let named = engine.run_simulation(problem).await.unwrap();
+        assert_eq!(named.provenance_chain.iter().find(|r| r.event_type == "solver_run").unwrap().metadata["solver_name"], "DummySolver");
+    }
+
+    #[actix_rt::test]
+    async fn test_unmatched_region_is_reported_as_warning() {
+        let mut mesh = meshing::structured_cube_mesh(2, 1.0);
+        meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        mesh.geometry_warnings.push(meshing::GeometryWarning {
+            defect: meshing::GeometryDefect::SmallEdges,
+            message: "Found 1 small edge".to_string(),
+        });
+        let mut problem = test_problem(Some(mesh), test_material(200e9, 0.3), vec![
+            bc("face_x_neg", "Dirichlet", BcValue::Vector3([0.0, 0.0, 0.0])),
+            bc("face_x_pos", "Force", BcValue::Vector3([100.0, 0.0, 0.0])),
+            bc("top_face", "Force", BcValue::Vector3([0.0, 0.0, -100.0])),
+        ]);
+        problem.solver_settings.solver_name = "FemSolver".to_string();
+
+        let mut engine = CoreEngine::new();
+        let solution = engine.run_simulation(problem).await.unwrap();
+
+        let codes: Vec<WarningCode> = solution.warnings.iter().map(|w| w.code).collect();
+        assert_eq!(codes, [WarningCode::GeometryDefect, WarningCode::UnmatchedRegion]);
+        assert!(solution.warnings[1].message.contains("top_face"), "{}", solution.warnings[1].message);
+        let run = solution.provenance_chain.last().unwrap();
+        assert_eq!(run.metadata["warnings"][0]["code"], "UnmatchedRegion");
+    }
+
+    #[actix_rt::test]
+    async fn test_adaptive_refinement_concentrates_at_clamped_root() {
+        let mut mesh = meshing::structured_cube_mesh(2, 1.0);
+        meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        // A block clamped on one face and sheared on the opposite one, whose
+        // stresses concentrate along the edges of the clamped face.
+        let problem = test_problem(Some(mesh), test_material(200e9, 0.3), vec![
+            bc("face_x_neg", "Dirichlet", BcValue::Vector3([0.0, 0.0, 0.0])),
+            bc("face_x_pos", "Force", BcValue::Vector3([0.0, 0.0, -1000.0])),
+        ]);
+
+        let mut engine = CoreEngine::new();
+        let solution = engine.run_adaptive(problem, 1e-3, 4).await.unwrap();
+
+        let errors: Vec<f64> = serde_json::from_value(solution.metadata["estimated_errors"].clone()).unwrap();
+        let counts: Vec<usize> = serde_json::from_value(solution.metadata["num_elements"].clone()).unwrap();
+        assert_eq!(errors.len(), 4);
+        assert!(errors.windows(2).all(|w| w[1] < w[0]), "Estimated errors {:?}", errors);
+        assert!(counts.windows(2).all(|w| w[1] > w[0]), "Element counts {:?}", counts);
+        assert_eq!(solution.mesh.num_elements(), counts[3]);
+        assert_eq!(solution.data.len(), solution.mesh.num_nodes() * 3);
+
+        // The uniform starting mesh has half its elements on the clamped side.
+        let mesh = &solution.mesh;
+        let near_root = (0..mesh.num_elements()).filter(|&e| mesh.element_nodes(e).map(|n| n[0]).sum::<f64>() / 4.0 < 0.5).count();
+        assert!(near_root as f64 > 0.6 * mesh.num_elements() as f64, "{} of {} elements near the root", near_root, mesh.num_elements());
+
+        // The target is out of reach in four cycles.
+        assert_eq!(solution.warnings.last().unwrap().code, WarningCode::NotConverged);
+        assert_eq!(solution.provenance_chain.iter().filter(|r| r.event_type == "adaptive_cycle").count(), 4);
+        assert_eq!(solution.provenance_chain.iter().filter(|r| r.event_type == "solver_run").count(), 4);
+    }
+
+    #[actix_rt::test]
+    async fn test_non_finite_solution_is_rejected() {
+        let mut mesh = meshing::structured_cube_mesh(1, 1.0);
+        meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let mut problem = test_problem(Some(mesh), test_material(200e9, 0.3), vec![
+            bc("face_x_neg", "Dirichlet", BcValue::Vector3([0.0, 0.0, 0.0])),
+            // A NaN load, as from a failed upstream computation.
+            bc("face_x_pos", "Force", BcValue::Vector3([f64::NAN, 0.0, 0.0])),
+        ]);
+        problem.solver_settings.solver_name = "FemSolver".to_string();
+
+        let mut engine = CoreEngine::new();
+        match engine.run_simulation(problem).await {
+            Err(EngineError::SolverFailed(msg)) => assert!(msg.contains("FemSolver produced non-finite values") && msg.contains("in data"), "Unexpected message: {}", msg),
+            other => panic!("Expected the NaN solution to be rejected, got {:?}", other.map(|s| s.data)),
+        }
+        assert!(engine.provenance_chain.records_by_event("solver_run").is_empty());
+    }
+
+    #[test]
+    fn test_iterations_are_recorded_when_requested() {
+        let mut mesh = meshing::structured_cube_mesh(2, 1.0);
+        meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let problem = |record_iterations| ProblemDefinition {
             solver_settings: SolverSettings {
-                solver_name: "DummySolver".to_string(),
-                tolerance: 1e-5,
-                max_iterations: 10,
+                solver_name: "FemSolver".to_string(),
+                // Out of reach, so conjugate gradients run all 6 iterations.
+                tolerance: 1e-30,
+                max_iterations: 6,
+                linear_solver: LinearSolver::ConjugateGradient,
+                record_iterations,
+                ..Default::default()
             },
-            mesh: None,
+            ..test_problem(Some(mesh.clone()), test_material(200e9, 0.3), vec![
+                bc("face_x_neg", "Dirichlet", BcValue::Vector3([0.0, 0.0, 0.0])),
+                bc("face_x_pos", "Force", BcValue::Vector3([100.0, 0.0, 0.0])),
+            ])
+        };
+        let mut engine = CoreEngine::new();
+        let mut iteration_records = |record_iterations| {
+            let solution = engine.run_simulation_blocking(problem(record_iterations)).unwrap();
+            solution.provenance_chain.into_iter().filter(|r| r.event_type == "solver_iteration").collect::<Vec<_>>()
         };
 
+        assert!(iteration_records(None).is_empty());
+        let every = iteration_records(Some(1));
+        assert_eq!(every.len(), 6);
+        let residuals: Vec<f64> = every.iter().map(|r| r.metadata["residual_norm"].as_f64().unwrap()).collect();
+        assert!(residuals.iter().all(|r| r.is_finite() && *r > 0.0));
+        let every_other = iteration_records(Some(2));
+        let numbers: Vec<u64> = every_other.iter().map(|r| r.metadata["iteration"].as_u64().unwrap()).collect();
+        assert_eq!(numbers, [2, 4, 6]);
+        assert_eq!(every_other[0].metadata["residual_norm"].as_f64().unwrap(), residuals[1]);
+    }
+
+    #[actix_rt::test]
+    async fn test_disabled_provenance_leaves_chain_empty() {
+        let dir = meshing::test_dir("provenance_disabled");
+        let mut engine = CoreEngine::new();
+        engine.set_meshing_config(meshing::MeshingConfig {
+            working_dir: Some(dir.clone()),
+            gmsh_executable: Some(meshing::fake_gmsh(&dir)),
+            ..Default::default()
+        });
+
+        let problem = || {
+            let mut problem = test_problem(None, test_material(1.0, 0.0), vec![]);
+            problem.solver_settings.solver_name = "DummySolver".to_string();
+            problem
+        };
+
+        let recorded = engine.run_simulation(problem()).await.unwrap();
+        engine.set_provenance_enabled(false);
+        let unrecorded = engine.run_simulation(problem()).await.unwrap();
+
+        assert!(!recorded.provenance_chain.is_empty());
+        assert!(unrecorded.provenance_chain.is_empty());
+        assert_eq!(unrecorded.data, recorded.data);
+        assert_eq!(unrecorded.mesh.nodes, recorded.mesh.nodes);
+        assert_eq!(unrecorded.mesh.elements, recorded.mesh.elements);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn test_e2e_simulation_run_with_dummy_solver() {
+        let mut engine = CoreEngine::new();
+
+        let mut problem = test_problem(None, test_material(1.0, 0.0), vec![]);
+        problem.physics.equations = vec!["2*x=y".to_string()];
+        problem.solver_settings.solver_name = "DummySolver".to_string();
+
         match engine.run_simulation(problem).await {
             Ok(solution) => {
                 println!("Simulation for {} completed successfully.", solution.id);
@@ -256,43 +2340,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_e2e_simulation_run_blocking_with_dummy_solver() {
+        let mut engine = CoreEngine::new();
+
+        let mut problem = test_problem(None, test_material(1.0, 0.0), vec![]);
+        problem.solver_settings.solver_name = "DummySolver".to_string();
+
+        match engine.run_simulation_blocking(problem) {
+            Ok(solution) => {
+                assert_eq!(solution.data.len(), solution.mesh.nodes.len());
+                assert!(!solution.provenance_chain.is_empty());
+            },
+            Err(e) => {
+                panic!("Blocking E2E simulation failed unexpectedly: {}", e);
+            }
+        }
+    }
+
     #[actix_rt::test]
     async fn test_e2e_simulation_run_with_fem_solver() {
         let mut engine = CoreEngine::new();
 
-        let problem = ProblemDefinition {
-            id: "e2e_test_fem_01".to_string(),
-            geometry: GeometryDefinition::Primitive(GeometricPrimitive {
-                shape: "cube".to_string(),
-                dimensions: vec![1.0, 1.0, 1.0],
-            }),
-            physics: PhysicsDefinition {
-                equations: vec![],
-                boundary_conditions: vec![
-                    BoundaryCondition {
-                        region: "face_x_neg".to_string(), // Assuming Gmsh names faces
-                        condition_type: "Dirichlet".to_string(),
-                        value: vec![0.0, 0.0, 0.0],
-                    },
-                    BoundaryCondition {
-                        region: "face_x_pos".to_string(),
-                        condition_type: "Force".to_string(),
-                        value: vec![100.0, 0.0, 0.0],
-                    },
-                ],
-                material: Material {
-                    youngs_modulus: 200e9, // Steel
-                    poissons_ratio: 0.3,
-                },
-                processed_equations: None,
-            },
-            solver_settings: SolverSettings {
-                solver_name: "FemSolver".to_string(),
-                tolerance: 1e-5,
-                max_iterations: 10,
-            },
-            mesh: None,
-        };
+        let mut problem = test_problem(None, test_material(200e9, 0.3), vec![
+            bc("face_x_neg", "Dirichlet", BcValue::Vector3([0.0, 0.0, 0.0])), // Assuming Gmsh names faces
+            bc("face_x_pos", "Force", BcValue::Vector3([100.0, 0.0, 0.0])),
+        ]);
+        problem.solver_settings.solver_name = "FemSolver".to_string();
 
         match engine.run_simulation(problem).await {
             Ok(solution) => {
@@ -314,28 +2388,9 @@ mod tests {
     async fn test_e2e_simulation_run_with_fdm_solver() {
         let mut engine = CoreEngine::new();
 
-        let problem = ProblemDefinition {
-            id: "e2e_test_fdm_01".to_string(),
-            geometry: GeometryDefinition::Primitive(GeometricPrimitive {
-                shape: "cube".to_string(), // Geometry is not directly used by FDM, but required
-                dimensions: vec![1.0, 1.0, 1.0],
-            }),
-            physics: PhysicsDefinition {
-                equations: vec![],
-                boundary_conditions: vec![],
-                material: Material {
-                    youngs_modulus: 1.0,
-                    poissons_ratio: 0.0,
-                },
-                processed_equations: None,
-            },
-            solver_settings: SolverSettings {
-                solver_name: "FdmSolver".to_string(),
-                tolerance: 1e-5,
-                max_iterations: 10,
-            },
-            mesh: None,
-        };
+        // Geometry is not directly used by FDM, but required.
+        let mut problem = test_problem(None, test_material(1.0, 0.0), vec![]);
+        problem.solver_settings.solver_name = "FdmSolver".to_string();
 
         match engine.run_simulation(problem).await {
             Ok(solution) => {
@@ -2,41 +2,473 @@
 
 //! Handles geometry processing and mesh generation by interfacing with Gmsh.
 
-use crate::{GeometryDefinition, Mesh, EngineError, GeometricPrimitive};
+use crate::{GeometryDefinition, Mesh, EngineError, GeometricPrimitive, Transform};
+use std::collections::{HashMap, VecDeque};
 use std::fs;
+use std::io::{BufRead, BufReader, Read};
 use std::env;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+pub use mshio::ElementType;
+
+/// The Gmsh executable used when `MeshingConfig::gmsh_executable` is not set.
+const DEFAULT_GMSH_EXECUTABLE: &str = "/home/pana/gmsh-4.14.0-Linux64-sdk/bin/gmsh";
+
+/// The most bytes kept from each Gmsh output stream, split between its start and end.
+const MAX_CAPTURED_OUTPUT: usize = 64 * 1024;
+
+/// Options controlling how Gmsh is invoked and where its files live.
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct MeshingConfig {
+    /// Directory for the intermediate `.geo`/`.msh` files. Defaults to the system temp dir.
+    pub working_dir: Option<PathBuf>,
+    /// Keeps the intermediate files after meshing, whether it succeeded or not.
+    pub keep_intermediate: bool,
+    /// Path to the Gmsh executable. Defaults to the bundled SDK location.
+    pub gmsh_executable: Option<PathBuf>,
+    /// 3D meshing algorithm. Defaults to Gmsh's own choice.
+    #[serde(default)]
+    pub algorithm: Option<MeshAlgorithm>,
+    /// Runs the Netgen optimizer on the tetrahedra to improve element quality.
+    #[serde(default)]
+    pub optimize: bool,
+    /// Imports CAD files as they are, without Gmsh's OpenCASCADE shape healing.
+    #[serde(default)]
+    pub skip_geometry_healing: bool,
+}
+
+/// The Gmsh 3D meshing algorithms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum MeshAlgorithm {
+    Delaunay,
+    Frontal,
+    Hxt,
+    Mmg3d,
+}
+
+impl MeshAlgorithm {
+    /// The name Gmsh expects after `-algo`.
+    fn gmsh_name(self) -> &'static str {
+        match self {
+            MeshAlgorithm::Delaunay => "del3d",
+            MeshAlgorithm::Frontal => "front3d",
+            MeshAlgorithm::Hxt => "hxt",
+            MeshAlgorithm::Mmg3d => "mmg3d",
+        }
+    }
+}
+
+/// A kind of defect found in imported CAD geometry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum GeometryDefect {
+    /// Edges shorter than the healing tolerance.
+    SmallEdges,
+    /// Spot or strip faces thinner than the healing tolerance.
+    SmallFaces,
+    /// Degenerated edges or faces.
+    DegenerateShapes,
+    /// Gaps between faces that leave a shell open.
+    OpenShell,
+    /// Surface edges shared by other than two faces.
+    NonManifoldEdges,
+    /// Any other warning Gmsh reported while importing or meshing the geometry.
+    Other,
+}
+
+/// A geometry defect and the message describing it.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct GeometryWarning {
+    pub defect: GeometryDefect,
+    pub message: String,
+}
+
+/// File extensions of the CAD formats Gmsh imports through OpenCASCADE.
+const CAD_EXTENSIONS: [&str; 5] = ["step", "stp", "iges", "igs", "brep"];
+
+/// Name of the script that heals a CAD file before meshing it.
+const HEALING_GEO: &str = "temp_heal.geo";
+
+/// Tolerance, in model units, below which healing removes edges and faces.
+const HEALING_TOLERANCE: f64 = 1e-6;
+
+/// Returns whether `path` names a CAD file that Gmsh can heal.
+fn is_cad_file(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| CAD_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Returns the absolute, canonical form of a geometry file path.
+///
+/// A relative path is taken from the current directory, like any other path a
+/// caller passes in. Failing that, it is looked up in the meshing `work_dir`,
+/// where Gmsh used to resolve it. Errors if the file exists in neither place.
+fn resolve_geometry_file(path: &str, work_dir: &Path) -> Result<String, EngineError> {
+    let candidates = [Path::new(path).to_path_buf(), work_dir.join(path)];
+    let resolved = candidates.iter().find_map(|candidate| fs::canonicalize(candidate).ok().filter(|resolved| resolved.is_file())).ok_or_else(|| {
+        let current_dir = env::current_dir().map_or_else(|_| "the current directory".to_string(), |dir| dir.display().to_string());
+        EngineError::MeshingFailed(format!("Geometry file {} not found in {} or {}", path, current_dir, work_dir.display()))
+    })?;
+    resolved
+        .into_os_string()
+        .into_string()
+        .map_err(|resolved| EngineError::MeshingFailed(format!("Geometry file path {} is not valid UTF-8", resolved.to_string_lossy())))
+}
+
+/// Builds a Gmsh script that merges the CAD file at `path` with shape healing enabled.
+fn healing_script(path: &str) -> Result<String, EngineError> {
+    if path.contains('"') {
+        return Err(EngineError::MeshingFailed(format!("CAD file path {} cannot contain quotes", path)));
+    }
+    Ok(format!(
+        "Geometry.Tolerance = {:e};\nGeometry.OCCFixDegenerated = 1;\nGeometry.OCCFixSmallEdges = 1;\nGeometry.OCCFixSmallFaces = 1;\nGeometry.OCCSewFaces = 1;\nGeometry.OCCMakeSolids = 1;\nMerge \"{}\";\n",
+        HEALING_TOLERANCE, path
+    ))
+}
+
+/// Collects the warnings of a Gmsh log, classified by their wording.
+pub fn geometry_warnings_from_log(log: &str) -> Vec<GeometryWarning> {
+    log.lines()
+        .filter_map(|line| line.trim().strip_prefix("Warning"))
+        .map(|rest| rest.trim_start_matches([' ', ':']).trim())
+        .filter(|message| !message.is_empty())
+        .map(|message| {
+            let lower = message.to_lowercase();
+            let defect = if lower.contains("small edge") {
+                GeometryDefect::SmallEdges
+            } else if ["small face", "spot face", "strip face"].iter().any(|k| lower.contains(k)) {
+                GeometryDefect::SmallFaces
+            } else if lower.contains("degenerat") {
+                GeometryDefect::DegenerateShapes
+            } else if ["non-manifold", "non manifold", "nonmanifold"].iter().any(|k| lower.contains(k)) {
+                GeometryDefect::NonManifoldEdges
+            } else if ["free edge", "gap", "not closed", "open shell", "sew"].iter().any(|k| lower.contains(k)) {
+                GeometryDefect::OpenShell
+            } else {
+                GeometryDefect::Other
+            };
+            GeometryWarning { defect, message: message.to_string() }
+        })
+        .collect()
+}
+
+/// Builds the Gmsh meshing options for `config`, excluding input and output paths.
+fn gmsh_mesh_args(config: &MeshingConfig) -> Vec<&'static str> {
+    let mut args = vec!["-3"];
+    if let Some(algorithm) = config.algorithm {
+        args.extend(["-algo", algorithm.gmsh_name()]);
+    }
+    if config.optimize {
+        args.push("-optimize_netgen");
+    }
+    args
+}
 
 /// Generates a mesh from a given geometry definition using the gmsh executable.
 pub fn generate_mesh_from_geo(geo_def: &GeometryDefinition) -> Result<Mesh, EngineError> {
-    let temp_dir = env::temp_dir();
-    let output_msh_path = temp_dir.join("temp.msh");
+    generate_mesh_with_config(geo_def, &MeshingConfig::default())
+}
+
+/// Generates a mesh like [`generate_mesh_from_geo`], using the given meshing options.
+pub fn generate_mesh_with_config(geo_def: &GeometryDefinition, config: &MeshingConfig) -> Result<Mesh, EngineError> {
+    generate_mesh_with_progress(geo_def, config, None)
+}
+
+/// Generates a mesh like [`generate_mesh_with_config`], reporting progress as Gmsh runs.
+///
+/// `progress` receives the completed fraction in `[0, 1]`, parsed from Gmsh's
+/// log as it is written. Fractions only increase and a successful run ends
+/// with exactly `1.0`.
+pub fn generate_mesh_with_progress(
+    geo_def: &GeometryDefinition,
+    config: &MeshingConfig,
+    progress: Option<&(dyn Fn(f64) + Sync)>,
+) -> Result<Mesh, EngineError> {
+    let work_dir = config.working_dir.clone().unwrap_or_else(env::temp_dir);
+    fs::create_dir_all(&work_dir)
+        .map_err(|e| EngineError::MeshingFailed(format!("Failed to create meshing directory {}: {}", work_dir.display(), e)))?;
+    let temp_geo_path = work_dir.join("temp.geo");
+    let output_msh_path = work_dir.join("temp.msh");
+
+    // Gmsh runs in the working directory, so it must get an absolute file path.
+    let resolved;
+    let geo_def = match geo_def {
+        GeometryDefinition::File(path) => {
+            resolved = GeometryDefinition::File(resolve_geometry_file(path, &work_dir)?);
+            &resolved
+        }
+        GeometryDefinition::Primitive(_) => geo_def,
+    };
+    let result = run_gmsh(geo_def, config, progress, &work_dir, &temp_geo_path, &output_msh_path);
+
+    if config.keep_intermediate {
+        println!("Keeping intermediate meshing files in {}", work_dir.display());
+    } else {
+        match geo_def {
+            GeometryDefinition::Primitive(_) => {
+                let _ = fs::remove_file(&temp_geo_path);
+            }
+            GeometryDefinition::File(_) => {
+                let _ = fs::remove_file(work_dir.join(HEALING_GEO));
+            }
+        }
+        let _ = fs::remove_file(&output_msh_path);
+    }
+
+    result
+}
+
+/// The number of meshes a [`MeshCache`] keeps by default.
+pub const DEFAULT_MESH_CACHE_CAPACITY: usize = 8;
+
+/// Generated meshes keyed by a hash of their geometry and meshing options.
+///
+/// Meshing the same input again returns a copy of the stored mesh instead of
+/// running Gmsh. Failed runs are not stored, so a retry meshes afresh. Once
+/// `capacity` meshes are held, the least recently used one is dropped; a
+/// capacity of zero disables caching.
+#[derive(Debug, Clone)]
+pub struct MeshCache {
+    capacity: usize,
+    /// Keys and meshes, least recently used first.
+    entries: Vec<(String, Mesh)>,
+}
+
+impl Default for MeshCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_MESH_CACHE_CAPACITY)
+    }
+}
+
+impl MeshCache {
+    pub fn new(capacity: usize) -> Self {
+        MeshCache { capacity, entries: Vec::new() }
+    }
+
+    /// Returns the number of cached meshes.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Changes the size bound, dropping the least recently used meshes beyond it.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        let excess = self.entries.len().saturating_sub(capacity);
+        self.entries.drain(..excess);
+    }
+
+    /// Drops every cached mesh.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Drops the mesh of one geometry and configuration, returning whether it was cached.
+    pub fn invalidate(&mut self, geo_def: &GeometryDefinition, config: &MeshingConfig) -> Result<bool, EngineError> {
+        let key = mesh_cache_key(geo_def, config)?;
+        let before = self.entries.len();
+        self.entries.retain(|(cached, _)| *cached != key);
+        Ok(self.entries.len() < before)
+    }
+
+    /// Returns the cached mesh for the geometry and configuration, generating and storing it if needed.
+    pub fn generate(&mut self, geo_def: &GeometryDefinition, config: &MeshingConfig) -> Result<Mesh, EngineError> {
+        self.generate_with_progress(geo_def, config, None)
+    }
+
+    /// Like [`MeshCache::generate`], reporting progress as in [`generate_mesh_with_progress`].
+    ///
+    /// A cached mesh reports `1.0` straight away.
+    pub fn generate_with_progress(
+        &mut self,
+        geo_def: &GeometryDefinition,
+        config: &MeshingConfig,
+        progress: Option<&(dyn Fn(f64) + Sync)>,
+    ) -> Result<Mesh, EngineError> {
+        let key = mesh_cache_key(geo_def, config)?;
+        if let Some(position) = self.entries.iter().position(|(cached, _)| *cached == key) {
+            println!("Reusing cached mesh {}", key);
+            if let Some(progress) = progress {
+                progress(1.0);
+            }
+            let entry = self.entries.remove(position);
+            let mesh = entry.1.clone();
+            self.entries.push(entry);
+            return Ok(mesh);
+        }
+
+        let mesh = generate_mesh_with_progress(geo_def, config, progress)?;
+        if self.capacity > 0 {
+            if self.entries.len() >= self.capacity {
+                self.entries.remove(0);
+            }
+            self.entries.push((key, mesh.clone()));
+        }
+        Ok(mesh)
+    }
+}
+
+/// Hashes the geometry and meshing options and, for a geometry file, its contents.
+fn mesh_cache_key(geo_def: &GeometryDefinition, config: &MeshingConfig) -> Result<String, EngineError> {
+    let mut inputs = serde_json::to_vec(&(geo_def, config))
+        .map_err(|e| EngineError::MeshingFailed(format!("Failed to serialize mesh cache key: {}", e)))?;
+    // A file can change under the same name. A missing one fails when meshing.
+    if let GeometryDefinition::File(path) = geo_def {
+        let work_dir = config.working_dir.clone().unwrap_or_else(env::temp_dir);
+        if let Some(file) = resolve_geometry_file(path, &work_dir).ok().and_then(|path| fs::File::open(path).ok()) {
+            inputs.extend(crate::provenance::calculate_hash_streaming(file).map_err(EngineError::MeshingFailed)?.into_bytes());
+        }
+    }
+    crate::provenance::calculate_hash_streaming(inputs.as_slice()).map_err(EngineError::MeshingFailed)
+}
+
+/// Reads `reader` to the end, keeping at most `limit` bytes of it in memory.
+///
+/// The first and last `limit / 2` bytes are kept and the bytes between them
+/// are replaced by a marker giving their count, so a verbose run still shows
+/// how it started and why it ended.
+fn read_bounded(mut reader: impl Read, limit: usize) -> std::io::Result<String> {
+    let head_limit = limit / 2;
+    let tail_limit = limit - head_limit;
+    let mut head = Vec::new();
+    let mut tail = VecDeque::new();
+    let mut skipped = 0usize;
+    let mut buffer = [0u8; 8192];
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let (to_head, rest) = buffer[..read].split_at((head_limit - head.len()).min(read));
+        head.extend_from_slice(to_head);
+        tail.extend(rest);
+        let excess = tail.len().saturating_sub(tail_limit);
+        tail.drain(..excess);
+        skipped += excess;
+    }
+
+    let mut text = String::from_utf8_lossy(&head).into_owned();
+    if skipped > 0 {
+        text.push_str(&format!("\n[... {} bytes of output truncated ...]\n", skipped));
+    }
+    text.push_str(&String::from_utf8_lossy(tail.make_contiguous()));
+    Ok(text)
+}
+
+/// Tracks meshing progress through the lines of a Gmsh log.
+///
+/// Gmsh meshes curves, surfaces and volumes in turn, announcing each with
+/// `Meshing 1D...` to `Meshing 3D...` and numbering entities within a
+/// dimension as `[ 40%]`. Each dimension counts for a third of the run.
+#[derive(Debug, Default)]
+struct GmshProgress {
+    dimension: Option<usize>,
+    reported: f64,
+}
+
+impl GmshProgress {
+    /// Parses one log line, returning the overall fraction if it advanced.
+    fn advance(&mut self, line: &str) -> Option<f64> {
+        let message = line.split_once(':').map_or(line, |(_, message)| message).trim();
+        let dimension_of = |rest: &str| rest.chars().next().and_then(|c| c.to_digit(10)).map(|d| d as usize).filter(|d| (1..=3).contains(d));
+        let fraction = if let Some(dimension) = message.strip_prefix("Meshing ").and_then(dimension_of) {
+            self.dimension = Some(dimension);
+            (dimension - 1) as f64 / 3.0
+        } else if let Some(dimension) = message.strip_prefix("Done meshing ").and_then(dimension_of) {
+            self.dimension = Some(dimension);
+            dimension as f64 / 3.0
+        } else {
+            let percent: f64 = message.strip_prefix('[')?.split_once("%]")?.0.trim().parse().ok()?;
+            let within = (percent / 100.0).clamp(0.0, 1.0);
+            match self.dimension {
+                Some(dimension) => (dimension as f64 - 1.0 + within) / 3.0,
+                None => within,
+            }
+        };
+        // The last step is left to the caller, which reports 1.0 once the mesh is read.
+        let fraction = fraction.min(0.99);
+        (fraction > self.reported).then(|| {
+            self.reported = fraction;
+            fraction
+        })
+    }
+}
+
+/// A reader that passes bytes through while handing each complete line to a callback.
+struct LineObserver<R, F> {
+    inner: R,
+    partial: Vec<u8>,
+    on_line: F,
+}
+
+impl<R, F> LineObserver<R, F> {
+    fn new(inner: R, on_line: F) -> Self {
+        LineObserver { inner, partial: Vec::new(), on_line }
+    }
+}
+
+impl<R: Read, F: FnMut(&str)> Read for LineObserver<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        for &byte in &buf[..read] {
+            if byte == b'\n' {
+                (self.on_line)(String::from_utf8_lossy(&self.partial).trim_end_matches('\r'));
+                self.partial.clear();
+            } else if self.partial.len() < MAX_CAPTURED_OUTPUT {
+                self.partial.push(byte);
+            }
+        }
+        Ok(read)
+    }
+}
+
+/// Writes the input geometry, runs Gmsh on it and parses the resulting mesh.
+fn run_gmsh(
+    geo_def: &GeometryDefinition,
+    config: &MeshingConfig,
+    progress: Option<&(dyn Fn(f64) + Sync)>,
+    work_dir: &Path,
+    temp_geo_path: &Path,
+    output_msh_path: &Path,
+) -> Result<Mesh, EngineError> {
     let output_msh_str = output_msh_path.to_str().ok_or_else(|| EngineError::MeshingFailed("Failed to convert output MSH path to string".to_string()))?;
 
-    let mut command = Command::new("/home/pana/gmsh-4.14.0-Linux64-sdk/bin/gmsh");
+    let gmsh_executable = config.gmsh_executable.as_deref().unwrap_or_else(|| Path::new(DEFAULT_GMSH_EXECUTABLE));
+    let mut command = Command::new(gmsh_executable);
     command.arg("-nopopup").arg("-batch");
-    command.current_dir(&temp_dir); // Set working directory for Gmsh
+    command.current_dir(work_dir); // Set working directory for Gmsh
 
     match geo_def {
+        GeometryDefinition::File(path) if is_cad_file(path) && !config.skip_geometry_healing => {
+            fs::write(work_dir.join(HEALING_GEO), healing_script(path)?)
+                .map_err(|e| EngineError::MeshingFailed(format!("Failed to write geometry healing script: {}", e)))?;
+            command.arg(HEALING_GEO);
+        }
         GeometryDefinition::File(path) => {
             command.arg(path);
         }
         GeometryDefinition::Primitive(primitive) => {
             let geo_content = create_primitive_geometry(primitive)?;
-            let temp_geo_path = temp_dir.join("temp.geo");
             
-            fs::write(&temp_geo_path, geo_content.as_bytes())
+            fs::write(temp_geo_path, geo_content.as_bytes())
                 .map_err(|e| EngineError::MeshingFailed(format!("Failed to write temp GEO file: {}", e)))?;
             
             // Ensure data is synced to disk
-            let file = fs::File::open(&temp_geo_path)
+            let file = fs::File::open(temp_geo_path)
                 .map_err(|e| EngineError::MeshingFailed(format!("Failed to open temp GEO file for sync: {}", e)))?;
             file.sync_all()
                 .map_err(|e| EngineError::MeshingFailed(format!("Failed to sync temp GEO file: {}", e)))?;
 
             println!("Wrote GEO content to: {}", temp_geo_path.display());
             println!("Checking GEO file permissions:");
-            let ls_output = Command::new("ls").arg("-l").arg(&temp_geo_path).output()
+            let ls_output = Command::new("ls").arg("-l").arg(temp_geo_path).output()
                 .map_err(|e| EngineError::MeshingFailed(format!("Failed to run ls command: {}", e)))?;
             println!("ls -l output:\n{}", String::from_utf8_lossy(&ls_output.stdout));
             
@@ -44,29 +476,87 @@ pub fn generate_mesh_from_geo(geo_def: &GeometryDefinition) -> Result<Mesh, Engi
         }
     }
 
-    command.arg("-3").arg("-o").arg(output_msh_str);
+    command.args(gmsh_mesh_args(config)).arg("-o").arg(output_msh_str);
+    if progress.is_some() {
+        // Info-level messages carry the progress lines, whatever the user's Gmsh defaults.
+        command.args(["-v", "5"]);
+    }
 
     println!("Running Gmsh command: {:?}", command);
-    let output = command.output()
+    let mut child = command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()
         .map_err(|e| EngineError::MeshingFailed(format!("Failed to execute Gmsh command: {}", e)))?;
+    // Both pipes are drained concurrently so neither can fill up and stall Gmsh.
+    // Gmsh versions differ in which stream gets the progress lines, so both are watched.
+    let tracker = std::sync::Mutex::new(GmshProgress::default());
+    let on_line = |line: &str| {
+        let Some(progress) = progress else { return };
+        let advanced = tracker.lock().unwrap_or_else(|e| e.into_inner()).advance(line);
+        if let Some(fraction) = advanced {
+            progress(fraction);
+        }
+    };
+    let stderr_pipe = LineObserver::new(child.stderr.take().expect("stderr is piped"), on_line);
+    let stdout_pipe = LineObserver::new(child.stdout.take().expect("stdout is piped"), on_line);
+    let (stdout, stderr) = std::thread::scope(|scope| {
+        let stderr_reader = scope.spawn(|| read_bounded(stderr_pipe, MAX_CAPTURED_OUTPUT));
+        let stdout = read_bounded(stdout_pipe, MAX_CAPTURED_OUTPUT);
+        let stderr = stderr_reader.join().unwrap_or_else(|_| Err(std::io::Error::other("stderr reader panicked")));
+        (stdout, stderr)
+    });
+    let status = child.wait()
+        .map_err(|e| EngineError::MeshingFailed(format!("Failed to wait for Gmsh command: {}", e)))?;
+    let (stdout, stderr) = match (stdout, stderr) {
+        (Ok(stdout), Ok(stderr)) => (stdout, stderr),
+        (Err(e), _) | (_, Err(e)) => return Err(EngineError::MeshingFailed(format!("Failed to read Gmsh output: {}", e))),
+    };
+    let mut geometry_warnings = match geo_def {
+        GeometryDefinition::File(_) => geometry_warnings_from_log(&format!("{}\n{}", stdout, stderr)),
+        GeometryDefinition::Primitive(_) => Vec::new(),
+    };
 
-    if !output.status.success() {
-        return Err(EngineError::MeshingFailed(format!("Gmsh command failed: {}\nStdout: {}\nStderr: {}",
-            output.status,
-            String::from_utf8_lossy(&output.stdout),
-            String::from_utf8_lossy(&output.stderr)
+    if !status.success() {
+        let defects: String = geometry_warnings.iter().map(|w| format!("\n  {:?}: {}", w.defect, w.message)).collect();
+        let defects = if defects.is_empty() { defects } else { format!("\nGeometry defects:{}", defects) };
+        return Err(EngineError::MeshingFailed(format!("Gmsh command failed: {}{}\nStdout: {}\nStderr: {}",
+            status,
+            defects,
+            stdout,
+            stderr
         )));
     }
 
-    let mesh = extract_mesh_data_from_file(output_msh_str)?;
+    let mut mesh = extract_mesh_data_from_file(output_msh_str)?;
+
+    if let GeometryDefinition::File(_) = geo_def {
+        let edges = non_manifold_edges(&mesh);
+        if !edges.is_empty() {
+            geometry_warnings.push(GeometryWarning {
+                defect: GeometryDefect::NonManifoldEdges,
+                message: format!("{} mesh surface edges are not shared by exactly two faces, e.g. nodes {:?}", edges.len(), edges[0]),
+            });
+        }
+        for warning in &geometry_warnings {
+            println!("Geometry warning ({:?}): {}", warning.defect, warning.message);
+        }
+    }
 
-    // Clean up temporary files
-    if let GeometryDefinition::Primitive(_) = geo_def {
-        let temp_geo_path = temp_dir.join("temp.geo");
-        let _ = fs::remove_file(&temp_geo_path);
+    // Primitives carry no physical groups, so name their faces by coordinate.
+    // Faces are detected before any transform so rotated primitives keep them.
+    if let GeometryDefinition::Primitive(primitive) = geo_def {
+        let (min, max) = bounding_box(&mesh);
+        let extent = (0..3).map(|axis| max[axis] - min[axis]).fold(0.0, f64::max);
+        detect_coordinate_regions(&mut mesh, 1e-6 * extent);
+        if let Some(transform) = &primitive.transform {
+            apply_transform(&mut mesh, transform);
+        }
     }
-    let _ = fs::remove_file(&output_msh_path);
 
+    mesh.bounding_box = Some(bounding_box(&mesh));
+    mesh.source_geometry = Some(geo_def.clone());
+    mesh.geometry_warnings = geometry_warnings;
+    if let Some(progress) = progress {
+        progress(1.0);
+    }
     Ok(mesh)
 }
 
@@ -121,41 +611,1967 @@ Volume(1) = {{1}};
                 lx, ly, lx, ly, lz, lx, lz, lx, ly, lz, ly, lz
             ))
         }
+        "extruded_polygon" => {
+            let (vertices, height) = extruded_polygon(&primitive.dimensions)?;
+            let n = vertices.len();
+            let mut geo = String::new();
+            for (i, [x, y]) in vertices.iter().enumerate() {
+                geo.push_str(&format!("Point({}) = {{{}, {}, 0, 1.0}};\n", i + 1, x, y));
+            }
+            for i in 0..n {
+                geo.push_str(&format!("Line({}) = {{{}, {}}};\n", i + 1, i + 1, (i + 1) % n + 1));
+            }
+            let lines: Vec<String> = (1..=n).map(|i| i.to_string()).collect();
+            geo.push_str(&format!("Curve Loop(1) = {{{}}};\nPlane Surface(1) = {{1}};\n", lines.join(", ")));
+            geo.push_str(&format!("Extrude {{0, 0, {}}} {{ Surface{{1}}; }}\n", height));
+            Ok(geo)
+        }
         _ => {
-            return Err(EngineError::MeshingFailed(format!("Unsupported primitive shape: {}", primitive.shape)));
+            Err(EngineError::MeshingFailed(format!("Unsupported primitive shape: {}", primitive.shape)))
+        }
+    }
+}
+
+/// Splits the dimensions `[x1, y1, ..., xn, yn, height]` of an extruded polygon
+/// into its vertices and height, checking that the profile is a simple polygon.
+///
+/// The polygon is closed implicitly from the last vertex back to the first; a
+/// repeated first vertex at the end is accepted and dropped.
+fn extruded_polygon(dimensions: &[f64]) -> Result<(Vec<[f64; 2]>, f64), EngineError> {
+    let invalid = |reason: String| Err(EngineError::MeshingFailed(format!("Extruded polygon {}", reason)));
+    if dimensions.len().is_multiple_of(2) || dimensions.iter().any(|d| !d.is_finite()) {
+        return invalid(format!("requires finite dimensions [x1, y1, ..., xn, yn, height], got {:?}", dimensions));
+    }
+    let (coords, &[height]) = dimensions.split_at(dimensions.len() - 1) else { unreachable!() };
+    let mut vertices: Vec<[f64; 2]> = coords.chunks_exact(2).map(|c| [c[0], c[1]]).collect();
+    if vertices.len() > 1 && vertices.first() == vertices.last() {
+        vertices.pop();
+    }
+    let n = vertices.len();
+    if n < 3 {
+        return invalid(format!("needs at least 3 vertices, got {}", n));
+    }
+    if height <= 0.0 {
+        return invalid(format!("height must be positive, got {}", height));
+    }
+
+    let cross = |o: [f64; 2], a: [f64; 2], b: [f64; 2]| (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0]);
+    let area = 0.5 * (0..n).map(|i| cross([0.0, 0.0], vertices[i], vertices[(i + 1) % n])).sum::<f64>();
+    let extent = vertices.iter().flat_map(|v| v.iter()).fold(0.0f64, |m, c| m.max(c.abs())).max(f64::MIN_POSITIVE);
+    let tol = 1e-12 * extent * extent;
+
+    // Whether point p, collinear with segment ab, lies within its bounding box.
+    let on_segment = |p: [f64; 2], a: [f64; 2], b: [f64; 2]| {
+        (0..2).all(|k| p[k] >= a[k].min(b[k]) - tol && p[k] <= a[k].max(b[k]) + tol)
+    };
+    let segments_touch = |a: [f64; 2], b: [f64; 2], c: [f64; 2], d: [f64; 2]| {
+        let (d1, d2, d3, d4) = (cross(a, b, c), cross(a, b, d), cross(c, d, a), cross(c, d, b));
+        if ((d1 > tol && d2 < -tol) || (d1 < -tol && d2 > tol)) && ((d3 > tol && d4 < -tol) || (d3 < -tol && d4 > tol)) {
+            return true;
+        }
+        (d1.abs() <= tol && on_segment(c, a, b))
+            || (d2.abs() <= tol && on_segment(d, a, b))
+            || (d3.abs() <= tol && on_segment(a, c, d))
+            || (d4.abs() <= tol && on_segment(b, c, d))
+    };
+    for i in 0..n {
+        let (a, b, next) = (vertices[i], vertices[(i + 1) % n], vertices[(i + 2) % n]);
+        if a == b {
+            return invalid(format!("repeats vertex {}", i + 1));
+        }
+        // Adjacent edges only share a vertex, unless the second folds back along the first.
+        let (u, v) = ([b[0] - a[0], b[1] - a[1]], [next[0] - b[0], next[1] - b[1]]);
+        if cross(a, b, next).abs() <= tol && u[0] * v[0] + u[1] * v[1] < 0.0 {
+            return invalid(format!("folds back on itself at vertex {}", (i + 1) % n + 1));
+        }
+        for j in i + 2..n {
+            if (j + 1) % n == i {
+                continue;
+            }
+            if segments_touch(a, b, vertices[j], vertices[(j + 1) % n]) {
+                return invalid(format!("intersects itself: edges {} and {} cross", i + 1, j + 1));
+            }
         }
     }
+    if area.abs() <= tol {
+        return invalid("has zero area".to_string());
+    }
+    Ok((vertices, height))
 }
 
 /// Extracts node and element data from a MSH file into our `Mesh` struct.
+///
+/// Nodes and elements are ordered by their MSH tags rather than by their
+/// position in the file, so the same mesh always yields the same solution
+/// vector layout. Connectivity is translated from node tags to these indices.
+///
+/// ASCII MSH 4.1 files, which Gmsh writes by default, are streamed a line at a
+/// time so huge meshes never sit in memory as raw bytes next to the parsed
+/// mesh. Other versions and binary files are parsed whole by `mshio`.
 fn extract_mesh_data_from_file(file_path: &str) -> Result<Mesh, EngineError> {
     println!("Reading MSH file: {}", file_path);
+    let file = fs::File::open(file_path).map_err(|e| EngineError::MeshingFailed(e.to_string()))?;
+    let mut lines = MshLines::new(BufReader::new(file), file_path);
+    let is_ascii_msh41 = lines.advance()? && lines.line() == "$MeshFormat" && lines.advance()? && {
+        let mut format = lines.line().split_whitespace();
+        format.next() == Some("4.1") && format.next() == Some("0")
+    };
+    let mesh = if is_ascii_msh41 {
+        println!("Streaming ASCII MSH 4.1 sections...");
+        lines.skip_section("MeshFormat")?;
+        stream_msh41_ascii(lines)?
+    } else {
+        drop(lines);
+        parse_msh_with_mshio(file_path)?
+    };
+    println!("Extracted {} nodes and {} elements.", mesh.num_nodes(), mesh.num_elements());
+    validate_mesh(&mesh)?;
+    Ok(mesh)
+}
+
+/// Counts the elements of every MSH element type in the file at `path`.
+///
+/// Mesh import keeps only the 4- and 10-node tetrahedra, so this shows what else the
+/// file holds, such as surface triangles or second-order elements, before
+/// picking a solver. The whole file is parsed with `mshio`.
+pub fn element_type_summary<P: AsRef<Path>>(path: P) -> Result<HashMap<ElementType, usize>, EngineError> {
+    let path = path.as_ref();
+    let msh_bytes = fs::read(path).map_err(|e| EngineError::MeshingFailed(format!("Failed to read {}: {}", path.display(), e)))?;
+    let msh = mshio::parse_msh_bytes(&msh_bytes).map_err(|e| EngineError::MeshingFailed(e.to_string()))?;
+
+    let mut counts = HashMap::new();
+    for block in msh.data.elements.iter().flat_map(|elements| &elements.element_blocks) {
+        *counts.entry(block.element_type).or_insert(0) += block.elements.len();
+    }
+    Ok(counts)
+}
+
+/// Parses a whole MSH file in memory with `mshio`, for the formats that are not streamed.
+fn parse_msh_with_mshio(file_path: &str) -> Result<Mesh, EngineError> {
     let msh_bytes = fs::read(file_path).map_err(|e| EngineError::MeshingFailed(e.to_string()))?;
     println!("Parsing MSH bytes...");
     let msh = mshio::parse_msh_bytes(&msh_bytes).map_err(|e| EngineError::MeshingFailed(e.to_string()))?;
     println!("MSH parsed successfully.");
 
-    let nodes: Vec<[f64; 3]> = msh.data.nodes.unwrap().node_blocks.iter().flat_map(|b| b.nodes.iter()).map(|n| [n.x, n.y, n.z]).collect();
-    println!("Extracted {} nodes.", nodes.len());
+    let msh_nodes = msh.data.nodes.ok_or_else(|| EngineError::MeshingFailed(format!("MSH file {} has no nodes", file_path)))?;
+    let mut tagged_nodes: Vec<(u64, [f64; 3])> = Vec::new();
+    let coordinates = |n: &mshio::mshfile::Node<f64>| [n.x, n.y, n.z];
+    let mut next_tag = msh_nodes.min_node_tag;
+    for block in &msh_nodes.node_blocks {
+        match &block.node_tags {
+            Some(tags) => tagged_nodes.extend(tags.iter().map(|(&tag, &i)| (tag, coordinates(&block.nodes[i])))),
+            // Dense tags run consecutively in file order, so the parser does not keep them.
+            None => tagged_nodes.extend((next_tag..).zip(block.nodes.iter().map(coordinates))),
+        }
+        next_tag += block.nodes.len() as u64;
+    }
+    let (nodes, node_index) = index_nodes(tagged_nodes);
+
+    let msh_elements = msh.data.elements.ok_or_else(|| EngineError::MeshingFailed(format!("MSH file {} has no elements", file_path)))?;
+    let mut tagged_elements: Vec<(u64, Vec<usize>)> = Vec::new();
+    for block in msh_elements.element_blocks.iter().filter(|b| matches!(b.element_type, mshio::ElementType::Tet4 | mshio::ElementType::Tet10)) {
+        for element in &block.elements {
+            tagged_elements.push((element.element_tag, element_connectivity(&node_index, element.element_tag, element.nodes.iter().copied())?));
+        }
+    }
+    mesh_from_tagged_elements(file_path, nodes, tagged_elements)
+}
+
+/// The MSH element type numbers of 4-node and 10-node tetrahedra.
+const MSH_TET4: u64 = 4;
+const MSH_TET10: u64 = 11;
+
+/// Reads the sections of an ASCII MSH 4.1 file following its `$MeshFormat`.
+///
+/// Only `$Nodes` and `$Elements` are interpreted; other sections are skipped.
+/// Beyond the current line, memory holds just the nodes and tetrahedra read so far.
+fn stream_msh41_ascii<R: BufRead>(mut lines: MshLines<R>) -> Result<Mesh, EngineError> {
+    let mut indexed_nodes: Option<IndexedNodes> = None;
+    let mut tagged_elements: Option<Vec<(u64, Vec<usize>)>> = None;
+
+    while lines.advance()? {
+        let Some(section) = lines.line().strip_prefix('$').map(str::to_string) else {
+            return Err(lines.error(format!("expected a section header, found {:?}", lines.line())));
+        };
+        match section.as_str() {
+            "Nodes" => {
+                let header: Vec<u64> = lines.numbers(2)?;
+                let mut tagged_nodes = Vec::with_capacity(header[1] as usize);
+                for _ in 0..header[0] {
+                    let count = lines.numbers::<u64>(4)?[3] as usize;
+                    let first = tagged_nodes.len();
+                    for _ in 0..count {
+                        tagged_nodes.push((lines.numbers::<u64>(1)?[0], [0.0; 3]));
+                    }
+                    // Coordinates follow all the block's tags; parametric nodes add trailing values.
+                    for (_, node) in &mut tagged_nodes[first..] {
+                        let xyz: Vec<f64> = lines.numbers(3)?;
+                        *node = [xyz[0], xyz[1], xyz[2]];
+                    }
+                }
+                indexed_nodes = Some(index_nodes(tagged_nodes));
+            }
+            "Elements" => {
+                let Some((_, node_index)) = &indexed_nodes else {
+                    return Err(lines.error("$Elements section before $Nodes".to_string()));
+                };
+                let header: Vec<u64> = lines.numbers(2)?;
+                let mut tets = Vec::new();
+                for _ in 0..header[0] {
+                    let block: Vec<u64> = lines.numbers(4)?;
+                    let nodes_per_element = match block[2] {
+                        MSH_TET4 => Some(4),
+                        MSH_TET10 => Some(10),
+                        _ => None,
+                    };
+                    for _ in 0..block[3] {
+                        let element: Vec<u64> = lines.numbers(2)?;
+                        let Some(count) = nodes_per_element else {
+                            continue;
+                        };
+                        if element.len() != count + 1 {
+                            return Err(lines.error(format!("tetrahedron {} has {} nodes", element[0], element.len() - 1)));
+                        }
+                        tets.push((element[0], element_connectivity(node_index, element[0], element[1..].iter().copied())?));
+                    }
+                }
+                tagged_elements = Some(tets);
+            }
+            _ => {}
+        }
+        lines.skip_section(&section)?;
+    }
+
+    let (nodes, _) = indexed_nodes.ok_or_else(|| EngineError::MeshingFailed(format!("MSH file {} has no nodes", lines.path)))?;
+    let tagged_elements = tagged_elements.ok_or_else(|| EngineError::MeshingFailed(format!("MSH file {} has no elements", lines.path)))?;
+    mesh_from_tagged_elements(lines.path, nodes, tagged_elements)
+}
+
+/// A line-by-line reader over an MSH file that reports errors with line numbers.
+struct MshLines<'a, R> {
+    reader: R,
+    path: &'a str,
+    line: String,
+    line_number: usize,
+}
+
+impl<'a, R: BufRead> MshLines<'a, R> {
+    fn new(reader: R, path: &'a str) -> Self {
+        MshLines { reader, path, line: String::new(), line_number: 0 }
+    }
+
+    /// Reads the next line, returning `false` at the end of the file.
+    fn advance(&mut self) -> Result<bool, EngineError> {
+        self.line.clear();
+        let read = self.reader.read_line(&mut self.line).map_err(|e| self.error(e.to_string()))?;
+        self.line_number += 1;
+        Ok(read > 0)
+    }
+
+    /// The current line without surrounding whitespace.
+    fn line(&self) -> &str {
+        self.line.trim()
+    }
+
+    /// Reads the next line as at least `min` whitespace-separated numbers.
+    fn numbers<T: std::str::FromStr>(&mut self, min: usize) -> Result<Vec<T>, EngineError> {
+        if !self.advance()? {
+            return Err(self.error("unexpected end of file".to_string()));
+        }
+        let numbers = self.line
+            .split_whitespace()
+            .map(|token| token.parse().map_err(|_| self.error(format!("invalid number {:?}", token))))
+            .collect::<Result<Vec<T>, _>>()?;
+        if numbers.len() < min {
+            return Err(self.error(format!("expected at least {} numbers, found {:?}", min, self.line())));
+        }
+        Ok(numbers)
+    }
 
-    let mut elements = Vec::new();
-    let mut element_type = "Unknown".to_string();
+    /// Skips to just past the `$End<section>` line.
+    fn skip_section(&mut self, section: &str) -> Result<(), EngineError> {
+        let end = format!("$End{}", section);
+        while self.advance()? {
+            if self.line() == end {
+                return Ok(());
+            }
+        }
+        Err(self.error(format!("missing {}", end)))
+    }
 
-    if let Some(element_block) = msh.data.elements.unwrap().element_blocks.iter().find(|b| b.element_type == mshio::ElementType::Tet4) {
-        element_type = "Tetrahedron".to_string();
-        elements = element_block.elements.iter().map(|e| e.nodes.iter().map(|n| *n as usize - 1).collect()).collect(); // Convert to 0-based index
+    fn error(&self, message: String) -> EngineError {
+        EngineError::MeshingFailed(format!("{}:{}: {}", self.path, self.line_number, message))
     }
+}
+
+/// Node coordinates in tag order with the index of every node tag.
+type IndexedNodes = (Vec<[f64; 3]>, HashMap<u64, usize>);
+
+/// Orders nodes by tag, returning their coordinates and the index of every tag.
+fn index_nodes(mut tagged_nodes: Vec<(u64, [f64; 3])>) -> IndexedNodes {
+    tagged_nodes.sort_unstable_by_key(|&(tag, _)| tag);
+    let node_index = tagged_nodes.iter().enumerate().map(|(i, &(tag, _))| (tag, i)).collect();
+    (tagged_nodes.into_iter().map(|(_, node)| node).collect(), node_index)
+}
+
+/// Translates an element's node tags into node indices.
+fn element_connectivity(node_index: &HashMap<u64, usize>, element_tag: u64, node_tags: impl Iterator<Item = u64>) -> Result<Vec<usize>, EngineError> {
+    node_tags
+        .map(|tag| node_index.get(&tag).copied().ok_or_else(|| EngineError::MeshingFailed(format!("Element {} refers to unknown node {}", element_tag, tag))))
+        .collect()
+}
 
+/// Builds a tetrahedral mesh from its nodes and its `(tag, connectivity)` elements, ordered by tag.
+///
+/// Second-order files give a `Tetrahedron10` mesh; a file mixing 4-node and
+/// 10-node tetrahedra is rejected, since solvers expect one element type.
+fn mesh_from_tagged_elements(path: &str, nodes: Vec<[f64; 3]>, mut tagged_elements: Vec<(u64, Vec<usize>)>) -> Result<Mesh, EngineError> {
+    tagged_elements.sort_unstable_by_key(|(tag, _)| *tag);
+    let element_type = match tagged_elements.first().map(|(_, connectivity)| connectivity.len()) {
+        None => "Unknown",
+        Some(4) => "Tetrahedron",
+        Some(_) => "Tetrahedron10",
+    }
+    .to_string();
+    if tagged_elements.windows(2).any(|pair| pair[0].1.len() != pair[1].1.len()) {
+        return Err(EngineError::MeshingFailed(format!("MSH file {} mixes 4-node and 10-node tetrahedra", path)));
+    }
     Ok(Mesh {
         nodes,
-        elements,
+        elements: tagged_elements.into_iter().map(|(_, connectivity)| connectivity).collect(),
         element_type,
         boundary_regions: std::collections::HashMap::new(),
+        source_geometry: None,
+        bounding_box: None,
+        geometry_warnings: Vec::new(),
     })
 }
 
-impl From<i32> for EngineError {
-    fn from(err: i32) -> Self {
-        EngineError::MeshingFailed(format!("Gmsh error code: {}", err))
+/// Rejects meshes with no nodes or no elements, which Gmsh produces for bad geometry.
+pub fn validate_mesh(mesh: &Mesh) -> Result<(), EngineError> {
+    if mesh.nodes.is_empty() {
+        return Err(EngineError::MeshingFailed("mesh has no nodes".to_string()));
+    }
+    if mesh.elements.is_empty() {
+        return Err(EngineError::MeshingFailed("mesh has no elements".to_string()));
+    }
+    Ok(())
+}
+
+/// Computes the axis-aligned bounding box of the mesh nodes as `(min, max)` corners.
+///
+/// An empty mesh yields a degenerate box with both corners at the origin.
+pub fn bounding_box(mesh: &Mesh) -> ([f64; 3], [f64; 3]) {
+    if mesh.nodes.is_empty() {
+        return ([0.0; 3], [0.0; 3]);
+    }
+
+    let mut min = [f64::INFINITY; 3];
+    let mut max = [f64::NEG_INFINITY; 3];
+    for node in &mesh.nodes {
+        for axis in 0..3 {
+            min[axis] = min[axis].min(node[axis]);
+            max[axis] = max[axis].max(node[axis]);
+        }
+    }
+    (min, max)
+}
+
+/// Moves every node through `transform`. Connectivity and regions are unchanged.
+pub fn apply_transform(mesh: &mut Mesh, transform: &Transform) {
+    for node in &mut mesh.nodes {
+        *node = transform.apply(*node);
+    }
+    if mesh.bounding_box.is_some() {
+        mesh.bounding_box = Some(bounding_box(mesh));
+    }
+}
+
+/// Translates all nodes so that the center of the bounding box sits at the origin.
+pub fn recenter(mesh: &mut Mesh) {
+    let (min, max) = bounding_box(mesh);
+    let center = [
+        0.5 * (min[0] + max[0]),
+        0.5 * (min[1] + max[1]),
+        0.5 * (min[2] + max[2]),
+    ];
+    for node in &mut mesh.nodes {
+        for axis in 0..3 {
+            node[axis] -= center[axis];
+        }
+    }
+    if mesh.bounding_box.is_some() {
+        mesh.bounding_box = Some(bounding_box(mesh));
+    }
+}
+
+/// The six axis-aligned bounding-box faces as `(name, axis, is_max_side)`.
+const COORDINATE_FACES: [(&str, usize, bool); 6] = [
+    ("face_x_neg", 0, false),
+    ("face_x_pos", 0, true),
+    ("face_y_neg", 1, false),
+    ("face_y_pos", 1, true),
+    ("face_z_neg", 2, false),
+    ("face_z_pos", 2, true),
+];
+
+/// Classifies nodes lying on the bounding-box faces into `boundary_regions`.
+///
+/// A node belongs to `face_x_neg` when its x coordinate is within `tol` of the
+/// bounding-box minimum, and so on for the other five faces. Edge and corner
+/// nodes belong to every face they touch. Existing regions with the same names
+/// are replaced; other regions are left untouched.
+pub fn detect_coordinate_regions(mesh: &mut Mesh, tol: f64) {
+    let (min, max) = bounding_box(mesh);
+
+    for (name, axis, is_max_side) in COORDINATE_FACES {
+        let target = if is_max_side { max[axis] } else { min[axis] };
+        let region: Vec<usize> = mesh.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| (node[axis] - target).abs() <= tol)
+            .map(|(i, _)| i)
+            .collect();
+        mesh.boundary_regions.insert(name.to_string(), region);
+    }
+}
+
+/// Element quality statistics of a tetrahedral mesh.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeshQuality {
+    /// Worst element aspect ratio.
+    pub min_aspect_ratio: f64,
+    /// Mean element aspect ratio.
+    pub mean_aspect_ratio: f64,
+}
+
+/// Computes the signed volume of a tetrahedron.
+///
+/// The volume is positive when `(n2-n1, n3-n1, n4-n1)` is right-handed, the
+/// orientation Gmsh uses, and negative for an inverted element.
+pub fn signed_tetrahedron_volume(nodes: [[f64; 3]; 4]) -> f64 {
+    let sub = |a: [f64; 3], b: [f64; 3]| [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    let (a, b, c) = (sub(nodes[1], nodes[0]), sub(nodes[2], nodes[0]), sub(nodes[3], nodes[0]));
+    (a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0]) + a[2] * (b[0] * c[1] - b[1] * c[0])) / 6.0
+}
+
+/// Returns the indices of the 4-node elements with negative signed volume.
+pub fn find_inverted_elements(mesh: &Mesh) -> Vec<usize> {
+    (0..mesh.num_elements())
+        .filter(|&e| {
+            <[[f64; 3]; 4]>::try_from(mesh.element_nodes(e).collect::<Vec<_>>())
+                .is_ok_and(|nodes| signed_tetrahedron_volume(nodes) < 0.0)
+        })
+        .collect()
+}
+
+/// Swaps the last two nodes of every inverted tetrahedron and returns how many were flipped.
+pub fn flip_inverted_elements(mesh: &mut Mesh) -> usize {
+    let inverted = find_inverted_elements(mesh);
+    for &e in &inverted {
+        mesh.elements[e].swap(2, 3);
+    }
+    inverted.len()
+}
+
+/// Corner pairs of the six edge nodes of a 10-node tetrahedron, in Gmsh order:
+/// node `4 + i` sits on the edge between the corners in entry `i`.
+pub const TETRAHEDRON10_EDGES: [[usize; 2]; 6] = [[0, 1], [1, 2], [2, 0], [3, 0], [3, 2], [3, 1]];
+
+/// Raises a linear tetrahedral mesh to 10-node tetrahedra by adding a node at
+/// the midpoint of every edge.
+///
+/// Shared edges get a single node. Such a node joins a boundary region when
+/// both edge ends belong to it, which is exact for planar faces.
+pub fn to_quadratic_tetrahedra(mesh: &Mesh) -> Result<Mesh, EngineError> {
+    if mesh.element_type != "Tetrahedron" {
+        return Err(EngineError::MeshingFailed(format!("Only Tetrahedron meshes can be raised to Tetrahedron10, found {}", mesh.element_type)));
+    }
+
+    let mut quadratic = mesh.clone();
+    quadratic.element_type = "Tetrahedron10".to_string();
+    let mut edge_nodes: HashMap<(usize, usize), usize> = HashMap::new();
+    for (e, element) in quadratic.elements.iter_mut().enumerate() {
+        if element.len() != 4 || element.iter().any(|&n| n >= mesh.nodes.len()) {
+            return Err(EngineError::MeshingFailed(format!("Element {} is not a valid 4-node tetrahedron", e)));
+        }
+        for [a, b] in TETRAHEDRON10_EDGES {
+            let (a, b) = (element[a], element[b]);
+            let node = *edge_nodes.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                let (pa, pb) = (mesh.nodes[a], mesh.nodes[b]);
+                quadratic.nodes.push(std::array::from_fn(|axis| 0.5 * (pa[axis] + pb[axis])));
+                quadratic.nodes.len() - 1
+            });
+            element.push(node);
+        }
+    }
+
+    for nodes in quadratic.boundary_regions.values_mut() {
+        let members: std::collections::HashSet<usize> = nodes.iter().copied().collect();
+        let mut midside: Vec<usize> = edge_nodes
+            .iter()
+            .filter(|((a, b), _)| members.contains(a) && members.contains(b))
+            .map(|(_, &node)| node)
+            .collect();
+        midside.sort_unstable();
+        nodes.extend(midside);
+    }
+    Ok(quadratic)
+}
+
+/// Bisection passes after which [`refine_tetrahedra`] gives up on restoring conformity.
+const MAX_REFINEMENT_PASSES: usize = 100;
+
+/// Refines the marked elements of a linear tetrahedral mesh by longest-edge bisection.
+///
+/// Every marked element is split in two at the midpoint of its longest edge.
+/// Neighbours left with a split edge are bisected at their own longest edge in
+/// turn until the mesh is conforming again, so refinement spreads a little
+/// beyond the marked elements. Like [`to_quadratic_tetrahedra`], a midpoint
+/// joins the boundary regions holding both edge ends.
+pub fn refine_tetrahedra(mesh: &Mesh, marked: &[usize]) -> Result<Mesh, EngineError> {
+    if mesh.element_type != "Tetrahedron" {
+        return Err(EngineError::MeshingFailed(format!("Only Tetrahedron meshes can be refined, found {}", mesh.element_type)));
+    }
+    let mut elements = Vec::with_capacity(mesh.num_elements());
+    for (e, element) in mesh.elements.iter().enumerate() {
+        match <[usize; 4]>::try_from(element.as_slice()) {
+            Ok(tet) if tet.iter().all(|&n| n < mesh.nodes.len()) => elements.push(tet),
+            _ => return Err(EngineError::MeshingFailed(format!("Element {} is not a valid 4-node tetrahedron", e))),
+        }
+    }
+    if let Some(&e) = marked.iter().find(|&&e| e >= elements.len()) {
+        return Err(EngineError::MeshingFailed(format!("Cannot refine element {} of a mesh with {} elements", e, elements.len())));
+    }
+
+    let mut refined = mesh.clone();
+    let mut midpoints: HashMap<(usize, usize), usize> = HashMap::new();
+    let mut split = vec![false; elements.len()];
+    marked.iter().for_each(|&e| split[e] = true);
+    for _ in 0..MAX_REFINEMENT_PASSES {
+        let hanging = |tet: &[usize; 4]| TETRAHEDRON10_EDGES.iter().any(|&[a, b]| midpoints.contains_key(&(tet[a].min(tet[b]), tet[a].max(tet[b]))));
+        let to_split: Vec<bool> = elements.iter().zip(&split).map(|(tet, &marked)| marked || hanging(tet)).collect();
+        if !to_split.contains(&true) {
+            refined.elements = elements.iter().map(|tet| tet.to_vec()).collect();
+            add_midpoints_to_regions(&mut refined, &midpoints);
+            return Ok(refined);
+        }
+
+        let mut next = Vec::with_capacity(elements.len() + marked.len());
+        for (tet, split_now) in elements.iter().zip(to_split) {
+            if !split_now {
+                next.push(*tet);
+                continue;
+            }
+            // Ties between equal edges are broken by node index, so that
+            // neighbours sharing an edge agree on which one is longest.
+            let [a, b] = TETRAHEDRON10_EDGES
+                .iter()
+                .map(|&[i, j]| if tet[i] < tet[j] { [i, j] } else { [j, i] })
+                .max_by(|&[i, j], &[k, l]| {
+                    let length = |p: usize, q: usize| (0..3).map(|axis| (refined.nodes[tet[p]][axis] - refined.nodes[tet[q]][axis]).powi(2)).sum::<f64>();
+                    length(i, j).total_cmp(&length(k, l)).then((tet[k], tet[l]).cmp(&(tet[i], tet[j])))
+                })
+                .unwrap();
+            let (na, nb) = (tet[a], tet[b]);
+            let midpoint = *midpoints.entry((na, nb)).or_insert_with(|| {
+                let (pa, pb) = (refined.nodes[na], refined.nodes[nb]);
+                refined.nodes.push(std::array::from_fn(|axis| 0.5 * (pa[axis] + pb[axis])));
+                refined.nodes.len() - 1
+            });
+            // Replacing one end of the edge by its midpoint keeps the orientation.
+            let (mut first, mut second) = (*tet, *tet);
+            first[a] = midpoint;
+            second[b] = midpoint;
+            next.extend([first, second]);
+        }
+        split = vec![false; next.len()];
+        elements = next;
+    }
+    Err(EngineError::MeshingFailed(format!("Refinement did not restore a conforming mesh within {} passes", MAX_REFINEMENT_PASSES)))
+}
+
+/// Adds each edge midpoint to the boundary regions holding both ends of its edge.
+fn add_midpoints_to_regions(mesh: &mut Mesh, midpoints: &HashMap<(usize, usize), usize>) {
+    for nodes in mesh.boundary_regions.values_mut() {
+        let mut members: std::collections::HashSet<usize> = nodes.iter().copied().collect();
+        // Midpoints are numbered in creation order, so ends are always visited first.
+        let mut edges: Vec<(&(usize, usize), &usize)> = midpoints.iter().collect();
+        edges.sort_unstable_by_key(|(_, &node)| node);
+        for (&(a, b), &node) in edges {
+            if members.contains(&a) && members.contains(&b) {
+                members.insert(node);
+                nodes.push(node);
+            }
+        }
+    }
+}
+
+/// Computes the aspect-ratio quality of a tetrahedron, `6 sqrt(2) V / l_rms^3`.
+///
+/// The measure is normalized so that a regular tetrahedron scores 1 and flat
+/// or needle-shaped elements approach 0.
+pub fn tetrahedron_aspect_ratio(nodes: [[f64; 3]; 4]) -> f64 {
+    let sub = |a: [f64; 3], b: [f64; 3]| [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    let volume = signed_tetrahedron_volume(nodes).abs();
+
+    let mut sum_sq = 0.0;
+    for i in 0..4 {
+        for j in i + 1..4 {
+            let e = sub(nodes[i], nodes[j]);
+            sum_sq += e[0] * e[0] + e[1] * e[1] + e[2] * e[2];
+        }
+    }
+    let l_rms = (sum_sq / 6.0).sqrt();
+    if l_rms == 0.0 {
+        return 0.0;
+    }
+    6.0 * std::f64::consts::SQRT_2 * volume / l_rms.powi(3)
+}
+
+/// Summarizes the aspect ratio of every 4-node element in the mesh.
+///
+/// A mesh without tetrahedra reports zero for both statistics.
+pub fn mesh_quality(mesh: &Mesh) -> MeshQuality {
+    let ratios: Vec<f64> = (0..mesh.num_elements())
+        .filter_map(|e| <[[f64; 3]; 4]>::try_from(mesh.element_nodes(e).collect::<Vec<_>>()).ok())
+        .map(tetrahedron_aspect_ratio)
+        .collect();
+    if ratios.is_empty() {
+        return MeshQuality { min_aspect_ratio: 0.0, mean_aspect_ratio: 0.0 };
+    }
+    MeshQuality {
+        min_aspect_ratio: ratios.iter().cloned().fold(f64::INFINITY, f64::min),
+        mean_aspect_ratio: ratios.iter().sum::<f64>() / ratios.len() as f64,
+    }
+}
+
+/// Averages a per-element field onto the nodes for smooth contouring.
+///
+/// `element_values` holds `components` values per element. Each node gets the
+/// mean over its elements weighted by element volume; elements other than
+/// tetrahedra weigh 1, and nodes outside every element get zeros. The result
+/// holds `components` values per node.
+pub fn average_to_nodes(mesh: &Mesh, element_values: &[f64], components: usize) -> Result<Vec<f64>, EngineError> {
+    if element_values.len() != mesh.num_elements() * components {
+        return Err(EngineError::SolverFailed(format!(
+            "Expected {} element values for {} components, got {}",
+            mesh.num_elements() * components,
+            components,
+            element_values.len()
+        )));
+    }
+
+    let mut sums = vec![0.0; mesh.num_nodes() * components];
+    let mut weights = vec![0.0; mesh.num_nodes()];
+    for (e, values) in element_values.chunks_exact(components.max(1)).enumerate() {
+        let element = &mesh.elements[e];
+        if element.iter().any(|&n| n >= mesh.num_nodes()) {
+            return Err(EngineError::SolverFailed(format!("Element {} refers to a missing node", e)));
+        }
+        let weight = match <[[f64; 3]; 4]>::try_from(mesh.element_nodes(e).collect::<Vec<_>>()) {
+            Ok(nodes) => signed_tetrahedron_volume(nodes).abs(),
+            Err(_) => 1.0,
+        };
+        for &n in element {
+            weights[n] += weight;
+            for (sum, value) in sums[n * components..(n + 1) * components].iter_mut().zip(values) {
+                *sum += weight * value;
+            }
+        }
+    }
+    for (node_sums, &weight) in sums.chunks_exact_mut(components.max(1)).zip(&weights) {
+        if weight > 0.0 {
+            node_sums.iter_mut().for_each(|sum| *sum /= weight);
+        }
     }
-}
\ No newline at end of file
+    Ok(sums)
+}
+
+/// Prepended to the region names of the second mesh by [`merge_meshes`] when asked to.
+pub const MERGED_REGION_PREFIX: &str = "b_";
+
+/// Combines two meshes of the same element type into one, e.g. the parts of an assembly.
+///
+/// The nodes and elements of `b` follow those of `a`, with `b`'s node indices
+/// offset accordingly. Regions of `b` are renamed with [`MERGED_REGION_PREFIX`]
+/// when `prefix_b_regions` is set; otherwise a region present in both meshes
+/// holds the nodes of both. Nodes the parts share are kept twice, so the parts
+/// stay disconnected until [`merge_coincident_nodes`] ties them together.
+pub fn merge_meshes(a: &Mesh, b: &Mesh, prefix_b_regions: bool) -> Result<Mesh, EngineError> {
+    if a.element_type != b.element_type {
+        return Err(EngineError::MeshingFailed(format!("Cannot merge a {} mesh with a {} mesh", a.element_type, b.element_type)));
+    }
+
+    let offset = a.nodes.len();
+    let mut merged = Mesh {
+        nodes: a.nodes.iter().chain(&b.nodes).copied().collect(),
+        elements: a.elements.iter().cloned().chain(b.elements.iter().map(|element| element.iter().map(|&n| n + offset).collect())).collect(),
+        element_type: a.element_type.clone(),
+        boundary_regions: a.boundary_regions.clone(),
+        source_geometry: None,
+        bounding_box: None,
+        geometry_warnings: a.geometry_warnings.iter().chain(&b.geometry_warnings).cloned().collect(),
+    };
+    for (name, nodes) in &b.boundary_regions {
+        let name = if prefix_b_regions { format!("{}{}", MERGED_REGION_PREFIX, name) } else { name.clone() };
+        merged.boundary_regions.entry(name).or_default().extend(nodes.iter().map(|&n| n + offset));
+    }
+    if a.bounding_box.is_some() || b.bounding_box.is_some() {
+        merged.bounding_box = Some(bounding_box(&merged));
+    }
+    Ok(merged)
+}
+
+/// Merges nodes lying within `tol` of each other and returns how many were removed.
+///
+/// The first node encountered in each cluster is kept. Element connectivity and
+/// `boundary_regions` are remapped to the surviving nodes, and region lists are
+/// de-duplicated. Elements are not removed even if merging collapses them.
+pub fn merge_coincident_nodes(mesh: &mut Mesh, tol: f64) -> usize {
+    use std::collections::HashMap;
+
+    // Bucket kept nodes on a grid with cell size `tol` so only neighbouring cells
+    // need to be searched. A non-positive tolerance merges exact duplicates only.
+    let cell_of = |p: &[f64; 3]| -> [i64; 3] {
+        if tol > 0.0 {
+            [(p[0] / tol).floor() as i64, (p[1] / tol).floor() as i64, (p[2] / tol).floor() as i64]
+        } else {
+            [p[0].to_bits() as i64, p[1].to_bits() as i64, p[2].to_bits() as i64]
+        }
+    };
+    let search_radius: i64 = if tol > 0.0 { 1 } else { 0 };
+
+    let mut grid: HashMap<[i64; 3], Vec<usize>> = HashMap::new();
+    let mut kept: Vec<[f64; 3]> = Vec::with_capacity(mesh.nodes.len());
+    let mut remap = Vec::with_capacity(mesh.nodes.len());
+
+    for node in &mesh.nodes {
+        let cell = cell_of(node);
+        let mut found = None;
+        'search: for dx in -search_radius..=search_radius {
+            for dy in -search_radius..=search_radius {
+                for dz in -search_radius..=search_radius {
+                    let neighbour = [cell[0] + dx, cell[1] + dy, cell[2] + dz];
+                    for &candidate in grid.get(&neighbour).into_iter().flatten() {
+                        let p = kept[candidate];
+                        let dist_sq = (p[0] - node[0]).powi(2) + (p[1] - node[1]).powi(2) + (p[2] - node[2]).powi(2);
+                        if dist_sq <= tol * tol {
+                            found = Some(candidate);
+                            break 'search;
+                        }
+                    }
+                }
+            }
+        }
+
+        let index = found.unwrap_or_else(|| {
+            kept.push(*node);
+            grid.entry(cell).or_default().push(kept.len() - 1);
+            kept.len() - 1
+        });
+        remap.push(index);
+    }
+
+    let merged = mesh.nodes.len() - kept.len();
+    mesh.nodes = kept;
+
+    for element in &mut mesh.elements {
+        for node in element.iter_mut() {
+            *node = remap[*node];
+        }
+    }
+
+    for region in mesh.boundary_regions.values_mut() {
+        let mut seen = std::collections::HashSet::new();
+        *region = region.iter().map(|&n| remap[n]).filter(|n| seen.insert(*n)).collect();
+    }
+
+    merged
+}
+
+/// Returns, for every node, the sorted list of nodes sharing an element with it.
+///
+/// A node is never its own neighbor. Invalid node indices in the connectivity are ignored.
+pub fn node_adjacency(mesh: &Mesh) -> Vec<Vec<usize>> {
+    let num_nodes = mesh.num_nodes();
+    let mut adjacency = vec![Vec::new(); num_nodes];
+    for element in &mesh.elements {
+        for &a in element.iter().filter(|&&n| n < num_nodes) {
+            adjacency[a].extend(element.iter().copied().filter(|&b| b != a && b < num_nodes));
+        }
+    }
+    for neighbors in &mut adjacency {
+        neighbors.sort_unstable();
+        neighbors.dedup();
+    }
+    adjacency
+}
+
+/// Moves every interior node to the centroid of its neighbors, `iterations` times.
+///
+/// Nodes on a boundary face, one belonging to a single element, stay fixed, so
+/// the domain shape and `boundary_regions` are preserved. Returns the mesh
+/// quality `(before, after)`; smoothing can invalidate elements on strongly
+/// non-convex node neighborhoods, so callers may want to check the result.
+pub fn laplacian_smooth(mesh: &mut Mesh, iterations: usize) -> (MeshQuality, MeshQuality) {
+    let before = mesh_quality(mesh);
+    let adjacency = node_adjacency(mesh);
+
+    let num_nodes = mesh.num_nodes();
+    let mut on_boundary = vec![false; num_nodes];
+    for (_, face) in surface_faces(mesh) {
+        for &n in face.iter().filter(|&&n| n < num_nodes) {
+            on_boundary[n] = true;
+        }
+    }
+
+    for _ in 0..iterations {
+        let previous = mesh.nodes.clone();
+        for (node, neighbors) in adjacency.iter().enumerate() {
+            if on_boundary[node] || neighbors.is_empty() {
+                continue;
+            }
+            let mut centroid = [0.0; 3];
+            for &neighbor in neighbors {
+                for axis in 0..3 {
+                    centroid[axis] += previous[neighbor][axis] / neighbors.len() as f64;
+                }
+            }
+            mesh.nodes[node] = centroid;
+        }
+    }
+
+    let after = mesh_quality(mesh);
+    println!(
+        "Laplacian smoothing ({} iterations): mean aspect ratio {:.4} -> {:.4}, min {:.4} -> {:.4}",
+        iterations, before.mean_aspect_ratio, after.mean_aspect_ratio, before.min_aspect_ratio, after.min_aspect_ratio
+    );
+    (before, after)
+}
+
+/// Returns, for every element, the sorted list of elements sharing a face with it.
+///
+/// Faces are known for 4-node tetrahedra and 8-node hexahedra in Gmsh node order;
+/// other elements have no faces and therefore no neighbors.
+pub fn element_adjacency(mesh: &Mesh) -> Vec<Vec<usize>> {
+    let mut owners: std::collections::HashMap<Vec<usize>, Vec<usize>> = std::collections::HashMap::new();
+    for (elem_idx, element) in mesh.elements.iter().enumerate() {
+        for mut face in element_faces(element) {
+            face.sort_unstable();
+            owners.entry(face).or_default().push(elem_idx);
+        }
+    }
+
+    let mut adjacency = vec![Vec::new(); mesh.num_elements()];
+    for elements in owners.values() {
+        for &a in elements {
+            adjacency[a].extend(elements.iter().copied().filter(|&b| b != a));
+        }
+    }
+    for neighbors in &mut adjacency {
+        neighbors.sort_unstable();
+        neighbors.dedup();
+    }
+    adjacency
+}
+
+/// Returns the sorted surface edges not shared by exactly two surface faces.
+///
+/// The surface is made of the element faces owned by a single element. Every
+/// edge of a manifold surface joins two of its faces; more mean solids touching
+/// along the edge, which Gmsh produces from unsewn or overlapping CAD shapes.
+pub fn non_manifold_edges(mesh: &Mesh) -> Vec<[usize; 2]> {
+    let mut edge_counts: HashMap<[usize; 2], usize> = HashMap::new();
+    for (_, face) in surface_faces(mesh) {
+        for (i, &a) in face.iter().enumerate() {
+            let b = face[(i + 1) % face.len()];
+            *edge_counts.entry([a.min(b), a.max(b)]).or_insert(0) += 1;
+        }
+    }
+
+    let mut edges: Vec<[usize; 2]> = edge_counts.into_iter().filter(|&(_, count)| count != 2).map(|(edge, _)| edge).collect();
+    edges.sort_unstable();
+    edges
+}
+
+/// Returns the boundary triangles of a tetrahedral mesh with their owning element.
+///
+/// A face is on the boundary when it belongs to a single element, so the
+/// triangles of a closed domain form a closed surface. Each triangle's nodes
+/// are ordered so that its right-hand normal points out of the owning element.
+/// Only 4-node tetrahedra and 10-node tetrahedra contribute, the latter with
+/// the triangle of their corner nodes; other elements are skipped.
+pub fn boundary_faces(mesh: &Mesh) -> Vec<(usize, [usize; 3])> {
+    surface_faces(mesh)
+        .into_iter()
+        .filter(|(elem_idx, _)| matches!(mesh.elements[*elem_idx].len(), 4 | 10))
+        .map(|(elem_idx, face)| {
+            let mut triangle = [face[0], face[1], face[2]];
+            let opposite = mesh.elements[elem_idx][..4].iter().find(|n| !face.contains(n)).and_then(|&n| mesh.node(n));
+            if let (Some(a), Some(b), Some(c), Some(d)) = (mesh.node(triangle[0]), mesh.node(triangle[1]), mesh.node(triangle[2]), opposite) {
+                if signed_tetrahedron_volume([a, b, c, d]) > 0.0 {
+                    triangle.swap(1, 2);
+                }
+            }
+            (elem_idx, triangle)
+        })
+        .collect()
+}
+
+/// Returns the surface area of every named boundary region.
+///
+/// A region covers the [`boundary_faces`] whose three nodes all belong to it,
+/// so regions sharing an edge do not count each other's faces. Regions without
+/// any such face, e.g. single points, have zero area.
+pub fn region_areas(mesh: &Mesh) -> HashMap<String, f64> {
+    let faces = boundary_faces(mesh);
+    mesh.boundary_regions
+        .iter()
+        .map(|(name, nodes)| {
+            let members: std::collections::HashSet<usize> = nodes.iter().copied().collect();
+            let area = faces
+                .iter()
+                .filter(|(_, triangle)| triangle.iter().all(|n| members.contains(n)))
+                .map(|(_, triangle)| triangle_area(triangle.map(|n| mesh.nodes[n])))
+                .sum();
+            (name.clone(), area)
+        })
+        .collect()
+}
+
+/// Returns the total volume of the mesh's tetrahedra.
+///
+/// Quadratic tetrahedra count the volume of their corner tetrahedron, which is
+/// exact for straight edges. Inverted elements add their absolute volume.
+pub fn total_volume(mesh: &Mesh) -> f64 {
+    mesh.elements
+        .iter()
+        .filter(|element| matches!(element.len(), 4 | 10) && element.iter().all(|&n| n < mesh.num_nodes()))
+        .map(|element| signed_tetrahedron_volume([mesh.nodes[element[0]], mesh.nodes[element[1]], mesh.nodes[element[2]], mesh.nodes[element[3]]]).abs())
+        .sum()
+}
+
+/// Computes the area of a triangle.
+fn triangle_area([a, b, c]: [[f64; 3]; 3]) -> f64 {
+    let (u, v) = ([b[0] - a[0], b[1] - a[1], b[2] - a[2]], [c[0] - a[0], c[1] - a[1], c[2] - a[2]]);
+    let cross = [u[1] * v[2] - u[2] * v[1], u[2] * v[0] - u[0] * v[2], u[0] * v[1] - u[1] * v[0]];
+    0.5 * (cross[0] * cross[0] + cross[1] * cross[1] + cross[2] * cross[2]).sqrt()
+}
+
+/// Returns the element faces belonging to a single element, in element order.
+///
+/// Each face is paired with its owning element and keeps its [`element_faces`] node order.
+fn surface_faces(mesh: &Mesh) -> Vec<(usize, Vec<usize>)> {
+    let mut face_counts: HashMap<Vec<usize>, usize> = HashMap::new();
+    let mut faces = Vec::new();
+    for (elem_idx, element) in mesh.elements.iter().enumerate() {
+        for face in element_faces(element) {
+            let mut key = face.clone();
+            key.sort_unstable();
+            *face_counts.entry(key.clone()).or_insert(0) += 1;
+            faces.push((key, elem_idx, face));
+        }
+    }
+    faces.into_iter().filter(|(key, _, _)| face_counts[key] == 1).map(|(_, elem_idx, face)| (elem_idx, face)).collect()
+}
+
+/// One part of a decomposed mesh.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Partition {
+    /// Indices of the elements assigned to this partition.
+    pub elements: Vec<usize>,
+    /// Sorted indices of every node used by those elements.
+    pub nodes: Vec<usize>,
+    /// Sorted indices of the nodes this partition shares with other partitions.
+    pub interface_nodes: Vec<usize>,
+}
+
+/// Splits the mesh elements into `n` balanced partitions by recursive coordinate bisection.
+///
+/// Element centroids are repeatedly split along the longest extent of the current
+/// group, with group sizes proportional to the number of partitions on each side,
+/// so element counts differ by at most one. A value of `n` below one is treated as one.
+pub fn partition(mesh: &Mesh, n: usize) -> Vec<Partition> {
+    let centroids: Vec<[f64; 3]> = mesh.elements
+        .iter()
+        .map(|element| {
+            let coords: Vec<[f64; 3]> = element.iter().filter_map(|&i| mesh.node(i)).collect();
+            let count = coords.len().max(1) as f64;
+            std::array::from_fn(|axis| coords.iter().map(|c| c[axis]).sum::<f64>() / count)
+        })
+        .collect();
+
+    let mut groups = Vec::with_capacity(n.max(1));
+    bisect((0..mesh.num_elements()).collect(), n.max(1), &centroids, &mut groups);
+
+    let mut owners = vec![Vec::new(); mesh.num_nodes()];
+    let mut partitions: Vec<Partition> = groups
+        .into_iter()
+        .enumerate()
+        .map(|(part, mut elements)| {
+            elements.sort_unstable();
+            let mut nodes: Vec<usize> = elements.iter().flat_map(|&e| mesh.elements[e].iter().copied()).filter(|&i| i < mesh.num_nodes()).collect();
+            nodes.sort_unstable();
+            nodes.dedup();
+            for &node in &nodes {
+                owners[node].push(part);
+            }
+            Partition { elements, nodes, interface_nodes: Vec::new() }
+        })
+        .collect();
+
+    for part in &mut partitions {
+        part.interface_nodes = part.nodes.iter().copied().filter(|&node| owners[node].len() > 1).collect();
+    }
+    partitions
+}
+
+/// Recursively splits `elements` into `parts` groups, appending them to `groups`.
+fn bisect(mut elements: Vec<usize>, parts: usize, centroids: &[[f64; 3]], groups: &mut Vec<Vec<usize>>) {
+    if parts == 1 {
+        groups.push(elements);
+        return;
+    }
+
+    let extent = |axis: usize| {
+        let (lo, hi) = elements.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &e| (lo.min(centroids[e][axis]), hi.max(centroids[e][axis])));
+        hi - lo
+    };
+    let axis = (0..3).max_by(|&a, &b| extent(a).total_cmp(&extent(b))).unwrap_or(0);
+    elements.sort_by(|&a, &b| centroids[a][axis].total_cmp(&centroids[b][axis]).then(a.cmp(&b)));
+
+    let left_parts = parts / 2;
+    let split = elements.len() * left_parts / parts;
+    let right = elements.split_off(split);
+    bisect(elements, left_parts, centroids, groups);
+    bisect(right, parts - left_parts, centroids, groups);
+}
+
+/// Lists the faces of an element as node index lists.
+///
+/// Quadratic tetrahedra list the triangles of their corner nodes.
+fn element_faces(element: &[usize]) -> Vec<Vec<usize>> {
+    const TETRAHEDRON_FACES: [[usize; 3]; 4] = [[0, 1, 2], [0, 1, 3], [0, 2, 3], [1, 2, 3]];
+    const HEXAHEDRON_FACES: [[usize; 4]; 6] = [[0, 1, 2, 3], [4, 5, 6, 7], [0, 1, 5, 4], [1, 2, 6, 5], [2, 3, 7, 6], [3, 0, 4, 7]];
+    match element.len() {
+        4 | 10 => TETRAHEDRON_FACES.iter().map(|face| face.iter().map(|&i| element[i]).collect()).collect(),
+        8 => HEXAHEDRON_FACES.iter().map(|face| face.iter().map(|&i| element[i]).collect()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl From<i32> for EngineError {
+    fn from(err: i32) -> Self {
+        EngineError::MeshingFailed(format!("Gmsh error code: {}", err))
+    }
+}
+/// Builds a structured tetrahedral mesh of the cube `[0, size]^3` for tests.
+///
+/// Each of the `n^3` hexahedral cells is split into six positively oriented
+/// tetrahedra sharing the cell's main diagonal.
+#[cfg(test)]
+pub(crate) fn structured_cube_mesh(n: usize, size: f64) -> Mesh {
+    structured_box_mesh([n; 3], [size; 3])
+}
+
+/// Builds a structured tetrahedral mesh of the box `[0, size[0]] x [0, size[1]] x [0, size[2]]`
+/// with `cells[axis]` cells along each axis, split as in [`structured_cube_mesh`].
+#[cfg(test)]
+pub(crate) fn structured_box_mesh(cells: [usize; 3], size: [f64; 3]) -> Mesh {
+    let [nx, ny, nz] = cells;
+    let h: [f64; 3] = std::array::from_fn(|axis| size[axis] / cells[axis] as f64);
+    let index = |i: usize, j: usize, k: usize| (k * (ny + 1) + j) * (nx + 1) + i;
+
+    let mut nodes = Vec::with_capacity((nx + 1) * (ny + 1) * (nz + 1));
+    for k in 0..=nz {
+        for j in 0..=ny {
+            for i in 0..=nx {
+                nodes.push([i as f64 * h[0], j as f64 * h[1], k as f64 * h[2]]);
+            }
+        }
+    }
+
+    const AXIS_ORDERS: [[usize; 3]; 6] = [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]];
+    let mut elements = Vec::with_capacity(6 * nx * ny * nz);
+    for k in 0..nz {
+        for j in 0..ny {
+            for i in 0..nx {
+                for order in AXIS_ORDERS {
+                    let mut corner = [i, j, k];
+                    let mut tet = vec![index(i, j, k)];
+                    for axis in order {
+                        corner[axis] += 1;
+                        tet.push(index(corner[0], corner[1], corner[2]));
+                    }
+                    if signed_tetrahedron_volume([nodes[tet[0]], nodes[tet[1]], nodes[tet[2]], nodes[tet[3]]]) < 0.0 {
+                        tet.swap(2, 3);
+                    }
+                    elements.push(tet);
+                }
+            }
+        }
+    }
+
+    Mesh {
+        nodes,
+        elements,
+        element_type: "Tetrahedron".to_string(),
+        boundary_regions: std::collections::HashMap::new(),
+        source_geometry: None,
+        bounding_box: None,
+        geometry_warnings: Vec::new(),
+    }
+}
+
+/// Creates an empty scratch directory for a test under the system temp dir.
+#[cfg(test)]
+pub(crate) fn test_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("core_engine_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Writes a stand-in Gmsh executable into `dir` for tests.
+///
+/// The script ignores its input and copies the repository's `dummy.msh` unit
+/// cube to the path given after `-o`, mimicking a successful Gmsh run.
+#[cfg(test)]
+pub(crate) fn fake_gmsh(dir: &Path) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("dummy.msh");
+    let script = format!(
+        "#!/bin/sh\nwhile [ $# -gt 0 ]; do\n  if [ \"$1\" = \"-o\" ]; then cp '{}' \"$2\"; fi\n  shift\ndone\n",
+        fixture.display()
+    );
+    let path = dir.join("fake_gmsh.sh");
+    fs::write(&path, script).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+/// Writes an executable stand-in for Gmsh into `dir` running the shell `body`.
+#[cfg(test)]
+pub(crate) fn gmsh_script(dir: &Path, body: &str) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = dir.join("gmsh.sh");
+    fs::write(&path, format!("#!/bin/sh\n{}", body)).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_cube() -> GeometryDefinition {
+        GeometryDefinition::Primitive(GeometricPrimitive {
+            shape: "cube".to_string(),
+            dimensions: vec![1.0, 1.0, 1.0],
+            transform: None,
+        })
+    }
+
+    fn off_origin_tetrahedron() -> Mesh {
+        Mesh {
+            nodes: vec![
+                [100.0, 50.0, -20.0],
+                [102.0, 50.0, -20.0],
+                [100.0, 54.0, -20.0],
+                [100.0, 50.0, -14.0],
+            ],
+            elements: vec![vec![0, 1, 2, 3]],
+            element_type: "Tetrahedron".to_string(),
+            boundary_regions: HashMap::new(),
+            source_geometry: None,
+            bounding_box: None,
+            geometry_warnings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let mesh = off_origin_tetrahedron();
+        let (min, max) = bounding_box(&mesh);
+        assert_eq!(min, [100.0, 50.0, -20.0]);
+        assert_eq!(max, [102.0, 54.0, -14.0]);
+    }
+
+    #[test]
+    fn test_recenter_makes_bounding_box_symmetric() {
+        let mut mesh = off_origin_tetrahedron();
+        recenter(&mut mesh);
+
+        let (min, max) = bounding_box(&mesh);
+        for axis in 0..3 {
+            assert!((min[axis] + max[axis]).abs() < 1e-12, "Axis {} is not centered: [{}, {}]", axis, min[axis], max[axis]);
+        }
+        assert_eq!(max, [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_detect_coordinate_regions_on_cube() {
+        let n = 2;
+        let mut mesh = structured_cube_mesh(n, 1.0);
+        detect_coordinate_regions(&mut mesh, 1e-9);
+
+        let per_face = (n + 1) * (n + 1);
+        for (name, axis, is_max_side) in COORDINATE_FACES {
+            let region = &mesh.boundary_regions[name];
+            assert_eq!(region.len(), per_face, "Unexpected node count on {}", name);
+            let expected = if is_max_side { 1.0 } else { 0.0 };
+            for &node in region {
+                assert_eq!(mesh.nodes[node][axis], expected, "Node {} misclassified on {}", node, name);
+            }
+        }
+
+        // The cube's center node lies on no face.
+        let center = mesh.nodes.iter().position(|p| *p == [0.5, 0.5, 0.5]).unwrap();
+        assert!(mesh.boundary_regions.values().all(|region| !region.contains(&center)));
+    }
+
+    #[test]
+    fn test_merge_coincident_nodes() {
+        // Two tetrahedra sharing a face, but with the shared nodes duplicated.
+        let mut mesh = Mesh {
+            nodes: vec![
+                [0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0],
+                [1.0, 0.0, 1e-12], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0], [1.0, 1.0, 1.0],
+            ],
+            elements: vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7]],
+            element_type: "Tetrahedron".to_string(),
+            boundary_regions: HashMap::from([("shared".to_string(), vec![1, 4, 7])]),
+            source_geometry: None,
+            bounding_box: None,
+            geometry_warnings: Vec::new(),
+        };
+
+        let merged = merge_coincident_nodes(&mut mesh, 1e-9);
+
+        assert_eq!(merged, 3);
+        assert_eq!(mesh.nodes.len(), 5);
+        assert_eq!(mesh.elements[0], vec![0, 1, 2, 3]);
+        assert_eq!(mesh.elements[1], vec![1, 2, 3, 4]);
+        assert_eq!(mesh.nodes[4], [1.0, 1.0, 1.0]);
+        assert_eq!(mesh.boundary_regions["shared"], vec![1, 4]);
+    }
+
+    #[test]
+    fn test_merge_meshes_of_two_cubes() {
+        let mut a = structured_cube_mesh(2, 1.0);
+        detect_coordinate_regions(&mut a, 1e-9);
+        // The second cube sits next to the first along x.
+        let mut b = structured_cube_mesh(1, 1.0);
+        b.nodes.iter_mut().for_each(|node| node[0] += 1.0);
+        detect_coordinate_regions(&mut b, 1e-9);
+
+        let merged = merge_meshes(&a, &b, true).unwrap();
+        assert_eq!(merged.nodes.len(), a.nodes.len() + b.nodes.len());
+        assert_eq!(merged.elements.len(), a.elements.len() + b.elements.len());
+        assert_eq!(merged.elements[a.elements.len()], b.elements[0].iter().map(|n| n + a.nodes.len()).collect::<Vec<_>>());
+        assert_eq!(merged.boundary_regions.len(), a.boundary_regions.len() + b.boundary_regions.len());
+        for (name, nodes) in &a.boundary_regions {
+            assert_eq!(&merged.boundary_regions[name], nodes);
+        }
+        for (name, nodes) in &b.boundary_regions {
+            let renamed = &merged.boundary_regions[&format!("b_{}", name)];
+            assert!(renamed.iter().zip(nodes).all(|(&m, &n)| merged.nodes[m] == b.nodes[n]));
+        }
+        assert!((total_volume(&merged) - 2.0).abs() < 1e-12);
+
+        // Without the prefix, regions of the same name are united.
+        let mut shared = merge_meshes(&a, &b, false).unwrap();
+        assert_eq!(shared.boundary_regions.len(), a.boundary_regions.len());
+        assert_eq!(shared.boundary_regions["face_y_neg"].len(), a.boundary_regions["face_y_neg"].len() + b.boundary_regions["face_y_neg"].len());
+        // Tying the parts removes the duplicated nodes of the shared face.
+        assert_eq!(merge_coincident_nodes(&mut shared, 1e-9), b.boundary_regions["face_x_neg"].len());
+
+        let quadratic = to_quadratic_tetrahedra(&b).unwrap();
+        assert!(matches!(merge_meshes(&a, &quadratic, true), Err(EngineError::MeshingFailed(_))));
+    }
+
+    #[test]
+    fn test_merge_coincident_nodes_keeps_distinct_nodes() {
+        let mut mesh = structured_cube_mesh(2, 1.0);
+        let before = mesh.nodes.len();
+        assert_eq!(merge_coincident_nodes(&mut mesh, 1e-6), 0);
+        assert_eq!(mesh.nodes.len(), before);
+    }
+
+    #[test]
+    fn test_keep_intermediate_files() {
+        let dir = test_dir("keep_intermediate");
+        let config = MeshingConfig {
+            working_dir: Some(dir.clone()),
+            keep_intermediate: true,
+            gmsh_executable: Some(fake_gmsh(&dir)),
+            ..Default::default()
+        };
+
+        let mesh = generate_mesh_with_config(&unit_cube(), &config).unwrap();
+        assert_eq!(mesh.element_type, "Tetrahedron");
+        assert!(dir.join("temp.geo").exists());
+        assert!(dir.join("temp.msh").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_verbose_gmsh_output_is_truncated() {
+        let dir = test_dir("verbose_gmsh");
+        // About 4 MB on each stream, far above the capture limit, then a failure.
+        let gmsh = gmsh_script(&dir, "echo 'Info    : Reading temp.geo'\n\
+            i=0\nwhile [ $i -lt 50000 ]; do\n  echo 'Info    : Meshing curve with a rather long progress message padding it out'\n  \
+            echo 'Warning : chatty diagnostic line repeated to flood standard error' >&2\n  i=$((i + 1))\ndone\n\
+            echo 'Error   : Last words before exiting' >&2\nexit 1\n");
+        let config = MeshingConfig { working_dir: Some(dir.clone()), gmsh_executable: Some(gmsh), ..Default::default() };
+
+        let message = match generate_mesh_with_config(&unit_cube(), &config) {
+            Err(EngineError::MeshingFailed(message)) => message,
+            other => panic!("Unexpected result: {:?}", other.map(|mesh| mesh.nodes.len())),
+        };
+        assert!(message.len() < 3 * MAX_CAPTURED_OUTPUT, "captured {} bytes", message.len());
+        assert_eq!(message.matches("bytes of output truncated").count(), 2, "{}", &message[..200]);
+        assert!(message.contains("Info    : Reading temp.geo"));
+        assert!(message.ends_with("Error   : Last words before exiting\n"));
+
+        let short = read_bounded("short output".as_bytes(), 64).unwrap();
+        assert_eq!(short, "short output");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_intermediate_files_removed_by_default() {
+        let dir = test_dir("cleanup_intermediate");
+        let config = MeshingConfig {
+            working_dir: Some(dir.clone()),
+            gmsh_executable: Some(fake_gmsh(&dir)),
+            ..Default::default()
+        };
+
+        generate_mesh_with_config(&unit_cube(), &config).unwrap();
+        assert!(!dir.join("temp.geo").exists());
+        assert!(!dir.join("temp.msh").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_adjacency_graphs_of_cube_mesh() {
+        // In a single cell all six tetrahedra share the main diagonal from node 0 to node 7.
+        let cell = structured_cube_mesh(1, 1.0);
+        let nodes = node_adjacency(&cell);
+        assert_eq!(nodes[0], vec![1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(nodes[7], vec![0, 1, 2, 3, 4, 5, 6]);
+        assert!(element_adjacency(&cell).iter().all(|neighbors| neighbors.len() == 2));
+
+        let mesh = structured_cube_mesh(2, 1.0);
+        let nodes = node_adjacency(&mesh);
+        let elements = element_adjacency(&mesh);
+        for (a, neighbors) in nodes.iter().enumerate() {
+            assert!(neighbors.iter().all(|&b| nodes[b].contains(&a)));
+        }
+        for (a, neighbors) in elements.iter().enumerate() {
+            assert!(!neighbors.contains(&a));
+            assert!(neighbors.iter().all(|&b| elements[b].contains(&a)));
+        }
+
+        // 48 tetrahedra with 4 faces each, 48 of them on the boundary.
+        let interior_faces = (48 * 4 - 6 * 4 * 2) / 2;
+        assert_eq!(elements.iter().map(Vec::len).sum::<usize>(), 2 * interior_faces);
+        // The center node connects to the offsets whose components share a sign:
+        // seven in the positive octant and seven in the negative one.
+        assert_eq!(nodes[13].len(), 14);
+    }
+
+    #[test]
+    fn test_partition_cube_mesh() {
+        let mesh = structured_cube_mesh(2, 1.0);
+
+        for n in [2, 4] {
+            let parts = partition(&mesh, n);
+            assert_eq!(parts.len(), n);
+
+            let sizes: Vec<usize> = parts.iter().map(|p| p.elements.len()).collect();
+            assert_eq!(sizes, vec![mesh.elements.len() / n; n]);
+
+            let mut assigned: Vec<usize> = parts.iter().flat_map(|p| p.elements.iter().copied()).collect();
+            assigned.sort_unstable();
+            assert_eq!(assigned, (0..mesh.elements.len()).collect::<Vec<_>>());
+
+            for part in &parts {
+                assert!(!part.interface_nodes.is_empty());
+                assert!(part.interface_nodes.iter().all(|node| part.nodes.contains(node)));
+                for &node in &part.interface_nodes {
+                    assert!(parts.iter().filter(|other| other.nodes.contains(&node)).count() > 1);
+                }
+            }
+        }
+
+        // Splitting in two cuts the cube at its mid-plane, which holds nine nodes.
+        assert_eq!(partition(&mesh, 2)[0].interface_nodes.len(), 9);
+    }
+
+    #[test]
+    fn test_gmsh_args_from_config() {
+        assert_eq!(gmsh_mesh_args(&MeshingConfig::default()), vec!["-3"]);
+
+        let config = MeshingConfig {
+            algorithm: Some(MeshAlgorithm::Frontal),
+            optimize: true,
+            ..Default::default()
+        };
+        assert_eq!(gmsh_mesh_args(&config), vec!["-3", "-algo", "front3d", "-optimize_netgen"]);
+
+        let parsed: MeshingConfig = serde_json::from_str(r#"{"keep_intermediate": false, "algorithm": "Delaunay"}"#).unwrap();
+        assert_eq!(parsed.algorithm, Some(MeshAlgorithm::Delaunay));
+        assert!(!parsed.optimize);
+    }
+
+    #[test]
+    fn test_optimize_flag_is_passed_to_gmsh() {
+        let dir = test_dir("optimize_flag");
+        let fake = fake_gmsh(&dir);
+        let gmsh = gmsh_script(&dir, &format!("echo \"$@\" > args.txt\nexec '{}' \"$@\"\n", fake.display()));
+        let mut config = MeshingConfig {
+            working_dir: Some(dir.clone()),
+            gmsh_executable: Some(gmsh),
+            ..Default::default()
+        };
+        let optimizes = |config: &MeshingConfig| {
+            generate_mesh_with_config(&unit_cube(), config).unwrap();
+            fs::read_to_string(dir.join("args.txt")).unwrap().split_whitespace().any(|arg| arg == "-optimize_netgen")
+        };
+
+        assert!(!optimizes(&config));
+        config.optimize = true;
+        assert!(optimizes(&config));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_regular_tetrahedron_has_unit_aspect_ratio() {
+        let regular = [[1.0, 1.0, 1.0], [1.0, -1.0, -1.0], [-1.0, 1.0, -1.0], [-1.0, -1.0, 1.0]];
+        assert!((tetrahedron_aspect_ratio(regular) - 1.0).abs() < 1e-12);
+        assert_eq!(tetrahedron_aspect_ratio([[0.0; 3], [1.0, 0.0, 0.0], [2.0, 0.0, 0.0], [3.0, 0.0, 0.0]]), 0.0);
+
+        // Every Kuhn tetrahedron is congruent, with edges 1, 1, 1, sqrt(2), sqrt(2), sqrt(3).
+        let quality = mesh_quality(&structured_cube_mesh(2, 1.0));
+        let expected = 6.0 * std::f64::consts::SQRT_2 / 6.0 / (10.0f64 / 6.0).powf(1.5);
+        assert!((quality.min_aspect_ratio - expected).abs() < 1e-12);
+        assert!((quality.mean_aspect_ratio - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_translated_cube_bounding_box_is_offset() {
+        let dir = test_dir("translated_cube");
+        let config = MeshingConfig {
+            working_dir: Some(dir.clone()),
+            gmsh_executable: Some(fake_gmsh(&dir)),
+            ..Default::default()
+        };
+        let geometry = GeometryDefinition::Primitive(GeometricPrimitive {
+            shape: "cube".to_string(),
+            dimensions: vec![1.0, 1.0, 1.0],
+            transform: Some(Transform {
+                translation: [10.0, -2.0, 0.5],
+                ..Default::default()
+            }),
+        });
+
+        let plain = generate_mesh_with_config(&unit_cube(), &config).unwrap();
+        let moved = generate_mesh_with_config(&geometry, &config).unwrap();
+        let (plain_min, plain_max) = bounding_box(&plain);
+        let (min, max) = bounding_box(&moved);
+        for (axis, offset) in [10.0, -2.0, 0.5].into_iter().enumerate() {
+            assert!((min[axis] - plain_min[axis] - offset).abs() < 1e-12);
+            assert!((max[axis] - plain_max[axis] - offset).abs() < 1e-12);
+        }
+        assert_eq!(moved.boundary_regions["face_x_neg"].len(), plain.boundary_regions["face_x_neg"].len());
+        assert_eq!(moved.bounding_box, Some((min, max)));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_msh_nodes_and_elements_are_ordered_by_tag() {
+        // Sparse tags listed out of order across two node blocks.
+        let dir = test_dir("msh_tag_order");
+        let path = dir.join("shuffled.msh");
+        fs::write(
+            &path,
+            "$MeshFormat\n4.1 0 8\n$EndMeshFormat\n$Nodes\n2 5 10 50\n3 1 0 3\n30\n10\n50\n0 1 0\n0 0 0\n1 1 1\n3 2 0 2\n20\n40\n1 0 0\n0 0 1\n$EndNodes\n\
+             $Elements\n1 2 3 7\n3 1 4 2\n7 10 20 30 40\n3 20 10 50 40\n$EndElements\n",
+        )
+        .unwrap();
+
+        let mesh = extract_mesh_data_from_file(path.to_str().unwrap()).unwrap();
+        assert_eq!(mesh.nodes, vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0], [1.0, 1.0, 1.0]]);
+        assert_eq!(mesh.elements, vec![vec![1, 0, 4, 3], vec![0, 1, 2, 3]]);
+        assert_eq!(mesh.element_type, "Tetrahedron");
+
+        let again = extract_mesh_data_from_file(path.to_str().unwrap()).unwrap();
+        assert_eq!(again.nodes, mesh.nodes);
+        assert_eq!(again.elements, mesh.elements);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_second_order_msh_imports_tetrahedron10() {
+        let dir = test_dir("msh_tet10");
+        let path = dir.join("quadratic.msh");
+        let nodes = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n0 0 0\n1 0 0\n0 1 0\n0 0 1\n\
+                     0.5 0 0\n0.5 0.5 0\n0 0.5 0\n0 0 0.5\n0 0.5 0.5\n0.5 0 0.5\n";
+        let header = format!("$MeshFormat\n4.1 0 8\n$EndMeshFormat\n$Nodes\n1 10 1 10\n3 1 0 10\n{}$EndNodes\n", nodes);
+        fs::write(&path, format!("{}$Elements\n1 1 1 1\n3 1 11 1\n1 1 2 3 4 5 6 7 8 9 10\n$EndElements\n", header)).unwrap();
+
+        let streamed = extract_mesh_data_from_file(path.to_str().unwrap()).unwrap();
+        let parsed = parse_msh_with_mshio(path.to_str().unwrap()).unwrap();
+        assert_eq!(streamed.element_type, "Tetrahedron10");
+        assert_eq!(streamed.elements, vec![(0..10).collect::<Vec<usize>>()]);
+        assert_eq!(parsed.element_type, streamed.element_type);
+        assert_eq!(parsed.elements, streamed.elements);
+
+        // Linear and quadratic tetrahedra in one file are refused.
+        fs::write(&path, format!("{}$Elements\n2 2 1 2\n3 1 11 1\n1 1 2 3 4 5 6 7 8 9 10\n3 2 4 1\n2 1 2 3 4\n$EndElements\n", header)).unwrap();
+        let err = extract_mesh_data_from_file(path.to_str().unwrap());
+        assert!(matches!(&err, Err(EngineError::MeshingFailed(msg)) if msg.contains("mixes 4-node and 10-node")), "{:?}", err.map(|m| m.num_elements()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_large_msh_streams_like_the_whole_file_parser() {
+        use std::io::Write;
+        let source = structured_cube_mesh(24, 1.0);
+        let dir = test_dir("msh_streaming");
+        let path = dir.join("large.msh");
+
+        // Nodes in two blocks, then a surface triangle block that is skipped and the tetrahedra.
+        let mut out = std::io::BufWriter::new(fs::File::create(&path).unwrap());
+        let (n, e) = (source.num_nodes(), source.num_elements());
+        write!(out, "$MeshFormat\n4.1 0 8\n$EndMeshFormat\n$PhysicalNames\n1\n3 1 \"solid\"\n$EndPhysicalNames\n").unwrap();
+        writeln!(out, "$Nodes\n2 {} 1 {}", n, n).unwrap();
+        for (entity, range) in [(1, 0..n / 2), (2, n / 2..n)] {
+            writeln!(out, "3 {} 0 {}", entity, range.len()).unwrap();
+            for i in range.clone() {
+                writeln!(out, "{}", i + 1).unwrap();
+            }
+            for i in range {
+                let [x, y, z] = source.nodes[i];
+                writeln!(out, "{} {} {}", x, y, z).unwrap();
+            }
+        }
+        writeln!(out, "$EndNodes\n$Elements\n2 {} 1 {}\n2 1 2 1\n{} 1 2 3", e + 1, e + 1, e + 1).unwrap();
+        writeln!(out, "3 1 4 {}", e).unwrap();
+        for (i, element) in source.elements.iter().enumerate() {
+            writeln!(out, "{} {} {} {} {}", i + 1, element[0] + 1, element[1] + 1, element[2] + 1, element[3] + 1).unwrap();
+        }
+        writeln!(out, "$EndElements").unwrap();
+        drop(out);
+
+        let streamed = extract_mesh_data_from_file(path.to_str().unwrap()).unwrap();
+        let parsed = parse_msh_with_mshio(path.to_str().unwrap()).unwrap();
+        assert_eq!(streamed.num_nodes(), n);
+        assert_eq!(streamed.nodes, parsed.nodes);
+        assert_eq!(streamed.elements, parsed.elements);
+        assert_eq!(streamed.elements, source.elements);
+        assert_eq!(streamed.element_type, "Tetrahedron");
+
+        // Truncated files are rejected, naming what is missing.
+        let text = fs::read_to_string(&path).unwrap();
+        fs::write(&path, &text[..text.find("$Elements").unwrap()]).unwrap();
+        let err = extract_mesh_data_from_file(path.to_str().unwrap());
+        assert!(matches!(&err, Err(EngineError::MeshingFailed(msg)) if msg.ends_with("large.msh has no elements")), "{:?}", err.map(|m| m.num_elements()));
+        fs::write(&path, &text[..text.find("$EndNodes").unwrap()]).unwrap();
+        let err = extract_mesh_data_from_file(path.to_str().unwrap());
+        assert!(matches!(&err, Err(EngineError::MeshingFailed(msg)) if msg.contains("missing $EndNodes")), "{:?}", err.map(|m| m.num_elements()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_element_type_summary_counts_mixed_elements() {
+        let dir = test_dir("element_summary");
+        let path = dir.join("mixed.msh");
+        // A corner point, two edges, three surface triangles and two tetrahedra.
+        fs::write(
+            &path,
+            "$MeshFormat\n4.1 0 8\n$EndMeshFormat\n$Nodes\n1 5 1 5\n3 1 0 5\n1\n2\n3\n4\n5\n0 0 0\n1 0 0\n0 1 0\n0 0 1\n1 1 1\n$EndNodes\n\
+             $Elements\n4 8 1 8\n0 1 15 1\n1 1\n1 1 1 2\n2 1 2\n3 2 3\n2 1 2 3\n4 1 2 3\n5 1 2 4\n6 2 3 5\n3 1 4 2\n7 1 2 3 4\n8 2 3 4 5\n$EndElements\n",
+        )
+        .unwrap();
+
+        let summary = element_type_summary(&path).unwrap();
+        let expected: HashMap<ElementType, usize> = [(ElementType::Pnt, 1), (ElementType::Lin2, 2), (ElementType::Tri3, 3), (ElementType::Tet4, 2)].into_iter().collect();
+        assert_eq!(summary, expected);
+        assert_eq!(extract_mesh_data_from_file(path.to_str().unwrap()).unwrap().num_elements(), 2);
+
+        assert!(element_type_summary(dir.join("missing.msh")).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_laplacian_smooth_improves_perturbed_mesh() {
+        let mut mesh = structured_cube_mesh(4, 1.0);
+        let interior = |p: &[f64; 3]| p.iter().all(|&x| x > 1e-9 && x < 1.0 - 1e-9);
+        for (i, node) in mesh.nodes.iter_mut().enumerate().filter(|(_, p)| interior(p)) {
+            node[0] += 0.08 * ((i % 3) as f64 - 1.0);
+            node[1] += 0.08 * ((i % 5) as f64 / 2.0 - 1.0);
+        }
+        let original = mesh.nodes.clone();
+
+        let (before, after) = laplacian_smooth(&mut mesh, 5);
+        assert!(after.mean_aspect_ratio > before.mean_aspect_ratio, "{:?} -> {:?}", before, after);
+        assert_eq!(mesh_quality(&mesh), after);
+        assert!(find_inverted_elements(&mesh).is_empty());
+        for (moved, initial) in mesh.nodes.iter().zip(&original) {
+            if !interior(initial) {
+                assert_eq!(moved, initial);
+            }
+        }
+    }
+
+    #[test]
+    fn test_average_to_nodes_of_uniform_field_is_uniform() {
+        let mesh = structured_cube_mesh(2, 1.0);
+        let field: Vec<f64> = (0..mesh.num_elements()).flat_map(|_| [3.5, -1.0]).collect();
+        let nodal = average_to_nodes(&mesh, &field, 2).unwrap();
+        assert_eq!(nodal.len(), mesh.num_nodes() * 2);
+        for values in nodal.chunks_exact(2) {
+            assert!((values[0] - 3.5).abs() < 1e-12 && (values[1] + 1.0).abs() < 1e-12, "{:?}", values);
+        }
+        assert!(average_to_nodes(&mesh, &field[1..], 2).is_err());
+    }
+
+    #[test]
+    fn test_to_quadratic_tetrahedra_adds_shared_midside_nodes() {
+        let mut mesh = structured_cube_mesh(1, 1.0);
+        detect_coordinate_regions(&mut mesh, 1e-9);
+        let quadratic = to_quadratic_tetrahedra(&mesh).unwrap();
+
+        // 12 cube edges, 6 face diagonals and the main diagonal.
+        assert_eq!(quadratic.num_nodes(), 8 + 19);
+        assert_eq!(quadratic.element_type, "Tetrahedron10");
+        for (element, linear) in quadratic.elements.iter().zip(&mesh.elements) {
+            assert_eq!(&element[..4], &linear[..]);
+            for (i, [a, b]) in TETRAHEDRON10_EDGES.into_iter().enumerate() {
+                let (pa, pb, mid) = (quadratic.nodes[element[a]], quadratic.nodes[element[b]], quadratic.nodes[element[4 + i]]);
+                assert!((0..3).all(|axis| (mid[axis] - 0.5 * (pa[axis] + pb[axis])).abs() < 1e-15));
+            }
+        }
+        // A face holds its 4 corners, 4 edge midpoints and its diagonal's midpoint.
+        assert_eq!(quadratic.boundary_regions["face_x_neg"].len(), 9);
+        assert!(to_quadratic_tetrahedra(&quadratic).is_err());
+    }
+
+    #[test]
+    fn test_refine_tetrahedra_keeps_the_mesh_conforming() {
+        let mut mesh = structured_cube_mesh(2, 1.0);
+        detect_coordinate_regions(&mut mesh, 1e-9);
+        let refined = refine_tetrahedra(&mesh, &[0, 17]).unwrap();
+
+        assert!(refined.num_elements() > mesh.num_elements() + 2);
+        assert!(refined.num_elements() < 2 * mesh.num_elements());
+        assert!((total_volume(&refined) - 1.0).abs() < 1e-12);
+        assert!(find_inverted_elements(&refined).is_empty());
+        // A hanging node would leave internal faces on the surface.
+        let surface: f64 = boundary_faces(&refined).iter().map(|(_, face)| triangle_area(face.map(|n| refined.nodes[n]))).sum();
+        assert!((surface - 6.0).abs() < 1e-12, "Surface area {}", surface);
+        for (name, area) in region_areas(&refined) {
+            assert!((area - 1.0).abs() < 1e-12, "{}: {}", name, area);
+        }
+        assert!(refine_tetrahedra(&mesh, &[mesh.num_elements()]).is_err());
+    }
+
+    #[test]
+    fn test_empty_mesh_is_rejected() {
+        // A lone point element and no Tet4 blocks, as Gmsh writes for a volume it cannot mesh.
+        let dir = test_dir("empty_mesh");
+        let path = dir.join("empty.msh");
+        fs::write(
+            &path,
+            "$MeshFormat\n4.1 0 8\n$EndMeshFormat\n$Nodes\n1 1 1 1\n0 1 0 1\n1\n0 0 0\n$EndNodes\n\
+             $Elements\n1 1 1 1\n0 1 15 1\n1 1\n$EndElements\n",
+        )
+        .unwrap();
+
+        match extract_mesh_data_from_file(path.to_str().unwrap()) {
+            Err(EngineError::MeshingFailed(msg)) => assert_eq!(msg, "mesh has no elements"),
+            other => panic!("Expected an empty-mesh error, got {:?}", other.map(|m| m.elements.len())),
+        }
+
+        let mut mesh = off_origin_tetrahedron();
+        assert!(validate_mesh(&mesh).is_ok());
+        mesh.elements.clear();
+        assert!(matches!(validate_mesh(&mesh), Err(EngineError::MeshingFailed(msg)) if msg == "mesh has no elements"));
+        mesh.nodes.clear();
+        assert!(matches!(validate_mesh(&mesh), Err(EngineError::MeshingFailed(msg)) if msg == "mesh has no nodes"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_primitive_mesh_carries_source_geometry() {
+        let dir = test_dir("source_geometry");
+        let config = MeshingConfig {
+            working_dir: Some(dir.clone()),
+            gmsh_executable: Some(fake_gmsh(&dir)),
+            ..Default::default()
+        };
+        let mesh = generate_mesh_with_config(&unit_cube(), &config).unwrap();
+
+        match &mesh.source_geometry {
+            Some(GeometryDefinition::Primitive(primitive)) => {
+                assert_eq!(primitive.shape, "cube");
+                assert_eq!(primitive.dimensions, vec![1.0, 1.0, 1.0]);
+            }
+            other => panic!("Expected the source primitive, got {:?}", other),
+        }
+        let (min, max) = mesh.bounding_box.unwrap();
+        for axis in 0..3 {
+            assert!(min[axis].abs() < 1e-12 && (max[axis] - 1.0).abs() < 1e-12, "Unexpected box {:?} {:?}", min, max);
+        }
+
+        let restored: Mesh = serde_json::from_str(&serde_json::to_string(&mesh).unwrap()).unwrap();
+        assert_eq!(restored.bounding_box, mesh.bounding_box);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_transform_scales_then_rotates_then_translates() {
+        let transform = Transform {
+            translation: [1.0, 0.0, 0.0],
+            rotation: [0.0, 0.0, std::f64::consts::FRAC_PI_2],
+            scale: [2.0, 1.0, 1.0],
+        };
+        // (1, 0, 0) -> scaled (2, 0, 0) -> rotated about z (0, 2, 0) -> translated (1, 2, 0).
+        let p = transform.apply([1.0, 0.0, 0.0]);
+        assert!((p[0] - 1.0).abs() < 1e-12 && (p[1] - 2.0).abs() < 1e-12 && p[2].abs() < 1e-12);
+
+        let parsed: Transform = serde_json::from_str(r#"{"translation": [1.0, 2.0, 3.0]}"#).unwrap();
+        assert_eq!(parsed.scale, [1.0; 3]);
+        assert_eq!(parsed.apply([0.0; 3]), [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_inverted_elements_detected_and_flipped() {
+        let mut mesh = structured_cube_mesh(1, 1.0);
+        assert!(find_inverted_elements(&mesh).is_empty());
+
+        mesh.elements[2].swap(0, 1);
+        mesh.elements[4].swap(1, 3);
+        assert_eq!(find_inverted_elements(&mesh), vec![2, 4]);
+
+        assert_eq!(flip_inverted_elements(&mut mesh), 2);
+        assert!(find_inverted_elements(&mesh).is_empty());
+    }
+
+    #[test]
+    fn test_cad_import_is_healed_and_reports_defects() {
+        let dir = test_dir("cad_healing");
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("dummy.msh");
+        let gmsh = gmsh_script(&dir, &format!(
+            "echo \"$@\" > args.txt\necho 'Warning : Removed 2 small edges'\necho 'Warning : Sewing left 1 free edge' >&2\nwhile [ $# -gt 0 ]; do\n  if [ \"$1\" = \"-o\" ]; then cp '{}' \"$2\"; fi\n  shift\ndone\n",
+            fixture.display()
+        ));
+        let config = MeshingConfig {
+            working_dir: Some(dir.clone()),
+            gmsh_executable: Some(gmsh),
+            keep_intermediate: true,
+            ..Default::default()
+        };
+        for name in ["part.STEP", "part.step", "part.geo"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        let absolute = |name: &str| fs::canonicalize(dir.join(name)).unwrap().display().to_string();
+
+        let mesh = generate_mesh_with_config(&GeometryDefinition::File("part.STEP".to_string()), &config).unwrap();
+        let args = fs::read_to_string(dir.join("args.txt")).unwrap();
+        assert!(args.contains(HEALING_GEO), "{}", args);
+        let script = fs::read_to_string(dir.join(HEALING_GEO)).unwrap();
+        assert!(script.contains("Geometry.OCCFixSmallEdges = 1;"));
+        assert!(script.ends_with(&format!("Merge \"{}\";\n", absolute("part.STEP"))));
+
+        let defects: Vec<GeometryDefect> = mesh.geometry_warnings.iter().map(|w| w.defect).collect();
+        assert_eq!(defects, vec![GeometryDefect::SmallEdges, GeometryDefect::OpenShell]);
+        assert_eq!(mesh.geometry_warnings[0].message, "Removed 2 small edges");
+
+        // Healing can be skipped, and non-CAD files are passed through as before.
+        for (path, config) in [("part.step", MeshingConfig { skip_geometry_healing: true, ..config.clone() }), ("part.geo", config.clone())] {
+            let _ = fs::remove_file(dir.join(HEALING_GEO));
+            generate_mesh_with_config(&GeometryDefinition::File(path.to_string()), &config).unwrap();
+            assert!(fs::read_to_string(dir.join("args.txt")).unwrap().starts_with(&format!("-nopopup -batch {} ", absolute(path))));
+            assert!(!dir.join(HEALING_GEO).exists());
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_failed_cad_import_names_the_defect() {
+        let dir = test_dir("cad_healing_failure");
+        let gmsh = gmsh_script(&dir, "echo 'Warning : Found non-manifold edge 12'\necho 'Error   : Could not mesh volume 1'\nexit 1\n");
+        let config = MeshingConfig { working_dir: Some(dir.clone()), gmsh_executable: Some(gmsh), ..Default::default() };
+        fs::write(dir.join("part.step"), "").unwrap();
+
+        let err = generate_mesh_with_config(&GeometryDefinition::File("part.step".to_string()), &config).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("Geometry defects:\n  NonManifoldEdges: Found non-manifold edge 12"), "{}", message);
+        assert!(!dir.join(HEALING_GEO).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_relative_geometry_path_is_resolved_before_gmsh_runs() {
+        let dir = test_dir("relative_geometry");
+        let gmsh = gmsh_script(&dir, "echo \"$@\" > args.txt\nexit 1\n");
+        let config = MeshingConfig { working_dir: Some(dir.clone()), gmsh_executable: Some(gmsh), ..Default::default() };
+
+        // Tests run from the package root, where the fixture lives, while Gmsh runs in `dir`.
+        let fixture = fs::canonicalize(Path::new(env!("CARGO_MANIFEST_DIR")).join("dummy.msh")).unwrap();
+        let relative = fixture.strip_prefix(env::current_dir().unwrap().canonicalize().unwrap()).unwrap();
+        assert!(relative.is_relative());
+        assert!(generate_mesh_with_config(&GeometryDefinition::File(relative.display().to_string()), &config).is_err());
+        let args = fs::read_to_string(dir.join("args.txt")).unwrap();
+        assert!(args.starts_with(&format!("-nopopup -batch {} ", fixture.display())), "{}", args);
+
+        // A missing file fails before Gmsh is started.
+        fs::remove_file(dir.join("args.txt")).unwrap();
+        let err = generate_mesh_with_config(&GeometryDefinition::File("missing.step".to_string()), &config).unwrap_err();
+        assert!(err.to_string().contains("Geometry file missing.step not found"), "{}", err);
+        assert!(!dir.join("args.txt").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_non_manifold_edges_of_solids_touching_along_an_edge() {
+        assert!(non_manifold_edges(&structured_cube_mesh(2, 1.0)).is_empty());
+
+        // Two tetrahedra sharing only the edge (0, 1) have four surface faces there.
+        let mut mesh = structured_cube_mesh(1, 1.0);
+        mesh.nodes = vec![[0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [-1.0, 0.0, 0.0], [-1.0, -1.0, 0.0]];
+        mesh.elements = vec![vec![0, 1, 2, 3], vec![0, 1, 4, 5]];
+        assert_eq!(non_manifold_edges(&mesh), vec![[0, 1]]);
+    }
+
+    #[test]
+    fn test_boundary_faces_form_a_closed_outward_surface() {
+        let n = 2;
+        let mesh = structured_cube_mesh(n, 2.0);
+        let faces = boundary_faces(&mesh);
+        // Two triangles per square on each of the six cube faces.
+        assert_eq!(faces.len(), 6 * n * n * 2);
+
+        // A closed surface of genus zero has Euler characteristic V - E + F = 2.
+        let mut vertices: Vec<usize> = faces.iter().flat_map(|(_, t)| t.iter().copied()).collect();
+        vertices.sort_unstable();
+        vertices.dedup();
+        let mut edges: Vec<[usize; 2]> = faces.iter().flat_map(|(_, t)| (0..3).map(|i| [t[i].min(t[(i + 1) % 3]), t[i].max(t[(i + 1) % 3])])).collect();
+        edges.sort_unstable();
+        edges.dedup();
+        assert_eq!(vertices.len(), (n + 1).pow(3) - (n - 1).pow(3));
+        assert_eq!(vertices.len() as i64 - edges.len() as i64 + faces.len() as i64, 2);
+
+        // With outward normals the divergence theorem recovers the enclosed volume.
+        let volume: f64 = faces.iter().map(|(elem_idx, t)| {
+            assert!(mesh.elements[*elem_idx].iter().filter(|node| t.contains(node)).count() == 3);
+            signed_tetrahedron_volume([[0.0, 0.0, 0.0], mesh.nodes[t[0]], mesh.nodes[t[1]], mesh.nodes[t[2]]])
+        }).sum();
+        assert!((volume - 8.0).abs() < 1e-9, "{}", volume);
+    }
+
+    #[test]
+    fn test_boundary_faces_of_quadratic_tetrahedra_are_their_corner_triangles() {
+        let mesh = structured_cube_mesh(2, 2.0);
+        let quadratic = to_quadratic_tetrahedra(&mesh).unwrap();
+        assert_eq!(boundary_faces(&quadratic), boundary_faces(&mesh));
+    }
+
+    #[test]
+    fn test_region_areas_and_total_volume_of_unit_cube() {
+        let mut mesh = structured_cube_mesh(3, 1.0);
+        detect_coordinate_regions(&mut mesh, 1e-9);
+        assert!((total_volume(&mesh) - 1.0).abs() < 1e-12);
+
+        let areas = region_areas(&mesh);
+        assert_eq!(areas.len(), 6);
+        for (name, area) in &areas {
+            assert!((area - 1.0).abs() < 1e-12, "{}: {}", name, area);
+        }
+
+        mesh.boundary_regions.insert("corner".to_string(), vec![0]);
+        assert_eq!(region_areas(&mesh)["corner"], 0.0);
+    }
+
+    #[test]
+    fn test_extruded_l_profile_geometry() {
+        // An L of two unit-width legs, 2 long, extruded by 0.5.
+        let l_profile = vec![0.0, 0.0, 2.0, 0.0, 2.0, 1.0, 1.0, 1.0, 1.0, 2.0, 0.0, 2.0, 0.5];
+        let primitive = |dimensions: Vec<f64>| GeometricPrimitive { shape: "extruded_polygon".to_string(), dimensions, transform: None };
+        let geo = create_primitive_geometry(&primitive(l_profile.clone())).unwrap();
+        assert!(geo.contains("Point(6) = {0, 2, 0, 1.0};"));
+        assert!(geo.contains("Line(6) = {6, 1};"));
+        assert!(geo.contains("Curve Loop(1) = {1, 2, 3, 4, 5, 6};"));
+        assert!(geo.contains("Extrude {0, 0, 0.5} { Surface{1}; }"));
+
+        // An explicitly closed profile is the same polygon.
+        let mut closed = l_profile.clone();
+        closed.splice(12..12, [0.0, 0.0]);
+        assert_eq!(create_primitive_geometry(&primitive(closed)).unwrap(), geo);
+
+        let bow_tie = vec![0.0, 0.0, 1.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0];
+        let spike = vec![0.0, 0.0, 2.0, 0.0, 1.0, 0.0, 1.0, 1.0, 1.0];
+        for (dimensions, reason) in [
+            (bow_tie, "intersects itself"),
+            (spike, "folds back"),
+            (vec![0.0, 0.0, 1.0, 0.0, 1.0], "at least 3 vertices"),
+            (vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0], "height"),
+            (vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0], "requires finite dimensions"),
+        ] {
+            match create_primitive_geometry(&primitive(dimensions)) {
+                Err(EngineError::MeshingFailed(msg)) => assert!(msg.contains(reason), "Expected {:?} in {}", reason, msg),
+                other => panic!("Expected {:?}, got {:?}", reason, other),
+            }
+        }
+
+        // Meshing needs a real Gmsh, so it only runs where one is installed.
+        if !Path::new(DEFAULT_GMSH_EXECUTABLE).exists() {
+            println!("Skipping L profile meshing: no Gmsh at {}", DEFAULT_GMSH_EXECUTABLE);
+            return;
+        }
+        let dir = test_dir("extruded_l_profile");
+        let config = MeshingConfig { working_dir: Some(dir.clone()), ..Default::default() };
+        let mesh = generate_mesh_with_config(&GeometryDefinition::Primitive(primitive(l_profile)), &config).unwrap();
+        assert_eq!(mesh.element_type, "Tetrahedron");
+        assert!(mesh.num_elements() > 0);
+        assert!((total_volume(&mesh) - 3.0 * 0.5).abs() < 1e-9, "Volume {}", total_volume(&mesh));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_real_gmsh_heals_slightly_defective_step() {
+        // Needs a real Gmsh with OpenCASCADE, so it only runs where one is installed.
+        let gmsh = Path::new(DEFAULT_GMSH_EXECUTABLE);
+        if !gmsh.exists() {
+            println!("Skipping STEP healing test: no Gmsh at {}", gmsh.display());
+            return;
+        }
+        let dir = test_dir("step_healing");
+        // Two boxes separated by a gap far below the healing tolerance.
+        fs::write(dir.join("make.geo"), "SetFactory(\"OpenCASCADE\");\nBox(1) = {0, 0, 0, 1, 1, 1};\nBox(2) = {1 + 1e-9, 0, 0, 1, 1, 1};\n").unwrap();
+        let status = Command::new(gmsh).current_dir(&dir).args(["-nopopup", "-batch", "make.geo", "-0", "-o", "defective.step"]).status().unwrap();
+        assert!(status.success());
+
+        let config = MeshingConfig { working_dir: Some(dir.clone()), ..Default::default() };
+        match generate_mesh_with_config(&GeometryDefinition::File("defective.step".to_string()), &config) {
+            Ok(mesh) => {
+                assert!(mesh.num_elements() > 0);
+                println!("Healed STEP meshed with warnings: {:?}", mesh.geometry_warnings);
+            }
+            Err(e) => assert!(e.to_string().contains("Geometry defects:"), "Unexplained meshing failure: {}", e),
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -5,6 +5,8 @@
 use serde::{Serialize, Deserialize};
 use chrono::{DateTime, Utc};
 use sha2::{Sha256, Digest};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use std::io::{BufRead, Read, Write};
 
 /// Represents a single record in the provenance chain.
 #[derive(Debug, Serialize, Deserialize)]
@@ -15,6 +17,35 @@ pub struct ProvenanceRecord {
     pub software_version: String,
     pub previous_record_hash: Option<String>,
     pub metadata: serde_json::Value,
+    /// Where the hashed payload is stored when it is kept outside the record.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload_uri: Option<String>,
+    /// The build of the engine that wrote the record, when it was captured at compile time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build: Option<BuildInfo>,
+}
+
+/// Identifies the exact engine binary beyond its `software_version`.
+///
+/// `build.rs` captures the git commit, from `CORE_ENGINE_GIT_HASH` or
+/// `git rev-parse HEAD`, and the cargo profile. Like any build script output
+/// they are only refreshed when the package is rebuilt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    pub git_hash: Option<String>,
+    pub profile: Option<String>,
+}
+
+impl BuildInfo {
+    /// Returns the metadata of the running build, or `None` if none was captured.
+    pub fn current() -> Option<BuildInfo> {
+        let captured = |value: Option<&str>| value.filter(|v| !v.is_empty()).map(str::to_string);
+        let build = BuildInfo {
+            git_hash: captured(option_env!("CORE_ENGINE_GIT_HASH")),
+            profile: captured(option_env!("CORE_ENGINE_BUILD_PROFILE")),
+        };
+        (build.git_hash.is_some() || build.profile.is_some()).then_some(build)
+    }
 }
 
 impl ProvenanceRecord {
@@ -26,16 +57,26 @@ impl ProvenanceRecord {
         previous_record_hash: Option<String>,
         metadata: serde_json::Value,
     ) -> Self {
-        let timestamp = Utc::now();
-        let data_hash = calculate_hash(data);
+        Self::with_data_hash(event_type, calculate_hash(data), software_version, previous_record_hash, metadata)
+    }
 
+    /// Creates a record for data whose hash has already been computed.
+    pub fn with_data_hash(
+        event_type: String,
+        data_hash: String,
+        software_version: String,
+        previous_record_hash: Option<String>,
+        metadata: serde_json::Value,
+    ) -> Self {
         ProvenanceRecord {
-            timestamp,
+            timestamp: Utc::now(),
             event_type,
             data_hash,
             software_version,
             previous_record_hash,
             metadata,
+            payload_uri: None,
+            build: BuildInfo::current(),
         }
     }
 
@@ -53,18 +94,154 @@ fn calculate_hash(data: &[u8]) -> String {
     format!("{:x}", hasher.finalize())
 }
 
+/// How floating-point numbers in JSON payloads are written before hashing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FloatPrecision {
+    /// The shortest form that round-trips, so equal `f64` values hash alike
+    /// however they were formatted.
+    #[default]
+    Exact,
+    /// Rounded to this many significant digits, so results that differ only
+    /// in their last bits, e.g. across platforms, hash alike.
+    Significant(u32),
+}
+
+/// Largest integer up to which every integer has an exact `f64` representation.
+const MAX_EXACT_INTEGER: u64 = 1 << 53;
+
+/// Writes a JSON number in the canonical scientific notation of `precision`.
+///
+/// Integers beyond [`MAX_EXACT_INTEGER`] are kept as written, since converting
+/// them would merge distinct values.
+fn canonical_number(number: &serde_json::Number, precision: FloatPrecision) -> String {
+    if number.as_u64().is_some_and(|n| n > MAX_EXACT_INTEGER) || number.as_i64().is_some_and(|n| n.unsigned_abs() > MAX_EXACT_INTEGER) {
+        return number.to_string();
+    }
+    let x = number.as_f64().unwrap_or(0.0);
+    if x == 0.0 {
+        // Also folds negative zero.
+        return "0e0".to_string();
+    }
+    match precision {
+        FloatPrecision::Exact => format!("{:e}", x),
+        FloatPrecision::Significant(digits) => format!("{:.*e}", digits.max(1) as usize - 1, x),
+    }
+}
+
+/// Writes `value` as compact JSON with sorted object keys and canonical numbers.
+fn write_canonical(value: &serde_json::Value, precision: FloatPrecision, out: &mut String) {
+    match value {
+        serde_json::Value::Number(number) => out.push_str(&canonical_number(number, precision)),
+        serde_json::Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, precision, out);
+            }
+            out.push(']');
+        }
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+            out.push('{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&serde_json::Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(item, precision, out);
+            }
+            out.push('}');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+/// Hashes a payload, canonicalizing it first if it is JSON.
+///
+/// JSON payloads are rewritten with sorted keys, no whitespace and every number
+/// in the notation of `precision`, so the same data hashes identically however
+/// it was formatted. Other payloads are hashed as they are.
+pub fn calculate_canonical_hash(data: &[u8], precision: FloatPrecision) -> String {
+    match serde_json::from_slice::<serde_json::Value>(data) {
+        Ok(value) => {
+            let mut canonical = String::with_capacity(data.len());
+            write_canonical(&value, precision, &mut canonical);
+            calculate_hash(canonical.as_bytes())
+        }
+        Err(_) => calculate_hash(data),
+    }
+}
+
+/// Size of the buffer used by [`calculate_hash_streaming`].
+const HASH_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Calculates the SHA256 hash of everything `reader` yields, reading it in chunks.
+///
+/// Produces the same digest as hashing the full byte slice at once, without
+/// holding the payload in memory.
+pub fn calculate_hash_streaming<R: Read>(mut reader: R) -> Result<String, String> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; HASH_CHUNK_SIZE];
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(format!("Failed to read provenance payload: {}", e)),
+        };
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// A difference between two provenance chains, as reported by [`ProvenanceChain::diff`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChainDiff {
+    /// The first index at which the record hashes differ. Records from there on
+    /// belong to different lineages.
+    DivergesAt(usize),
+    /// The records at `index` differ in `field`: `event_type`, `data_hash`,
+    /// `software_version`, `build` or `metadata`.
+    FieldChanged {
+        index: usize,
+        field: &'static str,
+        left: serde_json::Value,
+        right: serde_json::Value,
+    },
+    /// Only one chain has a record at `index`; `in_left` tells which.
+    Missing { index: usize, in_left: bool },
+}
+
 /// Manages the chain of ProvenanceRecords.
 pub struct ProvenanceChain {
     records: Vec<ProvenanceRecord>,
+    float_precision: FloatPrecision,
+}
+
+impl Default for ProvenanceChain {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ProvenanceChain {
     /// Creates a new empty ProvenanceChain.
     pub fn new() -> Self {
-        ProvenanceChain { records: Vec::new() }
+        ProvenanceChain { records: Vec::new(), float_precision: FloatPrecision::default() }
+    }
+
+    /// Sets how numbers in JSON payloads are canonicalized by later [`add_record`](Self::add_record) calls.
+    pub fn set_float_precision(&mut self, precision: FloatPrecision) {
+        self.float_precision = precision;
     }
 
     /// Adds a new record to the chain.
+    ///
+    /// JSON payloads are hashed in canonical form; see [`calculate_canonical_hash`].
     pub fn add_record(
         &mut self,
         event_type: String,
@@ -73,13 +250,41 @@ impl ProvenanceChain {
         metadata: serde_json::Value,
     ) -> Result<(), String> {
         let previous_record_hash = self.records.last().map(|r| r.calculate_record_hash());
-        let record = ProvenanceRecord::new(
+        let record = ProvenanceRecord::with_data_hash(
             event_type,
-            data,
+            calculate_canonical_hash(data, self.float_precision),
+            software_version,
+            previous_record_hash,
+            metadata,
+        );
+        self.records.push(record);
+        Ok(())
+    }
+
+    /// Adds a record whose payload is hashed in chunks from `reader`.
+    ///
+    /// The payload itself is not kept. When it is stored elsewhere, `payload_uri`
+    /// records its location next to the hash so it can be retrieved and verified later.
+    /// Unlike [`add_record`](Self::add_record), the bytes are hashed as they are,
+    /// so JSON is not canonicalized.
+    pub fn add_record_streaming<R: Read>(
+        &mut self,
+        event_type: String,
+        reader: R,
+        software_version: String,
+        payload_uri: Option<String>,
+        metadata: serde_json::Value,
+    ) -> Result<(), String> {
+        let data_hash = calculate_hash_streaming(reader)?;
+        let previous_record_hash = self.records.last().map(|r| r.calculate_record_hash());
+        let mut record = ProvenanceRecord::with_data_hash(
+            event_type,
+            data_hash,
             software_version,
             previous_record_hash,
             metadata,
         );
+        record.payload_uri = payload_uri;
         self.records.push(record);
         Ok(())
     }
@@ -89,6 +294,85 @@ impl ProvenanceChain {
         &self.records
     }
 
+    /// Returns the records of the given event type, oldest first.
+    pub fn records_by_event(&self, event_type: &str) -> Vec<&ProvenanceRecord> {
+        self.records.iter().filter(|r| r.event_type == event_type).collect()
+    }
+
+    /// Returns the most recent record of the given event type.
+    pub fn latest_by_event(&self, event_type: &str) -> Option<&ProvenanceRecord> {
+        self.records.iter().rev().find(|r| r.event_type == event_type)
+    }
+
+    /// Compares this chain (the left side) with `other` record by record.
+    ///
+    /// The first entry is the [`ChainDiff::DivergesAt`] index, if any, followed
+    /// by the field differences and missing records in index order. Identical
+    /// chains yield no entries. Hashes cover the timestamps, so chains produced
+    /// by separate runs diverge at their first record even where no field differs.
+    pub fn diff(&self, other: &ProvenanceChain) -> Vec<ChainDiff> {
+        let mut diffs = Vec::new();
+        let len = self.records.len().max(other.records.len());
+        let diverges_at = (0..len).find(|&i| {
+            match (self.records.get(i), other.records.get(i)) {
+                (Some(left), Some(right)) => left.calculate_record_hash() != right.calculate_record_hash(),
+                _ => true,
+            }
+        });
+        diffs.extend(diverges_at.map(ChainDiff::DivergesAt));
+
+        for index in 0..len {
+            let (left, right) = match (self.records.get(index), other.records.get(index)) {
+                (Some(left), Some(right)) => (left, right),
+                (left, _) => {
+                    diffs.push(ChainDiff::Missing { index, in_left: left.is_some() });
+                    continue;
+                }
+            };
+            let fields = [
+                ("event_type", serde_json::json!(left.event_type), serde_json::json!(right.event_type)),
+                ("data_hash", serde_json::json!(left.data_hash), serde_json::json!(right.data_hash)),
+                ("software_version", serde_json::json!(left.software_version), serde_json::json!(right.software_version)),
+                ("build", serde_json::json!(left.build), serde_json::json!(right.build)),
+                ("metadata", left.metadata.clone(), right.metadata.clone()),
+            ];
+            for (field, left, right) in fields {
+                if left != right {
+                    diffs.push(ChainDiff::FieldChanged { index, field, left, right });
+                }
+            }
+        }
+        diffs
+    }
+
+    /// Renders the chain as a Graphviz DOT digraph, e.g. for `dot -Tsvg`.
+    ///
+    /// Each record is a node `r<index>` labeled with its event type and
+    /// timestamp. An edge runs to each record from the record whose hash its
+    /// `previous_record_hash` names; a link to a record outside the chain gets no edge.
+    pub fn to_dot(&self) -> String {
+        let escape = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"");
+        let index_by_hash: std::collections::HashMap<String, usize> =
+            self.records.iter().enumerate().map(|(index, record)| (record.calculate_record_hash(), index)).collect();
+
+        let mut dot = String::from("digraph provenance {\n    rankdir=LR;\n    node [shape=box];\n");
+        for (index, record) in self.records.iter().enumerate() {
+            dot.push_str(&format!(
+                "    r{} [label=\"{}\\n{}\"];\n",
+                index,
+                escape(&record.event_type),
+                record.timestamp.to_rfc3339()
+            ));
+        }
+        for (index, record) in self.records.iter().enumerate() {
+            if let Some(previous) = record.previous_record_hash.as_ref().and_then(|hash| index_by_hash.get(hash)) {
+                dot.push_str(&format!("    r{} -> r{};\n", previous, index));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
     /// Serializes the entire chain to a JSON string.
     pub fn to_json(&self) -> Result<String, String> {
         serde_json::to_string_pretty(&self.records)
@@ -99,7 +383,27 @@ impl ProvenanceChain {
     pub fn from_json(json_str: &str) -> Result<Self, String> {
         let records = serde_json::from_str(json_str)
             .map_err(|e| format!("Failed to deserialize provenance chain: {}", e))?;
-        Ok(ProvenanceChain { records })
+        Ok(ProvenanceChain { records, float_precision: FloatPrecision::default() })
+    }
+
+    /// Serializes the chain to JSON and gzip-compresses it.
+    ///
+    /// The payload is a plain gzip of the output of [`ProvenanceChain::to_json`],
+    /// so it can be inspected with standard tools such as `zcat`.
+    pub fn to_json_gz(&self) -> Result<Vec<u8>, String> {
+        let json = self.to_json()?;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(json.as_bytes())
+            .and_then(|_| encoder.finish())
+            .map_err(|e| format!("Failed to compress provenance chain: {}", e))
+    }
+
+    /// Deserializes a provenance chain from gzip-compressed JSON.
+    pub fn from_json_gz(bytes: &[u8]) -> Result<Self, String> {
+        let mut json = String::new();
+        GzDecoder::new(bytes).read_to_string(&mut json)
+            .map_err(|e| format!("Failed to decompress provenance chain: {}", e))?;
+        Self::from_json(&json)
     }
 
     /// Consumes the ProvenanceChain and returns its records.
@@ -111,6 +415,93 @@ impl ProvenanceChain {
     pub fn drain_records(&mut self) -> Vec<ProvenanceRecord> {
         std::mem::take(&mut self.records)
     }
+
+    /// Reads a chain written as JSON lines by a [`StreamingProvenanceChain`].
+    ///
+    /// Blank lines are skipped. Fails if a line does not parse or a record's
+    /// `previous_record_hash` does not match the hash of the record before it.
+    pub fn from_ndjson<R: BufRead>(reader: R) -> Result<Self, String> {
+        let mut records: Vec<ProvenanceRecord> = Vec::new();
+        for (line_number, line) in reader.lines().enumerate() {
+            let line = line.map_err(|e| format!("Failed to read provenance line {}: {}", line_number + 1, e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: ProvenanceRecord = serde_json::from_str(&line)
+                .map_err(|e| format!("Failed to parse provenance line {}: {}", line_number + 1, e))?;
+            let expected = records.last().map(|r| r.calculate_record_hash());
+            if record.previous_record_hash != expected {
+                return Err(format!("Provenance chain broken at line {}: previous hash does not match", line_number + 1));
+            }
+            records.push(record);
+        }
+        Ok(ProvenanceChain { records, float_precision: FloatPrecision::default() })
+    }
+}
+
+/// A provenance chain that writes each record to `writer` as soon as it is added.
+///
+/// Records are emitted as newline-delimited JSON, one record per line. Only the
+/// hash of the last record is kept in memory to link the next one, so long runs
+/// do not accumulate records. Read the output back with [`ProvenanceChain::from_ndjson`].
+pub struct StreamingProvenanceChain<W: Write> {
+    writer: W,
+    last_record_hash: Option<String>,
+    len: usize,
+    float_precision: FloatPrecision,
+}
+
+impl<W: Write> StreamingProvenanceChain<W> {
+    /// Creates a streaming chain writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        StreamingProvenanceChain { writer, last_record_hash: None, len: 0, float_precision: FloatPrecision::default() }
+    }
+
+    /// Sets how numbers in JSON payloads are canonicalized before hashing.
+    pub fn set_float_precision(&mut self, precision: FloatPrecision) {
+        self.float_precision = precision;
+    }
+
+    /// Adds a new record, linking it to the previous one, and writes it out.
+    ///
+    /// The payload is hashed like [`ProvenanceChain::add_record`] does.
+    pub fn add_record(
+        &mut self,
+        event_type: String,
+        data: &[u8],
+        software_version: String,
+        metadata: serde_json::Value,
+    ) -> Result<(), String> {
+        let record = ProvenanceRecord::with_data_hash(
+            event_type,
+            calculate_canonical_hash(data, self.float_precision),
+            software_version,
+            self.last_record_hash.clone(),
+            metadata,
+        );
+        let line = serde_json::to_string(&record).map_err(|e| format!("Failed to serialize provenance record: {}", e))?;
+        writeln!(self.writer, "{}", line)
+            .and_then(|_| self.writer.flush())
+            .map_err(|e| format!("Failed to write provenance record: {}", e))?;
+        self.last_record_hash = Some(record.calculate_record_hash());
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Returns the number of records written so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no records have been written.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Consumes the chain and returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
 }
 
 #[cfg(test)]
@@ -137,6 +528,28 @@ mod tests {
         assert_eq!(record.data_hash, expected_hash);
     }
 
+    #[test]
+    fn test_records_carry_build_metadata() {
+        let record = ProvenanceRecord::new("mesh_generation".to_string(), b"data", "v1.0.0".to_string(), None, serde_json::json!({}));
+        let build = record.build.clone().expect("build.rs sets the build profile");
+        assert_eq!(build.profile.as_deref(), option_env!("CORE_ENGINE_BUILD_PROFILE"));
+        assert!(!build.profile.unwrap().is_empty());
+        if let Some(git_hash) = option_env!("CORE_ENGINE_GIT_HASH") {
+            assert_eq!(build.git_hash.as_deref(), Some(git_hash));
+        }
+
+        // The build is part of the record hash, and records written before it
+        // existed still read and hash as they did.
+        let mut other_build: ProvenanceRecord = serde_json::from_value(serde_json::to_value(&record).unwrap()).unwrap();
+        other_build.build = Some(BuildInfo { git_hash: Some("0123abc".to_string()), profile: Some("release".to_string()) });
+        assert_ne!(other_build.calculate_record_hash(), record.calculate_record_hash());
+        let mut legacy = serde_json::to_value(&record).unwrap();
+        legacy.as_object_mut().unwrap().remove("build");
+        let legacy: ProvenanceRecord = serde_json::from_value(legacy).unwrap();
+        assert!(legacy.build.is_none());
+        assert!(!serde_json::to_string(&legacy).unwrap().contains("build"));
+    }
+
     #[test]
     fn test_provenance_chain_linking() {
         let mut chain = ProvenanceChain::new();
@@ -205,4 +618,184 @@ mod tests {
         assert_eq!(chain.records()[0].event_type, deserialized_chain.records()[0].event_type);
         assert_eq!(chain.records()[1].data_hash, deserialized_chain.records()[1].data_hash);
     }
+
+    #[test]
+    fn test_provenance_chain_gzip_round_trip() {
+        let mut chain = ProvenanceChain::new();
+        for i in 0..20 {
+            chain.add_record(
+                "solver_run".to_string(),
+                format!("iteration {}", i).as_bytes(),
+                "v1.0.0".to_string(),
+                serde_json::json!({"iteration": i}),
+            ).unwrap();
+        }
+
+        let json = chain.to_json().unwrap();
+        let compressed = chain.to_json_gz().unwrap();
+        assert!(compressed.len() < json.len(), "Compressed size {} is not smaller than {}", compressed.len(), json.len());
+
+        let restored = ProvenanceChain::from_json_gz(&compressed).unwrap();
+        assert_eq!(restored.to_json().unwrap(), json);
+    }
+
+    #[test]
+    fn test_streaming_chain_ndjson_round_trip() {
+        let mut stream = StreamingProvenanceChain::new(Vec::new());
+        for (event, data) in [("initial_setup", "config"), ("mesh_generation", "mesh"), ("solver_run", "solution")] {
+            stream.add_record(
+                event.to_string(),
+                data.as_bytes(),
+                "v1.0.0".to_string(),
+                serde_json::json!({"event": event}),
+            ).unwrap();
+        }
+        assert_eq!(stream.len(), 3);
+
+        let buffer = stream.into_inner();
+        assert_eq!(buffer.iter().filter(|&&b| b == b'\n').count(), 3);
+
+        let chain = ProvenanceChain::from_ndjson(buffer.as_slice()).unwrap();
+        let records = chain.records();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].previous_record_hash, None);
+        assert_eq!(records[1].previous_record_hash, Some(records[0].calculate_record_hash()));
+        assert_eq!(records[2].previous_record_hash, Some(records[1].calculate_record_hash()));
+        assert_eq!(records[2].data_hash, calculate_hash(b"solution"));
+
+        // Dropping the middle record breaks the linkage.
+        let text = String::from_utf8(buffer).unwrap();
+        let tampered: Vec<&str> = text.lines().enumerate().filter(|(i, _)| *i != 1).map(|(_, l)| l).collect();
+        assert!(ProvenanceChain::from_ndjson(tampered.join("\n").as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_streaming_hash_matches_one_shot_hash() {
+        // Larger than several chunks and not a multiple of the chunk size.
+        let payload: Vec<u8> = (0..(3 * HASH_CHUNK_SIZE + 12345)).map(|i| (i % 251) as u8).collect();
+        assert_eq!(calculate_hash_streaming(payload.as_slice()).unwrap(), calculate_hash(&payload));
+
+        let mut chain = ProvenanceChain::new();
+        chain.add_record("mesh_generation".to_string(), &payload, "v1.0.0".to_string(), serde_json::json!({})).unwrap();
+        chain.add_record_streaming(
+            "mesh_generation".to_string(),
+            payload.as_slice(),
+            "v1.0.0".to_string(),
+            Some("file:///data/mesh.json".to_string()),
+            serde_json::json!({}),
+        ).unwrap();
+
+        let records = chain.records();
+        assert_eq!(records[0].data_hash, records[1].data_hash);
+        assert_eq!(records[0].payload_uri, None);
+        assert_eq!(records[1].payload_uri.as_deref(), Some("file:///data/mesh.json"));
+        assert_eq!(records[1].previous_record_hash, Some(records[0].calculate_record_hash()));
+
+        // Records without a payload location serialize exactly as before.
+        assert!(!serde_json::to_string(&records[0]).unwrap().contains("payload_uri"));
+        let restored = ProvenanceChain::from_json(&chain.to_json().unwrap()).unwrap();
+        assert_eq!(restored.records()[1].payload_uri, records[1].payload_uri);
+    }
+
+    #[test]
+    fn test_diff_of_chains_diverging_at_solver_stage() {
+        let mut left = ProvenanceChain::new();
+        left.add_record("problem_definition".to_string(), b"problem", "v1.0.0".to_string(), serde_json::json!({"problem_id": "bar"})).unwrap();
+        left.add_record("mesh_generation".to_string(), b"mesh", "v1.0.0".to_string(), serde_json::json!({})).unwrap();
+
+        // The right chain shares the recorded problem and mesh, then runs a different solve.
+        let mut right = ProvenanceChain::from_json(&left.to_json().unwrap()).unwrap();
+        assert!(left.diff(&right).is_empty());
+
+        left.add_record("solver_run".to_string(), b"solution a", "v1.0.0".to_string(), serde_json::json!({"tolerance": 1e-5})).unwrap();
+        right.add_record("solver_run".to_string(), b"solution b", "v1.1.0".to_string(), serde_json::json!({"tolerance": 1e-8})).unwrap();
+        right.add_record("export".to_string(), b"vtk", "v1.1.0".to_string(), serde_json::json!({})).unwrap();
+
+        let diffs = left.diff(&right);
+        assert_eq!(diffs[0], ChainDiff::DivergesAt(2));
+        let changed: Vec<&str> = diffs
+            .iter()
+            .filter_map(|d| match d {
+                ChainDiff::FieldChanged { index: 2, field, .. } => Some(*field),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(changed, ["data_hash", "software_version", "metadata"]);
+        assert!(diffs.contains(&ChainDiff::FieldChanged {
+            index: 2,
+            field: "metadata",
+            left: serde_json::json!({"tolerance": 1e-5}),
+            right: serde_json::json!({"tolerance": 1e-8}),
+        }));
+        assert_eq!(diffs.last(), Some(&ChainDiff::Missing { index: 3, in_left: false }));
+        assert_eq!(diffs.len(), 5);
+    }
+
+    #[test]
+    fn test_canonical_hash_ignores_number_formatting() {
+        let compact = br#"{"id":"run","data":[0.1,1.0,-0.0,250,1e-7]}"#;
+        let spaced = br#"{ "data": [1e-1, 1, 0, 2.5e2, 0.0000001], "id": "run" }"#;
+        assert_ne!(calculate_hash(compact), calculate_hash(spaced));
+        assert_eq!(calculate_canonical_hash(compact, FloatPrecision::Exact), calculate_canonical_hash(spaced, FloatPrecision::Exact));
+
+        // Exact hashing tells apart values that differ in their last bit, rounding does not.
+        let noisy = br#"{"id":"run","data":[0.10000000000000002,1.0,0.0,250.0,1e-7]}"#;
+        assert_ne!(calculate_canonical_hash(compact, FloatPrecision::Exact), calculate_canonical_hash(noisy, FloatPrecision::Exact));
+        let rounded = FloatPrecision::Significant(12);
+        assert_eq!(calculate_canonical_hash(compact, rounded), calculate_canonical_hash(noisy, rounded));
+
+        // Integers that do not fit an f64 exactly stay distinct.
+        assert_ne!(calculate_canonical_hash(b"9007199254740993", rounded), calculate_canonical_hash(b"9007199254740992", rounded));
+
+        let mut chain = ProvenanceChain::new();
+        chain.set_float_precision(rounded);
+        chain.add_record("solver_run".to_string(), compact, "v1.0.0".to_string(), serde_json::json!({})).unwrap();
+        chain.add_record("solver_run".to_string(), noisy, "v1.0.0".to_string(), serde_json::json!({})).unwrap();
+        assert_eq!(chain.records()[0].data_hash, chain.records()[1].data_hash);
+    }
+
+    #[test]
+    fn test_records_by_event_type() {
+        let mut chain = ProvenanceChain::new();
+        chain.add_record("mesh_generation".to_string(), b"mesh", "v1.0.0".to_string(), serde_json::json!({})).unwrap();
+        chain.add_record("solver_run".to_string(), b"first", "v1.0.0".to_string(), serde_json::json!({"run": 1})).unwrap();
+        chain.add_record("equation_processing".to_string(), b"eqs", "v1.0.0".to_string(), serde_json::json!({})).unwrap();
+        chain.add_record("solver_run".to_string(), b"second", "v1.0.0".to_string(), serde_json::json!({"run": 2})).unwrap();
+
+        let mesh = chain.latest_by_event("mesh_generation").unwrap();
+        assert_eq!(mesh.data_hash, calculate_hash(b"mesh"));
+
+        let runs = chain.records_by_event("solver_run");
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].metadata["run"], 1);
+        assert_eq!(chain.latest_by_event("solver_run").unwrap().metadata["run"], 2);
+
+        assert!(chain.records_by_event("export").is_empty());
+        assert!(chain.latest_by_event("export").is_none());
+    }
+
+    #[test]
+    fn test_dot_graph_has_a_node_per_record_and_an_edge_per_link() {
+        let mut chain = ProvenanceChain::new();
+        for event in ["problem_definition", "mesh_generation", "solver_run"] {
+            chain.add_record(event.to_string(), event.as_bytes(), "v1.0.0".to_string(), serde_json::json!({})).unwrap();
+        }
+
+        let dot = chain.to_dot();
+        assert!(dot.starts_with("digraph provenance {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert_eq!(dot.lines().filter(|line| line.contains("[label=")).count(), 3);
+        assert_eq!(dot.lines().filter(|line| line.contains("->")).count(), 2);
+        let timestamp = chain.records()[1].timestamp.to_rfc3339();
+        assert!(dot.contains(&format!("    r1 [label=\"mesh_generation\\n{}\"];\n", timestamp)));
+        assert!(dot.contains("    r0 -> r1;\n    r1 -> r2;\n"));
+
+        // A chain continued from records elsewhere has no edge for the outside link.
+        let mut records = chain.take_records();
+        records.remove(0);
+        let tail = ProvenanceChain { records, float_precision: FloatPrecision::default() };
+        let dot = tail.to_dot();
+        assert_eq!(dot.lines().filter(|line| line.contains("[label=")).count(), 2);
+        assert_eq!(dot.lines().filter(|line| line.contains("->")).count(), 1);
+    }
 }
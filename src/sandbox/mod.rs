@@ -3,6 +3,10 @@
 
 //! Provides sandboxed execution environments for user code.
 
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::Duration;
 use wasmer::{Store, Module, Instance, Function, Value};
 
 /// Executes a WebAssembly (Wasm) module in a sandboxed environment.
@@ -48,45 +52,369 @@ pub fn run_sandboxed_wasm(wasm_bytes: &[u8]) -> Result<i32, String> {
     result[0].i32().ok_or_else(|| "Wasm function did not return an i32 value".to_string())
 }
 
-pub async fn run_sandboxed_docker(_script_path: &str, _script_content: &str) -> Result<String, String> {
-    use docker_api::Docker;
-    
-    use docker_api::opts::{ImageBuildOpts, ContainerCreateOpts, LogsOpts, ContainerRemoveOpts};
+/// Largest linear memory, in 64 KiB pages, that a Wasm solver plugin may use.
+pub const WASM_MEMORY_LIMIT_PAGES: u32 = 1024;
+
+/// Runs a Wasm solver plugin on a serialized problem.
+///
+/// The guest must export `memory`, `alloc(size: i32) -> i32` and
+/// `solve(input_ptr: i32, input_len: i32, num_nodes: i32) -> i32`. The host
+/// copies `input` into a buffer from `alloc` and calls `solve`, which returns
+/// the address of a little-endian `u32` count followed by that many `f64`
+/// values; a negative address reports failure. Like [`run_sandboxed_wasm`], the
+/// guest has no imports, and it fails if its memory exceeds [`WASM_MEMORY_LIMIT_PAGES`].
+pub fn run_wasm_solver(wasm_bytes: &[u8], input: &[u8], num_nodes: u32) -> Result<Vec<f64>, String> {
+    let mut store = Store::default();
+    let module = Module::new(&store, wasm_bytes)
+        .map_err(|e| format!("Failed to compile Wasm module: {}", e))?;
+    let instance = Instance::new(&mut store, &module, &wasmer::imports! {})
+        .map_err(|e| format!("Failed to instantiate Wasm module: {}", e))?;
+
+    let memory = instance.exports.get_memory("memory")
+        .map_err(|e| format!("Failed to find exported 'memory': {}", e))?
+        .clone();
+    let alloc = instance.exports.get_typed_function::<i32, i32>(&store, "alloc")
+        .map_err(|e| format!("Failed to find exported 'alloc' function: {}", e))?;
+    let solve = instance.exports.get_typed_function::<(i32, i32, i32), i32>(&store, "solve")
+        .map_err(|e| format!("Failed to find exported 'solve' function: {}", e))?;
+    let check_memory = |store: &Store| {
+        let pages = memory.view(store).size().0;
+        if pages > WASM_MEMORY_LIMIT_PAGES {
+            return Err(format!("Wasm solver uses {} memory pages, above the limit of {}", pages, WASM_MEMORY_LIMIT_PAGES));
+        }
+        Ok(())
+    };
+    check_memory(&store)?;
+
+    let input_len = i32::try_from(input.len()).map_err(|_| format!("Wasm solver input of {} bytes is too large", input.len()))?;
+    let num_nodes = i32::try_from(num_nodes).map_err(|_| format!("Wasm solver cannot take {} nodes", num_nodes))?;
+    let input_ptr = alloc.call(&mut store, input_len)
+        .map_err(|e| format!("Failed to call 'alloc' function: {}", e))?;
+    check_memory(&store)?;
+    memory.view(&store).write(input_ptr as u32 as u64, input)
+        .map_err(|e| format!("Failed to copy the input into Wasm memory: {}", e))?;
+
+    let output_ptr = solve.call(&mut store, input_ptr, input_len, num_nodes)
+        .map_err(|e| format!("Failed to call 'solve' function: {}", e))?;
+    check_memory(&store)?;
+    if output_ptr < 0 {
+        return Err(format!("Wasm solver reported failure with code {}", output_ptr));
+    }
+
+    let view = memory.view(&store);
+    let mut count = [0u8; 4];
+    view.read(output_ptr as u64, &mut count)
+        .map_err(|e| format!("Failed to read the Wasm solver output: {}", e))?;
+    let mut values = vec![0u8; u32::from_le_bytes(count) as usize * 8];
+    view.read(output_ptr as u64 + 4, &mut values)
+        .map_err(|e| format!("Failed to read the Wasm solver output: {}", e))?;
+    Ok(values.chunks_exact(8).map(|b| f64::from_le_bytes(b.try_into().unwrap())).collect())
+}
+
+/// The image used when no other is configured.
+const DEFAULT_DOCKER_IMAGE: &str = "python:3.10-slim";
+
+/// Options for the Docker sandbox.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SandboxConfig {
+    /// Image the container runs, used verbatim.
+    pub image: String,
+    /// Builds `Dockerfile` in `build_context` before running. Disable for ready-made images.
+    pub build_image: bool,
+    /// Directory holding the Dockerfile for image builds.
+    pub build_context: String,
+    /// How transient Docker failures are retried.
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    /// Interpreter of the local fallback when Docker is unavailable. Defaults to `python3` on the `PATH`.
+    #[serde(default)]
+    pub local_python: Option<PathBuf>,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        SandboxConfig {
+            image: DEFAULT_DOCKER_IMAGE.to_string(),
+            build_image: true,
+            build_context: ".".to_string(),
+            retry: RetryPolicy::default(),
+            local_python: None,
+        }
+    }
+}
+
+/// Bounded exponential backoff for transient Docker failures.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Attempts per operation, including the first; 1 disables retries.
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled after every further failure.
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between attempts.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+/// A failed Docker operation.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum DockerError {
+    /// May succeed if repeated, e.g. a busy daemon or a dropped connection.
+    Transient(String),
+    /// Will fail again, e.g. a missing image or an invalid request.
+    Permanent(String),
+}
+
+impl std::fmt::Display for DockerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DockerError::Transient(msg) | DockerError::Permanent(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl DockerError {
+    /// Classifies a Docker API error; connection problems, conflicts and
+    /// server-side faults are transient.
+    fn from_api(context: &str, error: docker_api::Error) -> Self {
+        use docker_api::Error;
+        use docker_api::conn::Error as ConnError;
+
+        let transient = match &error {
+            Error::Hyper(_) | Error::IO(_) | Error::ConnectionNotUpgraded => true,
+            Error::Error(ConnError::Hyper(_) | ConnError::IO(_) | ConnError::ConnectionNotUpgraded) => true,
+            Error::Fault { code, .. } | Error::Error(ConnError::Fault { code, .. }) => code.is_server_error() || code.as_u16() == 409,
+            _ => false,
+        };
+        let msg = format!("{}: {}", context, error);
+        if transient { DockerError::Transient(msg) } else { DockerError::Permanent(msg) }
+    }
+}
+
+/// Runs `operation` until it succeeds, fails permanently or runs out of attempts.
+async fn with_retry<T, F, Fut>(policy: &RetryPolicy, name: &str, mut operation: F) -> Result<T, String>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, DockerError>>,
+{
+    let mut backoff = policy.initial_backoff;
+    let mut attempt = 1;
+    loop {
+        match operation().await {
+            Ok(value) => return Ok(value),
+            Err(DockerError::Transient(msg)) if attempt < policy.max_attempts => {
+                println!("Warning: {} failed (attempt {}/{}), retrying in {:?}: {}", name, attempt, policy.max_attempts, backoff, msg);
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(policy.max_backoff);
+                attempt += 1;
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+/// The container operations of a sandbox run.
+///
+/// Implemented for [`docker_api::Docker`]; tests substitute their own backend.
+pub(crate) trait ContainerBackend {
+    /// Builds the image if configured and creates a container, returning its id.
+    async fn create(&self, config: &SandboxConfig) -> Result<String, DockerError>;
+    /// Starts the container.
+    async fn start(&self, id: &str) -> Result<(), DockerError>;
+    /// Waits for the container to exit.
+    async fn wait(&self, id: &str) -> Result<(), DockerError>;
+    /// Collects the container's stdout and stderr.
+    async fn logs(&self, id: &str) -> Result<String, DockerError>;
+    /// Force-removes the container.
+    async fn remove(&self, id: &str) -> Result<(), DockerError>;
+}
+
+impl ContainerBackend for docker_api::Docker {
+    async fn create(&self, config: &SandboxConfig) -> Result<String, DockerError> {
+        Ok(create_container(self, config).await?.id().to_string())
+    }
+
+    async fn start(&self, id: &str) -> Result<(), DockerError> {
+        self.containers().get(id).start().await.map_err(|e| DockerError::from_api("Failed to start container", e))
+    }
+
+    async fn wait(&self, id: &str) -> Result<(), DockerError> {
+        self.containers().get(id).wait().await.map(|_| ()).map_err(|e| DockerError::from_api("Failed to wait for container", e))
+    }
+
+    async fn logs(&self, id: &str) -> Result<String, DockerError> {
+        use docker_api::opts::LogsOpts;
+        use futures_util::stream::StreamExt;
+
+        let container = self.containers().get(id);
+        let chunks: Vec<_> = container.logs(&LogsOpts::builder().stdout(true).stderr(true).build()).collect().await;
+        let logs: Vec<String> = chunks
+            .into_iter()
+            .map(|chunk| chunk.map(tty_chunk_text).map_err(|e| DockerError::from_api("Failed to read container logs", e)))
+            .collect::<Result<_, _>>()?;
+        Ok(logs.join("\n"))
+    }
+
+    async fn remove(&self, id: &str) -> Result<(), DockerError> {
+        use docker_api::opts::ContainerRemoveOpts;
+
+        self.containers()
+            .get(id)
+            .remove(&ContainerRemoveOpts::builder().force(true).build())
+            .await
+            .map(|_| ())
+            .map_err(|e| DockerError::from_api("Failed to remove container", e))
+    }
+}
+
+impl SandboxConfig {
+    /// Uses an existing image, e.g. one with SymPy preinstalled, without building.
+    pub fn with_image(image: impl Into<String>) -> Self {
+        SandboxConfig {
+            image: image.into(),
+            build_image: false,
+            ..Default::default()
+        }
+    }
+}
+
+/// Runs a script in a Docker container using the default [`SandboxConfig`].
+pub async fn run_sandboxed_docker(script_path: &str, script_content: &str) -> Result<String, String> {
+    run_sandboxed_docker_with_config(script_path, script_content, &SandboxConfig::default()).await
+}
+
+/// Creates the sandbox container from the configured image, building it first if requested.
+async fn create_container(docker: &docker_api::Docker, config: &SandboxConfig) -> Result<docker_api::Container, DockerError> {
+    use docker_api::opts::{ImageBuildOpts, ContainerCreateOpts};
     use futures_util::stream::StreamExt;
 
-    // 1. Create a new Docker instance.
-    let docker = Docker::new("unix:///var/run/docker.sock").unwrap();
+    // Build the Docker image unless a ready image was supplied.
+    if config.build_image {
+        let images = docker.images();
+        let build_opts = ImageBuildOpts::builder(&config.build_context).dockerfile("Dockerfile").build();
+        let mut stream = images.build(&build_opts);
+        while let Some(result) = stream.next().await {
+            result.map_err(|e| DockerError::from_api("Failed to build Docker image", e))?;
+        }
+    }
 
-    // 2. Build the Docker image.
-    let images = docker.images();
-    let build_opts = ImageBuildOpts::builder(".").dockerfile("Dockerfile").build();
-    let mut stream = images.build(&build_opts);
-    while let Some(result) = stream.next().await {
-        result.unwrap();
+    let create_opts = ContainerCreateOpts::builder().image(&config.image).build();
+    docker.containers().create(&create_opts).await.map_err(|e| DockerError::from_api(&format!("Failed to create container from {}", config.image), e))
+}
+
+/// Runs a script in a Docker container described by `config`.
+pub async fn run_sandboxed_docker_with_config(_script_path: &str, _script_content: &str, config: &SandboxConfig) -> Result<String, String> {
+    let docker = docker_api::Docker::new("unix:///var/run/docker.sock").map_err(|e| format!("Failed to connect to Docker: {}", e))?;
+    run_container(&docker, config).await
+}
+
+/// Creates, runs and removes a container, retrying transient failures of the
+/// create, start and wait steps as `config.retry` allows.
+async fn run_container(backend: &impl ContainerBackend, config: &SandboxConfig) -> Result<String, String> {
+    let policy = &config.retry;
+    let id = with_retry(policy, "container create", || backend.create(config)).await?;
+
+    let output = async {
+        with_retry(policy, "container start", || backend.start(&id)).await?;
+        with_retry(policy, "container wait", || backend.wait(&id)).await?;
+        backend.logs(&id).await.map_err(|e| e.to_string())
     }
+    .await;
 
-    // 3. Create the container.
-    let create_opts = ContainerCreateOpts::builder().image("python:3.10-slim").build();
-    let container = docker.containers().create(&create_opts).await.unwrap();
+    // Remove the container even when the run failed, reporting the run's error first.
+    let removed = backend.remove(&id).await.map_err(|e| e.to_string());
+    let output = output?;
+    removed?;
+    Ok(output)
+}
+
+/// The interpreter used by the local sandbox when none is configured.
+const DEFAULT_LOCAL_PYTHON: &str = "python3";
 
-    // 4. Start the container.
-    container.start().await.unwrap();
+/// The captured result of a script run by the local sandbox.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SandboxOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// The exit status, or `None` if the process was killed by a signal.
+    pub exit_code: Option<i32>,
+}
 
-    // 5. Wait for the container to finish and get the logs.
-    container.wait().await.unwrap();
-    let logs_stream = container.logs(&LogsOpts::builder().stdout(true).stderr(true).build());
-    let logs: Vec<String> = logs_stream.map(|l| {
-        match l.unwrap() {
-            docker_api::conn::TtyChunk::StdOut(bytes) => String::from_utf8(bytes).unwrap(),
-            docker_api::conn::TtyChunk::StdErr(bytes) => String::from_utf8(bytes).unwrap(),
-            _ => "".to_string(),
+impl SandboxOutput {
+    /// Returns the standard output of a successful run, or the standard error of a failed one.
+    pub fn into_stdout(self) -> Result<String, String> {
+        match self.exit_code {
+            Some(0) => Ok(self.stdout),
+            code => Err(format!("script exited with status {:?}: {}", code, self.stderr.trim())),
         }
-    }).collect().await;
+    }
+}
+
+/// Runs a Python script with the local `python3`, feeding it `stdin`.
+pub fn run_sandboxed_local(script_path: &str, stdin: &str) -> Result<SandboxOutput, String> {
+    run_sandboxed_local_with_config(script_path, stdin, &SandboxConfig::default())
+}
+
+/// Runs a Python script with the interpreter of `config.local_python`, feeding it `stdin`.
+///
+/// This is a lightweight alternative to Docker for desktop use: the script runs
+/// once, without retries, as an ordinary process with the user's permissions,
+/// so it offers no isolation. Fails if the interpreter cannot be started; a
+/// script that fails is reported through the returned exit code.
+pub fn run_sandboxed_local_with_config(script_path: &str, stdin: &str, config: &SandboxConfig) -> Result<SandboxOutput, String> {
+    let python = config.local_python.clone().unwrap_or_else(|| PathBuf::from(DEFAULT_LOCAL_PYTHON));
 
-    // 6. Clean up the container.
-    container.remove(&ContainerRemoveOpts::builder().force(true).build()).await.unwrap();
+    // Check the interpreter runs before handing it the script.
+    let version = Command::new(&python)
+        .arg("--version")
+        .output()
+        .map_err(|e| format!("Python interpreter {} is not available: {}", python.display(), e))?;
+    if !version.status.success() {
+        return Err(format!("Python interpreter {} failed to report its version: {}", python.display(), String::from_utf8_lossy(&version.stderr).trim()));
+    }
 
-    Ok(logs.join("\n"))
+    let mut child = Command::new(&python)
+        .arg(script_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", python.display(), e))?;
+    // A script that exits without reading its input closes the pipe early, which is not an error.
+    if let Some(mut input) = child.stdin.take() {
+        if let Err(e) = input.write_all(stdin.as_bytes()) {
+            if e.kind() != std::io::ErrorKind::BrokenPipe {
+                return Err(format!("Failed to write the script input: {}", e));
+            }
+        }
+    }
+    let output = child.wait_with_output().map_err(|e| format!("Failed to wait for {}: {}", python.display(), e))?;
+
+    Ok(SandboxOutput {
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        exit_code: output.status.code(),
+    })
+}
+
+/// Decodes a chunk of container output.
+///
+/// Containers may print arbitrary bytes, so invalid UTF-8 is replaced with
+/// U+FFFD instead of failing the run.
+fn tty_chunk_text(chunk: docker_api::conn::TtyChunk) -> String {
+    match chunk {
+        docker_api::conn::TtyChunk::StdOut(bytes) | docker_api::conn::TtyChunk::StdErr(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        _ => String::new(),
+    }
 }
 
 #[cfg(test)]
@@ -125,6 +453,134 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_non_utf8_container_output_is_decoded_lossily() {
+        use docker_api::conn::TtyChunk;
+
+        assert_eq!(tty_chunk_text(TtyChunk::StdOut(b"x = 1\n".to_vec())), "x = 1\n");
+        assert_eq!(tty_chunk_text(TtyChunk::StdErr(vec![b'o', 0xff, b'k'])), "o\u{fffd}k");
+        assert_eq!(tty_chunk_text(TtyChunk::StdIn(vec![0xc3])), "");
+    }
+
+    #[test]
+    fn test_with_image_skips_build() {
+        let config = SandboxConfig::with_image("registry.example.com/sympy:1.12-py3.11");
+        assert_eq!(config.image, "registry.example.com/sympy:1.12-py3.11");
+        assert!(!config.build_image);
+        assert!(SandboxConfig::default().build_image);
+    }
+
+    #[test]
+    fn test_local_python_runs_script() {
+        let dir = std::env::temp_dir().join(format!("core_engine_local_python_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("add.py");
+        std::fs::write(&script, "print(1+1)\n").unwrap();
+
+        let output = run_sandboxed_local(script.to_str().unwrap(), "").unwrap();
+        assert_eq!(output.exit_code, Some(0));
+        assert_eq!(output.into_stdout().unwrap().trim(), "2");
+
+        let echo = dir.join("echo.py");
+        std::fs::write(&echo, "import sys\ndata = sys.stdin.read()\nprint(data.upper())\nsys.exit(3 if data else 0)\n").unwrap();
+        let output = run_sandboxed_local(echo.to_str().unwrap(), "x + y").unwrap();
+        assert_eq!((output.stdout.trim(), output.exit_code), ("X + Y", Some(3)));
+        assert!(output.into_stdout().is_err());
+
+        let config = SandboxConfig { local_python: Some(dir.join("no_such_python")), ..Default::default() };
+        let err = run_sandboxed_local_with_config(script.to_str().unwrap(), "", &config).unwrap_err();
+        assert!(err.contains("is not available"), "{}", err);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// A backend whose container creation fails with the queued errors first.
+    #[derive(Default)]
+    struct FlakyBackend {
+        create_errors: std::cell::RefCell<Vec<DockerError>>,
+        calls: std::cell::RefCell<Vec<&'static str>>,
+    }
+
+    impl ContainerBackend for FlakyBackend {
+        async fn create(&self, _config: &SandboxConfig) -> Result<String, DockerError> {
+            self.calls.borrow_mut().push("create");
+            match self.create_errors.borrow_mut().pop() {
+                Some(error) => Err(error),
+                None => Ok("sandbox-1".to_string()),
+            }
+        }
+
+        async fn start(&self, _id: &str) -> Result<(), DockerError> {
+            self.calls.borrow_mut().push("start");
+            Ok(())
+        }
+
+        async fn wait(&self, _id: &str) -> Result<(), DockerError> {
+            self.calls.borrow_mut().push("wait");
+            Ok(())
+        }
+
+        async fn logs(&self, _id: &str) -> Result<String, DockerError> {
+            Ok("3".to_string())
+        }
+
+        async fn remove(&self, _id: &str) -> Result<(), DockerError> {
+            self.calls.borrow_mut().push("remove");
+            Ok(())
+        }
+    }
+
+    fn fast_retry() -> SandboxConfig {
+        SandboxConfig {
+            retry: RetryPolicy {
+                max_attempts: 3,
+                initial_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(2),
+            },
+            ..Default::default()
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_transient_create_failure_is_retried() {
+        let backend = FlakyBackend::default();
+        backend.create_errors.borrow_mut().push(DockerError::Transient("daemon busy".to_string()));
+
+        assert_eq!(run_container(&backend, &fast_retry()).await.unwrap(), "3");
+        assert_eq!(*backend.calls.borrow(), ["create", "create", "start", "wait", "remove"]);
+    }
+
+    #[actix_rt::test]
+    async fn test_permanent_failure_is_not_retried() {
+        let backend = FlakyBackend::default();
+        backend.create_errors.borrow_mut().push(DockerError::Permanent("No such image".to_string()));
+
+        assert_eq!(run_container(&backend, &fast_retry()).await.unwrap_err(), "No such image");
+        assert_eq!(*backend.calls.borrow(), ["create"]);
+    }
+
+    #[actix_rt::test]
+    async fn test_retries_are_bounded() {
+        let backend = FlakyBackend::default();
+        backend.create_errors.borrow_mut().extend((0..5).map(|_| DockerError::Transient("daemon busy".to_string())));
+
+        assert!(run_container(&backend, &fast_retry()).await.is_err());
+        assert_eq!(*backend.calls.borrow(), ["create"; 3]);
+    }
+
+    // Requires a Docker daemon with the `python:3.11-slim` image available.
+    #[cfg(feature = "docker")]
+    #[actix_rt::test]
+    async fn test_provided_image_is_used_verbatim() {
+        let docker = docker_api::Docker::new("unix:///var/run/docker.sock").unwrap();
+        let config = SandboxConfig::with_image("python:3.11-slim");
+
+        let container = create_container(&docker, &config).await.unwrap();
+        let details = container.inspect().await.unwrap();
+        container.remove(&docker_api::opts::ContainerRemoveOpts::builder().force(true).build()).await.unwrap();
+
+        assert_eq!(details.config.and_then(|c| c.image).as_deref(), Some("python:3.11-slim"));
+    }
+
     // #[actix_rt::test]
     // async fn test_docker_sandboxing() {
     //     // This test requires Docker to be running.
@@ -0,0 +1,124 @@
+// src/solver/assembly.rs
+
+//! Assembly and constraint helpers shared by nodal finite element solvers.
+//!
+//! Global unknowns are numbered node-major: component `c` of node `n` is
+//! `n * dofs_per_node + c`. Element matrices use the same ordering over the
+//! element's nodes.
+
+use nalgebra::{ComplexField, DMatrix, DVector, RealField};
+
+/// A single `(global_row, global_col, value)` matrix contribution.
+pub type Triplet = (usize, usize, f64);
+
+/// The kind of field a physics solves for, which fixes the unknowns per node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    /// One unknown per node, such as a temperature.
+    Scalar,
+    /// Three unknowns per node, such as a displacement.
+    Vector,
+}
+
+impl FieldKind {
+    /// Returns the number of degrees of freedom per node.
+    pub const fn dofs_per_node(self) -> usize {
+        match self {
+            FieldKind::Scalar => 1,
+            FieldKind::Vector => 3,
+        }
+    }
+}
+
+/// Returns the global index of `component` at `node`.
+pub fn global_dof(field: FieldKind, node: usize, component: usize) -> usize {
+    node * field.dofs_per_node() + component
+}
+
+/// Scatters an element matrix over `element`'s nodes into global triplets.
+pub fn element_triplets(field: FieldKind, element: &[usize], ke: &DMatrix<f64>) -> Vec<Triplet> {
+    let dofs = field.dofs_per_node();
+    let mut triplets = Vec::with_capacity(ke.len());
+    for (i, &node_i) in element.iter().enumerate() {
+        for (j, &node_j) in element.iter().enumerate() {
+            for dof_i in 0..dofs {
+                for dof_j in 0..dofs {
+                    triplets.push((
+                        global_dof(field, node_i, dof_i),
+                        global_dof(field, node_j, dof_j),
+                        ke[(i * dofs + dof_i, j * dofs + dof_j)],
+                    ));
+                }
+            }
+        }
+    }
+    triplets
+}
+
+/// Sums triplets into a dense global matrix for `num_nodes` nodes.
+///
+/// Each contribution is rounded to `T` before it is summed, so an `f32`
+/// matrix never exists in double precision first.
+pub fn assemble_triplets<T: RealField + Copy>(field: FieldKind, num_nodes: usize, triplets: impl IntoIterator<Item = Triplet>) -> DMatrix<T> {
+    let total_dof = num_nodes * field.dofs_per_node();
+    let mut global = DMatrix::<T>::zeros(total_dof, total_dof);
+    for (row, col, value) in triplets {
+        global[(row, col)] += T::from_subset(&value);
+    }
+    global
+}
+
+/// Imposes `(dof, value)` constraints on an assembled system by elimination.
+///
+/// Each known value is moved to the right-hand side, its row and column are
+/// zeroed with a unit diagonal, and its right-hand side entry becomes the value.
+/// Real and complex systems are handled alike.
+pub fn apply_dirichlet<T: ComplexField + Copy>(k: &mut DMatrix<T>, f: &mut DVector<T>, prescribed: &[(usize, T)]) {
+    let total_dof = k.nrows();
+    for &(dof_idx, value) in prescribed {
+        for row in 0..total_dof {
+            f[row] -= k[(row, dof_idx)] * value;
+        }
+        k.row_mut(dof_idx).fill(T::zero());
+        k.column_mut(dof_idx).fill(T::zero());
+        k[(dof_idx, dof_idx)] = T::one();
+    }
+    for &(dof_idx, value) in prescribed {
+        f[dof_idx] = value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scalar_and_vector_assembly_share_the_code_path() {
+        let mesh = crate::meshing::structured_cube_mesh(1, 1.0);
+        // A graph-Laplacian element matrix: symmetric with zero row sums.
+        let scalar_ke = DMatrix::from_fn(4, 4, |i, j| if i == j { 3.0 } else { -1.0 });
+        let vector_ke = scalar_ke.kronecker(&DMatrix::<f64>::identity(3, 3));
+
+        let assemble = |field, ke: &DMatrix<f64>| -> DMatrix<f64> {
+            assemble_triplets(field, mesh.nodes.len(), mesh.elements.iter().flat_map(|element| element_triplets(field, element, ke)))
+        };
+        let scalar = assemble(FieldKind::Scalar, &scalar_ke);
+        let vector = assemble(FieldKind::Vector, &vector_ke);
+
+        assert_eq!(scalar.shape(), (8, 8));
+        assert_eq!(vector.shape(), (24, 24));
+        assert!(scalar.row_iter().all(|row| row.sum().abs() < 1e-12));
+        assert_eq!(vector, scalar.kronecker(&DMatrix::<f64>::identity(3, 3)));
+        assert_eq!(global_dof(FieldKind::Vector, 5, 2), 17);
+
+        let mut k = scalar.clone();
+        let mut f = DVector::zeros(8);
+        apply_dirichlet(&mut k, &mut f, &[(0, 1.0)]);
+        assert_eq!(f[0], 1.0);
+        assert_eq!(k.row(0).sum(), 1.0);
+        assert_eq!(k.column(0).sum(), 1.0);
+        for row in 1..8 {
+            assert_eq!(f[row], -scalar[(row, 0)]);
+        }
+    }
+}
@@ -3,67 +3,720 @@
 
 //! A basic Finite Difference Method (FDM) solver.
 
-use crate::{ProblemDefinition, EngineError};
+use crate::{ProblemDefinition, EngineError, BcValue, TimeStepping};
+use crate::kernel::integrate::{self, SystemMatrices, TimeScheme};
 use crate::solver::Solver;
-use nalgebra::{DMatrix, DVector};
+use crate::symbolic::weak_form::route_physics;
+use nalgebra::{DMatrix, DVector, Matrix3};
+use std::collections::HashMap;
 
-/// A simple FDM solver for 1D steady-state heat conduction.
+/// A simple FDM solver for steady-state heat conduction.
 ///
-/// This solver discretizes a 1D domain and solves for the temperature
-/// distribution given boundary conditions.
+/// This solver discretizes a 1D domain, or a 2D/3D structured grid when
+/// `SolverSettings::grid_dimensions` gives more than one axis, and solves for the
+/// temperature distribution given boundary conditions. If the physics contains a
+/// Poisson equation `div(grad(T)) = f` with a constant source `f`, the source is applied.
+///
+/// Conduction uses the material's `thermal_conductivity` `K`, solving
+/// `div(K grad(T)) = f`; without one `K` is the identity. Grids use the full
+/// tensor, including the mixed derivatives of off-diagonal terms, while 1D
+/// domains use its `xx` component.
+///
+/// With `SolverSettings::time_stepping` set, a 1D domain is instead marched in
+/// time under `dT/dt = div(K grad(T)) - f`, with unit heat capacity, whose steady state is the Poisson solution.
 pub struct FdmSolver;
 
+/// Reads the conductivity tensor of the material, the identity when it has none.
+pub(crate) fn conductivity(problem: &ProblemDefinition) -> Result<Matrix3<f64>, EngineError> {
+    problem.physics.material.thermal_conductivity.as_ref().map_or(Ok(Matrix3::identity()), |k| k.tensor())
+}
+
+/// A boundary condition at one end of the 1D domain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EndCondition {
+    /// Prescribed temperature.
+    Fixed(f64),
+    /// Convective exchange `-k dT/dn = h (T - t_inf)`.
+    Convective { h: f64, t_inf: f64 },
+}
+
+/// Finds the condition a problem prescribes on `region`, if any.
+fn end_condition(problem: &ProblemDefinition, region: &str) -> Result<Option<EndCondition>, EngineError> {
+    let Some(bc) = problem.physics.boundary_conditions.iter().find(|bc| bc.region == region) else {
+        return Ok(None);
+    };
+    match (bc.condition_type.as_str(), bc.value) {
+        ("Dirichlet", BcValue::Scalar(t)) => Ok(Some(EndCondition::Fixed(t))),
+        ("Convective", BcValue::Convective { h, t_inf }) => Ok(Some(EndCondition::Convective { h, t_inf })),
+        (condition_type, value) => Err(EngineError::SolverFailed(format!("FdmSolver cannot apply a {} condition with value {:?} on {}", condition_type, value, region))),
+    }
+}
+
+/// The 1D system matrix, stored as its three bands in the layout of
+/// [`kernel::solve_tridiagonal`](crate::kernel::solve_tridiagonal).
+#[derive(Debug, Clone, PartialEq)]
+struct Tridiagonal {
+    lower: Vec<f64>,
+    diag: Vec<f64>,
+    upper: Vec<f64>,
+}
+
+impl Tridiagonal {
+    fn zeros(n: usize) -> Self {
+        Tridiagonal { lower: vec![0.0; n - 1], diag: vec![0.0; n], upper: vec![0.0; n - 1] }
+    }
+
+    /// Sets entry `(row, col)`, which must lie on one of the bands.
+    fn set(&mut self, row: usize, col: usize, value: f64) {
+        match col as isize - row as isize {
+            -1 => self.lower[col] = value,
+            0 => self.diag[row] = value,
+            1 => self.upper[row] = value,
+            _ => panic!("({}, {}) is outside the tridiagonal bands", row, col),
+        }
+    }
+}
+
+/// Writes the boundary row for `node`, whose interior neighbour is `inner`.
+///
+/// Convective ends use a one-sided difference for the outward normal gradient.
+fn apply_end_condition(a: &mut Tridiagonal, b: &mut DVector<f64>, node: usize, inner: usize, condition: EndCondition, dx: f64, k: f64) {
+    match condition {
+        EndCondition::Fixed(t) => {
+            a.set(node, node, 1.0);
+            b[node] = t;
+        }
+        EndCondition::Convective { h, t_inf } => {
+            // -k (T_node - T_inner) / dx = h (T_node - t_inf)
+            a.set(node, node, -k / dx - h);
+            a.set(node, inner, k / dx);
+            b[node] = -h * t_inf;
+        }
+    }
+}
+
+/// Nodes along the 1D domain when no grid is given.
+const DEFAULT_NUM_NODES: usize = 11;
+
+/// Reads the nodes per axis from the solver settings.
+///
+/// `grid_dimensions` must have 1 to 3 axes of at least 3 nodes. Without it the
+/// grid is 1D with `grid_resolution` nodes, at least 2, or [`DEFAULT_NUM_NODES`].
+fn grid_dimensions(problem: &ProblemDefinition) -> Result<Vec<usize>, EngineError> {
+    let settings = &problem.solver_settings;
+    match (&settings.grid_dimensions, settings.grid_resolution) {
+        (Some(_), Some(_)) => Err(EngineError::SolverFailed("FdmSolver accepts either grid_dimensions or grid_resolution, not both".to_string())),
+        (Some(dims), None) => {
+            if dims.is_empty() || dims.len() > 3 || dims.iter().any(|&n| n < 3) {
+                return Err(EngineError::SolverFailed(format!("FdmSolver needs 1 to 3 grid dimensions of at least 3 nodes, got {:?}", dims)));
+            }
+            Ok(dims.clone())
+        }
+        (None, resolution) => {
+            let num_nodes = resolution.unwrap_or(DEFAULT_NUM_NODES);
+            if num_nodes < 2 {
+                return Err(EngineError::SolverFailed(format!("FdmSolver needs a grid resolution of at least 2 nodes, got {}", num_nodes)));
+            }
+            Ok(vec![num_nodes])
+        }
+    }
+}
+
+/// Reads the constant source term `f` of `div(grad(T)) = f`, zero for pure conduction.
+fn constant_source(problem: &ProblemDefinition) -> Result<f64, EngineError> {
+    Ok(route_physics(&problem.physics)
+        .map(|route| route.form.constant_source().ok_or_else(|| EngineError::SolverFailed("FdmSolver only supports constant source terms".to_string())))
+        .transpose()?
+        .unwrap_or(0.0))
+}
+
+/// Checks that a transient solve is 1D, implicit and takes at least one positive step.
+///
+/// The boundary rows of the 1D system are algebraic constraints without a time
+/// derivative, which only the implicit schemes can enforce.
+fn check_time_stepping(stepping: &TimeStepping, num_axes: usize) -> Result<(), EngineError> {
+    if num_axes != 1 {
+        return Err(EngineError::SolverFailed(format!("FdmSolver only supports time stepping in 1D, got a {}D grid", num_axes)));
+    }
+    if !matches!(stepping.scheme, TimeScheme::BackwardEuler | TimeScheme::CrankNicolson) {
+        return Err(EngineError::SolverFailed(format!("FdmSolver needs an implicit time scheme, got {:?}", stepping.scheme)));
+    }
+    if !(stepping.time_step > 0.0 && stepping.time_step.is_finite()) || stepping.num_steps == 0 {
+        return Err(EngineError::SolverFailed(format!(
+            "FdmSolver needs a positive time step and at least one step, got {} steps of {}",
+            stepping.num_steps, stepping.time_step
+        )));
+    }
+    Ok(())
+}
+
+/// Face region names per axis as `(negative side, positive side)`.
+const GRID_FACES: [(&str, &str); 3] = [("face_x_neg", "face_x_pos"), ("face_y_neg", "face_y_pos"), ("face_z_neg", "face_z_pos")];
+
 impl Solver for FdmSolver {
     fn name(&self) -> &'static str {
         "FdmSolver"
     }
 
-    fn solve(&self, _problem: &mut ProblemDefinition) -> Result<super::SolverSolutionData, EngineError> {
+    fn capabilities(&self) -> super::SolverCapabilities {
+        super::SolverCapabilities { physics: vec![super::PhysicsKind::HeatConduction], dimensions: vec![1, 2, 3] }
+    }
+
+    fn check_compatibility(&self, problem: &ProblemDefinition) -> Result<(), EngineError> {
+        let dims = grid_dimensions(problem)?;
+        conductivity(problem)?;
+        for &(neg, pos) in &GRID_FACES[..dims.len()] {
+            for region in [neg, pos] {
+                if let Some(EndCondition::Convective { .. }) = end_condition(problem, region)? {
+                    if dims.len() > 1 {
+                        return Err(EngineError::SolverFailed(format!("FdmSolver only supports convective conditions in 1D, found one on {}", region)));
+                    }
+                }
+            }
+        }
+        if let Some(stepping) = &problem.solver_settings.time_stepping {
+            check_time_stepping(stepping, dims.len())?;
+        }
+        if let Some(route) = route_physics(&problem.physics) {
+            if route.form.constant_source().is_none() {
+                return Err(EngineError::SolverFailed(format!("FdmSolver only supports constant source terms, got {:?}", route.form)));
+            }
+        }
+        Ok(())
+    }
+
+    fn solve(&self, problem: &ProblemDefinition) -> Result<super::SolverSolutionData, EngineError> {
+        let dims = grid_dimensions(problem)?;
+        let source = constant_source(problem)?;
+        if let Some(stepping) = &problem.solver_settings.time_stepping {
+            check_time_stepping(stepping, dims.len())?;
+            let history = self.solve_1d_transient(problem, dims[0], source, stepping)?;
+            println!("--- FdmSolver Finished ---");
+            let time_steps: Vec<(f64, Vec<f64>)> = history.into_iter().map(|(t, u)| (t, u.iter().cloned().collect())).collect();
+            return Ok(super::SolverSolutionData {
+                data: time_steps.last().map(|(_, field)| field.clone()).unwrap_or_default(),
+                time_steps,
+                fields: HashMap::new(),
+                metadata: serde_json::Map::new(),
+                warnings: Vec::new(),
+                iterations: Vec::new(),
+            });
+        }
+        let t_solution = if dims.len() == 1 {
+            self.solve_1d(problem, dims[0], source)?
+        } else {
+            self.solve_grid(problem, &dims, source)?
+        };
+
+        // Return temperatures as solution data.
+        println!("--- FdmSolver Finished ---");
+
+        Ok(super::SolverSolutionData {
+            data: t_solution.iter().cloned().collect(), // Convert DVector to Vec<f64>
+            time_steps: Vec::new(),
+            fields: HashMap::new(),
+            metadata: serde_json::Map::new(),
+            warnings: Vec::new(),
+            iterations: Vec::new(),
+        })
+    }
+}
+
+impl FdmSolver {
+    /// Solves 1D steady-state heat conduction on `[0, 1]` with `num_nodes` nodes.
+    fn solve_1d(&self, problem: &ProblemDefinition, num_nodes: usize, source: f64) -> Result<DVector<f64>, EngineError> {
         println!("--- Running FdmSolver (1D Heat Conduction) ---");
 
-        // For simplicity, we'll assume a 1D domain of length L with N nodes.
-        // The problem definition should ideally contain these parameters.
-        // For now, we hardcode them for demonstration.
+        let (a_global, b_global) = self.assemble_1d(problem, num_nodes, source)?;
+
+        // Solve for nodal temperatures (T); the stencil only couples neighbours.
+        crate::kernel::solve_tridiagonal(&a_global.lower, &a_global.diag, &a_global.upper, b_global.as_slice())
+            .map_err(|_| EngineError::SolverFailed("FDM matrix is singular.".to_string()))
+    }
+
+    /// Marches 1D heat conduction on `[0, 1]` from a uniform initial temperature.
+    ///
+    /// Interior rows of the steady system `A T = b` become `dT/dt = (A T - b) / dx^2`,
+    /// while the boundary rows keep no time derivative and hold at every step. The
+    /// initial state satisfies them too, so it is consistent for Crank-Nicolson.
+    fn solve_1d_transient(&self, problem: &ProblemDefinition, num_nodes: usize, source: f64, stepping: &TimeStepping) -> Result<Vec<(f64, DVector<f64>)>, EngineError> {
+        println!("--- Running FdmSolver (1D Transient Heat Conduction) ---");
+
+        let (a, b) = self.assemble_1d(problem, num_nodes, source)?;
+        let dx = 1.0 / (num_nodes - 1) as f64;
+        let last = num_nodes - 1;
+        let is_boundary = |i: usize| i == 0 || i == last;
+
+        let band = |i: usize, j: usize| match j as isize - i as isize {
+            -1 => a.lower[j],
+            0 => a.diag[i],
+            1 => a.upper[i],
+            _ => 0.0,
+        };
+        let scale = |i: usize| if is_boundary(i) { 1.0 } else { -1.0 / (dx * dx) };
+        let system = SystemMatrices {
+            mass: DMatrix::from_fn(num_nodes, num_nodes, |i, j| if i == j && !is_boundary(i) { 1.0 } else { 0.0 }),
+            stiffness: DMatrix::from_fn(num_nodes, num_nodes, |i, j| scale(i) * band(i, j)),
+            load: DVector::from_fn(num_nodes, |i, _| scale(i) * b[i]),
+        };
+
+        let mut u0 = DVector::from_element(num_nodes, stepping.initial_value);
+        for (node, inner) in [(0, 1), (last, last - 1)] {
+            u0[node] = (b[node] - band(node, inner) * u0[inner]) / a.diag[node];
+        }
+
+        integrate::march(stepping.scheme, |_| system.clone(), u0, 0.0, stepping.time_step, stepping.num_steps)
+    }
+
+    /// Assembles the tridiagonal 1D system and its right-hand side.
+    fn assemble_1d(&self, problem: &ProblemDefinition, num_nodes: usize, source: f64) -> Result<(Tridiagonal, DVector<f64>), EngineError> {
+        // For simplicity, we'll assume a 1D domain of unit length.
         let length = 1.0; // Length of the 1D domain
-        let num_nodes = 11; // Number of nodes (including boundary nodes)
-        let _dx = length / (num_nodes - 1) as f64; // Grid spacing
+        let dx = length / (num_nodes - 1) as f64; // Grid spacing
+        let k = conductivity(problem)?[(0, 0)];
 
         // Initialize global stiffness matrix (A) and load vector (B).
-        // For 1D steady-state heat conduction (d^2T/dx^2 = 0),
-        // the discretized equation is (T_i-1 - 2*T_i + T_i+1) / dx^2 = 0
-        // which simplifies to T_i-1 - 2*T_i + T_i+1 = 0
-        let mut a_global = DMatrix::<f64>::zeros(num_nodes, num_nodes);
+        // For 1D steady-state heat conduction (k d^2T/dx^2 = f),
+        // the discretized equation is k (T_i-1 - 2*T_i + T_i+1) / dx^2 = f
+        // which simplifies to k (T_i-1 - 2*T_i + T_i+1) = f * dx^2
+        let mut a_global = Tridiagonal::zeros(num_nodes);
         let mut b_global = DVector::<f64>::zeros(num_nodes);
 
         // Assemble the system (internal nodes).
         for i in 1..num_nodes - 1 {
-            a_global[(i, i - 1)] = 1.0;
-            a_global[(i, i)] = -2.0;
-            a_global[(i, i + 1)] = 1.0;
+            a_global.set(i, i - 1, k);
+            a_global.set(i, i, -2.0 * k);
+            a_global.set(i, i + 1, k);
+            b_global[i] = source * dx * dx;
         }
 
         // Apply boundary conditions.
-        // We'll assume fixed temperatures at both ends.
-        // T(0) = T_left, T(L) = T_right
-        let t_left = 100.0;
-        let t_right = 0.0;
+        // Fixed temperatures T(0) = 100, T(L) = 0 unless the problem overrides an end
+        // through the "face_x_neg" / "face_x_pos" regions.
+        let left = end_condition(problem, "face_x_neg")?.unwrap_or(EndCondition::Fixed(100.0));
+        let right = end_condition(problem, "face_x_pos")?.unwrap_or(EndCondition::Fixed(0.0));
 
-        // Node 0 (left boundary)
-        a_global[(0, 0)] = 1.0;
-        b_global[0] = t_left;
+        // Node 0 (left boundary), outward normal -x.
+        apply_end_condition(&mut a_global, &mut b_global, 0, 1, left, dx, k);
 
-        // Node N-1 (right boundary)
-        a_global[(num_nodes - 1, num_nodes - 1)] = 1.0;
-        b_global[num_nodes - 1] = t_right;
+        // Node N-1 (right boundary), outward normal +x.
+        apply_end_condition(&mut a_global, &mut b_global, num_nodes - 1, num_nodes - 2, right, dx, k);
 
-        // Solve for nodal temperatures (T).
-        let t_solution = a_global.try_inverse().ok_or_else(|| EngineError::SolverFailed("FDM matrix is singular.".to_string()))? * b_global;
+        Ok((a_global, b_global))
+    }
 
-        // Return temperatures as solution data.
-        println!("--- FdmSolver Finished ---");
+    /// Solves steady-state heat conduction on the unit square or cube with a
+    /// 5-point (2D) or 7-point (3D) stencil, widened to 9 or 19 points by the
+    /// mixed derivatives of an anisotropic conductivity.
+    ///
+    /// Every boundary face has a fixed temperature, read from the matching
+    /// `face_*` Dirichlet condition. As in 1D, `face_x_neg` defaults to 100 and all
+    /// other faces to 0. Nodes on several faces take the value of the first face
+    /// in x, y, z order. The result is flattened with x varying fastest.
+    ///
+    /// The system is solved with conjugate gradients on the stencil itself, so
+    /// memory and work per iteration grow linearly with the number of nodes.
+    fn solve_grid(&self, problem: &ProblemDefinition, dims: &[usize], source: f64) -> Result<DVector<f64>, EngineError> {
+        println!("--- Running FdmSolver ({}D Heat Conduction) ---", dims.len());
 
-        Ok(super::SolverSolutionData {
-            data: t_solution.iter().cloned().collect(), // Convert DVector to Vec<f64>
-        })
+        let spacing: Vec<f64> = dims.iter().map(|&n| 1.0 / (n - 1) as f64).collect();
+        let k = conductivity(problem)?;
+        let mut face_values = Vec::with_capacity(dims.len());
+        for (axis, &(neg, pos)) in GRID_FACES[..dims.len()].iter().enumerate() {
+            let fixed = |region: &str, default: f64| match end_condition(problem, region)? {
+                None => Ok(default),
+                Some(EndCondition::Fixed(t)) => Ok(t),
+                Some(condition) => Err(EngineError::SolverFailed(format!("FdmSolver cannot apply {:?} on {} of a {}D grid", condition, region, dims.len()))),
+            };
+            face_values.push([fixed(neg, if axis == 0 { 100.0 } else { 0.0 })?, fixed(pos, 0.0)?]);
+        }
+
+        let strides: Vec<usize> = (0..dims.len()).map(|axis| dims[..axis].iter().product()).collect();
+        let total: usize = dims.iter().product();
+
+        // The stencil of L(T) as (node offset, weight) pairs:
+        // sum over axes of k_aa (T_-1 - 2 T + T_+1) / h^2
+        let mut stencil: Vec<(isize, f64)> = vec![(0, 0.0)];
+        for axis in 0..dims.len() {
+            let weight = k[(axis, axis)] / (spacing[axis] * spacing[axis]);
+            let stride = strides[axis] as isize;
+            stencil[0].1 -= 2.0 * weight;
+            stencil.extend([(-stride, weight), (stride, weight)]);
+        }
+        // plus 2 k_ab d2T/(da db) per axis pair, from the four diagonal neighbours
+        for a in 0..dims.len() {
+            for b in a + 1..dims.len() {
+                let weight = 2.0 * k[(a, b)] / (4.0 * spacing[a] * spacing[b]);
+                if weight == 0.0 {
+                    continue;
+                }
+                let (sa, sb) = (strides[a] as isize, strides[b] as isize);
+                stencil.extend([(sa + sb, weight), (-sa - sb, weight), (sa - sb, -weight), (sb - sa, -weight)]);
+            }
+        }
+
+        let fixed: Vec<Option<f64>> = (0..total)
+            .map(|node| {
+                (0..dims.len()).find_map(|axis| match node / strides[axis] % dims[axis] {
+                    0 => Some(face_values[axis][0]),
+                    i if i == dims[axis] - 1 => Some(face_values[axis][1]),
+                    _ => None,
+                })
+            })
+            .collect();
+
+        // Solve L(T) = f for the correction to the fixed temperatures, which move to
+        // the right-hand side. Negated, the interior rows are symmetric positive
+        // definite and boundary rows are the identity, so conjugate gradients apply
+        // the stencil directly and the matrix is never stored.
+        let neighbours = |node: usize| stencil.iter().map(move |&(offset, weight)| ((node as isize + offset) as usize, weight));
+        let apply = |x: &DVector<f64>| {
+            DVector::from_fn(total, |node, _| match fixed[node] {
+                Some(_) => x[node],
+                None => -neighbours(node).filter(|&(n, _)| fixed[n].is_none()).map(|(n, weight)| weight * x[n]).sum::<f64>(),
+            })
+        };
+        let b_global = DVector::from_fn(total, |node, _| match fixed[node] {
+            Some(_) => 0.0,
+            None => neighbours(node).filter_map(|(n, weight)| fixed[n].map(|t| weight * t)).sum::<f64>() - source,
+        });
+        let max_iterations = u32::try_from(10 * total).unwrap_or(u32::MAX);
+        let solution = crate::kernel::conjugate_gradient_with(apply, &b_global, DVector::zeros(total), 1e-14 * b_global.norm(), max_iterations, None);
+        if !solution.converged {
+            return Err(EngineError::SolverFailed(format!(
+                "FDM conjugate gradients did not converge: {}",
+                solution.termination_reason.unwrap_or_default()
+            )));
+        }
+        Ok(DVector::from_fn(total, |node, _| fixed[node].unwrap_or(0.0) + solution.x[node]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BoundaryCondition, GeometricPrimitive, GeometryDefinition, Material, MaterialProperty, PhysicsDefinition, SolverSettings, ThermalConductivity};
+    use std::collections::HashMap;
+
+    fn heat_problem(equations: Vec<String>) -> ProblemDefinition {
+        ProblemDefinition {
+            id: "fdm_unit_test".to_string(),
+            geometry: GeometryDefinition::Primitive(GeometricPrimitive {
+                shape: "cube".to_string(),
+                dimensions: vec![1.0, 1.0, 1.0],
+                transform: None,
+            }),
+            physics: PhysicsDefinition {
+                equations,
+                boundary_conditions: vec![],
+                thermal_boundary_conditions: vec![],
+                material: Material {
+                    youngs_modulus: MaterialProperty::Constant(1.0),
+                    poissons_ratio: MaterialProperty::Constant(0.0),
+                    density: None,
+                    thermal_expansion: None,
+                    reference_temperature: 0.0,
+                    thermal_conductivity: None,
+                    viscosity: None,
+                },
+                boundary_conditions_file: None,
+                constraints: vec![],
+                processed_equations: None,
+            },
+            solver_settings: SolverSettings {
+                solver_name: "FdmSolver".to_string(),
+                tolerance: 1e-5,
+                max_iterations: 10,
+                ..Default::default()
+            },
+            mesh: None,
+            aux_fields: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_poisson_source_term() {
+        // T'' = -2 with T(0) = 100, T(1) = 0 gives T = 100 - 99x - x^2,
+        // which the central difference stencil reproduces exactly.
+        let problem = heat_problem(vec!["div(grad(T)) = -2".to_string()]);
+        FdmSolver.check_compatibility(&problem).unwrap();
+        let result = FdmSolver.solve(&problem).unwrap();
+
+        for (i, value) in result.data.iter().enumerate() {
+            let x = i as f64 / 10.0;
+            let expected = 100.0 - 99.0 * x - x * x;
+            assert!((value - expected).abs() < 1e-9, "Node {}: expected {}, got {}", i, expected, value);
+        }
+    }
+
+    #[test]
+    fn test_grid_resolution_reproduces_linear_profile() {
+        for num_nodes in [11, 101] {
+            let mut problem = heat_problem(vec![]);
+            problem.solver_settings.grid_resolution = Some(num_nodes);
+            let result = FdmSolver.solve(&problem).unwrap();
+
+            assert_eq!(result.data.len(), num_nodes);
+            for (i, value) in result.data.iter().enumerate() {
+                let expected = 100.0 * (1.0 - i as f64 / (num_nodes - 1) as f64);
+                assert!((value - expected).abs() < 1e-8, "{} nodes, node {}: expected {}, got {}", num_nodes, i, expected, value);
+            }
+        }
+
+        let mut problem = heat_problem(vec![]);
+        problem.solver_settings.grid_resolution = Some(1);
+        assert!(FdmSolver.check_compatibility(&problem).is_err());
+        problem.solver_settings.grid_resolution = Some(11);
+        problem.solver_settings.grid_dimensions = Some(vec![11]);
+        assert!(FdmSolver.check_compatibility(&problem).is_err());
+    }
+
+    #[test]
+    fn test_tridiagonal_solve_matches_dense_inversion() {
+        let mut problem = heat_problem(vec!["div(grad(T)) = -2".to_string()]);
+        problem.solver_settings.grid_resolution = Some(51);
+        problem.physics.boundary_conditions = vec![BoundaryCondition {
+            region: "face_x_pos".to_string(),
+            condition_type: "Convective".to_string(),
+            value: BcValue::Convective { h: 10.0, t_inf: 20.0 },
+        }];
+
+        let (a, b) = FdmSolver.assemble_1d(&problem, 51, -2.0).unwrap();
+        let dense = DMatrix::from_fn(51, 51, |i, j| match j as isize - i as isize {
+            -1 => a.lower[j],
+            0 => a.diag[i],
+            1 => a.upper[i],
+            _ => 0.0,
+        });
+        let expected = dense.try_inverse().unwrap() * b;
+
+        let result = FdmSolver.solve(&problem).unwrap();
+        for (value, expected) in result.data.iter().zip(expected.iter()) {
+            assert!((value - expected).abs() < 1e-9 * expected.abs().max(1.0), "{} vs {}", value, expected);
+        }
+    }
+
+    #[test]
+    fn test_transient_solve_approaches_steady_state() {
+        let steady = FdmSolver.solve(&heat_problem(vec!["div(grad(T)) = -2".to_string()])).unwrap();
+
+        for scheme in [TimeScheme::BackwardEuler, TimeScheme::CrankNicolson] {
+            let mut problem = heat_problem(vec!["div(grad(T)) = -2".to_string()]);
+            problem.physics.boundary_conditions = vec![BoundaryCondition {
+                region: "face_x_pos".to_string(),
+                condition_type: "Convective".to_string(),
+                value: BcValue::Convective { h: 2.0, t_inf: 0.0 },
+            }];
+            let steady_convective = FdmSolver.solve(&problem).unwrap();
+            problem.solver_settings.time_stepping = Some(TimeStepping { scheme, time_step: 0.01, num_steps: 300, initial_value: 20.0 });
+            FdmSolver.check_compatibility(&problem).unwrap();
+            let result = FdmSolver.solve(&problem).unwrap();
+
+            assert_eq!(result.time_steps.len(), 301);
+            let (t0, initial) = &result.time_steps[0];
+            assert_eq!(*t0, 0.0);
+            assert_eq!(initial[0], 100.0);
+            assert_eq!(initial[5], 20.0);
+            // -k (T_10 - T_9) / dx = h T_10 holds from the first step on.
+            assert!((-(initial[10] - initial[9]) * 10.0 - 2.0 * initial[10]).abs() < 1e-9);
+            assert!((result.time_steps[300].0 - 3.0).abs() < 1e-9);
+            assert_eq!(result.data, result.time_steps[300].1);
+            for (value, expected) in result.data.iter().zip(&steady_convective.data) {
+                assert!((value - expected).abs() < 1e-4, "{:?}: {} vs {}", scheme, value, expected);
+            }
+        }
+        assert!(steady.time_steps.is_empty());
+    }
+
+    #[test]
+    fn test_unsupported_time_stepping_is_rejected() {
+        let stepping = |scheme, time_step| Some(TimeStepping { scheme, time_step, num_steps: 10, initial_value: 0.0 });
+        let mut problem = heat_problem(vec![]);
+        problem.solver_settings.time_stepping = stepping(TimeScheme::ForwardEuler, 0.01);
+        assert!(FdmSolver.check_compatibility(&problem).is_err());
+        problem.solver_settings.time_stepping = stepping(TimeScheme::BackwardEuler, 0.0);
+        assert!(FdmSolver.check_compatibility(&problem).is_err());
+        problem.solver_settings.time_stepping = stepping(TimeScheme::BackwardEuler, 0.01);
+        problem.solver_settings.grid_dimensions = Some(vec![5, 5]);
+        assert!(FdmSolver.check_compatibility(&problem).is_err());
+    }
+
+    #[test]
+    fn test_heat_flows_along_high_conductivity_axis() {
+        // Hot x_neg face, cold elsewhere: the isotropic center is at the average
+        // of the edges, conduction along x pulls it towards the 1D profile of 50
+        // and conduction along y towards the cold y faces.
+        let center = |conductivity: Option<ThermalConductivity>| {
+            let mut problem = heat_problem(vec![]);
+            problem.solver_settings.grid_dimensions = Some(vec![21, 21]);
+            problem.physics.material.thermal_conductivity = conductivity;
+            FdmSolver.check_compatibility(&problem).unwrap();
+            FdmSolver.solve(&problem).unwrap().data[10 * 21 + 10]
+        };
+        let diagonal = |kx: f64, ky: f64| Some(ThermalConductivity::Tensor([[kx, 0.0, 0.0], [0.0, ky, 0.0], [0.0, 0.0, 1.0]]));
+
+        let isotropic = center(Some(ThermalConductivity::Isotropic(3.0)));
+        assert!((isotropic - 25.0).abs() < 1e-9, "{}", isotropic);
+        assert!((center(None) - isotropic).abs() < 1e-9);
+        let along_x = center(diagonal(100.0, 1.0));
+        let along_y = center(diagonal(1.0, 100.0));
+        assert!(along_x > 45.0, "{}", along_x);
+        assert!(along_y < 5.0, "{}", along_y);
+    }
+
+    #[test]
+    fn test_off_diagonal_conductivity_skews_the_field() {
+        // A heat source in a square with cold edges and the easy axis along (1, 1).
+        // Heat mostly escapes along that axis, whose chords through the main
+        // diagonal are longest, so the field peaks along it and is symmetric about
+        // it but not about x = 0.5.
+        let mut problem = heat_problem(vec!["div(grad(T)) = -2".to_string()]);
+        problem.solver_settings.grid_dimensions = Some(vec![21, 21]);
+        problem.physics.boundary_conditions = vec![BoundaryCondition {
+            region: "face_x_neg".to_string(),
+            condition_type: "Dirichlet".to_string(),
+            value: BcValue::Scalar(0.0),
+        }];
+        problem.physics.material.thermal_conductivity = Some(ThermalConductivity::Tensor([[1.0, 0.8, 0.0], [0.8, 1.0, 0.0], [0.0, 0.0, 1.0]]));
+        let t = FdmSolver.solve(&problem).unwrap().data;
+        let at = |i: usize, j: usize| t[j * 21 + i];
+
+        assert!((at(5, 15) - at(15, 5)).abs() < 1e-9);
+        assert!((at(5, 5) - at(15, 15)).abs() < 1e-9);
+        assert!(at(5, 5) > 1.5 * at(15, 5), "{} vs {}", at(5, 5), at(15, 5));
+
+        problem.physics.material.thermal_conductivity = Some(ThermalConductivity::Tensor([[1.0, 2.0, 0.0], [2.0, 1.0, 0.0], [0.0, 0.0, 1.0]]));
+        assert!(FdmSolver.check_compatibility(&problem).is_err());
+    }
+
+    #[test]
+    fn test_1d_conductivity_scales_the_source() {
+        // 4 T'' = -2 with T(0) = 100, T(1) = 0 gives T = 100 - 100x + x(1 - x) / 4.
+        let mut problem = heat_problem(vec!["div(grad(T)) = -2".to_string()]);
+        problem.physics.material.thermal_conductivity = Some(ThermalConductivity::Isotropic(4.0));
+        let result = FdmSolver.solve(&problem).unwrap();
+        for (i, value) in result.data.iter().enumerate() {
+            let x = i as f64 / 10.0;
+            let expected = 100.0 - 100.0 * x + 0.25 * x * (1.0 - x);
+            assert!((value - expected).abs() < 1e-9, "Node {}: expected {}, got {}", i, expected, value);
+        }
+    }
+
+    #[test]
+    fn test_non_constant_source_is_rejected() {
+        let problem = heat_problem(vec!["div(grad(T)) = sin(x)".to_string()]);
+        assert!(FdmSolver.check_compatibility(&problem).is_err());
+    }
+
+    #[test]
+    fn test_scalar_and_convective_end_conditions() {
+        // T(0) = 50 and -dT/dx = h (T - t_inf) at x = 1 with h = 1, t_inf = 10.
+        // The profile is linear: T = 50 + a x with -a = 50 + a - 10, so a = -20.
+        let mut problem = heat_problem(vec![]);
+        problem.physics.boundary_conditions = vec![
+            BoundaryCondition {
+                region: "face_x_neg".to_string(),
+                condition_type: "Dirichlet".to_string(),
+                value: BcValue::Scalar(50.0),
+            },
+            BoundaryCondition {
+                region: "face_x_pos".to_string(),
+                condition_type: "Convective".to_string(),
+                value: BcValue::Convective { h: 1.0, t_inf: 10.0 },
+            },
+        ];
+        FdmSolver.check_compatibility(&problem).unwrap();
+        let result = FdmSolver.solve(&problem).unwrap();
+
+        for (i, value) in result.data.iter().enumerate() {
+            let expected = 50.0 - 20.0 * (i as f64 / 10.0);
+            assert!((value - expected).abs() < 1e-9, "Node {}: expected {}, got {}", i, expected, value);
+        }
+    }
+
+    #[test]
+    fn test_vector_end_condition_is_rejected() {
+        let mut problem = heat_problem(vec![]);
+        problem.physics.boundary_conditions = vec![BoundaryCondition {
+            region: "face_x_neg".to_string(),
+            condition_type: "Dirichlet".to_string(),
+            value: BcValue::Vector3([1.0, 2.0, 3.0]),
+        }];
+        assert!(FdmSolver.check_compatibility(&problem).is_err());
+    }
+
+    #[test]
+    fn test_2d_square_center_is_average_of_edges() {
+        // By symmetry and superposition, the center of a square with four fixed
+        // edge temperatures is at their average.
+        let mut problem = heat_problem(vec![]);
+        problem.solver_settings.grid_dimensions = Some(vec![21, 21]);
+        let edge = |region: &str, t: f64| BoundaryCondition {
+            region: region.to_string(),
+            condition_type: "Dirichlet".to_string(),
+            value: BcValue::Scalar(t),
+        };
+        problem.physics.boundary_conditions = vec![
+            edge("face_x_neg", 100.0),
+            edge("face_x_pos", 20.0),
+            edge("face_y_neg", 0.0),
+            edge("face_y_pos", 40.0),
+        ];
+        FdmSolver.check_compatibility(&problem).unwrap();
+        let result = FdmSolver.solve(&problem).unwrap();
+
+        assert_eq!(result.data.len(), 21 * 21);
+        let center = result.data[10 * 21 + 10];
+        assert!((center - 40.0).abs() < 1e-9, "Center temperature {}", center);
+        assert!((result.data[5 * 21] - 100.0).abs() < 1e-9);
+        assert!((result.data[20 * 21 + 5] - 40.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_3d_grid_with_uniform_faces_is_uniform() {
+        // A constant field satisfies the 7-point stencil exactly on an anisotropic grid.
+        let mut problem = heat_problem(vec![]);
+        problem.solver_settings.grid_dimensions = Some(vec![5, 4, 3]);
+        problem.physics.boundary_conditions = GRID_FACES
+            .iter()
+            .flat_map(|&(neg, pos)| [neg, pos])
+            .map(|region| BoundaryCondition {
+                region: region.to_string(),
+                condition_type: "Dirichlet".to_string(),
+                value: BcValue::Scalar(7.5),
+            })
+            .collect();
+        let result = FdmSolver.solve(&problem).unwrap();
+
+        assert_eq!(result.data.len(), 5 * 4 * 3);
+        assert!(result.data.iter().all(|t| (t - 7.5).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_large_3d_grid_is_solved_matrix_free() {
+        // 35^3 nodes would need a dense matrix of 14 GB. By symmetry and
+        // superposition the center of a cube with one face at 100 is at 100 / 6.
+        let mut problem = heat_problem(vec![]);
+        problem.solver_settings.grid_dimensions = Some(vec![35, 35, 35]);
+        let result = FdmSolver.solve(&problem).unwrap();
+
+        assert_eq!(result.data.len(), 35 * 35 * 35);
+        let center = result.data[17 * 35 * 35 + 17 * 35 + 17];
+        assert!((center - 100.0 / 6.0).abs() < 1e-9, "Center temperature {}", center);
+    }
+
+    #[test]
+    fn test_convective_condition_rejected_on_grid() {
+        let mut problem = heat_problem(vec![]);
+        problem.solver_settings.grid_dimensions = Some(vec![5, 5]);
+        problem.physics.boundary_conditions = vec![BoundaryCondition {
+            region: "face_y_pos".to_string(),
+            condition_type: "Convective".to_string(),
+            value: BcValue::Convective { h: 1.0, t_inf: 10.0 },
+        }];
+        assert!(FdmSolver.check_compatibility(&problem).is_err());
+
+        problem.solver_settings.grid_dimensions = Some(vec![5, 2]);
+        assert!(FdmSolver.check_compatibility(&problem).is_err());
     }
 }
@@ -2,166 +2,1649 @@
 
 //! A basic Finite Element Method (FEM) solver.
 
-use crate::{ProblemDefinition, EngineError, Mesh, Material};
+use crate::{ProblemDefinition, EngineError, EngineWarning, Mesh, Material, MaterialProperty, BcValue, HarmonicExcitation, LinearSolver, MassMatrixType, OutputField, Precision, RayleighDamping, TimeStepping, WarningCode};
+use crate::kernel::integrate::{self, TimeScheme};
+use crate::kernel::quadrature::{gauss_points, ElementShape};
 use crate::solver::Solver;
-use nalgebra::{DMatrix, DVector};
+use crate::solver::assembly::{self, FieldKind, Triplet};
+use nalgebra::{Complex, ComplexField, DMatrix, DVector, RealField};
+use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Linear elasticity solves for a displacement vector at every node.
+const FIELD: FieldKind = FieldKind::Vector;
+
+/// Displacement degrees of freedom (x, y, z) per node.
+const DOF_PER_NODE: usize = FIELD.dofs_per_node();
+
+/// A scalar type the static solve can assemble and solve the system in.
+trait SystemScalar: RealField + Copy {
+    /// The setting that selects this type.
+    const PRECISION: Precision;
+}
+
+impl SystemScalar for f32 {
+    const PRECISION: Precision = Precision::F32;
+}
+
+impl SystemScalar for f64 {
+    const PRECISION: Precision = Precision::F64;
+}
+
+/// A user-supplied element stiffness routine, see [`FemSolver::with_element_stiffness`].
+///
+/// It receives an element's node coordinates in connectivity order and the
+/// problem's material, and returns the element stiffness matrix over the
+/// element's displacement DOFs in node-major order.
+pub type ElementStiffness = Arc<dyn Fn(&[[f64; 3]], &Material) -> DMatrix<f64> + Send + Sync>;
 
 /// A simple FEM solver for linear elasticity.
 ///
 /// This solver calculates nodal displacements for a given mesh under specified
 /// boundary conditions and material properties.
-pub struct FemSolver;
+#[derive(Clone, Default)]
+pub struct FemSolver {
+    element_stiffness: Option<ElementStiffness>,
+}
 
 impl Solver for FemSolver {
     fn name(&self) -> &'static str {
         "FemSolver"
     }
 
-    fn solve(&self, problem: &mut ProblemDefinition) -> Result<super::SolverSolutionData, EngineError> {
-        println!("--- Running FemSolver (Linear Elasticity) ---");
+    fn capabilities(&self) -> super::SolverCapabilities {
+        super::SolverCapabilities { physics: vec![super::PhysicsKind::LinearElasticity], dimensions: vec![3] }
+    }
+
+    fn check_compatibility(&self, problem: &ProblemDefinition) -> Result<(), EngineError> {
+        let mesh = problem.mesh.as_ref().ok_or_else(|| EngineError::SolverFailed("Mesh not found in problem definition".to_string()))?;
+        crate::meshing::validate_mesh(mesh)?;
+
+        if !matches!(mesh.element_type.as_str(), "Tetrahedron" | "Tetrahedron10") {
+            return Err(EngineError::SolverFailed(format!("FemSolver supports Tetrahedron and Tetrahedron10 meshes, but found {}", mesh.element_type)));
+        }
+
+        for bc in &problem.physics.boundary_conditions {
+            match (bc.condition_type.as_str(), &bc.value) {
+                ("Dirichlet" | "Force", BcValue::Vector3(_)) | ("Dirichlet", BcValue::MaskedVector3 { .. }) | ("Symmetry", BcValue::Scalar(_) | BcValue::Vector3(_)) | ("PointForce", BcValue::PointLoad { .. }) => {}
+                ("PointForce", value) => return Err(EngineError::SolverFailed(format!("PointForce condition needs a position and a force, got {:?}", value))),
+                ("Symmetry", value) => return Err(EngineError::SolverFailed(format!("Symmetry condition on {} needs an axis or normal, got {:?}", bc.region, value))),
+                ("Dirichlet" | "Force", value) => return Err(EngineError::SolverFailed(format!("{} condition on {} needs a 3-component value, got {:?}", bc.condition_type, bc.region, value))),
+                _ => return Err(EngineError::SolverFailed(format!("Unsupported boundary condition type: {}", bc.condition_type))),
+            }
+        }
+        if !problem.physics.constraints.is_empty() {
+            check_constraint_precision(problem.solver_settings.precision)?;
+        }
+
+        Ok(())
+    }
+
+    fn solve(&self, problem: &ProblemDefinition) -> Result<super::SolverSolutionData, EngineError> {
+        println!("--- Running FemSolver (Linear Elasticity) ---");
+
+        let mut warnings = Vec::new();
+        let mesh = self.checked_mesh(problem, &mut warnings)?;
+        check_poissons_ratio(problem, &mut warnings)?;
+        let temperatures = temperature_field(problem, &mesh)?;
+
+        // 1-2. Assemble element stiffness matrices into the global system.
+        let (material, num_threads) = (&problem.physics.material, problem.solver_settings.num_threads);
+        match problem.solver_settings.precision {
+            Precision::F64 => {
+                let k_global = self.assemble_global_stiffness::<f64>(&mesh, material, temperatures, num_threads)?;
+                self.solve_assembled(problem, &mesh, k_global, warnings).map(|(solution, _)| solution)
+            }
+            Precision::F32 => {
+                let k_global = self.assemble_global_stiffness::<f32>(&mesh, material, temperatures, num_threads)?;
+                self.solve_assembled(problem, &mesh, k_global, warnings).map(|(solution, _)| solution)
+            }
+        }
+    }
+}
+
+/// Poisson's ratio above which the elements start to lock volumetrically.
+const NEARLY_INCOMPRESSIBLE_POISSONS_RATIO: f64 = 0.49;
+
+/// Checks the material's Poisson's ratio, including every table entry.
+///
+/// Ratios outside `(-1, 0.5)` leave the elasticity matrix undefined and are
+/// rejected. Above [`NEARLY_INCOMPRESSIBLE_POISSONS_RATIO`] the material is
+/// nearly incompressible, which is a warning, or an error once the ratio
+/// exceeds `SolverSettings::max_poissons_ratio`.
+fn check_poissons_ratio(problem: &ProblemDefinition, warnings: &mut Vec<EngineWarning>) -> Result<(), EngineError> {
+    let ratios = match &problem.physics.material.poissons_ratio {
+        MaterialProperty::Constant(nu) => vec![*nu],
+        MaterialProperty::Table(table) => table.iter().map(|entry| entry[1]).collect(),
+    };
+    if let Some(nu) = ratios.iter().find(|&&nu| !(nu > -1.0 && nu < 0.5)) {
+        return Err(EngineError::SolverFailed(format!("Poisson's ratio {} is outside (-1, 0.5), where the elasticity matrix is undefined", nu)));
+    }
+
+    let largest = ratios.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let guidance = "the elements lock volumetrically and underestimate displacements. \
+        Raise the mesh to Tetrahedron10 elements with meshing::to_quadratic_tetrahedra, whose locking is milder, \
+        or use a ratio of at most 0.49";
+    if let Some(limit) = problem.solver_settings.max_poissons_ratio {
+        if largest > limit {
+            return Err(EngineError::SolverFailed(format!(
+                "Poisson's ratio {} exceeds max_poissons_ratio {}: the material is nearly incompressible, so {}",
+                largest, limit, guidance
+            )));
+        }
+    }
+    if largest > NEARLY_INCOMPRESSIBLE_POISSONS_RATIO {
+        warnings.push(EngineWarning::emit(
+            WarningCode::NearlyIncompressible,
+            format!("Poisson's ratio {} is nearly incompressible, so {}", largest, guidance),
+        ));
+    }
+    Ok(())
+}
+
+/// Reads the optional nodal temperature field, checking it matches the mesh.
+fn temperature_field<'a>(problem: &'a ProblemDefinition, mesh: &Mesh) -> Result<Option<&'a [f64]>, EngineError> {
+    let temperatures = problem.aux_fields.get("temperature").map(Vec::as_slice);
+    if let Some(t) = temperatures {
+        if t.len() != mesh.nodes.len() {
+            return Err(EngineError::SolverFailed(format!("Temperature field has {} values but the mesh has {} nodes", t.len(), mesh.nodes.len())));
+        }
+    }
+    Ok(temperatures)
+}
+
+impl FemSolver {
+    /// Creates a solver using the built-in tetrahedral elements.
+    pub const fn new() -> Self {
+        Self { element_stiffness: None }
+    }
+
+    /// Creates a solver that computes every element stiffness matrix with `element_stiffness`.
+    ///
+    /// The routine replaces the built-in element formulation, which makes the
+    /// solver a testbed for new elements; loads, constraints and the linear
+    /// solve are unchanged. Register the result with
+    /// [`crate::CoreEngine::register_solver`] to use it in place of the default
+    /// `FemSolver`. Temperature-dependent material properties are left to the
+    /// routine to evaluate.
+    pub fn with_element_stiffness(element_stiffness: impl Fn(&[[f64; 3]], &Material) -> DMatrix<f64> + Send + Sync + 'static) -> Self {
+        Self { element_stiffness: Some(Arc::new(element_stiffness)) }
+    }
+
+    /// Returns the problem's tetrahedral mesh, rejecting inverted elements or
+    /// returning a corrected copy when `flip_inverted_elements` is set.
+    fn checked_mesh<'a>(&self, problem: &'a ProblemDefinition, warnings: &mut Vec<EngineWarning>) -> Result<Cow<'a, Mesh>, EngineError> {
+        let mesh = problem.mesh.as_ref().ok_or_else(|| EngineError::SolverFailed("Mesh not found in problem definition".to_string()))?;
+
+        if !matches!(mesh.element_type.as_str(), "Tetrahedron" | "Tetrahedron10") {
+            return Err(EngineError::SolverFailed(format!("FemSolver supports Tetrahedron and Tetrahedron10 meshes, but found {}", mesh.element_type)));
+        }
+
+        // Reject or repair inverted elements before they reach the assembly.
+        let inverted = crate::meshing::find_inverted_elements(mesh);
+        if inverted.is_empty() {
+            Ok(Cow::Borrowed(mesh))
+        } else if problem.solver_settings.flip_inverted_elements {
+            warnings.push(EngineWarning::emit(
+                WarningCode::InvertedElements,
+                format!("flipping node order of {} inverted elements: {:?}", inverted.len(), inverted),
+            ));
+            let mut flipped = mesh.clone();
+            crate::meshing::flip_inverted_elements(&mut flipped);
+            Ok(Cow::Owned(flipped))
+        } else {
+            Err(EngineError::SolverFailed(format!("Mesh contains {} inverted elements: {:?}", inverted.len(), inverted)))
+        }
+    }
+
+    /// Applies the boundary conditions to an assembled stiffness matrix and solves for the displacements.
+    ///
+    /// The system is constrained and solved in the scalar type of `k_global`;
+    /// loads and results are kept in `f64`. `warnings` holds those of the
+    /// earlier steps and is returned with the solution. The full displacement
+    /// vector is returned alongside, since `SolverSettings::outputs` may leave it
+    /// out of the solution data.
+    fn solve_assembled<T: SystemScalar>(&self, problem: &ProblemDefinition, mesh: &Mesh, mut k_global: DMatrix<T>, mut warnings: Vec<EngineWarning>) -> Result<(super::SolverSolutionData, DVector<f64>), EngineError> {
+        let total_dof = mesh.nodes.len() * DOF_PER_NODE;
+        let temperatures = temperature_field(problem, mesh)?;
+        let mut f_free = self.assemble_thermal_load(mesh, &problem.physics.material, temperatures)?;
+
+        // 3. Apply boundary conditions.
+        warnings.extend(unmatched_regions(problem, mesh));
+        let prescribed = self.apply_boundary_conditions(problem, mesh, &mut f_free)?;
+
+        // Keep the unconstrained stiffness for the equilibrium check.
+        let k_free = k_global.clone();
+
+        // Add the multi-point constraint penalties, then modify K_global and F_global for prescribed DOFs.
+        let mut f_penalized = f_free.clone();
+        let constraints = PenaltyConstraints::apply(problem, mesh, &mut k_global, &mut f_penalized)?;
+        let mut f_global = f_penalized.map(|x| T::from_subset(&x));
+        let prescribed_values: Vec<(usize, T)> = prescribed.iter().map(|&(dof, value)| (dof, T::from_subset(&value))).collect();
+        assembly::apply_dirichlet(&mut k_global, &mut f_global, &prescribed_values);
+        let settings = &problem.solver_settings;
+        let mut metadata = serde_json::Map::new();
+        metadata.insert("precision".to_string(), serde_json::json!(T::PRECISION));
+        if settings.auto_stabilize {
+            let stabilized = stabilize_rigid_body_modes(mesh, &mut k_global, &prescribed_values);
+            if !stabilized.is_empty() {
+                warnings.push(EngineWarning::emit(WarningCode::RigidBodyModes, format!(
+                    "{} rigid body modes were not constrained; pinned degrees of freedom {:?} with weak springs",
+                    stabilized.len(), stabilized
+                )));
+                metadata.insert("stabilized_dofs".to_string(), serde_json::json!(stabilized));
+            }
+        }
+        if let Some(dir) = &settings.dump_system {
+            dump_system(dir, &problem.id, &k_global.map(|x| x.to_subset_unchecked()), &f_global.map(|x| x.to_subset_unchecked()))?;
+        }
+
+        // 4. Solve for displacements (U).
+        let mut iterations = Vec::new();
+        let u_solved = match settings.linear_solver {
+            LinearSolver::Direct => {
+                metadata.insert("converged".to_string(), serde_json::json!(true));
+                k_global.lu().solve(&f_global).ok_or_else(|| EngineError::SolverFailed(
+                    "Global stiffness matrix is singular; the boundary conditions may leave rigid body modes free (see `auto_stabilize`).".to_string(),
+                ))?
+            }
+            LinearSolver::ConjugateGradient => {
+                let tolerance = settings.tolerance * f_global.norm().to_subset_unchecked();
+                let solution = crate::kernel::conjugate_gradient(&k_global, &f_global, DVector::zeros(total_dof), tolerance, settings.max_iterations, settings.time_budget);
+                metadata.insert("converged".to_string(), serde_json::json!(solution.converged));
+                metadata.insert("iterations".to_string(), serde_json::json!(solution.iterations));
+                if let Some(every) = settings.record_iterations {
+                    iterations = super::select_iterations(&solution.residual_history, every);
+                }
+                if let Some(reason) = &solution.termination_reason {
+                    warnings.push(EngineWarning::emit(WarningCode::NotConverged, format!("FEM conjugate gradient solve stopped early: {}", reason)));
+                    metadata.insert("termination_reason".to_string(), serde_json::json!(reason));
+                }
+                solution.x
+            }
+        };
+        let u_global: DVector<f64> = u_solved.map(|x| x.to_subset_unchecked());
+
+        // 5. Check equilibrium: the residual K u - f vanishes on free DOFs and equals
+        // the reaction force on constrained ones. Constraint forces count as loads.
+        let k_u: DVector<f64> = (&k_free * &u_solved).map(|x| x.to_subset_unchecked());
+        let equilibrium = check_equilibrium(&k_u, &(&f_free + constraints.forces(&u_global)), &prescribed);
+        let load_scale = f_free.norm().max(equilibrium.total_reaction.iter().map(|r| r.abs()).fold(0.0, f64::max));
+        if load_scale > 0.0 && equilibrium.residual_norm > problem.solver_settings.tolerance * load_scale {
+            warnings.push(EngineWarning::emit(WarningCode::LargeResidual, format!(
+                "FEM residual norm {:e} exceeds tolerance {:e} relative to load {:e}",
+                equilibrium.residual_norm, problem.solver_settings.tolerance, load_scale
+            )));
+        }
+
+        if !constraints.terms.is_empty() {
+            metadata.insert("constraint_violations".to_string(), serde_json::json!(constraints.violations(&u_global)));
+        }
+        metadata.insert("residual_norm".to_string(), serde_json::json!(equilibrium.residual_norm));
+        metadata.insert("total_reaction".to_string(), serde_json::json!(equilibrium.total_reaction));
+        metadata.insert("region_reactions".to_string(), serde_json::json!(self.region_reactions(problem, mesh, &equilibrium.forces)?));
+        // Strain energy 0.5 u^T K u, which converges monotonically under mesh refinement.
+        metadata.insert("strain_energy".to_string(), serde_json::json!(0.5 * u_global.dot(&k_u)));
+
+        // 6. Return the displacements as solution data, with the other requested fields.
+        let fields = self.output_fields(problem, mesh, &u_global, &equilibrium.forces, &prescribed)?;
+        let outputs = &settings.outputs;
+        let data = if outputs.is_empty() || outputs.contains(&OutputField::Displacement) {
+            u_global.iter().cloned().collect() // Convert DVector to Vec<f64>
+        } else {
+            Vec::new()
+        };
+        println!("--- FemSolver Finished ---");
+
+        let solution = super::SolverSolutionData {
+            data,
+            time_steps: Vec::new(),
+            fields,
+            metadata,
+            warnings,
+            iterations,
+        };
+        Ok((solution, u_global))
+    }
+}
+
+/// Springs pinning free rigid body modes get this fraction of the stiffest diagonal entry.
+const STABILIZATION_STIFFNESS: f64 = 1e-8;
+
+/// Adds weak springs to `k` until they and the `prescribed` DOFs rule out every
+/// rigid body motion of `mesh`, returning the DOFs that got a spring.
+///
+/// Candidates follow the 3-2-1 rule on three well-separated nodes: the node
+/// farthest from the centroid, the node farthest from that one, and the node
+/// farthest from the line through both. A candidate is only used if it pins a
+/// mode that is still free, so at most six springs are added.
+fn stabilize_rigid_body_modes<T: RealField + Copy>(mesh: &Mesh, k: &mut DMatrix<T>, prescribed: &[(usize, T)]) -> Vec<usize> {
+    if mesh.nodes.is_empty() {
+        return Vec::new();
+    }
+    let n = mesh.nodes.len() as f64;
+    let centroid = mesh.nodes.iter().fold([0.0; 3], |c, p| [c[0] + p[0] / n, c[1] + p[1] / n, c[2] + p[2] / n]);
+    let sub = |a: [f64; 3], b: [f64; 3]| [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    let norm = |v: [f64; 3]| (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    let length = mesh.nodes.iter().map(|&p| norm(sub(p, centroid))).fold(0.0, f64::max).max(f64::MIN_POSITIVE);
+
+    // The rigid body modes evaluated at one DOF: three translations, then
+    // rotations about the centroid scaled to unit size at `length`.
+    let modes_at = |dof: usize| {
+        let r = sub(mesh.nodes[dof / DOF_PER_NODE], centroid).map(|x| x / length);
+        let rotations = match dof % DOF_PER_NODE {
+            0 => [0.0, r[2], -r[1]],
+            1 => [-r[2], 0.0, r[0]],
+            _ => [r[1], -r[0], 0.0],
+        };
+        let mut row = nalgebra::RowVector6::zeros();
+        row[dof % DOF_PER_NODE] = 1.0;
+        row.fixed_view_mut::<1, 3>(0, 3).copy_from_slice(&rotations);
+        row
+    };
+    // Constrained modes span the row space of the Gram matrix of the constrained DOFs.
+    let rank = |gram: &nalgebra::Matrix6<f64>| {
+        let singular_values = gram.singular_values();
+        let largest = singular_values.max();
+        singular_values.iter().filter(|&&s| s > 1e-9 * largest).count()
+    };
+    let mut gram = nalgebra::Matrix6::<f64>::zeros();
+    for &(dof, _) in prescribed {
+        let row = modes_at(dof);
+        gram += row.transpose() * row;
+    }
+    let mut constrained = rank(&gram);
+    if constrained == 6 {
+        return Vec::new();
+    }
+
+    let farthest_from = |distance: &dyn Fn([f64; 3]) -> f64| {
+        (0..mesh.nodes.len()).max_by(|&i, &j| distance(mesh.nodes[i]).total_cmp(&distance(mesh.nodes[j]))).unwrap()
+    };
+    let a = farthest_from(&|p| norm(sub(p, centroid)));
+    let b = farthest_from(&|p| norm(sub(p, mesh.nodes[a])));
+    let axis = sub(mesh.nodes[b], mesh.nodes[a]);
+    let c = farthest_from(&|p| {
+        let d = sub(p, mesh.nodes[a]);
+        norm([d[1] * axis[2] - d[2] * axis[1], d[2] * axis[0] - d[0] * axis[2], d[0] * axis[1] - d[1] * axis[0]])
+    });
+
+    let spring = T::from_subset(&STABILIZATION_STIFFNESS) * k.diagonal().max();
+    let mut stabilized = Vec::new();
+    for node in [a, b, c] {
+        for component in 0..DOF_PER_NODE {
+            let dof = assembly::global_dof(FIELD, node, component);
+            if constrained == 6 || stabilized.contains(&dof) || prescribed.iter().any(|&(p, _)| p == dof) {
+                continue;
+            }
+            let row = modes_at(dof);
+            let candidate = gram + row.transpose() * row;
+            let candidate_rank = rank(&candidate);
+            if candidate_rank > constrained {
+                gram = candidate;
+                constrained = candidate_rank;
+                k[(dof, dof)] += spring;
+                stabilized.push(dof);
+            }
+        }
+    }
+    stabilized
+}
+
+/// Penalty stiffness of a multi-point constraint, as a multiple of the stiffest diagonal entry.
+const MPC_PENALTY: f64 = 1e8;
+
+/// The multi-point constraints of a problem as imposed by the penalty method.
+///
+/// Each constraint `c . u = g` is scaled to a unit `c` and adds `alpha c c^T`
+/// to the stiffness and `alpha g c` to the load, with `alpha` the stiffest
+/// diagonal entry times `MPC_PENALTY`. The system stays symmetric positive
+/// definite, so both linear solvers apply, and the constraint holds to about
+/// `1 / MPC_PENALTY`. That needs double precision, so single-precision
+/// systems are rejected.
+struct PenaltyConstraints {
+    alpha: f64,
+    /// The unit coefficient vector and scaled right-hand side of each constraint.
+    terms: Vec<(DVector<f64>, f64)>,
+}
+
+impl PenaltyConstraints {
+    /// Adds the problem's multi-point constraints to `k` and `f`.
+    fn apply<T: SystemScalar>(problem: &ProblemDefinition, mesh: &Mesh, k: &mut DMatrix<T>, f: &mut DVector<f64>) -> Result<Self, EngineError> {
+        let constraints = &problem.physics.constraints;
+        if constraints.is_empty() {
+            return Ok(PenaltyConstraints { alpha: 0.0, terms: Vec::new() });
+        }
+        check_constraint_precision(T::PRECISION)?;
+        let alpha: f64 = MPC_PENALTY * k.diagonal().max().to_subset_unchecked();
+        let mut terms = Vec::with_capacity(constraints.len());
+        for (i, constraint) in constraints.iter().enumerate() {
+            let mut c = DVector::<f64>::zeros(f.len());
+            for term in &constraint.terms {
+                if term.component >= DOF_PER_NODE || !term.coefficient.is_finite() {
+                    return Err(EngineError::SolverFailed(format!(
+                        "Constraint {} has a term on component {} with coefficient {}; components are 0, 1 or 2 and coefficients finite",
+                        i, term.component, term.coefficient
+                    )));
+                }
+                c[assembly::global_dof(FIELD, nearest_node(mesh, term.position)?, term.component)] += term.coefficient;
+            }
+            let norm = c.norm();
+            if !(norm > 0.0 && constraint.value.is_finite()) {
+                return Err(EngineError::SolverFailed(format!("Constraint {} has no nonzero coefficient or a non-finite value {}", i, constraint.value)));
+            }
+            c /= norm;
+            let g = constraint.value / norm;
+
+            let dofs: Vec<usize> = (0..c.len()).filter(|&dof| c[dof] != 0.0).collect();
+            for &row in &dofs {
+                f[row] += alpha * g * c[row];
+                for &col in &dofs {
+                    k[(row, col)] += T::from_subset(&(alpha * c[row] * c[col]));
+                }
+            }
+            terms.push((c, g));
+        }
+        Ok(PenaltyConstraints { alpha, terms })
+    }
+
+    /// Returns the forces the penalty springs exert on the nodes at displacements `u`.
+    fn forces(&self, u: &DVector<f64>) -> DVector<f64> {
+        self.terms.iter().fold(DVector::zeros(u.len()), |forces, (c, g)| forces - c * (self.alpha * (c.dot(u) - g)))
+    }
+
+    /// Returns the residual `c . u - g` of each constraint in displacement units.
+    fn violations(&self, u: &DVector<f64>) -> Vec<f64> {
+        self.terms.iter().map(|(c, g)| c.dot(u) - g).collect()
+    }
+}
+
+/// Rejects multi-point constraints on a single-precision system, whose seven
+/// significant digits the penalty stiffness would swamp.
+fn check_constraint_precision(precision: Precision) -> Result<(), EngineError> {
+    if precision == Precision::F32 {
+        return Err(EngineError::SolverFailed("Multi-point constraints need F64 precision: their penalty stiffness leaves an F32 system without significant digits".to_string()));
+    }
+    Ok(())
+}
+
+/// Rejects multi-point constraints in the analyses that do not impose them.
+fn reject_constraints(solver_name: &str, problem: &ProblemDefinition) -> Result<(), EngineError> {
+    if !problem.physics.constraints.is_empty() {
+        return Err(EngineError::SolverFailed(format!(
+            "{} does not support multi-point constraints; {} were given",
+            solver_name, problem.physics.constraints.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Writes the stiffness matrix and force vector of problem `id` as Matrix Market files in `dir`.
+fn dump_system(dir: &std::path::Path, id: &str, k: &DMatrix<f64>, f: &DVector<f64>) -> Result<(), EngineError> {
+    let create = |name: &str| {
+        let path = dir.join(format!("{}_{}.mtx", id, name));
+        println!("Writing FEM {} to {}", name, path.display());
+        std::fs::create_dir_all(dir)
+            .and_then(|_| std::fs::File::create(&path))
+            .map(std::io::BufWriter::new)
+            .map_err(|e| EngineError::ExportFailed(format!("Failed to create {}: {}", path.display(), e)))
+    };
+    crate::export::write_matrix_market(k, create("stiffness")?)?;
+    crate::export::write_matrix_market(f, create("force")?)
+}
+
+/// A stiffness matrix assembled for one mesh, material and temperature field.
+struct CachedStiffness {
+    key: String,
+    k_global: DMatrix<f64>,
+}
+
+/// An [`FemSolver`] that keeps the last assembled stiffness matrix.
+///
+/// When a problem differs from the previous one only in its boundary
+/// conditions, the matrix is reused and only the constraints and loads are
+/// reapplied, which avoids the assembly in interactive load studies. The
+/// matrix is cached in `f64` and rounded for single-precision solves.
+#[derive(Default)]
+pub struct CachedFemSolver {
+    cache: Mutex<Option<CachedStiffness>>,
+    assemblies: AtomicUsize,
+}
+
+impl CachedFemSolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns how many times the global stiffness matrix has been assembled.
+    pub fn assembly_count(&self) -> usize {
+        self.assemblies.load(Ordering::Relaxed)
+    }
+
+    /// Drops the cached stiffness matrix so the next solve assembles it again.
+    pub fn clear_cache(&self) {
+        *self.cache.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+/// Hashes everything the stiffness matrix depends on: the mesh, material and temperatures.
+fn stiffness_key(mesh: &Mesh, material: &Material, temperatures: Option<&[f64]>) -> Result<String, EngineError> {
+    let inputs = serde_json::to_vec(&(&mesh.nodes, &mesh.elements, material, temperatures))
+        .map_err(|e| EngineError::SolverFailed(format!("Failed to serialize stiffness cache key: {}", e)))?;
+    crate::provenance::calculate_hash_streaming(inputs.as_slice()).map_err(EngineError::SolverFailed)
+}
+
+impl Solver for CachedFemSolver {
+    fn name(&self) -> &'static str {
+        "CachedFemSolver"
+    }
+
+    fn check_compatibility(&self, problem: &ProblemDefinition) -> Result<(), EngineError> {
+        FemSolver::new().check_compatibility(problem)
+    }
+
+    fn capabilities(&self) -> super::SolverCapabilities {
+        FemSolver::new().capabilities()
+    }
+
+    fn solve(&self, problem: &ProblemDefinition) -> Result<super::SolverSolutionData, EngineError> {
+        println!("--- Running CachedFemSolver (Linear Elasticity) ---");
+
+        let mut warnings = Vec::new();
+        let mesh = FemSolver::new().checked_mesh(problem, &mut warnings)?;
+        check_poissons_ratio(problem, &mut warnings)?;
+        let material = &problem.physics.material;
+        let temperatures = temperature_field(problem, &mesh)?;
+        let key = stiffness_key(&mesh, material, temperatures)?;
+
+        let k_global = {
+            let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+            match cache.as_ref() {
+                Some(cached) if cached.key == key => cached.k_global.clone(),
+                _ => {
+                    let k_global = FemSolver::new().assemble_global_stiffness(&mesh, material, temperatures, problem.solver_settings.num_threads)?;
+                    self.assemblies.fetch_add(1, Ordering::Relaxed);
+                    *cache = Some(CachedStiffness { key, k_global: k_global.clone() });
+                    k_global
+                }
+            }
+        };
+
+        match problem.solver_settings.precision {
+            Precision::F64 => FemSolver::new().solve_assembled(problem, &mesh, k_global, warnings),
+            Precision::F32 => FemSolver::new().solve_assembled(problem, &mesh, k_global.map(|x| x as f32), warnings),
+        }
+        .map(|(solution, _)| solution)
+    }
+}
+
+/// A recovery-based estimate of the stress error of a FEM solution, see [`FemSolver::estimate_error`].
+#[derive(Debug, Clone)]
+pub struct ErrorEstimate {
+    /// The error indicator of every element, the L2 norm of the stress error over it.
+    pub element_errors: Vec<f64>,
+    /// The global error relative to the stress, `|e| / sqrt(|sigma|^2 + |e|^2)`.
+    pub relative_error: f64,
+}
+
+/// A frequency-domain FEM solver for the steady response to harmonic loads.
+///
+/// For each excitation frequency `f` in `SolverSettings::harmonic`, with
+/// `omega = 2 pi f`, it solves `(K - omega^2 M + i omega C) u = f` under
+/// Rayleigh damping `C = alpha M + beta K`. Loads and prescribed displacements
+/// are in-phase amplitudes. The mass matrix needs a density and a linear
+/// tetrahedral mesh.
+///
+/// The solution data holds the displacement magnitudes `|u|` at the frequency
+/// with the largest response, which for a single frequency is that frequency.
+/// The metadata records their `phase` and the `max_displacement` per frequency.
+pub struct HarmonicFemSolver;
+
+impl HarmonicFemSolver {
+    /// Returns the problem's excitation, rejecting missing or invalid frequencies and damping.
+    fn excitation<'a>(&self, problem: &'a ProblemDefinition) -> Result<&'a HarmonicExcitation, EngineError> {
+        let excitation = problem.solver_settings.harmonic.as_ref()
+            .ok_or_else(|| EngineError::SolverFailed("HarmonicFemSolver requires harmonic excitation settings".to_string()))?;
+        if excitation.frequencies.is_empty() {
+            return Err(EngineError::SolverFailed("Harmonic excitation needs at least one frequency".to_string()));
+        }
+        if let Some(f) = excitation.frequencies.iter().find(|f| !f.is_finite() || **f < 0.0) {
+            return Err(EngineError::SolverFailed(format!("Excitation frequency must be finite and non-negative, got {}", f)));
+        }
+        let RayleighDamping { alpha, beta } = excitation.damping;
+        if !(alpha >= 0.0 && beta >= 0.0 && alpha.is_finite() && beta.is_finite()) {
+            return Err(EngineError::SolverFailed(format!("Rayleigh damping coefficients must be non-negative, got alpha {} and beta {}", alpha, beta)));
+        }
+        Ok(excitation)
+    }
+}
+
+/// Solves the damped harmonic system at angular frequency `omega` for the complex displacement amplitudes.
+fn harmonic_response(
+    k: &DMatrix<f64>,
+    m: &DMatrix<f64>,
+    damping: RayleighDamping,
+    omega: f64,
+    f: &DVector<f64>,
+    prescribed: &[(usize, f64)],
+) -> Result<DVector<Complex<f64>>, EngineError> {
+    let mut a = DMatrix::from_fn(k.nrows(), k.ncols(), |i, j| {
+        let c = damping.alpha * m[(i, j)] + damping.beta * k[(i, j)];
+        Complex::new(k[(i, j)] - omega * omega * m[(i, j)], omega * c)
+    });
+    let mut rhs = f.map(Complex::from);
+    let prescribed: Vec<(usize, Complex<f64>)> = prescribed.iter().map(|&(dof, value)| (dof, Complex::from(value))).collect();
+    assembly::apply_dirichlet(&mut a, &mut rhs, &prescribed);
+    a.lu().solve(&rhs).ok_or_else(|| EngineError::SolverFailed(format!(
+        "Harmonic system is singular at {} rad/s; add damping or move the frequency off resonance", omega
+    )))
+}
+
+impl Solver for HarmonicFemSolver {
+    fn name(&self) -> &'static str {
+        "HarmonicFemSolver"
+    }
+
+    fn capabilities(&self) -> super::SolverCapabilities {
+        super::SolverCapabilities { physics: vec![super::PhysicsKind::HarmonicElasticity], dimensions: vec![3] }
+    }
+
+    fn check_compatibility(&self, problem: &ProblemDefinition) -> Result<(), EngineError> {
+        FemSolver::new().check_compatibility(problem)?;
+        reject_constraints(self.name(), problem)?;
+        self.excitation(problem)?;
+        if problem.mesh.as_ref().is_some_and(|mesh| mesh.element_type != "Tetrahedron") {
+            return Err(EngineError::SolverFailed("HarmonicFemSolver supports only linear Tetrahedron meshes".to_string()));
+        }
+        if problem.physics.material.density.is_none() {
+            return Err(EngineError::SolverFailed("HarmonicFemSolver requires a material density".to_string()));
+        }
+        Ok(())
+    }
+
+    fn solve(&self, problem: &ProblemDefinition) -> Result<super::SolverSolutionData, EngineError> {
+        println!("--- Running HarmonicFemSolver (Frequency Response) ---");
+
+        let excitation = self.excitation(problem)?;
+        let mut warnings = Vec::new();
+        let mesh = FemSolver::new().checked_mesh(problem, &mut warnings)?;
+        check_poissons_ratio(problem, &mut warnings)?;
+        let material = &problem.physics.material;
+        let temperatures = temperature_field(problem, &mesh)?;
+        let settings = &problem.solver_settings;
+
+        let k_global = FemSolver::new().assemble_global_stiffness(&mesh, material, temperatures, settings.num_threads)?;
+        let m_global = FemSolver::new().assemble_global_mass(&mesh, material, temperatures, settings.mass_matrix)?;
+        // Only the mechanical loads oscillate; a thermal load would be a static preload.
+        let mut f_global = DVector::zeros(mesh.nodes.len() * DOF_PER_NODE);
+        warnings.extend(unmatched_regions(problem, &mesh));
+        let prescribed = FemSolver::new().apply_boundary_conditions(problem, &mesh, &mut f_global)?;
+
+        let responses = excitation.frequencies
+            .iter()
+            .map(|&frequency| harmonic_response(&k_global, &m_global, excitation.damping, 2.0 * std::f64::consts::PI * frequency, &f_global, &prescribed))
+            .collect::<Result<Vec<_>, _>>()?;
+        let max_displacement: Vec<f64> = responses.iter().map(|u| u.iter().map(|c| c.modulus()).fold(0.0, f64::max)).collect();
+        let peak = (0..responses.len()).fold(0, |best, i| if max_displacement[i] > max_displacement[best] { i } else { best });
+        let u_peak = &responses[peak];
+
+        let mut metadata = serde_json::Map::new();
+        metadata.insert("frequencies".to_string(), serde_json::json!(excitation.frequencies));
+        metadata.insert("max_displacement".to_string(), serde_json::json!(max_displacement));
+        metadata.insert("frequency".to_string(), serde_json::json!(excitation.frequencies[peak]));
+        metadata.insert("phase".to_string(), serde_json::json!(u_peak.iter().map(|c| c.argument()).collect::<Vec<f64>>()));
+
+        println!("--- HarmonicFemSolver Finished ---");
+
+        Ok(super::SolverSolutionData {
+            data: u_peak.iter().map(|c| c.modulus()).collect(),
+            time_steps: Vec::new(),
+            fields: HashMap::new(),
+            metadata,
+            warnings,
+            iterations: Vec::new(),
+        })
+    }
+}
+
+/// A linear buckling solver for critical load factors.
+///
+/// It solves the static problem with the [`FemSolver`], builds the geometric
+/// stiffness `K_g` of the resulting stress state and extracts the lowest
+/// positive load factors `lambda` of `(K + lambda K_g) phi = 0` with the
+/// Lanczos method. Multiplying the applied loads by `lambda` makes the
+/// structure unstable in the mode `phi`. `SolverSettings::num_modes` sets how
+/// many factors are extracted; `tolerance` and `max_iterations` control the
+/// eigensolver and, for the CG solver, the static solve.
+///
+/// The solution data holds the first mode shape scaled to a largest component
+/// of one, and the fields hold every mode as `buckling_mode_<n>`. The metadata
+/// records the `load_factors` in ascending order.
+pub struct BucklingSolver;
+
+impl Solver for BucklingSolver {
+    fn name(&self) -> &'static str {
+        "BucklingSolver"
+    }
+
+    fn check_compatibility(&self, problem: &ProblemDefinition) -> Result<(), EngineError> {
+        FemSolver::new().check_compatibility(problem)?;
+        reject_constraints(self.name(), problem)?;
+        if problem.solver_settings.num_modes == Some(0) {
+            return Err(EngineError::SolverFailed("BucklingSolver needs at least one mode".to_string()));
+        }
+        Ok(())
+    }
+
+    fn solve(&self, problem: &ProblemDefinition) -> Result<super::SolverSolutionData, EngineError> {
+        println!("--- Running BucklingSolver (Linear Buckling) ---");
+
+        let fem = FemSolver::new();
+        let mut warnings = Vec::new();
+        let mesh = fem.checked_mesh(problem, &mut warnings)?;
+        check_poissons_ratio(problem, &mut warnings)?;
+        let material = &problem.physics.material;
+        let temperatures = temperature_field(problem, &mesh)?;
+        let settings = &problem.solver_settings;
+
+        // The prestress comes from the static solution under the applied loads.
+        let k_global = fem.assemble_global_stiffness(&mesh, material, temperatures, settings.num_threads)?;
+        let (static_solution, displacements) = fem.solve_assembled(problem, &mesh, k_global.clone(), warnings)?;
+        let strains = fem.mesh_element_strains(&mesh, displacements.as_slice())?;
+        let stresses = fem.mesh_element_stresses(&mesh, material, temperatures, &strains)?;
+        let kg_global = fem.assemble_geometric_stiffness(&mesh, &stresses)?;
+
+        // Constrained degrees of freedom stay fixed in the buckling modes.
+        let total_dof = mesh.nodes.len() * DOF_PER_NODE;
+        let prescribed = fem.apply_boundary_conditions(problem, &mesh, &mut DVector::zeros(total_dof))?;
+        let mut constrained = vec![false; total_dof];
+        for &(dof, _) in &prescribed {
+            constrained[dof] = true;
+        }
+        let free: Vec<usize> = (0..total_dof).filter(|&dof| !constrained[dof]).collect();
+        let k_free = k_global.select_rows(&free).select_columns(&free);
+        let kg_free = kg_global.select_rows(&free).select_columns(&free);
+
+        // With K = L L^T, the modes are phi = L^-T y for the symmetric problem
+        // L^-1 (-K_g) L^-T y = (1 / lambda) y, whose largest eigenvalues give the lowest factors.
+        let l = k_free.cholesky()
+            .ok_or_else(|| EngineError::SolverFailed("Buckling needs a positive definite stiffness matrix; constrain the rigid body modes".to_string()))?
+            .l();
+        let operator = |y: &DVector<f64>| {
+            let phi = l.transpose().solve_upper_triangular(y).ok_or_else(|| EngineError::SolverFailed("Singular Cholesky factor in buckling solve".to_string()))?;
+            l.solve_lower_triangular(&(-(&kg_free * phi))).ok_or_else(|| EngineError::SolverFailed("Singular Cholesky factor in buckling solve".to_string()))
+        };
+        let num_modes = settings.num_modes.unwrap_or(1);
+        let pairs = crate::kernel::lanczos_operator(free.len(), num_modes.min(free.len()), operator, settings.tolerance, settings.max_iterations)?;
+
+        let mut load_factors = Vec::new();
+        let mut fields = HashMap::new();
+        let mut first_mode = Vec::new();
+        for pair in pairs.iter().filter(|pair| pair.value > 0.0) {
+            let y = l.transpose().solve_upper_triangular(&pair.vector)
+                .ok_or_else(|| EngineError::SolverFailed("Singular Cholesky factor in buckling solve".to_string()))?;
+            let mut mode = vec![0.0; total_dof];
+            for (&dof, &value) in free.iter().zip(y.iter()) {
+                mode[dof] = value;
+            }
+            let scale = mode.iter().fold(0.0f64, |m, v| if v.abs() > m.abs() { *v } else { m });
+            mode.iter_mut().for_each(|v| *v /= scale);
+
+            load_factors.push(1.0 / pair.value);
+            if first_mode.is_empty() {
+                first_mode = mode.clone();
+            }
+            fields.insert(format!("buckling_mode_{}", load_factors.len()), mode);
+        }
+        if load_factors.is_empty() {
+            return Err(EngineError::SolverFailed("The applied loads do not cause buckling: no positive load factor found".to_string()));
+        }
+
+        let mut metadata = serde_json::Map::new();
+        metadata.insert("load_factors".to_string(), serde_json::json!(load_factors));
+        println!("Critical load factor: {:e}", load_factors[0]);
+        println!("--- BucklingSolver Finished ---");
+
+        Ok(super::SolverSolutionData {
+            data: first_mode,
+            time_steps: Vec::new(),
+            fields,
+            metadata,
+            warnings: static_solution.warnings,
+            iterations: Vec::new(),
+        })
+    }
+}
+
+/// An explicit transient FEM solver for elastic waves.
+///
+/// It marches `M a + K u = f` with central differences over the
+/// `SolverSettings::time_stepping`, whose scheme must be `CentralDifference`.
+/// Forces are applied as a step at `t = 0` and prescribed displacements hold
+/// throughout. The body starts at rest, displaced by the nodal
+/// `aux_fields["initial_displacement"]` (three components per node) if given,
+/// or else uniformly by `initial_value`. The mass matrix needs a density and a
+/// linear tetrahedral mesh.
+///
+/// The scheme is only stable for steps below `2 / omega_max`, with `omega_max`
+/// the highest natural frequency of the constrained mesh, which is found with
+/// the Lanczos method; a longer step is rejected. The solution holds the
+/// displacements at every step and the metadata the `critical_time_step`.
+pub struct WaveFemSolver;
+
+impl WaveFemSolver {
+    /// Returns the problem's time stepping, rejecting missing or unsupported settings.
+    fn stepping<'a>(&self, problem: &'a ProblemDefinition) -> Result<&'a TimeStepping, EngineError> {
+        let stepping = problem.solver_settings.time_stepping.as_ref()
+            .ok_or_else(|| EngineError::SolverFailed("WaveFemSolver requires time stepping settings".to_string()))?;
+        if stepping.scheme != TimeScheme::CentralDifference {
+            return Err(EngineError::SolverFailed(format!("WaveFemSolver needs the CentralDifference time scheme, got {:?}", stepping.scheme)));
+        }
+        if !(stepping.time_step > 0.0 && stepping.time_step.is_finite()) || stepping.num_steps == 0 {
+            return Err(EngineError::SolverFailed(format!(
+                "WaveFemSolver needs a positive time step and at least one step, got {} steps of {}",
+                stepping.num_steps, stepping.time_step
+            )));
+        }
+        Ok(stepping)
+    }
+}
+
+impl Solver for WaveFemSolver {
+    fn name(&self) -> &'static str {
+        "WaveFemSolver"
+    }
+
+    fn capabilities(&self) -> super::SolverCapabilities {
+        super::SolverCapabilities { physics: vec![super::PhysicsKind::Elastodynamics], dimensions: vec![3] }
+    }
+
+    fn check_compatibility(&self, problem: &ProblemDefinition) -> Result<(), EngineError> {
+        FemSolver::new().check_compatibility(problem)?;
+        reject_constraints(self.name(), problem)?;
+        self.stepping(problem)?;
+        if problem.mesh.as_ref().is_some_and(|mesh| mesh.element_type != "Tetrahedron") {
+            return Err(EngineError::SolverFailed("WaveFemSolver supports only linear Tetrahedron meshes".to_string()));
+        }
+        if problem.physics.material.density.is_none() {
+            return Err(EngineError::SolverFailed("WaveFemSolver requires a material density".to_string()));
+        }
+        Ok(())
+    }
+
+    fn solve(&self, problem: &ProblemDefinition) -> Result<super::SolverSolutionData, EngineError> {
+        println!("--- Running WaveFemSolver (Explicit Elastodynamics) ---");
+
+        let stepping = self.stepping(problem)?;
+        let fem = FemSolver::new();
+        let mut warnings = Vec::new();
+        let mesh = fem.checked_mesh(problem, &mut warnings)?;
+        check_poissons_ratio(problem, &mut warnings)?;
+        let material = &problem.physics.material;
+        let temperatures = temperature_field(problem, &mesh)?;
+        let settings = &problem.solver_settings;
+
+        let k_global = fem.assemble_global_stiffness(&mesh, material, temperatures, settings.num_threads)?;
+        let m_global = fem.assemble_global_mass(&mesh, material, temperatures, settings.mass_matrix)?;
+        let total_dof = mesh.nodes.len() * DOF_PER_NODE;
+        let mut f_global = DVector::zeros(total_dof);
+        warnings.extend(unmatched_regions(problem, &mesh));
+        let prescribed = fem.apply_boundary_conditions(problem, &mesh, &mut f_global)?;
+
+        let mut u0 = match problem.aux_fields.get("initial_displacement") {
+            Some(field) if field.len() == total_dof => DVector::from_column_slice(field),
+            Some(field) => return Err(EngineError::SolverFailed(format!(
+                "Initial displacement field has {} values but the mesh has {} displacement DOFs", field.len(), total_dof
+            ))),
+            None => DVector::from_element(total_dof, stepping.initial_value),
+        };
+        let mut constrained = vec![false; total_dof];
+        for &(dof, value) in &prescribed {
+            constrained[dof] = true;
+            u0[dof] = value;
+        }
+        let free: Vec<usize> = (0..total_dof).filter(|&dof| !constrained[dof]).collect();
+        let l = m_global.select_rows(&free).select_columns(&free).cholesky()
+            .ok_or_else(|| EngineError::SolverFailed("Mass matrix is not positive definite; check the density and element volumes".to_string()))?
+            .l();
+        let singular = || EngineError::SolverFailed("Singular Cholesky factor in explicit dynamics".to_string());
+
+        // omega_max^2 is the largest eigenvalue of L^-1 K L^-T with M = L L^T on the free DOFs.
+        let k_free = k_global.select_rows(&free).select_columns(&free);
+        let operator = |y: &DVector<f64>| {
+            let x = l.transpose().solve_upper_triangular(y).ok_or_else(singular)?;
+            l.solve_lower_triangular(&(&k_free * x)).ok_or_else(singular)
+        };
+        let omega_max_squared = crate::kernel::lanczos_operator(free.len(), 1, operator, settings.tolerance, settings.max_iterations)?[0].value;
+        let critical_time_step = 2.0 / omega_max_squared.max(0.0).sqrt();
+        if stepping.time_step > critical_time_step {
+            return Err(EngineError::SolverFailed(format!(
+                "Time step {:e} exceeds the stable limit {:e} of central differences (CFL condition dt <= 2 / omega_max)",
+                stepping.time_step, critical_time_step
+            )));
+        }
+
+        // Constrained DOFs do not accelerate, so they keep their prescribed values.
+        let acceleration = |_t: f64, u: &DVector<f64>| {
+            let residual = &f_global - &k_global * u;
+            let y = l.solve_lower_triangular(&residual.select_rows(&free)).ok_or_else(singular)?;
+            let a_free = l.transpose().solve_upper_triangular(&y).ok_or_else(singular)?;
+            let mut a = DVector::zeros(total_dof);
+            for (&dof, &value) in free.iter().zip(a_free.iter()) {
+                a[dof] = value;
+            }
+            Ok(a)
+        };
+        let history = integrate::march_central_difference(acceleration, u0, DVector::zeros(total_dof), 0.0, stepping.time_step, stepping.num_steps)?;
+        let time_steps: Vec<(f64, Vec<f64>)> = history.into_iter().map(|(t, u)| (t, u.iter().cloned().collect())).collect();
+
+        let mut metadata = serde_json::Map::new();
+        metadata.insert("critical_time_step".to_string(), serde_json::json!(critical_time_step));
+        println!("--- WaveFemSolver Finished ---");
+
+        Ok(super::SolverSolutionData {
+            data: time_steps.last().map(|(_, u)| u.clone()).unwrap_or_default(),
+            time_steps,
+            fields: HashMap::new(),
+            metadata,
+            warnings,
+            iterations: Vec::new(),
+        })
+    }
+}
+
+/// Warns about boundary conditions on regions the mesh does not have, which
+/// would otherwise be skipped without a trace. Point forces are located by
+/// position and need no region.
+pub(crate) fn unmatched_regions(problem: &ProblemDefinition, mesh: &Mesh) -> Vec<EngineWarning> {
+    problem.physics.boundary_conditions
+        .iter()
+        .filter(|bc| !matches!(bc.value, BcValue::PointLoad { .. }) && !mesh.boundary_regions.contains_key(&bc.region))
+        .map(|bc| EngineWarning::emit(
+            WarningCode::UnmatchedRegion,
+            format!("{} condition on region {} was not applied: the mesh has no such region", bc.condition_type, bc.region),
+        ))
+        .collect()
+}
+
+/// Distance, relative to the mesh bounding-box diagonal, within which a point load snaps to a node.
+const POINT_LOAD_SNAP_TOLERANCE: f64 = 1e-3;
+
+/// Finds the node closest to `position`, failing if none lies within the snap tolerance.
+fn nearest_node(mesh: &Mesh, position: [f64; 3]) -> Result<usize, EngineError> {
+    let (min, max) = crate::meshing::bounding_box(mesh);
+    let diagonal = (0..3).map(|i| (max[i] - min[i]).powi(2)).sum::<f64>().sqrt();
+    let distance = |node: &[f64; 3]| (0..3).map(|i| (node[i] - position[i]).powi(2)).sum::<f64>().sqrt();
+
+    mesh.nodes
+        .iter()
+        .enumerate()
+        .map(|(idx, node)| (idx, distance(node)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .filter(|&(_, d)| d <= POINT_LOAD_SNAP_TOLERANCE * diagonal)
+        .map(|(idx, _)| idx)
+        .ok_or_else(|| EngineError::SolverFailed(format!("No mesh node within tolerance of point load at {:?}", position)))
+}
+
+/// Determines the normal axis (0 = x, 1 = y, 2 = z) of a symmetry plane.
+///
+/// The value may give the axis index as a scalar, or a normal vector whose dominant
+/// component selects the axis. A zero vector infers the axis along which the
+/// region's nodes are flat.
+fn symmetry_axis(region: &str, value: &BcValue, mesh: &Mesh, nodes: &[usize]) -> Result<usize, EngineError> {
+    match *value {
+        BcValue::Scalar(axis) if axis == 0.0 || axis == 1.0 || axis == 2.0 => Ok(axis as usize),
+        BcValue::Vector3(normal) if normal.iter().any(|c| *c != 0.0) => Ok((0..3)
+            .max_by(|&a, &b| normal[a].abs().total_cmp(&normal[b].abs()))
+            .unwrap_or(0)),
+        BcValue::Vector3(_) => {
+            let coords: Vec<[f64; 3]> = nodes.iter().filter_map(|&n| mesh.node(n)).collect();
+            let extents: Vec<f64> = (0..3)
+                .map(|axis| {
+                    let (lo, hi) = coords.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), c| (lo.min(c[axis]), hi.max(c[axis])));
+                    hi - lo
+                })
+                .collect();
+            let largest = extents.iter().cloned().fold(0.0, f64::max);
+            let axis = (0..3).min_by(|&a, &b| extents[a].total_cmp(&extents[b])).unwrap_or(0);
+            if coords.is_empty() || extents[axis] > 1e-6 * largest {
+                return Err(EngineError::SolverFailed(format!("Cannot infer a symmetry axis for region {}: it is not an axis-aligned plane", region)));
+            }
+            Ok(axis)
+        }
+        _ => Err(EngineError::SolverFailed(format!("Symmetry condition on {} needs an axis 0, 1 or 2 or a normal vector, got {:?}", region, value))),
+    }
+}
+
+/// Result of checking `K u = f` after a solve.
+#[derive(Debug, Clone, PartialEq)]
+struct Equilibrium {
+    /// Norm of `K u - f` over the free DOFs.
+    residual_norm: f64,
+    /// Sum of the reaction forces at constrained DOFs, per axis.
+    total_reaction: [f64; 3],
+    /// The full out-of-balance force `K u - f`, which is the reaction at constrained DOFs.
+    forces: DVector<f64>,
+}
+
+/// Computes the free-DOF residual and the total reaction of a solved system.
+///
+/// `k_u` is the product of the stiffness before the boundary conditions were
+/// imposed with the solved displacements, and `f` the matching load.
+fn check_equilibrium(k_u: &DVector<f64>, f: &DVector<f64>, prescribed: &[(usize, f64)]) -> Equilibrium {
+    let forces = k_u - f;
+    let mut residual = forces.clone();
+    let mut total_reaction = [0.0; 3];
+    let mut constrained: Vec<usize> = prescribed.iter().map(|&(dof, _)| dof).collect();
+    constrained.sort_unstable();
+    constrained.dedup();
+    for dof in constrained {
+        total_reaction[dof % DOF_PER_NODE] += residual[dof];
+        residual[dof] = 0.0;
+    }
+    Equilibrium {
+        residual_norm: residual.norm(),
+        total_reaction,
+        forces,
+    }
+}
+
+/// Returns the signed volume and the constant shape-function gradients of a linear tetrahedron.
+///
+/// The volume is positive when the nodes are ordered so that `(n2-n1, n3-n1, n4-n1)`
+/// forms a right-handed triad.
+fn shape_function_gradients(nodes: [[f64; 3]; 4]) -> Result<(f64, [[f64; 3]; 4]), EngineError> {
+    // Rows [1, x, y, z]; the inverse holds the coefficients of N_i = a + b x + c y + d z in column i.
+    let c = DMatrix::<f64>::from_fn(4, 4, |row, col| if col == 0 { 1.0 } else { nodes[row][col - 1] });
+    let volume = c.determinant() / 6.0;
+    let c_inv = c.try_inverse()
+        .filter(|_| volume.abs() > f64::EPSILON * 1e3 * characteristic_length(&nodes).powi(3))
+        .ok_or_else(|| EngineError::SolverFailed("Degenerate tetrahedron with zero volume".to_string()))?;
+
+    let gradients = std::array::from_fn(|i| [c_inv[(1, i)], c_inv[(2, i)], c_inv[(3, i)]]);
+    Ok((volume, gradients))
+}
+
+/// Largest coordinate extent of a set of nodes, used to scale degeneracy checks.
+fn characteristic_length(nodes: &[[f64; 3]]) -> f64 {
+    (0..3)
+        .map(|axis| {
+            let (lo, hi) = nodes.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), n| (lo.min(n[axis]), hi.max(n[axis])));
+            hi - lo
+        })
+        .fold(0.0, f64::max)
+}
+
+/// Returns the Jacobian determinant and physical shape-function gradients of a
+/// 10-node tetrahedron at the reference point `(xi, eta, zeta)`.
+///
+/// Corner `i` has `N = L_i (2 L_i - 1)` and the edge node between corners `a`
+/// and `b` has `N = 4 L_a L_b`, with `L = (1 - xi - eta - zeta, xi, eta, zeta)`.
+fn tet10_shape_function_gradients(nodes: &[[f64; 3]], point: [f64; 3]) -> Result<(f64, Vec<[f64; 3]>), EngineError> {
+    let l = [1.0 - point[0] - point[1] - point[2], point[0], point[1], point[2]];
+    let dl = [[-1.0, -1.0, -1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    let mut reference: Vec<[f64; 3]> = (0..4).map(|i| dl[i].map(|d| (4.0 * l[i] - 1.0) * d)).collect();
+    reference.extend(crate::meshing::TETRAHEDRON10_EDGES.iter().map(|&[a, b]| std::array::from_fn(|k| 4.0 * (l[a] * dl[b][k] + l[b] * dl[a][k]))));
+
+    // J[(r, c)] = d x_r / d xi_c
+    let mut jacobian = nalgebra::Matrix3::<f64>::zeros();
+    for (node, grad) in nodes.iter().zip(&reference) {
+        for r in 0..3 {
+            for c in 0..3 {
+                jacobian[(r, c)] += node[r] * grad[c];
+            }
+        }
+    }
+    let det = jacobian.determinant();
+    let inv_t = jacobian
+        .try_inverse()
+        .filter(|_| det.abs() > f64::EPSILON * 1e3 * characteristic_length(nodes).powi(3))
+        .ok_or_else(|| EngineError::SolverFailed("Degenerate tetrahedron with zero volume".to_string()))?
+        .transpose();
+    let gradients = reference
+        .iter()
+        .map(|g| {
+            let physical = inv_t * nalgebra::Vector3::new(g[0], g[1], g[2]);
+            [physical[0], physical[1], physical[2]]
+        })
+        .collect();
+    Ok((det, gradients))
+}
+
+/// An integration point as its weight and the shape-function gradients there.
+pub(crate) type IntegrationPoint = (f64, Vec<[f64; 3]>);
+
+/// Returns the integration points of a tetrahedron.
+///
+/// Weights include the Jacobian, so summing `weight * f` integrates `f` over
+/// the element. Linear tetrahedra have constant gradients and a single point
+/// weighted by their volume; 10-node ones use the points of
+/// `gauss_points(Tetrahedron, 2)`, in that order.
+pub(crate) fn integration_points(nodes: &[[f64; 3]]) -> Result<Vec<IntegrationPoint>, EngineError> {
+    match nodes.len() {
+        4 => {
+            let (volume, gradients) = shape_function_gradients([nodes[0], nodes[1], nodes[2], nodes[3]])?;
+            Ok(vec![(volume.abs(), gradients.to_vec())])
+        }
+        10 => gauss_points(ElementShape::Tetrahedron, 2)
+            .into_iter()
+            .map(|qp| {
+                let (det, gradients) = tet10_shape_function_gradients(nodes, qp.point)?;
+                Ok((qp.weight * det.abs(), gradients))
+            })
+            .collect(),
+        n => Err(EngineError::SolverFailed(format!("Unsupported tetrahedron with {} nodes", n))),
+    }
+}
+
+/// Strain-displacement matrix B (6 x 3n) in Voigt order [xx, yy, zz, xy, yz, zx].
+fn strain_displacement_matrix(gradients: &[[f64; 3]]) -> DMatrix<f64> {
+    let mut b = DMatrix::<f64>::zeros(6, gradients.len() * DOF_PER_NODE);
+    for (i, &[dx, dy, dz]) in gradients.iter().enumerate() {
+        let c = i * DOF_PER_NODE;
+        b[(0, c)] = dx;
+        b[(1, c + 1)] = dy;
+        b[(2, c + 2)] = dz;
+        b[(3, c)] = dy;
+        b[(3, c + 1)] = dx;
+        b[(4, c + 1)] = dz;
+        b[(4, c + 2)] = dy;
+        b[(5, c)] = dz;
+        b[(5, c + 2)] = dx;
+    }
+    b
+}
+
+/// The von Mises equivalent of a stress in Voigt order [xx, yy, zz, xy, yz, zx].
+fn von_mises(stress: &[f64; 6]) -> f64 {
+    let [xx, yy, zz, xy, yz, zx] = *stress;
+    (0.5 * ((xx - yy).powi(2) + (yy - zz).powi(2) + (zz - xx).powi(2)) + 3.0 * (xy * xy + yz * yz + zx * zx)).sqrt()
+}
+
+/// Mean nodal temperature of an element, used to evaluate its material properties.
+fn element_temperature(element: &[usize], temperatures: Option<&[f64]>) -> Option<f64> {
+    temperatures.map(|t| element.iter().map(|&n| t[n]).sum::<f64>() / element.len() as f64)
+}
+
+/// Thermal strain `alpha (T - T_ref)` on the normal Voigt components, or `None`
+/// when the material has no expansion coefficient or there is no temperature.
+fn thermal_strain(material: &Material, temperature: Option<f64>) -> Result<Option<DVector<f64>>, EngineError> {
+    let (Some(alpha), Some(t)) = (&material.thermal_expansion, temperature) else {
+        return Ok(None);
+    };
+    let strain = alpha.value(Some(t))? * (t - material.reference_temperature);
+    Ok(Some(DVector::from_row_slice(&[strain, strain, strain, 0.0, 0.0, 0.0])))
+}
+
+/// Isotropic linear elasticity matrix in Voigt notation with engineering shear strains.
+fn elasticity_matrix(youngs_modulus: f64, poissons_ratio: f64) -> DMatrix<f64> {
+    let lambda = youngs_modulus * poissons_ratio / ((1.0 + poissons_ratio) * (1.0 - 2.0 * poissons_ratio));
+    let mu = youngs_modulus / (2.0 * (1.0 + poissons_ratio));
+
+    let mut d = DMatrix::<f64>::zeros(6, 6);
+    for i in 0..3 {
+        for j in 0..3 {
+            d[(i, j)] = lambda;
+        }
+        d[(i, i)] += 2.0 * mu;
+        d[(i + 3, i + 3)] = mu;
+    }
+    d
+}
+
+impl FemSolver {
+    /// Sums the reaction forces per constrained region.
+    ///
+    /// Only the components a region's own Dirichlet or Symmetry condition fixes are
+    /// summed, so a region reports the force its supports carry.
+    fn region_reactions(&self, problem: &ProblemDefinition, mesh: &Mesh, forces: &DVector<f64>) -> Result<HashMap<String, [f64; 3]>, EngineError> {
+        let mut reactions: HashMap<String, [f64; 3]> = HashMap::new();
+        for bc in &problem.physics.boundary_conditions {
+            let Some(nodes) = mesh.boundary_regions.get(&bc.region) else {
+                continue;
+            };
+            let components: Vec<usize> = match (bc.condition_type.as_str(), &bc.value) {
+                ("Dirichlet", BcValue::Vector3(displacement)) => (0..DOF_PER_NODE).filter(|&i| displacement[i].is_finite()).collect(),
+                ("Dirichlet", BcValue::MaskedVector3 { constrained, .. }) => (0..DOF_PER_NODE).filter(|&i| constrained[i]).collect(),
+                ("Symmetry", value) => vec![symmetry_axis(&bc.region, value, mesh, nodes)?],
+                _ => continue,
+            };
+            let reaction = reactions.entry(bc.region.clone()).or_insert([0.0; 3]);
+            for &node in nodes {
+                for &i in &components {
+                    reaction[i] += forces[node * DOF_PER_NODE + i];
+                }
+            }
+        }
+        Ok(reactions)
+    }
+
+    /// Adds nodal forces to `f_global` and collects the prescribed displacements.
+    ///
+    /// Returns `(dof, value)` pairs for every constrained degree of freedom.
+    /// Regions missing from the mesh are skipped.
+    fn apply_boundary_conditions(&self, problem: &ProblemDefinition, mesh: &Mesh, f_global: &mut DVector<f64>) -> Result<Vec<(usize, f64)>, EngineError> {
+        let mut prescribed = Vec::new();
+
+        for bc in &problem.physics.boundary_conditions {
+            if let ("PointForce", BcValue::PointLoad { position, force }) = (bc.condition_type.as_str(), &bc.value) {
+                // Point loads are located by coordinates, not by region.
+                let node_idx = nearest_node(mesh, *position)?;
+                for (i, &value) in force.iter().enumerate() {
+                    f_global[node_idx * DOF_PER_NODE + i] += value;
+                }
+                continue;
+            }
+
+            // Find nodes belonging to the specified region.
+            let Some(region_nodes_indices) = mesh.boundary_regions.get(&bc.region) else {
+                continue;
+            };
+            if bc.condition_type == "Symmetry" {
+                // Only the displacement normal to the symmetry plane is constrained.
+                let axis = symmetry_axis(&bc.region, &bc.value, mesh, region_nodes_indices)?;
+                prescribed.extend(region_nodes_indices.iter().map(|&node_idx| (node_idx * DOF_PER_NODE + axis, 0.0)));
+                continue;
+            }
+            for &node_idx in region_nodes_indices {
+                match (bc.condition_type.as_str(), &bc.value) {
+                    ("Dirichlet", BcValue::Vector3(displacement)) => {
+                        // Apply displacement boundary conditions.
+                        for (i, &value) in displacement.iter().enumerate() {
+                            if value.is_finite() { // Only apply if value is not NaN (meaning unconstrained)
+                                prescribed.push((node_idx * DOF_PER_NODE + i, value));
+                            }
+                        }
+                    },
+                    ("Dirichlet", BcValue::MaskedVector3 { value, constrained }) => {
+                        // Only the flagged components are pinned; the others stay free.
+                        for i in (0..DOF_PER_NODE).filter(|&i| constrained[i]) {
+                            prescribed.push((node_idx * DOF_PER_NODE + i, value[i]));
+                        }
+                    },
+                    ("Force", BcValue::Vector3(force)) => {
+                        // Apply nodal forces.
+                        for (i, &value) in force.iter().enumerate() {
+                            f_global[node_idx * DOF_PER_NODE + i] += value;
+                        }
+                    },
+                    ("Dirichlet" | "Force", value) => return Err(EngineError::SolverFailed(format!("{} condition on {} needs a 3-component value, got {:?}", bc.condition_type, bc.region, value))),
+                    _ => return Err(EngineError::SolverFailed(format!("Unsupported boundary condition type: {}", bc.condition_type))),
+                }
+            }
+        }
+
+        Ok(prescribed)
+    }
+
+    /// Assembles the global stiffness matrix from all element contributions.
+    ///
+    /// Element matrices are computed in `f64` in parallel and then summed into the
+    /// global matrix of `T` in element order, so the result does not depend on the thread count.
+    /// `num_threads` of `None` uses the global rayon pool; `Some(1)` assembles serially.
+    /// When a nodal `temperatures` field is given, temperature-dependent material
+    /// properties are evaluated at each element's mean nodal temperature.
+    fn assemble_global_stiffness<T: RealField + Copy>(
+        &self,
+        mesh: &Mesh,
+        material: &Material,
+        temperatures: Option<&[f64]>,
+        num_threads: Option<usize>,
+    ) -> Result<DMatrix<T>, EngineError> {
+        let element_triplets: Vec<Vec<Triplet>> = match num_threads {
+            Some(1) => mesh.elements
+                .iter()
+                .enumerate()
+                .map(|(elem_idx, element)| self.element_triplets(mesh, elem_idx, element, material, temperatures))
+                .collect::<Result<_, _>>()?,
+            Some(threads) => {
+                let pool = rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .map_err(|e| EngineError::SolverFailed(format!("Failed to build assembly thread pool: {}", e)))?;
+                pool.install(|| self.par_element_triplets(mesh, material, temperatures))?
+            }
+            None => self.par_element_triplets(mesh, material, temperatures)?,
+        };
+
+        Ok(assembly::assemble_triplets(FIELD, mesh.nodes.len(), element_triplets.into_iter().flatten()))
+    }
+
+    /// Computes the triplets of every element on the current rayon pool.
+    fn par_element_triplets(&self, mesh: &Mesh, material: &Material, temperatures: Option<&[f64]>) -> Result<Vec<Vec<Triplet>>, EngineError> {
+        mesh.elements
+            .par_iter()
+            .enumerate()
+            .map(|(elem_idx, element)| self.element_triplets(mesh, elem_idx, element, material, temperatures))
+            .collect()
+    }
+
+    /// Computes the element stiffness matrix as global triplets.
+    fn element_triplets(
+        &self,
+        mesh: &Mesh,
+        elem_idx: usize,
+        element: &[usize],
+        material: &Material,
+        temperatures: Option<&[f64]>,
+    ) -> Result<Vec<Triplet>, EngineError> {
+        if !matches!(element.len(), 4 | 10) {
+            return Err(EngineError::SolverFailed(format!("Element {} is not a tetrahedron (node count: {})", elem_idx, element.len())));
+        }
+        // Ensure node indices are within bounds.
+        if element.iter().any(|&n| n >= mesh.nodes.len()) {
+            return Err(EngineError::SolverFailed(format!("Element {} contains out-of-bounds node index.", elem_idx)));
+        }
+
+        // Get node coordinates for the current element.
+        let nodes: Vec<[f64; 3]> = element.iter().map(|&n| mesh.nodes[n]).collect();
+
+        let ke = match &self.element_stiffness {
+            Some(element_stiffness) => {
+                let ke = element_stiffness(&nodes, material);
+                let size = nodes.len() * DOF_PER_NODE;
+                if ke.shape() != (size, size) {
+                    return Err(EngineError::SolverFailed(format!(
+                        "Custom element stiffness for element {} is {}x{}, expected {}x{}",
+                        elem_idx, ke.nrows(), ke.ncols(), size, size
+                    )));
+                }
+                ke
+            }
+            None => self.assemble_tetrahedron_stiffness_matrix(&nodes, material, element_temperature(element, temperatures))?,
+        };
+        Ok(assembly::element_triplets(FIELD, element, &ke))
+    }
+
+    /// Assembles the element stiffness matrix of a 4- or 10-node tetrahedron.
+    ///
+    /// The matrix is the integral of `B^T * D * B`, where `B` is the
+    /// strain-displacement matrix and `D` the isotropic elasticity matrix. For
+    /// linear (constant strain) tetrahedra this is `V * B^T * D * B`.
+    fn assemble_tetrahedron_stiffness_matrix(
+        &self,
+        nodes: &[[f64; 3]],
+        material: &Material,
+        temperature: Option<f64>,
+    ) -> Result<DMatrix<f64>, EngineError> {
+        let youngs_modulus = material.youngs_modulus.value(temperature)?;
+        let poissons_ratio = material.poissons_ratio.value(temperature)?;
+        let d = elasticity_matrix(youngs_modulus, poissons_ratio);
+
+        let size = nodes.len() * DOF_PER_NODE;
+        let mut ke = DMatrix::<f64>::zeros(size, size);
+        for (weight, gradients) in integration_points(nodes)? {
+            let b = strain_displacement_matrix(&gradients);
+            ke += b.transpose() * &d * b * weight;
+        }
+        Ok(ke)
+    }
+
+    /// Assembles the geometric (stress) stiffness matrix of the element `stresses`.
+    ///
+    /// An element contributes `integral(grad N_a^T S grad N_b) I` to each node
+    /// pair, where `S` is its stress tensor. Stresses are given per element in
+    /// Voigt order, so a 10-node tetrahedron uses its volume average.
+    pub fn assemble_geometric_stiffness(&self, mesh: &Mesh, stresses: &[[f64; 6]]) -> Result<DMatrix<f64>, EngineError> {
+        if stresses.len() != mesh.elements.len() {
+            return Err(EngineError::SolverFailed(format!("Expected {} element stresses, got {}", mesh.elements.len(), stresses.len())));
+        }
+        let mut triplets = Vec::new();
+        for (element, &[xx, yy, zz, xy, yz, zx]) in mesh.elements.iter().zip(stresses) {
+            let sigma = nalgebra::Matrix3::new(xx, xy, zx, xy, yy, yz, zx, yz, zz);
+            let nodes: Vec<[f64; 3]> = element.iter().map(|&n| mesh.nodes[n]).collect();
+            let mut scalar = DMatrix::<f64>::zeros(nodes.len(), nodes.len());
+            for (weight, gradients) in integration_points(&nodes)? {
+                let g = DMatrix::from_fn(3, nodes.len(), |r, c| gradients[c][r]);
+                scalar += g.transpose() * sigma * g * weight;
+            }
+            let kg = scalar.kronecker(&DMatrix::<f64>::identity(DOF_PER_NODE, DOF_PER_NODE));
+            triplets.extend(assembly::element_triplets(FIELD, element, &kg));
+        }
+        Ok(assembly::assemble_triplets(FIELD, mesh.nodes.len(), triplets))
+    }
+
+    /// Assembles the equivalent nodal forces `V B^T D eps_th` of the thermal strains.
+    ///
+    /// The load is zero without a temperature field or an expansion coefficient.
+    fn assemble_thermal_load(&self, mesh: &Mesh, material: &Material, temperatures: Option<&[f64]>) -> Result<DVector<f64>, EngineError> {
+        let mut f_thermal = DVector::<f64>::zeros(mesh.nodes.len() * DOF_PER_NODE);
+        for element in &mesh.elements {
+            let temperature = element_temperature(element, temperatures);
+            let Some(strain) = thermal_strain(material, temperature)? else {
+                continue;
+            };
+            let nodes: Vec<[f64; 3]> = element.iter().map(|&n| mesh.nodes[n]).collect();
+            let d = elasticity_matrix(material.youngs_modulus.value(temperature)?, material.poissons_ratio.value(temperature)?);
+            let stress = &d * strain;
+            let mut fe = DVector::<f64>::zeros(nodes.len() * DOF_PER_NODE);
+            for (weight, gradients) in integration_points(&nodes)? {
+                fe += strain_displacement_matrix(&gradients).transpose() * &stress * weight;
+            }
+            for (i, &node) in element.iter().enumerate() {
+                for dof in 0..DOF_PER_NODE {
+                    f_thermal[node * DOF_PER_NODE + dof] += fe[i * DOF_PER_NODE + dof];
+                }
+            }
+        }
+        Ok(f_thermal)
+    }
 
-        let mesh = problem.mesh.as_ref().ok_or_else(|| EngineError::SolverFailed("Mesh not found in problem definition".to_string()))?;
-        let material = &problem.physics.material;
+    /// Computes the strain `B u` of every element from the nodal `displacements`,
+    /// in Voigt order [xx, yy, zz, xy, yz, zx] with engineering shear strains.
+    ///
+    /// The strain of a 10-node tetrahedron varies inside it; its volume average is returned.
+    pub fn element_strains(&self, problem: &ProblemDefinition, displacements: &[f64]) -> Result<Vec<[f64; 6]>, EngineError> {
+        let mesh = self.checked_mesh(problem, &mut Vec::new())?;
+        self.mesh_element_strains(&mesh, displacements)
+    }
+
+    /// Computes the stress `D (B u - eps_th)` of every element from the nodal
+    /// `displacements`, in Voigt order [xx, yy, zz, xy, yz, zx].
+    ///
+    /// The stress of a 10-node tetrahedron varies inside it; its volume average is returned.
+    pub fn element_stresses(&self, problem: &ProblemDefinition, displacements: &[f64]) -> Result<Vec<[f64; 6]>, EngineError> {
+        let mesh = self.checked_mesh(problem, &mut Vec::new())?;
+        let strains = self.mesh_element_strains(&mesh, displacements)?;
+        self.mesh_element_stresses(&mesh, &problem.physics.material, temperature_field(problem, &mesh)?, &strains)
+    }
 
+    /// Estimates the discretization error of the stresses from the nodal
+    /// `displacements` by Zienkiewicz-Zhu gradient recovery.
+    ///
+    /// The constant element stresses are averaged to the nodes by volume, and an
+    /// element's indicator is the L2 norm over it of the difference between this
+    /// recovered field and its own stress, using the nodes as quadrature points.
+    /// Only linear tetrahedra are supported.
+    pub fn estimate_error(&self, problem: &ProblemDefinition, displacements: &[f64]) -> Result<ErrorEstimate, EngineError> {
+        let mesh = self.checked_mesh(problem, &mut Vec::new())?;
         if mesh.element_type != "Tetrahedron" {
-            return Err(EngineError::SolverFailed(format!("FemSolver currently only supports Tetrahedral meshes, but found {}", mesh.element_type)));
+            return Err(EngineError::SolverFailed(format!("Error estimation supports only Tetrahedron meshes, found {}", mesh.element_type)));
         }
+        let strains = self.mesh_element_strains(&mesh, displacements)?;
+        let stresses = self.mesh_element_stresses(&mesh, &problem.physics.material, temperature_field(problem, &mesh)?, &strains)?;
+        let flat: Vec<f64> = stresses.iter().flatten().copied().collect();
+        let recovered = crate::meshing::average_to_nodes(&mesh, &flat, 6)?;
+        let volumes = self.calculate_tetrahedron_volumes(&mesh)?;
 
-        // 1. Initialize global stiffness matrix (K) and force vector (F).
-        let num_nodes = mesh.nodes.len();
-        let dof_per_node = 3; // 3 degrees of freedom (x, y, z displacement) per node
-        let total_dof = num_nodes * dof_per_node;
-
-        let mut k_global = DMatrix::<f64>::zeros(total_dof, total_dof);
-        let mut f_global = DVector::<f64>::zeros(total_dof);
-
-        // 2. Assemble element stiffness matrices and global system.
-        // For simplicity, we'll assume a constant strain tetrahedron (CST) for now.
-        // This is a placeholder for the actual element stiffness matrix assembly.
-        // In a real FEM solver, this would involve complex matrix algebra.
-        for (elem_idx, element) in mesh.elements.iter().enumerate() {
-            if element.len() != 4 {
-                return Err(EngineError::SolverFailed(format!("Element {} is not a tetrahedron (node count: {})", elem_idx, element.len())));
-            }
+        let mut stress_norm_sq = 0.0;
+        let element_errors: Vec<f64> = mesh.elements
+            .iter()
+            .zip(&stresses)
+            .zip(&volumes)
+            .map(|((element, stress), &volume)| {
+                stress_norm_sq += volume * stress.iter().map(|s| s * s).sum::<f64>();
+                let difference_sq: f64 = element
+                    .iter()
+                    .map(|&n| (0..6).map(|c| (recovered[n * 6 + c] - stress[c]).powi(2)).sum::<f64>())
+                    .sum();
+                (volume * difference_sq / element.len() as f64).sqrt()
+            })
+            .collect();
+        let error_norm_sq: f64 = element_errors.iter().map(|e| e * e).sum();
+        let total = stress_norm_sq + error_norm_sq;
+        Ok(ErrorEstimate {
+            relative_error: if total > 0.0 { (error_norm_sq / total).sqrt() } else { 0.0 },
+            element_errors,
+        })
+    }
 
-            // Get node coordinates for the current element.
-            let n1 = mesh.nodes[element[0]];
-            let n2 = mesh.nodes[element[1]];
-            let n3 = mesh.nodes[element[2]];
-            let n4 = mesh.nodes[element[3]];
+    /// Computes the volume-averaged element strains on an already checked mesh.
+    fn mesh_element_strains(&self, mesh: &Mesh, displacements: &[f64]) -> Result<Vec<[f64; 6]>, EngineError> {
+        if displacements.len() != mesh.nodes.len() * DOF_PER_NODE {
+            return Err(EngineError::SolverFailed(format!("Expected {} displacement values, got {}", mesh.nodes.len() * DOF_PER_NODE, displacements.len())));
+        }
 
-            // Placeholder for element stiffness matrix (Ke).
-            // For a real implementation, this would be derived from material properties and element geometry.
-            let ke = self.assemble_tetrahedron_stiffness_matrix(n1, n2, n3, n4, material)?;
+        mesh.elements
+            .iter()
+            .map(|element| {
+                if !matches!(element.len(), 4 | 10) || element.iter().any(|&n| n >= mesh.nodes.len()) {
+                    return Err(EngineError::SolverFailed(format!("Invalid tetrahedron {:?}", element)));
+                }
+                let nodes: Vec<[f64; 3]> = element.iter().map(|&n| mesh.nodes[n]).collect();
+                let ue = DVector::from_iterator(element.len() * DOF_PER_NODE, element.iter().flat_map(|&n| displacements[n * DOF_PER_NODE..(n + 1) * DOF_PER_NODE].iter().copied()));
+                let mut strain = DVector::<f64>::zeros(6);
+                let mut volume = 0.0;
+                for (weight, gradients) in integration_points(&nodes)? {
+                    strain += strain_displacement_matrix(&gradients) * &ue * weight;
+                    volume += weight;
+                }
+                strain /= volume;
+                Ok(std::array::from_fn(|i| strain[i]))
+            })
+            .collect()
+    }
 
-            // Assemble Ke into K_global and Fe into F_global.
-            // This is a simplified assembly process.
-            for i in 0..4 {
-                for j in 0..4 {
-                    for dof_i in 0..dof_per_node {
-                        for dof_j in 0..dof_per_node {
-                            // Ensure node indices are within bounds.
-            if element[i] >= num_nodes || element[j] >= num_nodes {
-                println!("DEBUG: Element {:?} contains out-of-bounds node index. num_nodes: {}", element, num_nodes);
-                return Err(EngineError::SolverFailed(format!("Element {} contains out-of-bounds node index.", elem_idx)));
-            }
-            let global_row = element[i] * dof_per_node + dof_i;
-            let global_col = element[j] * dof_per_node + dof_j;
-            k_global[(global_row, global_col)] += ke[(i * dof_per_node + dof_i, j * dof_per_node + dof_j)];
-                        }
-                    }
+    /// Computes the element stresses from the element `strains`, removing any thermal strain.
+    fn mesh_element_stresses(&self, mesh: &Mesh, material: &Material, temperatures: Option<&[f64]>, strains: &[[f64; 6]]) -> Result<Vec<[f64; 6]>, EngineError> {
+        mesh.elements
+            .iter()
+            .zip(strains)
+            .map(|(element, strain)| {
+                let temperature = element_temperature(element, temperatures);
+                let mut strain = DVector::from_column_slice(strain);
+                if let Some(thermal) = thermal_strain(material, temperature)? {
+                    strain -= thermal;
                 }
-            }
-        }
+                let d = elasticity_matrix(material.youngs_modulus.value(temperature)?, material.poissons_ratio.value(temperature)?);
+                let stress = d * strain;
+                Ok(std::array::from_fn(|i| stress[i]))
+            })
+            .collect()
+    }
 
-        // 3. Apply boundary conditions.
-        let mut prescribed_dofs = Vec::new();
-        let mut prescribed_values = Vec::new();
+    /// Computes the requested [`OutputField`]s other than the displacements as nodal fields.
+    fn output_fields(&self, problem: &ProblemDefinition, mesh: &Mesh, u: &DVector<f64>, reactions: &DVector<f64>, prescribed: &[(usize, f64)]) -> Result<HashMap<String, Vec<f64>>, EngineError> {
+        let outputs = &problem.solver_settings.outputs;
+        let mut fields = HashMap::new();
 
-        for bc in &problem.physics.boundary_conditions {
-            // Find nodes belonging to the specified region.
-            if let Some(region_nodes_indices) = problem.mesh.as_ref().unwrap().boundary_regions.get(&bc.region) {
-                for &node_idx in region_nodes_indices {
-                    match bc.condition_type.as_str() {
-                        "Dirichlet" => {
-                            // Apply displacement boundary conditions.
-                            for i in 0..dof_per_node {
-                                if bc.value[i].is_finite() { // Only apply if value is not NaN (meaning unconstrained)
-                                    prescribed_dofs.push(node_idx * dof_per_node + i);
-                                    prescribed_values.push(bc.value[i]);
-                                }
-                            }
-                        },
-                        "Force" => {
-                            // Apply nodal forces.
-                            for i in 0..dof_per_node {
-                                f_global[node_idx * dof_per_node + i] += bc.value[i];
-                            }
-                        },
-                        _ => return Err(EngineError::SolverFailed(format!("Unsupported boundary condition type: {}", bc.condition_type))),
-                    }
+        if outputs.iter().any(|o| matches!(o, OutputField::Strain | OutputField::Stress | OutputField::VonMises)) {
+            let strains = self.mesh_element_strains(mesh, u.as_slice())?;
+            if outputs.contains(&OutputField::Strain) {
+                let values: Vec<f64> = strains.iter().flatten().copied().collect();
+                fields.insert("strain".to_string(), crate::meshing::average_to_nodes(mesh, &values, 6)?);
+            }
+            if outputs.iter().any(|o| matches!(o, OutputField::Stress | OutputField::VonMises)) {
+                let stresses = self.mesh_element_stresses(mesh, &problem.physics.material, temperature_field(problem, mesh)?, &strains)?;
+                if outputs.contains(&OutputField::Stress) {
+                    let values: Vec<f64> = stresses.iter().flatten().copied().collect();
+                    fields.insert("stress".to_string(), crate::meshing::average_to_nodes(mesh, &values, 6)?);
+                }
+                if outputs.contains(&OutputField::VonMises) {
+                    let values: Vec<f64> = stresses.iter().map(von_mises).collect();
+                    fields.insert("von_mises".to_string(), crate::meshing::average_to_nodes(mesh, &values, 1)?);
                 }
             }
         }
 
-        // Modify K_global and F_global for prescribed DOFs.
-        for (&dof_idx, &value) in prescribed_dofs.iter().zip(prescribed_values.iter()) {
-            // Set row and column to zero, then set diagonal to 1 and force to prescribed value.
-            for col in 0..total_dof {
-                k_global[(dof_idx, col)] = 0.0;
-            }
-            for row in 0..total_dof {
-                k_global[(row, dof_idx)] = 0.0;
+        if outputs.contains(&OutputField::ReactionForces) {
+            let mut nodal = vec![0.0; reactions.len()];
+            for &(dof, _) in prescribed {
+                nodal[dof] = reactions[dof];
             }
-            k_global[(dof_idx, dof_idx)] = 1.0;
-            f_global[dof_idx] = value;
+            fields.insert("reaction_forces".to_string(), nodal);
         }
+        Ok(fields)
+    }
 
-        // 4. Solve for displacements (U).
-        let u_global = k_global.try_inverse().ok_or_else(|| EngineError::SolverFailed("Global stiffness matrix is singular.".to_string()))? * f_global;
+    /// Averages the [`element_stresses`](Self::element_stresses) onto the nodes and
+    /// stores them as `aux_fields["stress"]`, six Voigt components per node.
+    pub fn store_nodal_stresses(&self, problem: &mut ProblemDefinition, displacements: &[f64]) -> Result<(), EngineError> {
+        let stresses: Vec<f64> = self.element_stresses(problem, displacements)?.into_iter().flatten().collect();
+        let mesh = problem.mesh.as_ref().ok_or_else(|| EngineError::SolverFailed("Mesh not found in problem definition".to_string()))?;
+        let nodal = crate::meshing::average_to_nodes(mesh, &stresses, 6)?;
+        problem.aux_fields.insert("stress".to_string(), nodal);
+        Ok(())
+    }
 
-        // 5. Return displacements as solution data.
-        println!("--- FemSolver Finished ---");
+    /// Assembles the element mass matrix of a linear tetrahedron.
+    ///
+    /// The consistent matrix couples nodes `i` and `j` with `rho V (1 + delta_ij) / 20`
+    /// for each displacement component; the lumped matrix puts the row sums,
+    /// `rho V / 4`, on the diagonal.
+    fn assemble_tetrahedron_mass_matrix(
+        &self,
+        nodes: [[f64; 3]; 4],
+        density: f64,
+        mass_type: MassMatrixType,
+    ) -> Result<DMatrix<f64>, EngineError> {
+        let (volume, _) = shape_function_gradients(nodes)?;
+        let mass = density * volume.abs();
 
-        Ok(super::SolverSolutionData {
-            data: u_global.iter().cloned().collect(), // Convert DVector to Vec<f64>
-        })
+        let mut me = DMatrix::<f64>::zeros(12, 12);
+        for i in 0..4 {
+            for j in 0..4 {
+                let value = match mass_type {
+                    MassMatrixType::Consistent => mass * if i == j { 2.0 } else { 1.0 } / 20.0,
+                    MassMatrixType::Lumped if i == j => mass / 4.0,
+                    MassMatrixType::Lumped => continue,
+                };
+                for dof in 0..DOF_PER_NODE {
+                    me[(i * DOF_PER_NODE + dof, j * DOF_PER_NODE + dof)] = value;
+                }
+            }
+        }
+        Ok(me)
     }
-}
 
-impl FemSolver {
-    /// Placeholder for assembling the element stiffness matrix for a tetrahedron.
-    /// This is a highly simplified version and needs proper implementation.
-    fn assemble_tetrahedron_stiffness_matrix(
+    /// Assembles the global mass matrix of the mesh for dynamic analyses.
+    ///
+    /// `mass_type` is normally taken from `SolverSettings::mass_matrix`. The material density is evaluated at each element's mean nodal temperature
+    /// when a `temperatures` field is given.
+    pub fn assemble_global_mass(
         &self,
-        _n1: [f64; 3],
-        _n2: [f64; 3],
-        _n3: [f64; 3],
-        _n4: [f64; 3],
+        mesh: &Mesh,
         material: &Material,
+        temperatures: Option<&[f64]>,
+        mass_type: MassMatrixType,
     ) -> Result<DMatrix<f64>, EngineError> {
-        // For a real FEM solver, this would involve:
-        // 1. Calculating the Jacobian and inverse Jacobian.
-        // 2. Forming the B matrix (strain-displacement matrix).
-        // 3. Forming the D matrix (constitutive matrix from material properties).
-        // 4. Integrating B^T * D * B over the element volume.
-
-        // For now, return a dummy 12x12 matrix (4 nodes * 3 DOF/node).
-        // This will allow the code to compile and the overall structure to be tested.
-        let youngs_modulus = material.youngs_modulus;
-        let _poissons_ratio = material.poissons_ratio;
-
-        // A very simplified placeholder for a stiffness matrix.
-        // This does NOT represent a correct physical stiffness matrix.
-        let mut ke = DMatrix::<f64>::zeros(12, 12);
-        ke[(0,0)] = youngs_modulus; // Just to make it non-zero
+        let density = material.density.as_ref().ok_or_else(|| EngineError::SolverFailed("Material density is required to assemble a mass matrix".to_string()))?;
+        let total_dof = mesh.nodes.len() * DOF_PER_NODE;
+        let mut m_global = DMatrix::<f64>::zeros(total_dof, total_dof);
 
-        Ok(ke)
+        for (elem_idx, element) in mesh.elements.iter().enumerate() {
+            if element.len() != 4 || element.iter().any(|&n| n >= mesh.nodes.len()) {
+                return Err(EngineError::SolverFailed(format!("Element {} is not a valid tetrahedron", elem_idx)));
+            }
+            let temperature = temperatures.map(|t| element.iter().map(|&n| t[n]).sum::<f64>() / element.len() as f64);
+            let nodes = [mesh.nodes[element[0]], mesh.nodes[element[1]], mesh.nodes[element[2]], mesh.nodes[element[3]]];
+            let me = self.assemble_tetrahedron_mass_matrix(nodes, density.value(temperature)?, mass_type)?;
+
+            for i in 0..4 {
+                for j in 0..4 {
+                    for dof in 0..DOF_PER_NODE {
+                        m_global[(element[i] * DOF_PER_NODE + dof, element[j] * DOF_PER_NODE + dof)] += me[(i * DOF_PER_NODE + dof, j * DOF_PER_NODE + dof)];
+                    }
+                }
+            }
+        }
+        Ok(m_global)
     }
 
     /// Calculates the volume of each tetrahedron in the mesh.
-    /// This function is kept for now but will be replaced by actual FEM results.
-    #[allow(dead_code)]
     fn calculate_tetrahedron_volumes(&self, mesh: &Mesh) -> Result<Vec<f64>, EngineError> {
         let mut volumes = Vec::with_capacity(mesh.elements.len());
 
@@ -197,4 +1680,1203 @@ impl FemSolver {
 
         Ok(volumes)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BoundaryCondition, GeometryDefinition, GeometricPrimitive, LinearSolver, MassMatrixType, MultiPointConstraint, Precision, MaterialProperty, PhysicsDefinition, SolverSettings};
+
+    fn problem_with_mesh(mesh: Mesh) -> ProblemDefinition {
+        ProblemDefinition {
+            id: "fem_unit_test".to_string(),
+            geometry: GeometryDefinition::Primitive(GeometricPrimitive {
+                shape: "cube".to_string(),
+                dimensions: vec![1.0, 1.0, 1.0],
+                transform: None,
+            }),
+            physics: PhysicsDefinition {
+                equations: vec![],
+                boundary_conditions: vec![],
+                thermal_boundary_conditions: vec![],
+                material: Material {
+                    youngs_modulus: MaterialProperty::Constant(200e9),
+                    poissons_ratio: MaterialProperty::Constant(0.3),
+                    density: None,
+                    thermal_expansion: None,
+                    reference_temperature: 0.0,
+                    thermal_conductivity: None,
+                    viscosity: None,
+                },
+                boundary_conditions_file: None,
+                constraints: vec![],
+                processed_equations: None,
+            },
+            solver_settings: SolverSettings {
+                solver_name: "FemSolver".to_string(),
+                tolerance: 1e-5,
+                max_iterations: 10,
+                ..Default::default()
+            },
+            mesh: Some(mesh),
+            aux_fields: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_check_compatibility_rejects_hexahedral_mesh() {
+        let hex_mesh = Mesh {
+            nodes: vec![
+                [0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0],
+                [0.0, 0.0, 1.0], [1.0, 0.0, 1.0], [1.0, 1.0, 1.0], [0.0, 1.0, 1.0],
+            ],
+            elements: vec![vec![0, 1, 2, 3, 4, 5, 6, 7]],
+            element_type: "Hexahedron".to_string(),
+            boundary_regions: HashMap::new(),
+            source_geometry: None,
+            bounding_box: None,
+            geometry_warnings: Vec::new(),
+        };
+        let problem = problem_with_mesh(hex_mesh);
+
+        match FemSolver::new().check_compatibility(&problem) {
+            Err(EngineError::SolverFailed(msg)) => assert!(msg.contains("Hexahedron"), "Unexpected message: {}", msg),
+            other => panic!("Expected a compatibility error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_check_compatibility_accepts_tetrahedral_mesh() {
+        let problem = problem_with_mesh(crate::meshing::structured_cube_mesh(1, 1.0));
+        assert!(FemSolver::new().check_compatibility(&problem).is_ok());
+    }
+
+    #[test]
+    fn test_check_compatibility_rejects_empty_mesh() {
+        let mut mesh = crate::meshing::structured_cube_mesh(1, 1.0);
+        mesh.elements.clear();
+        let problem = problem_with_mesh(mesh);
+
+        match FemSolver::new().check_compatibility(&problem) {
+            Err(EngineError::MeshingFailed(msg)) => assert_eq!(msg, "mesh has no elements"),
+            other => panic!("Expected an empty-mesh error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parallel_assembly_matches_serial() {
+        let mesh = crate::meshing::structured_cube_mesh(3, 1.0);
+        let material = Material {
+            youngs_modulus: MaterialProperty::Constant(200e9),
+            poissons_ratio: MaterialProperty::Constant(0.3),
+            density: None,
+            thermal_expansion: None,
+            reference_temperature: 0.0,
+            thermal_conductivity: None,
+            viscosity: None,
+        };
+
+        let serial: DMatrix<f64> = FemSolver::new().assemble_global_stiffness(&mesh, &material, None, Some(1)).unwrap();
+        let parallel = FemSolver::new().assemble_global_stiffness(&mesh, &material, None, Some(4)).unwrap();
+        let default_pool = FemSolver::new().assemble_global_stiffness(&mesh, &material, None, None).unwrap();
+
+        assert_eq!(serial.shape(), (mesh.nodes.len() * 3, mesh.nodes.len() * 3));
+        assert_eq!(serial, parallel);
+        assert_eq!(serial, default_pool);
+    }
+
+    #[test]
+    fn test_custom_element_stiffness_replaces_built_in_assembly() {
+        let mesh = crate::meshing::structured_cube_mesh(2, 1.0);
+        let mut problem = problem_with_mesh(mesh.clone());
+        let material = problem.physics.material.clone();
+
+        // A stiffer variant of the built-in element that counts its calls.
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let solver = FemSolver::with_element_stiffness(move |nodes, material| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            FemSolver::new().assemble_tetrahedron_stiffness_matrix(nodes, material, None).unwrap() * 2.0
+        });
+
+        let built_in = FemSolver::new().assemble_global_stiffness(&mesh, &material, None, None).unwrap();
+        let custom = solver.assemble_global_stiffness(&mesh, &material, None, Some(1)).unwrap();
+        assert_eq!(calls.load(Ordering::Relaxed), mesh.elements.len());
+        assert!((&custom - &built_in * 2.0).norm() <= 1e-12 * custom.norm());
+
+        // The whole solve runs on the custom elements: twice as stiff, half the displacement.
+        let mut region_mesh = mesh;
+        crate::meshing::detect_coordinate_regions(&mut region_mesh, 1e-9);
+        problem.mesh = Some(region_mesh);
+        problem.physics.boundary_conditions = vec![
+            BoundaryCondition { region: "face_x_neg".to_string(), condition_type: "Dirichlet".to_string(), value: BcValue::Vector3([0.0; 3]) },
+            BoundaryCondition { region: "face_x_pos".to_string(), condition_type: "Force".to_string(), value: BcValue::Vector3([1e6, 0.0, 0.0]) },
+        ];
+        let reference = FemSolver::new().solve(&problem).unwrap();
+        let stiffer = solver.solve(&problem).unwrap();
+        for (u, u_ref) in stiffer.data.iter().zip(&reference.data) {
+            assert!((u - u_ref / 2.0).abs() <= 1e-9 * reference.data.iter().fold(0.0f64, |m, v| m.max(v.abs())));
+        }
+
+        let wrong_size = FemSolver::with_element_stiffness(|_, _| DMatrix::zeros(3, 3));
+        match wrong_size.solve(&problem) {
+            Err(EngineError::SolverFailed(msg)) => assert!(msg.contains("is 3x3, expected 12x12"), "Unexpected message: {}", msg),
+            other => panic!("Expected a size error, got {:?}", other.map(|data| data.data.len())),
+        }
+    }
+
+    #[test]
+    fn test_buckling_of_cantilever_column_matches_euler() {
+        // A 1 x 1 x 10 column clamped at its base and compressed at its top.
+        let (length, side, youngs_modulus) = (10.0, 1.0, 200e9);
+        let linear = crate::meshing::structured_box_mesh([1, 1, 10], [side, side, length]);
+        let mut mesh = crate::meshing::to_quadratic_tetrahedra(&linear).unwrap();
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let top_nodes = mesh.boundary_regions["face_z_pos"].len() as f64;
+        let load = 1e6;
+
+        let mut problem = problem_with_mesh(mesh);
+        problem.physics.material.poissons_ratio = MaterialProperty::Constant(0.0);
+        problem.physics.boundary_conditions = vec![
+            BoundaryCondition { region: "face_z_neg".to_string(), condition_type: "Dirichlet".to_string(), value: BcValue::Vector3([0.0; 3]) },
+            BoundaryCondition { region: "face_z_pos".to_string(), condition_type: "Force".to_string(), value: BcValue::Vector3([0.0, 0.0, -load / top_nodes]) },
+        ];
+        problem.solver_settings.solver_name = "BucklingSolver".to_string();
+        problem.solver_settings.tolerance = 1e-8;
+        problem.solver_settings.max_iterations = 200;
+        problem.solver_settings.num_modes = Some(2);
+
+        let solution = BucklingSolver.solve(&problem).unwrap();
+        let factors: Vec<f64> = serde_json::from_value(solution.metadata["load_factors"].clone()).unwrap();
+
+        // Euler's critical load of a cantilever: pi^2 E I / (4 L^2).
+        let euler = std::f64::consts::PI.powi(2) * youngs_modulus * side.powi(4) / 12.0 / (4.0 * length * length);
+        let critical = factors[0] * load;
+        assert!((critical - euler).abs() <= 0.05 * euler, "critical load {:e} vs Euler {:e}", critical, euler);
+        assert!(factors.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        // The first mode sways the free end sideways; only the rotation of the
+        // cross-sections moves nodes along the column.
+        let [dx, dy, dz]: [f64; 3] = std::array::from_fn(|c| solution.data.chunks(3).map(|u| u[c].abs()).fold(0.0, f64::max));
+        assert!(dx.max(dy) == 1.0 && dz < 0.25, "mode extents {} {} {}", dx, dy, dz);
+        let mesh = problem.mesh.as_ref().unwrap();
+        let tip = mesh.boundary_regions["face_z_pos"][0];
+        assert!(solution.data[3 * tip].hypot(solution.data[3 * tip + 1]) > 0.9);
+        assert_eq!(solution.fields["buckling_mode_1"], solution.data);
+
+        // Without load there is no prestress and nothing to scale.
+        problem.physics.boundary_conditions[1].value = BcValue::Vector3([0.0; 3]);
+        problem.solver_settings.num_modes = Some(1);
+        assert!(matches!(BucklingSolver.solve(&problem), Err(EngineError::SolverFailed(msg)) if msg.contains("do not cause buckling")));
+    }
+
+    #[test]
+    fn test_buckling_does_not_depend_on_static_outputs() {
+        let mut mesh = crate::meshing::structured_box_mesh([1, 1, 4], [1.0, 1.0, 4.0]);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let mut problem = problem_with_mesh(mesh);
+        problem.physics.boundary_conditions = vec![
+            BoundaryCondition { region: "face_z_neg".to_string(), condition_type: "Dirichlet".to_string(), value: BcValue::Vector3([0.0; 3]) },
+            BoundaryCondition { region: "face_z_pos".to_string(), condition_type: "Force".to_string(), value: BcValue::Vector3([0.0, 0.0, -1e6]) },
+        ];
+        problem.solver_settings.tolerance = 1e-8;
+        problem.solver_settings.max_iterations = 200;
+        let load_factor = |problem: &ProblemDefinition| {
+            let solution = BucklingSolver.solve(problem).unwrap();
+            serde_json::from_value::<Vec<f64>>(solution.metadata["load_factors"].clone()).unwrap()[0]
+        };
+
+        let with_displacements = load_factor(&problem);
+        // The prestress comes from the displacements even when they are not an output.
+        problem.solver_settings.outputs = vec![OutputField::VonMises];
+        let without_displacements = load_factor(&problem);
+        assert!((with_displacements - without_displacements).abs() <= 1e-9 * with_displacements);
+    }
+
+    #[test]
+    fn test_wave_pulse_travels_at_the_bar_wave_speed() {
+        // A slender free bar along x. Without lateral contraction the
+        // longitudinal waves travel at c = sqrt(E / rho) = 1.
+        let (length, cells) = (1.0, 80);
+        let h = length / cells as f64;
+        let mesh = crate::meshing::structured_box_mesh([cells, 1, 1], [length, h, h]);
+        let (start, width) = (0.3, 0.05);
+        let initial: Vec<f64> = mesh.nodes.iter()
+            .flat_map(|p| [(-((p[0] - start) / width).powi(2)).exp(), 0.0, 0.0])
+            .collect();
+        let axis: Vec<usize> = (0..mesh.nodes.len()).filter(|&n| mesh.nodes[n][1] == 0.0 && mesh.nodes[n][2] == 0.0).collect();
+
+        let mut problem = problem_with_mesh(mesh);
+        problem.physics.material.youngs_modulus = MaterialProperty::Constant(1.0);
+        problem.physics.material.poissons_ratio = MaterialProperty::Constant(0.0);
+        problem.physics.material.density = Some(MaterialProperty::Constant(1.0));
+        problem.aux_fields.insert("initial_displacement".to_string(), initial);
+        problem.solver_settings.solver_name = "WaveFemSolver".to_string();
+        problem.solver_settings.mass_matrix = MassMatrixType::Lumped;
+        problem.solver_settings.tolerance = 1e-8;
+        problem.solver_settings.max_iterations = 200;
+        let (time_step, num_steps) = (0.002, 200);
+        problem.solver_settings.time_stepping = Some(TimeStepping { scheme: TimeScheme::CentralDifference, time_step, num_steps, initial_value: 0.0 });
+        WaveFemSolver.check_compatibility(&problem).unwrap();
+
+        let solution = WaveFemSolver.solve(&problem).unwrap();
+        assert_eq!(solution.time_steps.len(), num_steps + 1);
+        let critical: f64 = serde_json::from_value(solution.metadata["critical_time_step"].clone()).unwrap();
+        assert!(critical > time_step);
+
+        // The pulse splits in two; follow the half running towards +x.
+        let mesh = problem.mesh.as_ref().unwrap();
+        for step in [num_steps / 2, num_steps] {
+            let (t, u) = &solution.time_steps[step];
+            let ahead: Vec<usize> = axis.iter().copied().filter(|&n| mesh.nodes[n][0] > start + 0.05).collect();
+            let peak = ahead.iter().copied().max_by(|&a, &b| u[3 * a].total_cmp(&u[3 * b])).unwrap();
+            // Refine the peak position with a parabola through the neighbouring nodes.
+            let (left, right) = (u[3 * (peak - 1)], u[3 * (peak + 1)]);
+            let offset = 0.5 * (left - right) / (left - 2.0 * u[3 * peak] + right);
+            let position = mesh.nodes[peak][0] + offset * h;
+            let speed = (position - start) / t;
+            assert!((speed - 1.0).abs() < 0.02, "pulse speed {} at t = {}", speed, t);
+            assert!((u[3 * peak] - 0.5).abs() < 0.05, "pulse amplitude {}", u[3 * peak]);
+        }
+
+        // A step above the CFL limit is rejected.
+        problem.solver_settings.time_stepping = Some(TimeStepping { scheme: TimeScheme::CentralDifference, time_step: 1.5 * critical, num_steps, initial_value: 0.0 });
+        assert!(matches!(WaveFemSolver.solve(&problem), Err(EngineError::SolverFailed(msg)) if msg.contains("CFL")));
+        problem.solver_settings.time_stepping = Some(TimeStepping { scheme: TimeScheme::BackwardEuler, time_step, num_steps, initial_value: 0.0 });
+        assert!(WaveFemSolver.check_compatibility(&problem).is_err());
+    }
+
+    #[test]
+    fn test_nearly_incompressible_material_triggers_guard() {
+        let mut mesh = crate::meshing::structured_cube_mesh(1, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let mut problem = problem_with_mesh(mesh);
+        problem.physics.material.poissons_ratio = MaterialProperty::Constant(0.4999);
+        problem.physics.boundary_conditions = vec![
+            BoundaryCondition { region: "face_x_neg".to_string(), condition_type: "Dirichlet".to_string(), value: BcValue::Vector3([0.0; 3]) },
+            BoundaryCondition { region: "face_x_pos".to_string(), condition_type: "Force".to_string(), value: BcValue::Vector3([1e3, 0.0, 0.0]) },
+        ];
+
+        let solution = FemSolver::new().solve(&problem).unwrap();
+        let warning = solution.warnings.iter().find(|w| w.code == WarningCode::NearlyIncompressible).expect("no incompressibility warning");
+        assert!(warning.message.contains("0.4999") && warning.message.contains("Tetrahedron10"), "{}", warning.message);
+
+        problem.solver_settings.max_poissons_ratio = Some(0.49);
+        match FemSolver::new().solve(&problem) {
+            Err(EngineError::SolverFailed(msg)) => assert!(msg.contains("nearly incompressible"), "Unexpected message: {}", msg),
+            other => panic!("Expected the guard to reject the material, got {:?}", other.map(|data| data.data.len())),
+        }
+
+        problem.solver_settings.max_poissons_ratio = None;
+        problem.physics.material.poissons_ratio = MaterialProperty::Table(vec![[0.0, 0.3], [100.0, 0.5]]);
+        assert!(matches!(BucklingSolver.solve(&problem), Err(EngineError::SolverFailed(msg)) if msg.contains("outside (-1, 0.5)")));
+
+        problem.physics.material.poissons_ratio = MaterialProperty::Constant(0.3);
+        assert!(FemSolver::new().solve(&problem).unwrap().warnings.iter().all(|w| w.code != WarningCode::NearlyIncompressible));
+    }
+
+    #[test]
+    fn test_temperature_dependent_youngs_modulus_in_assembly() {
+        let mesh = crate::meshing::structured_cube_mesh(1, 1.0);
+        let material = Material {
+            youngs_modulus: MaterialProperty::Table(vec![[0.0, 100.0], [100.0, 50.0]]),
+            poissons_ratio: MaterialProperty::Constant(0.3),
+            density: None,
+            thermal_expansion: None,
+            reference_temperature: 0.0,
+            thermal_conductivity: None,
+            viscosity: None,
+        };
+
+        // A uniform 40 degree field puts every element at E = 80.
+        let temperatures = vec![40.0; mesh.nodes.len()];
+        let k: DMatrix<f64> = FemSolver::new().assemble_global_stiffness(&mesh, &material, Some(&temperatures), Some(1)).unwrap();
+
+        let constant = Material {
+            youngs_modulus: MaterialProperty::Constant(80.0),
+            poissons_ratio: MaterialProperty::Constant(0.3),
+            density: None,
+            thermal_expansion: None,
+            reference_temperature: 0.0,
+            thermal_conductivity: None,
+            viscosity: None,
+        };
+        let expected = FemSolver::new().assemble_global_stiffness(&mesh, &constant, None, Some(1)).unwrap();
+        assert!((&k - &expected).abs().max() < 1e-12 * expected.abs().max());
+
+        assert!(FemSolver::new().assemble_global_stiffness::<f64>(&mesh, &material, None, Some(1)).is_err());
+    }
+
+    #[test]
+    fn test_vector_boundary_conditions_are_applied() {
+        let mut mesh = crate::meshing::structured_cube_mesh(1, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let mut problem = problem_with_mesh(mesh);
+        problem.physics.boundary_conditions = vec![
+            BoundaryCondition {
+                region: "face_x_neg".to_string(),
+                condition_type: "Dirichlet".to_string(),
+                value: BcValue::Vector3([0.0, f64::NAN, 0.5]),
+            },
+            BoundaryCondition {
+                region: "face_x_pos".to_string(),
+                condition_type: "Force".to_string(),
+                value: BcValue::Vector3([10.0, 0.0, -1.0]),
+            },
+        ];
+        let mesh = problem.mesh.as_ref().unwrap();
+
+        let mut f = DVector::zeros(mesh.nodes.len() * 3);
+        let prescribed = FemSolver::new().apply_boundary_conditions(&problem, mesh, &mut f).unwrap();
+
+        // Four nodes on the fixed face, with the NaN y component left free.
+        assert_eq!(prescribed.len(), 8);
+        for &node in &mesh.boundary_regions["face_x_neg"] {
+            assert!(prescribed.contains(&(node * 3, 0.0)));
+            assert!(prescribed.contains(&(node * 3 + 2, 0.5)));
+            assert!(!prescribed.iter().any(|&(dof, _)| dof == node * 3 + 1));
+        }
+        for &node in &mesh.boundary_regions["face_x_pos"] {
+            assert_eq!([f[node * 3], f[node * 3 + 1], f[node * 3 + 2]], [10.0, 0.0, -1.0]);
+        }
+        assert_eq!(f.sum(), 4.0 * 9.0);
+    }
+
+    #[test]
+    fn test_masked_dirichlet_constrains_only_flagged_components() {
+        let mut mesh = crate::meshing::structured_cube_mesh(2, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let mut problem = problem_with_mesh(mesh);
+        let symmetry = |region: &str, axis: f64| BoundaryCondition {
+            region: region.to_string(),
+            condition_type: "Symmetry".to_string(),
+            value: BcValue::Scalar(axis),
+        };
+        problem.physics.boundary_conditions = vec![
+            BoundaryCondition {
+                region: "face_y_neg".to_string(),
+                condition_type: "Dirichlet".to_string(),
+                value: BcValue::MaskedVector3 { value: [0.0; 3], constrained: [false, true, false] },
+            },
+            symmetry("face_x_neg", 0.0),
+            symmetry("face_z_neg", 2.0),
+            BoundaryCondition {
+                region: "face_y_pos".to_string(),
+                condition_type: "Force".to_string(),
+                value: BcValue::Vector3([0.0, 1e3, 0.0]),
+            },
+        ];
+        FemSolver::new().check_compatibility(&problem).unwrap();
+
+        let mut masked_only = problem_with_mesh(problem.mesh.clone().unwrap());
+        masked_only.physics.boundary_conditions = vec![problem.physics.boundary_conditions[0].clone()];
+        let mesh = problem.mesh.as_ref().unwrap();
+        let face = &mesh.boundary_regions["face_y_neg"];
+        let mut f = DVector::zeros(mesh.nodes.len() * 3);
+        let prescribed = FemSolver::new().apply_boundary_conditions(&masked_only, mesh, &mut f).unwrap();
+        let expected: Vec<(usize, f64)> = face.iter().map(|&node| (node * 3 + 1, 0.0)).collect();
+        assert_eq!(prescribed, expected);
+
+        // Under tension along y the free x and z components contract with Poisson's ratio.
+        let u = FemSolver::new().solve(&problem).unwrap().data;
+        let corner = face.iter().copied().find(|&n| mesh.nodes[n] == [1.0, 0.0, 1.0]).unwrap();
+        assert_eq!(u[corner * 3 + 1], 0.0);
+        assert!(u[corner * 3] < 0.0 && u[corner * 3 + 2] < 0.0, "Corner displacement {:?}", &u[corner * 3..corner * 3 + 3]);
+    }
+
+    #[test]
+    fn test_conjugate_gradient_solve_and_time_budget() {
+        let mut mesh = crate::meshing::structured_cube_mesh(2, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let mut problem = problem_with_mesh(mesh);
+        problem.physics.boundary_conditions = vec![
+            BoundaryCondition {
+                region: "face_x_neg".to_string(),
+                condition_type: "Dirichlet".to_string(),
+                value: BcValue::Vector3([0.0, 0.0, 0.0]),
+            },
+            BoundaryCondition {
+                region: "face_x_pos".to_string(),
+                condition_type: "Force".to_string(),
+                value: BcValue::Vector3([100.0, 0.0, 0.0]),
+            },
+        ];
+        let direct = FemSolver::new().solve(&problem).unwrap();
+        assert_eq!(direct.metadata["converged"], true);
+
+        problem.solver_settings.linear_solver = LinearSolver::ConjugateGradient;
+        problem.solver_settings.tolerance = 1e-12;
+        problem.solver_settings.max_iterations = 1000;
+        let iterative = FemSolver::new().solve(&problem).unwrap();
+        assert_eq!(iterative.metadata["converged"], true);
+        let scale = direct.data.iter().fold(0.0f64, |m, v| m.max(v.abs()));
+        for (a, b) in iterative.data.iter().zip(&direct.data) {
+            assert!((a - b).abs() <= 1e-8 * scale);
+        }
+
+        problem.solver_settings.time_budget = Some(std::time::Duration::ZERO);
+        let partial = FemSolver::new().solve(&problem).unwrap();
+        assert_eq!(partial.metadata["converged"], false);
+        assert!(partial.metadata["termination_reason"].as_str().unwrap().contains("time budget"));
+        assert_eq!(partial.data.len(), direct.data.len());
+    }
+
+    #[test]
+    fn test_scalar_value_rejected_for_force() {
+        let mut problem = problem_with_mesh(crate::meshing::structured_cube_mesh(1, 1.0));
+        problem.physics.boundary_conditions = vec![BoundaryCondition {
+            region: "face_x_pos".to_string(),
+            condition_type: "Force".to_string(),
+            value: BcValue::Scalar(10.0),
+        }];
+        assert!(FemSolver::new().check_compatibility(&problem).is_err());
+    }
+
+    #[test]
+    fn test_element_stiffness_is_symmetric_with_rigid_body_null_space() {
+        let material = Material {
+            youngs_modulus: MaterialProperty::Constant(1.0),
+            poissons_ratio: MaterialProperty::Constant(0.25),
+            density: None,
+            thermal_expansion: None,
+            reference_temperature: 0.0,
+            thermal_conductivity: None,
+            viscosity: None,
+        };
+        let ke = FemSolver::new().assemble_tetrahedron_stiffness_matrix(
+            &[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            &material, None,
+        ).unwrap();
+        assert!((&ke - ke.transpose()).abs().max() < 1e-14);
+
+        // A rigid translation in x produces no forces.
+        let translation = DVector::from_fn(12, |i, _| if i % 3 == 0 { 1.0 } else { 0.0 });
+        assert!((&ke * translation).norm() < 1e-14);
+
+        let degenerate = FemSolver::new().assemble_tetrahedron_stiffness_matrix(
+            &[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 1.0, 0.0]],
+            &material, None,
+        );
+        assert!(degenerate.is_err());
+
+        // The quadratic element keeps both properties.
+        let corners = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        let mut tet10 = corners.to_vec();
+        tet10.extend(crate::meshing::TETRAHEDRON10_EDGES.map(|[a, b]| std::array::from_fn::<f64, 3, _>(|k| 0.5 * (corners[a][k] + corners[b][k]))));
+        let ke = FemSolver::new().assemble_tetrahedron_stiffness_matrix(&tet10, &material, None).unwrap();
+        assert_eq!(ke.shape(), (30, 30));
+        assert!((&ke - ke.transpose()).abs().max() < 1e-13);
+        let translation = DVector::from_fn(30, |i, _| if i % 3 == 0 { 1.0 } else { 0.0 });
+        assert!((&ke * translation).norm() < 1e-13);
+    }
+
+    #[test]
+    fn test_tet10_cantilever_is_closer_to_beam_theory_than_tet4() {
+        // A 5 x 1 x 1 cantilever clamped at x = 0 with a tip load P in -z.
+        let (length, youngs_modulus, load) = (5.0, 1000.0, 1.0);
+        let mut linear = crate::meshing::structured_cube_mesh(2, 1.0);
+        crate::meshing::apply_transform(&mut linear, &crate::Transform { scale: [length, 1.0, 1.0], ..Default::default() });
+        crate::meshing::detect_coordinate_regions(&mut linear, 1e-9);
+        let quadratic = crate::meshing::to_quadratic_tetrahedra(&linear).unwrap();
+
+        let tip_deflection = |mesh: Mesh| {
+            let tip = mesh.boundary_regions["face_x_pos"].clone();
+            let mut problem = problem_with_mesh(mesh);
+            problem.physics.material.youngs_modulus = MaterialProperty::Constant(youngs_modulus);
+            problem.physics.material.poissons_ratio = MaterialProperty::Constant(0.0);
+            problem.physics.boundary_conditions = vec![
+                BoundaryCondition {
+                    region: "face_x_neg".to_string(),
+                    condition_type: "Dirichlet".to_string(),
+                    value: BcValue::Vector3([0.0, 0.0, 0.0]),
+                },
+                BoundaryCondition {
+                    region: "face_x_pos".to_string(),
+                    condition_type: "Force".to_string(),
+                    value: BcValue::Vector3([0.0, 0.0, -load / tip.len() as f64]),
+                },
+            ];
+            FemSolver::new().check_compatibility(&problem).unwrap();
+            let u = FemSolver::new().solve(&problem).unwrap().data;
+            -tip.iter().map(|&n| u[n * DOF_PER_NODE + 2]).sum::<f64>() / tip.len() as f64
+        };
+
+        // Euler-Bernoulli: P L^3 / (3 E I) with I = 1/12.
+        let analytical = load * length.powi(3) / (3.0 * youngs_modulus / 12.0);
+        let error = |deflection: f64| (deflection - analytical).abs() / analytical;
+        let (tet4, tet10) = (tip_deflection(linear), tip_deflection(quadratic));
+        assert!(error(tet10) < error(tet4), "Tet10 {} vs Tet4 {} for {}", tet10, tet4, analytical);
+        assert!(error(tet10) < 0.05, "Tet10 deflection {} vs {}", tet10, analytical);
+    }
+
+    #[test]
+    fn test_residual_and_reaction_after_solve() {
+        let mut mesh = crate::meshing::structured_cube_mesh(2, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let loaded_nodes = mesh.boundary_regions["face_x_pos"].len() as f64;
+        let mut problem = problem_with_mesh(mesh);
+        problem.physics.boundary_conditions = vec![
+            BoundaryCondition {
+                region: "face_x_neg".to_string(),
+                condition_type: "Dirichlet".to_string(),
+                value: BcValue::Vector3([0.0, 0.0, 0.0]),
+            },
+            BoundaryCondition {
+                region: "face_x_pos".to_string(),
+                condition_type: "Force".to_string(),
+                value: BcValue::Vector3([1000.0, 0.0, -50.0]),
+            },
+        ];
+
+        let result = FemSolver::new().solve(&problem).unwrap();
+
+        let residual_norm = result.metadata["residual_norm"].as_f64().unwrap();
+        assert!(residual_norm < 1e-9 * 1000.0 * loaded_nodes, "Residual norm {}", residual_norm);
+
+        // The supports balance the applied load.
+        let reaction: Vec<f64> = result.metadata["total_reaction"].as_array().unwrap().iter().map(|r| r.as_f64().unwrap()).collect();
+        let applied = [1000.0 * loaded_nodes, 0.0, -50.0 * loaded_nodes];
+        for axis in 0..3 {
+            assert!((reaction[axis] + applied[axis]).abs() < 1e-6, "Axis {}: reaction {} vs load {}", axis, reaction[axis], applied[axis]);
+        }
+
+        // The loaded face moves along the load.
+        let mesh = problem.mesh.as_ref().unwrap();
+        for &node in &mesh.boundary_regions["face_x_pos"] {
+            assert!(result.data[node * 3] > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_auto_stabilize_solves_self_equilibrated_free_body() {
+        let mut mesh = crate::meshing::structured_cube_mesh(2, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let mut problem = problem_with_mesh(mesh);
+        // Equal and opposite pulls on opposite faces, with no supports at all.
+        problem.physics.boundary_conditions = ["face_x_neg", "face_x_pos"]
+            .iter()
+            .zip([-1000.0, 1000.0])
+            .map(|(region, fx)| BoundaryCondition {
+                region: region.to_string(),
+                condition_type: "Force".to_string(),
+                value: BcValue::Vector3([fx, 0.0, 0.0]),
+            })
+            .collect();
+        problem.solver_settings.auto_stabilize = true;
+
+        let result = FemSolver::new().solve(&problem).unwrap();
+        assert!(result.data.iter().all(|u| u.is_finite()));
+        assert_eq!(result.metadata["stabilized_dofs"].as_array().unwrap().len(), 6);
+        assert_eq!(result.warnings[0].code, WarningCode::RigidBodyModes);
+        assert!(result.warnings[0].message.contains("6 rigid body modes"));
+
+        // The body stretches along x without drifting away.
+        let mesh = problem.mesh.as_ref().unwrap();
+        let mean_ux = |region: &str| {
+            let nodes = &mesh.boundary_regions[region];
+            nodes.iter().map(|&n| result.data[n * 3]).sum::<f64>() / nodes.len() as f64
+        };
+        let (left, right) = (mean_ux("face_x_neg"), mean_ux("face_x_pos"));
+        let stretch = 9.0 * 1000.0 / 200e9;
+        assert!(right - left > 0.5 * stretch && right - left < 2.0 * stretch, "Stretch {} vs {}", right - left, stretch);
+        assert!((right + left).abs() < 1e-6 * stretch, "Drift {}", right + left);
+
+        // A supported problem needs no springs.
+        problem.physics.boundary_conditions[0] = BoundaryCondition {
+            region: "face_x_neg".to_string(),
+            condition_type: "Dirichlet".to_string(),
+            value: BcValue::Vector3([0.0, 0.0, 0.0]),
+        };
+        let supported = FemSolver::new().solve(&problem).unwrap();
+        assert!(!supported.metadata.contains_key("stabilized_dofs"));
+    }
+
+    #[test]
+    fn test_quarter_symmetry_constrains_only_normal_displacement() {
+        // A quarter of a bar pulled along x, with symmetry planes at x = 0 and y = 0
+        // and the base held in z.
+        let mut mesh = crate::meshing::structured_cube_mesh(2, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let mut problem = problem_with_mesh(mesh);
+        problem.physics.boundary_conditions = vec![
+            BoundaryCondition {
+                region: "face_x_neg".to_string(),
+                condition_type: "Symmetry".to_string(),
+                value: BcValue::Vector3([0.0, 0.0, 0.0]),
+            },
+            BoundaryCondition {
+                region: "face_y_neg".to_string(),
+                condition_type: "Symmetry".to_string(),
+                value: BcValue::Scalar(1.0),
+            },
+            BoundaryCondition {
+                region: "face_z_neg".to_string(),
+                condition_type: "Dirichlet".to_string(),
+                value: BcValue::Vector3([f64::NAN, f64::NAN, 0.0]),
+            },
+            BoundaryCondition {
+                region: "face_x_pos".to_string(),
+                condition_type: "Force".to_string(),
+                value: BcValue::Vector3([1e9, 0.0, 0.0]),
+            },
+        ];
+        FemSolver::new().check_compatibility(&problem).unwrap();
+        let result = FemSolver::new().solve(&problem).unwrap();
+        let mesh = problem.mesh.as_ref().unwrap();
+
+        for &node in &mesh.boundary_regions["face_x_neg"] {
+            assert_eq!(result.data[node * 3], 0.0);
+        }
+        for &node in &mesh.boundary_regions["face_y_neg"] {
+            assert_eq!(result.data[node * 3 + 1], 0.0);
+        }
+
+        // Poisson contraction moves the x = 0 plane tangentially towards y = 0.
+        let far_corner = mesh.boundary_regions["face_x_neg"]
+            .iter()
+            .copied()
+            .find(|&n| mesh.nodes[n][1] == 1.0 && mesh.nodes[n][2] == 1.0)
+            .unwrap();
+        assert!(result.data[far_corner * 3 + 1] < 0.0);
+    }
+
+    #[test]
+    fn test_symmetry_axis_inference() {
+        let mut mesh = crate::meshing::structured_cube_mesh(1, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let face = &mesh.boundary_regions["face_z_pos"];
+        assert_eq!(symmetry_axis("face_z_pos", &BcValue::Vector3([0.0; 3]), &mesh, face).unwrap(), 2);
+        assert_eq!(symmetry_axis("face_z_pos", &BcValue::Vector3([0.1, -0.9, 0.0]), &mesh, face).unwrap(), 1);
+        assert!(symmetry_axis("face_z_pos", &BcValue::Scalar(3.0), &mesh, face).is_err());
+
+        let all_nodes: Vec<usize> = (0..mesh.nodes.len()).collect();
+        assert!(symmetry_axis("volume", &BcValue::Vector3([0.0; 3]), &mesh, &all_nodes).is_err());
+    }
+
+    #[test]
+    fn test_point_force_snaps_to_nearest_node() {
+        let mesh = crate::meshing::structured_cube_mesh(2, 1.0);
+        let target = mesh.nodes.iter().position(|n| *n == [0.5, 1.0, 0.5]).unwrap();
+        let mut problem = problem_with_mesh(mesh);
+        problem.physics.boundary_conditions = vec![BoundaryCondition {
+            region: String::new(),
+            condition_type: "PointForce".to_string(),
+            value: BcValue::PointLoad { position: [0.5004, 0.9999, 0.5], force: [0.0, -25.0, 3.0] },
+        }];
+        FemSolver::new().check_compatibility(&problem).unwrap();
+        let mesh = problem.mesh.as_ref().unwrap();
+
+        let mut f = DVector::zeros(mesh.nodes.len() * 3);
+        FemSolver::new().apply_boundary_conditions(&problem, mesh, &mut f).unwrap();
+        assert_eq!([f[target * 3], f[target * 3 + 1], f[target * 3 + 2]], [0.0, -25.0, 3.0]);
+        assert_eq!(f.sum(), -22.0);
+
+        problem.physics.boundary_conditions[0].value = BcValue::PointLoad { position: [0.25, 1.0, 0.5], force: [1.0, 0.0, 0.0] };
+        let mesh = problem.mesh.as_ref().unwrap();
+        assert!(FemSolver::new().apply_boundary_conditions(&problem, mesh, &mut f).is_err());
+    }
+
+    #[test]
+    fn test_strain_energy_of_uniaxial_tension_bar() {
+        // A unit cube stretched by delta along x, free to contract laterally.
+        // The exact field is linear, so the energy is exactly 0.5 * F * delta
+        // with F = E * A * delta / L.
+        let delta = 1e-3;
+        let mut mesh = crate::meshing::structured_cube_mesh(2, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let mut problem = problem_with_mesh(mesh);
+        let symmetry = |region: &str, axis: f64| BoundaryCondition {
+            region: region.to_string(),
+            condition_type: "Symmetry".to_string(),
+            value: BcValue::Scalar(axis),
+        };
+        problem.physics.boundary_conditions = vec![
+            symmetry("face_x_neg", 0.0),
+            symmetry("face_y_neg", 1.0),
+            symmetry("face_z_neg", 2.0),
+            BoundaryCondition {
+                region: "face_x_pos".to_string(),
+                condition_type: "Dirichlet".to_string(),
+                value: BcValue::Vector3([delta, f64::NAN, f64::NAN]),
+            },
+        ];
+
+        let result = FemSolver::new().solve(&problem).unwrap();
+        let energy = result.metadata["strain_energy"].as_f64().unwrap();
+
+        let force = 200e9 * 1.0 * delta / 1.0;
+        let expected = 0.5 * force * delta;
+        assert!((energy - expected).abs() < 1e-9 * expected, "Energy {} vs {}", energy, expected);
+    }
+
+    #[test]
+    fn test_single_precision_solve_matches_double_precision() {
+        let mut mesh = crate::meshing::structured_cube_mesh(2, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let mut problem = problem_with_mesh(mesh);
+        problem.physics.boundary_conditions = vec![
+            BoundaryCondition { region: "face_x_neg".to_string(), condition_type: "Dirichlet".to_string(), value: BcValue::Vector3([0.0; 3]) },
+            BoundaryCondition { region: "face_x_pos".to_string(), condition_type: "Force".to_string(), value: BcValue::Vector3([1e6, -2e5, 0.0]) },
+        ];
+
+        let double = FemSolver::new().solve(&problem).unwrap();
+        problem.solver_settings.precision = Precision::F32;
+        let single = FemSolver::new().solve(&problem).unwrap();
+
+        assert_eq!(double.metadata["precision"], "F64");
+        assert_eq!(single.metadata["precision"], "F32");
+        let scale = double.data.iter().fold(0.0_f64, |m, u| m.max(u.abs()));
+        assert!(scale > 0.0);
+        for (s, d) in single.data.iter().zip(&double.data) {
+            assert!((s - d).abs() < 1e-4 * scale, "f32 displacement {} vs f64 {}", s, d);
+        }
+    }
+
+    #[test]
+    fn test_multi_point_constraints_tie_two_nodes_together() {
+        let mut mesh = crate::meshing::structured_cube_mesh(2, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let (loaded, other) = ([1.0, 1.0, 1.0], [1.0, 0.0, 0.0]);
+        let node = |p: [f64; 3]| mesh.nodes.iter().position(|n| *n == p).unwrap();
+        let (a, b) = (node(loaded), node(other));
+        let mut problem = problem_with_mesh(mesh);
+        problem.physics.boundary_conditions = vec![
+            BoundaryCondition { region: "face_x_neg".to_string(), condition_type: "Dirichlet".to_string(), value: BcValue::Vector3([0.0; 3]) },
+            BoundaryCondition { region: String::new(), condition_type: "PointForce".to_string(), value: BcValue::PointLoad { position: loaded, force: [0.0, -1e6, 2e5] } },
+        ];
+        let displacement = |data: &[f64], n: usize| [data[n * 3], data[n * 3 + 1], data[n * 3 + 2]];
+
+        let free = FemSolver::new().solve(&problem).unwrap();
+        let scale = displacement(&free.data, a).iter().fold(0.0_f64, |m, u| m.max(u.abs()));
+        assert!((0..3).any(|i| (displacement(&free.data, a)[i] - displacement(&free.data, b)[i]).abs() > 0.1 * scale));
+
+        problem.physics.constraints = (0..3).map(|component| MultiPointConstraint::tie(other, loaded, component)).collect();
+        let tied = FemSolver::new().solve(&problem).unwrap();
+        let (ua, ub) = (displacement(&tied.data, a), displacement(&tied.data, b));
+        for i in 0..3 {
+            assert!((ua[i] - ub[i]).abs() < 1e-6 * scale, "Tied nodes moved {:?} and {:?}", ua, ub);
+        }
+        assert!(ua.iter().any(|u| u.abs() > 0.01 * scale));
+        assert_eq!(tied.metadata["constraint_violations"].as_array().unwrap().len(), 3);
+        assert!(tied.warnings.iter().all(|w| w.code != WarningCode::LargeResidual));
+        // The connector forces are internal, so the support still carries the whole load.
+        let reaction: [f64; 3] = serde_json::from_value(tied.metadata["region_reactions"]["face_x_neg"].clone()).unwrap();
+        assert!((reaction[1] - 1e6).abs() < 1e-6 * 1e6 && (reaction[2] + 2e5).abs() < 1e-6 * 1e6, "Reaction {:?}", reaction);
+        // The strain energy excludes the penalty springs and equals the work of the load.
+        let work = 0.5 * (-1e6 * ua[1] + 2e5 * ua[2]);
+        let energy = tied.metadata["strain_energy"].as_f64().unwrap();
+        assert!((energy - work).abs() < 1e-6 * work, "Energy {} vs work {}", energy, work);
+    }
+
+    #[test]
+    fn test_constraints_are_rejected_where_they_cannot_be_imposed() {
+        let mut mesh = crate::meshing::structured_cube_mesh(1, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let mut problem = problem_with_mesh(mesh);
+        problem.physics.boundary_conditions = vec![
+            BoundaryCondition { region: "face_x_neg".to_string(), condition_type: "Dirichlet".to_string(), value: BcValue::Vector3([0.0; 3]) },
+        ];
+        problem.physics.constraints = vec![MultiPointConstraint::tie([1.0, 0.0, 0.0], [1.0, 1.0, 1.0], 0)];
+        FemSolver::new().check_compatibility(&problem).unwrap();
+
+        for solver in [&HarmonicFemSolver as &dyn Solver, &WaveFemSolver, &BucklingSolver] {
+            match solver.check_compatibility(&problem) {
+                Err(EngineError::SolverFailed(msg)) => assert!(msg.contains("does not support multi-point constraints"), "Unexpected message: {}", msg),
+                other => panic!("{} accepted constraints: {:?}", solver.name(), other),
+            }
+        }
+
+        problem.solver_settings.precision = Precision::F32;
+        assert!(matches!(FemSolver::new().check_compatibility(&problem), Err(EngineError::SolverFailed(msg)) if msg.contains("F64 precision")));
+        assert!(matches!(FemSolver::new().solve(&problem), Err(EngineError::SolverFailed(msg)) if msg.contains("F64 precision")));
+    }
+
+    #[test]
+    fn test_outputs_select_the_returned_fields() {
+        // Uniaxial tension as above: the stress is E * delta everywhere, along x only.
+        let delta = 1e-3;
+        let mut mesh = crate::meshing::structured_cube_mesh(2, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let mut problem = problem_with_mesh(mesh);
+        problem.physics.boundary_conditions = ["face_x_neg", "face_y_neg", "face_z_neg"]
+            .iter()
+            .enumerate()
+            .map(|(axis, region)| BoundaryCondition { region: region.to_string(), condition_type: "Symmetry".to_string(), value: BcValue::Scalar(axis as f64) })
+            .chain([BoundaryCondition { region: "face_x_pos".to_string(), condition_type: "Dirichlet".to_string(), value: BcValue::Vector3([delta, f64::NAN, f64::NAN]) }])
+            .collect();
+
+        problem.solver_settings.outputs = vec![OutputField::VonMises];
+        let result = FemSolver::new().solve(&problem).unwrap();
+        assert!(result.data.is_empty(), "displacements returned without being requested");
+        assert_eq!(result.fields.keys().collect::<Vec<_>>(), vec!["von_mises"]);
+        let von_mises = &result.fields["von_mises"];
+        assert_eq!(von_mises.len(), 27);
+        assert!(von_mises.iter().all(|&s| (s - 200e9 * delta).abs() < 1e-6 * 200e9 * delta), "{:?}", von_mises);
+
+        problem.solver_settings.outputs = vec![OutputField::Displacement, OutputField::Strain, OutputField::ReactionForces];
+        let result = FemSolver::new().solve(&problem).unwrap();
+        assert_eq!(result.data.len(), 27 * 3);
+        assert!(!result.fields.contains_key("stress") && !result.fields.contains_key("von_mises"));
+        assert!(result.fields["strain"].chunks(6).all(|e| (e[0] - delta).abs() < 1e-12));
+        let pulled: f64 = problem.mesh.as_ref().unwrap().boundary_regions["face_x_pos"].iter().map(|&n| result.fields["reaction_forces"][n * 3]).sum();
+        assert!((pulled - 200e9 * delta).abs() < 1e-6 * 200e9 * delta, "{}", pulled);
+    }
+
+    #[test]
+    fn test_uniform_stress_linearizes_to_pure_membrane() {
+        // Uniaxial tension as above, with the classification line across the block.
+        let delta = 1e-3;
+        let mut mesh = crate::meshing::structured_cube_mesh(2, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let mut problem = problem_with_mesh(mesh);
+        problem.physics.boundary_conditions = ["face_x_neg", "face_y_neg", "face_z_neg"]
+            .iter()
+            .enumerate()
+            .map(|(axis, region)| BoundaryCondition { region: region.to_string(), condition_type: "Symmetry".to_string(), value: BcValue::Scalar(axis as f64) })
+            .chain([BoundaryCondition { region: "face_x_pos".to_string(), condition_type: "Dirichlet".to_string(), value: BcValue::Vector3([delta, f64::NAN, f64::NAN]) }])
+            .collect();
+        problem.solver_settings.outputs = vec![OutputField::Stress];
+        let result = FemSolver::new().solve(&problem).unwrap();
+        let mut solution = crate::Solution::from_mesh("linearization".to_string(), problem.mesh.take().unwrap(), result.data);
+        solution.fields = result.fields;
+
+        let sigma = 200e9 * delta;
+        let linearized = solution.linearize_stress([0.3, 0.0, 0.4], [0.3, 1.0, 0.4], 5).unwrap();
+        assert!((linearized.thickness - 1.0).abs() < 1e-12);
+        assert!((linearized.membrane[0] - sigma).abs() < 1e-6 * sigma, "{:?}", linearized);
+        assert!(linearized.membrane[1..].iter().all(|s| s.abs() < 1e-6 * sigma), "{:?}", linearized);
+        assert!(linearized.bending.iter().all(|s| s.abs() < 1e-6 * sigma), "{:?}", linearized);
+
+        // A stress falling linearly through the thickness is pure bending.
+        let nodes = solution.mesh.nodes.clone();
+        solution.fields.insert("stress".to_string(), nodes.iter().flat_map(|p| [100.0 * (1.0 - 2.0 * p[1]), 0.0, 0.0, 0.0, 0.0, 0.0]).collect());
+        let linearized = solution.linearize_stress([0.3, 0.0, 0.4], [0.3, 1.0, 0.4], 5).unwrap();
+        assert!(linearized.membrane[0].abs() < 1e-9, "{:?}", linearized);
+        assert!((linearized.bending[0] - 100.0).abs() < 1e-9, "{:?}", linearized);
+
+        assert!(solution.linearize_stress([0.3, 0.0, 0.4], [0.3, 2.0, 0.4], 5).is_err());
+        assert!(solution.linearize_stress([0.3, 0.0, 0.4], [0.3, 0.0, 0.4], 5).is_err());
+        solution.fields.clear();
+        assert!(solution.linearize_stress([0.3, 0.0, 0.4], [0.3, 1.0, 0.4], 5).is_err());
+    }
+
+    #[test]
+    fn test_dump_system_writes_matrix_market_files() {
+        let mut mesh = crate::meshing::structured_cube_mesh(1, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let mut problem = problem_with_mesh(mesh);
+        problem.physics.boundary_conditions = vec![
+            BoundaryCondition { region: "face_x_neg".to_string(), condition_type: "Dirichlet".to_string(), value: BcValue::Vector3([0.0, 0.0, 0.0]) },
+            BoundaryCondition { region: "face_x_pos".to_string(), condition_type: "Force".to_string(), value: BcValue::Vector3([0.0, 0.0, -10.0]) },
+        ];
+        let dir = crate::meshing::test_dir("fem_dump_system");
+        problem.solver_settings.dump_system = Some(dir.clone());
+        FemSolver::new().solve(&problem).unwrap();
+
+        let read = |name: &str| {
+            let text = std::fs::read_to_string(dir.join(format!("fem_unit_test_{}.mtx", name))).unwrap();
+            let mut lines = text.lines();
+            assert_eq!(lines.next(), Some("%%MatrixMarket matrix coordinate real general"));
+            let size: Vec<usize> = lines.next().unwrap().split_whitespace().map(|v| v.parse().unwrap()).collect();
+            let entries: Vec<(usize, usize, f64)> = lines
+                .map(|line| {
+                    let fields: Vec<&str> = line.split_whitespace().collect();
+                    (fields[0].parse().unwrap(), fields[1].parse().unwrap(), fields[2].parse().unwrap())
+                })
+                .collect();
+            assert_eq!(entries.len(), size[2]);
+            assert!(entries.iter().all(|&(row, col, value)| (1..=size[0]).contains(&row) && (1..=size[1]).contains(&col) && value != 0.0));
+            (size[0], size[1], entries)
+        };
+
+        let (rows, cols, stiffness) = read("stiffness");
+        assert_eq!((rows, cols), (8 * DOF_PER_NODE, 8 * DOF_PER_NODE));
+        // Constrained DOFs keep only their unit diagonal.
+        assert!(stiffness.contains(&(1, 1, 1.0)) && !stiffness.iter().any(|&(row, col, _)| row == 1 && col != 1));
+        let (rows, cols, force) = read("force");
+        assert_eq!((rows, cols), (8 * DOF_PER_NODE, 1));
+        // Each of the four loaded nodes carries the nodal force.
+        assert!((force.iter().map(|&(_, _, f)| f).sum::<f64>() + 40.0).abs() < 1e-9);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fixed_face_reaction_balances_applied_load() {
+        let mut mesh = crate::meshing::structured_cube_mesh(2, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let loaded_nodes = mesh.boundary_regions["face_x_pos"].len() as f64;
+        let mut problem = problem_with_mesh(mesh);
+        problem.physics.boundary_conditions = vec![
+            BoundaryCondition {
+                region: "face_x_neg".to_string(),
+                condition_type: "Dirichlet".to_string(),
+                value: BcValue::Vector3([0.0, 0.0, 0.0]),
+            },
+            BoundaryCondition {
+                region: "face_x_pos".to_string(),
+                condition_type: "Force".to_string(),
+                value: BcValue::Vector3([500.0, -20.0, 0.0]),
+            },
+        ];
+
+        let result = FemSolver::new().solve(&problem).unwrap();
+        let reactions: HashMap<String, [f64; 3]> = serde_json::from_value(result.metadata["region_reactions"].clone()).unwrap();
+
+        assert_eq!(reactions.len(), 1);
+        let fixed = reactions["face_x_neg"];
+        let applied = [500.0 * loaded_nodes, -20.0 * loaded_nodes, 0.0];
+        for axis in 0..3 {
+            assert!((fixed[axis] + applied[axis]).abs() < 1e-6, "Axis {}: reaction {} vs load {}", axis, fixed[axis], applied[axis]);
+        }
+    }
+
+    #[test]
+    fn test_masked_dirichlet_region_reports_its_reaction() {
+        // Roller supports: face_x_neg carries x and face_y_neg carries y, with z pinned on face_z_neg.
+        let mut mesh = crate::meshing::structured_cube_mesh(2, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let loaded_nodes = mesh.boundary_regions["face_x_pos"].len() as f64;
+        let mut problem = problem_with_mesh(mesh);
+        let masked = |region: &str, constrained: [bool; 3]| BoundaryCondition {
+            region: region.to_string(),
+            condition_type: "Dirichlet".to_string(),
+            value: BcValue::MaskedVector3 { value: [0.0; 3], constrained },
+        };
+        problem.physics.boundary_conditions = vec![
+            masked("face_x_neg", [true, false, false]),
+            masked("face_y_neg", [false, true, false]),
+            masked("face_z_neg", [false, false, true]),
+            BoundaryCondition {
+                region: "face_x_pos".to_string(),
+                condition_type: "Force".to_string(),
+                value: BcValue::Vector3([300.0, 0.0, 0.0]),
+            },
+        ];
+
+        let result = FemSolver::new().solve(&problem).unwrap();
+        let reactions: HashMap<String, [f64; 3]> = serde_json::from_value(result.metadata["region_reactions"].clone()).unwrap();
+
+        assert_eq!(reactions.len(), 3);
+        assert!((reactions["face_x_neg"][0] + 300.0 * loaded_nodes).abs() < 1e-6, "Reaction {:?}", reactions["face_x_neg"]);
+        assert_eq!(&reactions["face_x_neg"][1..], &[0.0, 0.0]);
+        assert!(reactions["face_y_neg"][1].abs() < 1e-6 && reactions["face_z_neg"][2].abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_lumped_and_consistent_mass_have_equal_total_mass() {
+        let tet = [[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [0.0, 3.0, 0.0], [0.0, 0.0, 1.0]];
+        let element_mass = 7800.0 * 1.0;
+        let consistent = FemSolver::new().assemble_tetrahedron_mass_matrix(tet, 7800.0, MassMatrixType::Consistent).unwrap();
+        let lumped = FemSolver::new().assemble_tetrahedron_mass_matrix(tet, 7800.0, MassMatrixType::Lumped).unwrap();
+
+        assert!((consistent.sum() - 3.0 * element_mass).abs() < 1e-9);
+        assert!((lumped.sum() - 3.0 * element_mass).abs() < 1e-9);
+        assert_eq!(lumped, DMatrix::from_diagonal(&lumped.diagonal()));
+        for row in 0..12 {
+            assert!((consistent.row(row).sum() - lumped[(row, row)]).abs() < 1e-9);
+        }
+
+        let mesh = crate::meshing::structured_cube_mesh(2, 2.0);
+        let material = Material {
+            youngs_modulus: MaterialProperty::Constant(200e9),
+            poissons_ratio: MaterialProperty::Constant(0.3),
+            density: Some(MaterialProperty::Constant(1000.0)),
+            thermal_expansion: None,
+            reference_temperature: 0.0,
+            thermal_conductivity: None,
+            viscosity: None,
+        };
+        let cube_mass = 1000.0 * 8.0;
+        for mass_type in [MassMatrixType::Consistent, MassMatrixType::Lumped] {
+            let m = FemSolver::new().assemble_global_mass(&mesh, &material, None, mass_type).unwrap();
+            assert!((m.sum() - 3.0 * cube_mass).abs() < 1e-6, "{:?}: {}", mass_type, m.sum());
+        }
+
+        let massless = Material { density: None, ..material };
+        assert!(FemSolver::new().assemble_global_mass(&mesh, &massless, None, MassMatrixType::Lumped).is_err());
+    }
+
+    #[test]
+    fn test_inverted_element_rejected_or_flipped() {
+        let mut mesh = crate::meshing::structured_cube_mesh(1, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let mut problem = problem_with_mesh(mesh);
+        problem.physics.boundary_conditions = vec![
+            BoundaryCondition {
+                region: "face_x_neg".to_string(),
+                condition_type: "Dirichlet".to_string(),
+                value: BcValue::Vector3([0.0, 0.0, 0.0]),
+            },
+            BoundaryCondition {
+                region: "face_x_pos".to_string(),
+                condition_type: "Force".to_string(),
+                value: BcValue::Vector3([100.0, 0.0, 0.0]),
+            },
+        ];
+        let reference = FemSolver::new().solve(&problem).unwrap();
+
+        problem.mesh.as_mut().unwrap().elements[3].swap(0, 2);
+        match FemSolver::new().solve(&problem) {
+            Err(EngineError::SolverFailed(msg)) => assert!(msg.contains("[3]"), "Unexpected message: {}", msg),
+            other => panic!("Expected the inverted element to be rejected, got {:?}", other),
+        }
+
+        problem.solver_settings.flip_inverted_elements = true;
+        let flipped = FemSolver::new().solve(&problem).unwrap();
+        assert_eq!(flipped.warnings[0].code, WarningCode::InvertedElements);
+        for (a, b) in reference.data.iter().zip(&flipped.data) {
+            assert!((a - b).abs() <= 1e-9 * reference.data.iter().fold(0.0f64, |m, v| m.max(v.abs())));
+        }
+    }
+
+    #[test]
+    fn test_cached_solver_reuses_stiffness_when_only_loads_change() {
+        let mut mesh = crate::meshing::structured_cube_mesh(2, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let mut problem = problem_with_mesh(mesh);
+        let load = |fx: f64| BoundaryCondition {
+            region: "face_x_pos".to_string(),
+            condition_type: "Force".to_string(),
+            value: BcValue::Vector3([fx, 0.0, 0.0]),
+        };
+        problem.physics.boundary_conditions = vec![
+            BoundaryCondition {
+                region: "face_x_neg".to_string(),
+                condition_type: "Dirichlet".to_string(),
+                value: BcValue::Vector3([0.0, 0.0, 0.0]),
+            },
+            load(100.0),
+        ];
+
+        let solver = CachedFemSolver::new();
+        let first = solver.solve(&problem).unwrap();
+        assert_eq!(first.data, FemSolver::new().solve(&problem).unwrap().data);
+
+        problem.physics.boundary_conditions[1] = load(250.0);
+        let second = solver.solve(&problem).unwrap();
+        assert_eq!(solver.assembly_count(), 1);
+        let expected = FemSolver::new().solve(&problem).unwrap();
+        for ((a, b), c) in second.data.iter().zip(&expected.data).zip(&first.data) {
+            assert!((a - b).abs() <= 1e-12 * b.abs().max(1e-12));
+            assert!((a - 2.5 * c).abs() <= 1e-9 * c.abs().max(1e-12));
+        }
+
+        // A different material invalidates the cache.
+        problem.physics.material.youngs_modulus = MaterialProperty::Constant(100e9);
+        solver.solve(&problem).unwrap();
+        assert_eq!(solver.assembly_count(), 2);
+    }
+
+    /// A bar held between walls at x = 0 and x = 1, free laterally and heated by
+    /// dT, which carries the uniaxial stress -E alpha dT and no lateral stress.
+    /// Returns the problem and that stress.
+    fn heated_constrained_bar() -> (ProblemDefinition, f64) {
+        let (alpha, delta_t) = (1.2e-5, 80.0);
+        let mut mesh = crate::meshing::structured_cube_mesh(2, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let num_nodes = mesh.nodes.len();
+        let mut problem = problem_with_mesh(mesh);
+        problem.physics.material.thermal_expansion = Some(MaterialProperty::Constant(alpha));
+        problem.physics.material.reference_temperature = 20.0;
+        problem.aux_fields.insert("temperature".to_string(), vec![20.0 + delta_t; num_nodes]);
+        let symmetry = |region: &str, axis: f64| BoundaryCondition {
+            region: region.to_string(),
+            condition_type: "Symmetry".to_string(),
+            value: BcValue::Scalar(axis),
+        };
+        problem.physics.boundary_conditions = vec![
+            symmetry("face_x_neg", 0.0),
+            symmetry("face_x_pos", 0.0),
+            symmetry("face_y_neg", 1.0),
+            symmetry("face_z_neg", 2.0),
+        ];
+        (problem, -200e9 * alpha * delta_t)
+    }
+
+    #[test]
+    fn test_constrained_bar_heated_uniformly_has_thermal_stress() {
+        let (problem, expected) = heated_constrained_bar();
+
+        let result = FemSolver::new().solve(&problem).unwrap();
+        for stress in FemSolver::new().element_stresses(&problem, &result.data).unwrap() {
+            assert!((stress[0] - expected).abs() < 1e-6 * expected.abs(), "Stress {:?} vs {}", stress, expected);
+            for component in &stress[1..] {
+                assert!(component.abs() < 1e-6 * expected.abs(), "Stress {:?}", stress);
+            }
+        }
+        let reaction = result.metadata["region_reactions"]["face_x_pos"][0].as_f64().unwrap();
+        assert!((reaction - expected).abs() < 1e-6 * expected.abs(), "Reaction {} vs {}", reaction, expected);
+    }
+
+    #[test]
+    fn test_nodal_stresses_of_uniform_stress_field() {
+        let (mut problem, expected) = heated_constrained_bar();
+
+        let result = FemSolver::new().solve(&problem).unwrap();
+        FemSolver::new().store_nodal_stresses(&mut problem, &result.data).unwrap();
+        let nodal = &problem.aux_fields["stress"];
+        assert_eq!(nodal.len(), problem.mesh.as_ref().unwrap().nodes.len() * 6);
+        for stress in nodal.chunks_exact(6) {
+            assert!((stress[0] - expected).abs() < 1e-6 * expected.abs(), "Stress {:?} vs {}", stress, expected);
+            for component in &stress[1..] {
+                assert!(component.abs() < 1e-6 * expected.abs(), "Stress {:?}", stress);
+            }
+        }
+    }
+
+    #[test]
+    fn test_harmonic_response_amplifies_near_resonance() {
+        let mut mesh = crate::meshing::structured_cube_mesh(2, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let mut problem = problem_with_mesh(mesh);
+        problem.solver_settings.solver_name = "HarmonicFemSolver".to_string();
+        problem.physics.material.youngs_modulus = MaterialProperty::Constant(1000.0);
+        problem.physics.material.density = Some(MaterialProperty::Constant(1.0));
+        problem.physics.boundary_conditions = vec![
+            BoundaryCondition { region: "face_x_neg".to_string(), condition_type: "Dirichlet".to_string(), value: BcValue::Vector3([0.0, 0.0, 0.0]) },
+            BoundaryCondition { region: "face_x_pos".to_string(), condition_type: "Force".to_string(), value: BcValue::Vector3([0.0, 1.0, 0.0]) },
+        ];
+        assert!(HarmonicFemSolver.check_compatibility(&problem).is_err(), "missing excitation accepted");
+
+        // Find the natural frequency of the mode the load excites most, from the
+        // symmetric form L^-1 K L^-T of the constrained problem with M = L L^T.
+        let mesh = problem.mesh.as_ref().unwrap();
+        let material = &problem.physics.material;
+        let k = FemSolver::new().assemble_global_stiffness(mesh, material, None, Some(1)).unwrap();
+        let m = FemSolver::new().assemble_global_mass(mesh, material, None, MassMatrixType::Consistent).unwrap();
+        let mut f = DVector::zeros(k.nrows());
+        let prescribed = FemSolver::new().apply_boundary_conditions(&problem, mesh, &mut f).unwrap();
+        let free: Vec<usize> = (0..k.nrows()).filter(|dof| !prescribed.iter().any(|(p, _)| p == dof)).collect();
+        let l_inv = m.select_rows(&free).select_columns(&free).cholesky().unwrap().l().try_inverse().unwrap();
+        let modes = (&l_inv * k.select_rows(&free).select_columns(&free) * l_inv.transpose()).symmetric_eigen();
+        let modal_load = &l_inv * f.select_rows(&free);
+        let mode = (0..free.len())
+            .max_by(|&a, &b| modes.eigenvectors.column(a).dot(&modal_load).abs().total_cmp(&modes.eigenvectors.column(b).dot(&modal_load).abs()))
+            .unwrap();
+        let omega = modes.eigenvalues[mode].sqrt();
+        let resonance = omega / (2.0 * std::f64::consts::PI);
+
+        // Two percent of critical damping at the resonance.
+        let damping = RayleighDamping { alpha: 0.0, beta: 2.0 * 0.02 / omega };
+        problem.solver_settings.harmonic = Some(HarmonicExcitation::linear_sweep(0.0, resonance, 3, damping));
+        HarmonicFemSolver.check_compatibility(&problem).unwrap();
+        let sweep = HarmonicFemSolver.solve(&problem).unwrap();
+
+        let max_displacement: Vec<f64> = serde_json::from_value(sweep.metadata["max_displacement"].clone()).unwrap();
+        assert_eq!(max_displacement.len(), 3);
+        assert_eq!(sweep.metadata["frequency"], serde_json::json!(resonance));
+        assert!(max_displacement[2] > 10.0 * max_displacement[1], "no amplification at resonance: {:?}", max_displacement);
+        assert!(max_displacement[1] > max_displacement[0]);
+        assert_eq!(sweep.data.iter().cloned().fold(0.0, f64::max), max_displacement[2]);
+
+        // At zero frequency the amplitudes are those of the static solve.
+        problem.solver_settings.harmonic = Some(HarmonicExcitation { frequencies: vec![0.0], damping });
+        let harmonic = HarmonicFemSolver.solve(&problem).unwrap();
+        let stat = FemSolver::new().solve(&problem).unwrap();
+        for (h, s) in harmonic.data.iter().zip(&stat.data) {
+            assert!((h - s.abs()).abs() <= 1e-9 * max_displacement[0], "{} vs {}", h, s);
+        }
+
+        problem.physics.material.density = None;
+        assert!(HarmonicFemSolver.check_compatibility(&problem).is_err(), "massless material accepted");
+    }
+}
@@ -1,17 +1,155 @@
 
-pub mod fem_solver;
-pub mod fdm_solver;
-
 // src/solver/mod.rs
 
-/// Defines the solver framework, including the `Solver` trait and a dummy solver for testing.
+//! Defines the solver framework, including the `Solver` trait and a dummy solver for testing.
+
+pub mod assembly;
+pub mod fem_solver;
+pub mod fdm_solver;
+pub mod stokes_solver;
+pub mod thermal_solver;
+pub mod wasm_solver;
 
-use crate::{ProblemDefinition, EngineError};
+use crate::{BcValue, ProblemDefinition, EngineError, EngineWarning};
+use std::collections::HashMap;
 
 /// Represents the raw solution data returned by a solver.
 #[derive(Debug, serde::Serialize)]
 pub struct SolverSolutionData {
+    /// The steady-state field, or the last time step of a transient solve.
     pub data: Vec<f64>,
+    /// Every step of a transient solve as `(time, field)` pairs, empty otherwise.
+    pub time_steps: Vec<(f64, Vec<f64>)>,
+    /// Further named nodal fields, such as stresses, keyed like `Solution::fields`.
+    pub fields: HashMap<String, Vec<f64>>,
+    /// Solver-specific diagnostics, such as residual norms or reaction forces.
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+    /// Non-fatal issues found while solving.
+    pub warnings: Vec<EngineWarning>,
+    /// Residuals of the iterations selected by `SolverSettings::record_iterations`.
+    pub iterations: Vec<IterationResidual>,
+}
+
+/// The residual norm after one iteration of an iterative solve.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct IterationResidual {
+    pub iteration: u32,
+    pub residual_norm: f64,
+}
+
+/// Picks every `every`-th entry of a residual history, whose first entry is iteration 1.
+pub fn select_iterations(residual_history: &[f64], every: u32) -> Vec<IterationResidual> {
+    let every = every.max(1) as usize;
+    residual_history
+        .iter()
+        .enumerate()
+        .skip(every - 1)
+        .step_by(every)
+        .map(|(i, &residual_norm)| IterationResidual { iteration: i as u32 + 1, residual_norm })
+        .collect()
+}
+
+/// Number of offending indices [`check_finite`] lists per field.
+const MAX_REPORTED_NON_FINITE: usize = 5;
+
+/// Rejects solution data holding NaN or infinite values, naming how many there
+/// are and where, so a failed solve cannot reach exports or provenance.
+///
+/// The field, each time step and every named field are scanned.
+pub fn check_finite(solver_name: &str, solution: &SolverSolutionData) -> Result<(), EngineError> {
+    let mut named_fields: Vec<(&String, &Vec<f64>)> = solution.fields.iter().collect();
+    named_fields.sort_by_key(|(name, _)| *name);
+    let fields = std::iter::once(("data".to_string(), &solution.data))
+        .chain(solution.time_steps.iter().map(|(t, field)| (format!("time step {}", t), field)))
+        .chain(named_fields.into_iter().map(|(name, field)| (format!("field {}", name), field)));
+
+    let problems: Vec<String> = fields
+        .filter_map(|(name, values)| {
+            let bad: Vec<usize> = (0..values.len()).filter(|&i| !values[i].is_finite()).collect();
+            (!bad.is_empty()).then(|| format!(
+                "{} of {} values in {} (first at indices {:?})",
+                bad.len(), values.len(), name, &bad[..bad.len().min(MAX_REPORTED_NON_FINITE)]
+            ))
+        })
+        .collect();
+    if problems.is_empty() {
+        return Ok(());
+    }
+    Err(EngineError::SolverFailed(format!("{} produced non-finite values: {}", solver_name, problems.join("; "))))
+}
+
+/// The class of physical problem a solver handles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub enum PhysicsKind {
+    /// Static linear elasticity, solving for nodal displacements.
+    LinearElasticity,
+    /// The steady response of an elastic body to harmonic loads.
+    HarmonicElasticity,
+    /// Elastic waves marched in time.
+    Elastodynamics,
+    /// Heat conduction for a scalar temperature field.
+    HeatConduction,
+    /// Incompressible creeping flow, solving for velocity and pressure.
+    StokesFlow,
+}
+
+/// The physics and spatial dimension of a problem, matched against [`SolverCapabilities`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhysicsDescriptor {
+    pub kind: PhysicsKind,
+    pub dimension: usize,
+}
+
+impl PhysicsDescriptor {
+    /// Infers what a problem asks for from its settings and boundary conditions.
+    ///
+    /// Harmonic excitation settings make it a harmonic problem and a material
+    /// viscosity a Stokes flow problem. Otherwise vector-valued conditions,
+    /// forces and symmetry planes make it elasticity on a 3D mesh, marched in
+    /// time with time stepping settings, and purely scalar conditions heat
+    /// conduction on the problem's grid. Returns `None` for a problem without
+    /// boundary conditions.
+    pub fn infer(problem: &ProblemDefinition) -> Option<Self> {
+        let settings = &problem.solver_settings;
+        let conditions = &problem.physics.boundary_conditions;
+        let mechanical = conditions.iter().any(|bc| {
+            matches!(bc.condition_type.as_str(), "Force" | "PointForce" | "Symmetry")
+                || matches!(bc.value, BcValue::Vector3(_) | BcValue::MaskedVector3 { .. } | BcValue::PointLoad { .. })
+        });
+        let kind = if settings.harmonic.is_some() {
+            PhysicsKind::HarmonicElasticity
+        } else if problem.physics.material.viscosity.is_some() && !conditions.is_empty() {
+            PhysicsKind::StokesFlow
+        } else if mechanical && settings.time_stepping.is_some() {
+            PhysicsKind::Elastodynamics
+        } else if mechanical {
+            PhysicsKind::LinearElasticity
+        } else if !conditions.is_empty() {
+            PhysicsKind::HeatConduction
+        } else {
+            return None;
+        };
+        let dimension = match kind {
+            PhysicsKind::HeatConduction => settings.grid_dimensions.as_ref().map_or(1, Vec::len),
+            _ => 3,
+        };
+        Some(PhysicsDescriptor { kind, dimension })
+    }
+}
+
+/// What a solver can handle, so the engine can pick one when none is named.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SolverCapabilities {
+    pub physics: Vec<PhysicsKind>,
+    /// Spatial dimensions of the problems handled.
+    pub dimensions: Vec<usize>,
+}
+
+impl SolverCapabilities {
+    /// Returns whether a problem matching `descriptor` is within these capabilities.
+    pub fn supports(&self, descriptor: &PhysicsDescriptor) -> bool {
+        self.physics.contains(&descriptor.kind) && self.dimensions.contains(&descriptor.dimension)
+    }
 }
 
 /// The common interface for all physics solvers.
@@ -22,8 +160,26 @@ pub trait Solver {
     /// Returns the unique name of the solver.
     fn name(&self) -> &'static str;
 
+    /// Checks that the problem can be handled by this solver before any work is done.
+    ///
+    /// The default accepts every problem; solvers with requirements on the mesh,
+    /// element type or boundary conditions should override it.
+    fn check_compatibility(&self, _problem: &ProblemDefinition) -> Result<(), EngineError> {
+        Ok(())
+    }
+
+    /// Describes the problems this solver handles.
+    ///
+    /// The default advertises none, so the solver only runs when named.
+    fn capabilities(&self) -> SolverCapabilities {
+        SolverCapabilities::default()
+    }
+
     /// Solves the given problem.
-    fn solve(&self, problem: &mut ProblemDefinition) -> Result<SolverSolutionData, EngineError>;
+    ///
+    /// The problem is only borrowed, so several solvers can work on it at once;
+    /// any scratch state belongs in the solver or the returned data.
+    fn solve(&self, problem: &ProblemDefinition) -> Result<SolverSolutionData, EngineError>;
 }
 
 
@@ -38,7 +194,14 @@ impl Solver for DummySolver {
         "DummySolver"
     }
 
-    fn solve(&self, problem: &mut ProblemDefinition) -> Result<SolverSolutionData, EngineError> {
+    fn check_compatibility(&self, problem: &ProblemDefinition) -> Result<(), EngineError> {
+        if problem.mesh.is_none() {
+            return Err(EngineError::SolverFailed("DummySolver requires a mesh".to_string()));
+        }
+        Ok(())
+    }
+
+    fn solve(&self, problem: &ProblemDefinition) -> Result<SolverSolutionData, EngineError> {
         println!("--- Running DummySolver ---");
         println!("  Problem ID: {}", problem.id);
         println!("  Solver specified: {}", problem.solver_settings.solver_name);
@@ -60,7 +223,105 @@ impl Solver for DummySolver {
 
         Ok(SolverSolutionData {
             data: placeholder_data,
+            time_steps: Vec::new(),
+            fields: HashMap::new(),
+            metadata: serde_json::Map::new(),
+            warnings: Vec::new(),
+            iterations: Vec::new(),
         })
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BcValue, BoundaryCondition, GeometricPrimitive, GeometryDefinition, Material, MaterialProperty, PhysicsDefinition, SolverSettings};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_solvers_share_an_immutable_problem() {
+        let mut mesh = crate::meshing::structured_cube_mesh(1, 1.0);
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let problem = ProblemDefinition {
+            id: "shared".to_string(),
+            geometry: GeometryDefinition::Primitive(GeometricPrimitive {
+                shape: "cube".to_string(),
+                dimensions: vec![1.0, 1.0, 1.0],
+                transform: None,
+            }),
+            physics: PhysicsDefinition {
+                equations: vec![],
+                boundary_conditions: vec![
+                    BoundaryCondition {
+                        region: "face_x_neg".to_string(),
+                        condition_type: "Dirichlet".to_string(),
+                        value: BcValue::Vector3([0.0, 0.0, 0.0]),
+                    },
+                    BoundaryCondition {
+                        region: "face_x_pos".to_string(),
+                        condition_type: "Force".to_string(),
+                        value: BcValue::Vector3([100.0, 0.0, 0.0]),
+                    },
+                ],
+                thermal_boundary_conditions: vec![],
+                material: Material {
+                    youngs_modulus: MaterialProperty::Constant(200e9),
+                    poissons_ratio: MaterialProperty::Constant(0.3),
+                    density: None,
+                    thermal_expansion: None,
+                    reference_temperature: 0.0,
+                    thermal_conductivity: None,
+                    viscosity: None,
+                },
+                boundary_conditions_file: None,
+                constraints: vec![],
+                processed_equations: None,
+            },
+            solver_settings: SolverSettings {
+                solver_name: String::new(),
+                tolerance: 1e-5,
+                max_iterations: 10,
+                num_threads: Some(1),
+                ..Default::default()
+            },
+            mesh: Some(mesh),
+            aux_fields: HashMap::new(),
+        };
+
+        // Both solvers borrow the same problem at once from different threads.
+        let (fem, dummy) = std::thread::scope(|scope| {
+            let fem = scope.spawn(|| fem_solver::FemSolver::new().solve(&problem));
+            let dummy = scope.spawn(|| DummySolver.solve(&problem));
+            (fem.join().unwrap().unwrap(), dummy.join().unwrap().unwrap())
+        });
+
+        assert_eq!(fem.data.len(), 8 * 3);
+        assert_eq!(dummy.data.len(), 8);
+        assert!(fem.data.iter().any(|&u| u > 0.0));
+    }
+
+    #[test]
+    fn test_check_finite_reports_count_and_location() {
+        let mut solution = SolverSolutionData {
+            data: vec![0.0, 1.0, 2.0],
+            time_steps: vec![(0.5, vec![1.0, 2.0])],
+            fields: HashMap::from([("stress".to_string(), vec![3.0])]),
+            metadata: serde_json::Map::new(),
+            warnings: Vec::new(),
+            iterations: Vec::new(),
+        };
+        assert!(check_finite("Test", &solution).is_ok());
+
+        solution.data[1] = f64::NAN;
+        solution.fields.get_mut("stress").unwrap()[0] = f64::INFINITY;
+        match check_finite("Test", &solution) {
+            Err(EngineError::SolverFailed(msg)) => {
+                assert!(msg.contains("1 of 3 values in data (first at indices [1])"), "{}", msg);
+                assert!(msg.contains("field stress"), "{}", msg);
+                assert!(!msg.contains("time step"), "{}", msg);
+            }
+            other => panic!("Expected non-finite values to be rejected, got {:?}", other),
+        }
+    }
+}
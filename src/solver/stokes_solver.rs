@@ -0,0 +1,347 @@
+// src/solver/stokes_solver.rs
+
+//! A mixed finite element solver for steady incompressible Stokes flow.
+
+use crate::{BcValue, EngineError, Mesh, ProblemDefinition};
+use crate::meshing::{self, TETRAHEDRON10_EDGES};
+use crate::solver::Solver;
+use crate::solver::assembly::{self, FieldKind, Triplet};
+use crate::kernel::quadrature::{gauss_points, ElementShape};
+use crate::solver::fem_solver::{integration_points, unmatched_regions};
+use nalgebra::{DMatrix, DVector, Vector3};
+use std::collections::{HashMap, HashSet};
+
+/// The flow solves for a velocity vector at every node.
+const VELOCITY: FieldKind = FieldKind::Vector;
+
+/// Velocity degrees of freedom (x, y, z) per node.
+const DOF_PER_NODE: usize = VELOCITY.dofs_per_node();
+
+/// A Taylor-Hood solver for creeping, pressure-driven flow.
+///
+/// It solves `-mu lap(u) + grad(p) = 0` with `div(u) = 0` on a `Tetrahedron10`
+/// mesh, with quadratic velocities at every node and linear pressures at the
+/// corner nodes, a pairing that is stable without stabilization terms. The
+/// viscosity `mu` is the material's `viscosity`.
+///
+/// A `Dirichlet` condition prescribes the velocity on a region, as no-slip walls
+/// with a zero vector or as inflow; NaN or masked components stay free, which
+/// gives slip walls. A `Pressure` condition with a scalar value applies the
+/// traction `-p n` on the region's faces, the natural inflow and outflow
+/// condition of pressure-driven flow. Without any `Pressure` condition the
+/// pressure is only known up to a constant and is pinned to zero at the first
+/// corner node.
+///
+/// The solution data holds the velocities, and the fields `"velocity"` and
+/// `"pressure"` hold both at every node, edge nodes taking the mean pressure of
+/// their edge.
+pub struct StokesSolver;
+
+/// Maps each corner node of the mesh to its pressure unknown.
+fn pressure_numbering(mesh: &Mesh) -> Vec<Option<usize>> {
+    let mut numbering = vec![None; mesh.nodes.len()];
+    let mut count = 0;
+    for element in &mesh.elements {
+        for &node in &element[..4] {
+            if numbering[node].is_none() {
+                numbering[node] = Some(count);
+                count += 1;
+            }
+        }
+    }
+    numbering
+}
+
+/// Returns the element-local nodes of the face opposite `corner`: its three
+/// corners followed by the edge nodes between them.
+pub(crate) fn face_nodes(corner: usize) -> [usize; 6] {
+    let corners: Vec<usize> = (0..4).filter(|&c| c != corner).collect();
+    let edge = |a: usize, b: usize| 4 + TETRAHEDRON10_EDGES.iter().position(|&e| e == [a, b] || e == [b, a]).unwrap();
+    [corners[0], corners[1], corners[2], edge(corners[0], corners[1]), edge(corners[1], corners[2]), edge(corners[2], corners[0])]
+}
+
+impl StokesSolver {
+    /// Returns the material viscosity, which must be positive.
+    fn viscosity(&self, problem: &ProblemDefinition) -> Result<f64, EngineError> {
+        let viscosity = problem.physics.material.viscosity.as_ref()
+            .ok_or_else(|| EngineError::SolverFailed("StokesSolver requires a material viscosity".to_string()))?
+            .value(None)?;
+        if !(viscosity > 0.0 && viscosity.is_finite()) {
+            return Err(EngineError::SolverFailed(format!("StokesSolver needs a positive viscosity, got {}", viscosity)));
+        }
+        Ok(viscosity)
+    }
+
+    /// Computes the triplets of one element: the viscous block over the velocities
+    /// and the divergence block `-integral(q div v)` with its transpose.
+    ///
+    /// The pressure unknowns are numbered after all velocity unknowns.
+    fn element_triplets(&self, mesh: &Mesh, element: &[usize], viscosity: f64, pressure: &[Option<usize>]) -> Result<Vec<Triplet>, EngineError> {
+        let nodes: Vec<[f64; 3]> = element.iter().map(|&n| mesh.nodes[n]).collect();
+        let mut laplacian = DMatrix::<f64>::zeros(nodes.len(), nodes.len());
+        let mut divergence = DMatrix::<f64>::zeros(4, nodes.len() * DOF_PER_NODE);
+        // The velocity integration points are those of the quadratic rule.
+        for (qp, (weight, gradients)) in gauss_points(ElementShape::Tetrahedron, 2).iter().zip(integration_points(&nodes)?) {
+            let g = DMatrix::from_fn(3, nodes.len(), |r, c| gradients[c][r]);
+            laplacian += g.transpose() * g * (weight * viscosity);
+            // The linear pressure shape functions are the barycentric coordinates.
+            let [xi, eta, zeta] = qp.point;
+            let linear = [1.0 - xi - eta - zeta, xi, eta, zeta];
+            for (q, &m) in linear.iter().enumerate() {
+                for (a, gradient) in gradients.iter().enumerate() {
+                    for (c, &dn) in gradient.iter().enumerate() {
+                        divergence[(q, a * DOF_PER_NODE + c)] -= weight * m * dn;
+                    }
+                }
+            }
+        }
+
+        let velocity_dofs = mesh.nodes.len() * DOF_PER_NODE;
+        let ke = laplacian.kronecker(&DMatrix::<f64>::identity(DOF_PER_NODE, DOF_PER_NODE));
+        let mut triplets = assembly::element_triplets(VELOCITY, element, &ke);
+        for q in 0..4 {
+            let row = velocity_dofs + pressure[element[q]].expect("corner nodes have a pressure unknown");
+            for (a, &node) in element.iter().enumerate() {
+                for c in 0..DOF_PER_NODE {
+                    let value = divergence[(q, a * DOF_PER_NODE + c)];
+                    let col = assembly::global_dof(VELOCITY, node, c);
+                    triplets.push((row, col, value));
+                    triplets.push((col, row, value));
+                }
+            }
+        }
+        Ok(triplets)
+    }
+
+    /// Applies the boundary conditions, adding pressure tractions to `f` and
+    /// returning the prescribed velocity unknowns.
+    fn apply_boundary_conditions(&self, problem: &ProblemDefinition, mesh: &Mesh, f: &mut DVector<f64>) -> Result<Vec<(usize, f64)>, EngineError> {
+        let mut prescribed = Vec::new();
+        let faces = meshing::boundary_faces(mesh);
+        for bc in &problem.physics.boundary_conditions {
+            let Some(region_nodes) = mesh.boundary_regions.get(&bc.region) else {
+                continue;
+            };
+            match (bc.condition_type.as_str(), &bc.value) {
+                ("Dirichlet", BcValue::Vector3(velocity)) => {
+                    for &node in region_nodes {
+                        prescribed.extend((0..DOF_PER_NODE).filter(|&c| velocity[c].is_finite()).map(|c| (assembly::global_dof(VELOCITY, node, c), velocity[c])));
+                    }
+                }
+                ("Dirichlet", BcValue::MaskedVector3 { value, constrained }) => {
+                    for &node in region_nodes {
+                        prescribed.extend((0..DOF_PER_NODE).filter(|&c| constrained[c]).map(|c| (assembly::global_dof(VELOCITY, node, c), value[c])));
+                    }
+                }
+                ("Pressure", BcValue::Scalar(p)) => {
+                    // For a constant traction on a flat 6-node triangle, the corners
+                    // get nothing and each edge node a third of the force.
+                    let in_region: HashSet<usize> = region_nodes.iter().copied().collect();
+                    for (e, triangle) in &faces {
+                        let element = &mesh.elements[*e];
+                        let corner = (0..4).find(|&c| !triangle.contains(&element[c])).expect("a face leaves out one corner");
+                        if !face_nodes(corner).iter().all(|&i| in_region.contains(&element[i])) {
+                            continue;
+                        }
+                        // The triangle is ordered so that its normal points outward.
+                        let x = |n: usize| Vector3::from(mesh.nodes[n]);
+                        let normal = (x(triangle[1]) - x(triangle[0])).cross(&(x(triangle[2]) - x(triangle[0])));
+                        let force = normal * (-p / 6.0);
+                        for &i in &face_nodes(corner)[3..] {
+                            for c in 0..DOF_PER_NODE {
+                                f[assembly::global_dof(VELOCITY, element[i], c)] += force[c];
+                            }
+                        }
+                    }
+                }
+                ("Dirichlet", value) => return Err(EngineError::SolverFailed(format!("Dirichlet condition on {} needs a 3-component velocity, got {:?}", bc.region, value))),
+                ("Pressure", value) => return Err(EngineError::SolverFailed(format!("Pressure condition on {} needs a scalar value, got {:?}", bc.region, value))),
+                _ => return Err(EngineError::SolverFailed(format!("Unsupported boundary condition type for Stokes flow: {}", bc.condition_type))),
+            }
+        }
+        Ok(prescribed)
+    }
+}
+
+impl Solver for StokesSolver {
+    fn name(&self) -> &'static str {
+        "StokesSolver"
+    }
+
+    fn capabilities(&self) -> super::SolverCapabilities {
+        super::SolverCapabilities { physics: vec![super::PhysicsKind::StokesFlow], dimensions: vec![3] }
+    }
+
+    fn check_compatibility(&self, problem: &ProblemDefinition) -> Result<(), EngineError> {
+        let mesh = problem.mesh.as_ref().ok_or_else(|| EngineError::SolverFailed("Mesh not found in problem definition".to_string()))?;
+        crate::meshing::validate_mesh(mesh)?;
+        if mesh.element_type != "Tetrahedron10" {
+            return Err(EngineError::SolverFailed(format!(
+                "StokesSolver needs Tetrahedron10 elements for its quadratic velocities, but found {}; raise the mesh with meshing::to_quadratic_tetrahedra",
+                mesh.element_type
+            )));
+        }
+        self.viscosity(problem)?;
+
+        for bc in &problem.physics.boundary_conditions {
+            match (bc.condition_type.as_str(), &bc.value) {
+                ("Dirichlet", BcValue::Vector3(_) | BcValue::MaskedVector3 { .. }) | ("Pressure", BcValue::Scalar(_)) => {}
+                ("Dirichlet", value) => return Err(EngineError::SolverFailed(format!("Dirichlet condition on {} needs a 3-component velocity, got {:?}", bc.region, value))),
+                ("Pressure", value) => return Err(EngineError::SolverFailed(format!("Pressure condition on {} needs a scalar value, got {:?}", bc.region, value))),
+                _ => return Err(EngineError::SolverFailed(format!("Unsupported boundary condition type for Stokes flow: {}", bc.condition_type))),
+            }
+        }
+        Ok(())
+    }
+
+    fn solve(&self, problem: &ProblemDefinition) -> Result<super::SolverSolutionData, EngineError> {
+        println!("--- Running StokesSolver (Incompressible Stokes Flow) ---");
+
+        let mesh = problem.mesh.as_ref().ok_or_else(|| EngineError::SolverFailed("Mesh not found in problem definition".to_string()))?;
+        if mesh.element_type != "Tetrahedron10" {
+            return Err(EngineError::SolverFailed(format!("StokesSolver needs Tetrahedron10 elements, but found {}", mesh.element_type)));
+        }
+        let viscosity = self.viscosity(problem)?;
+        let pressure = pressure_numbering(mesh);
+        let velocity_dofs = mesh.nodes.len() * DOF_PER_NODE;
+        let pressure_dofs = pressure.iter().flatten().count();
+        let total_dof = velocity_dofs + pressure_dofs;
+
+        // Assemble the saddle-point system [A B^T; B 0] [u; p] = [f; 0].
+        let mut k_global = DMatrix::<f64>::zeros(total_dof, total_dof);
+        for element in &mesh.elements {
+            for (row, col, value) in self.element_triplets(mesh, element, viscosity, &pressure)? {
+                k_global[(row, col)] += value;
+            }
+        }
+        let mut f_global = DVector::<f64>::zeros(total_dof);
+        let warnings = unmatched_regions(problem, mesh);
+        let mut prescribed = self.apply_boundary_conditions(problem, mesh, &mut f_global)?;
+        if pressure_dofs > 0 && !problem.physics.boundary_conditions.iter().any(|bc| bc.condition_type == "Pressure") {
+            prescribed.push((velocity_dofs, 0.0));
+        }
+        assembly::apply_dirichlet(&mut k_global, &mut f_global, &prescribed);
+
+        let solution = k_global.lu().solve(&f_global).ok_or_else(|| EngineError::SolverFailed(
+            "Stokes system is singular; check that the velocity conditions leave no free rigid motion".to_string(),
+        ))?;
+
+        let velocity: Vec<f64> = solution.rows(0, velocity_dofs).iter().cloned().collect();
+        let mut nodal_pressure = vec![0.0; mesh.nodes.len()];
+        for (node, index) in pressure.iter().enumerate() {
+            if let Some(index) = index {
+                nodal_pressure[node] = solution[velocity_dofs + index];
+            }
+        }
+        for element in mesh.elements.iter().filter(|element| element.len() == 10) {
+            for (i, [a, b]) in TETRAHEDRON10_EDGES.iter().enumerate() {
+                nodal_pressure[element[4 + i]] = 0.5 * (nodal_pressure[element[*a]] + nodal_pressure[element[*b]]);
+            }
+        }
+
+        let mut metadata = serde_json::Map::new();
+        metadata.insert("velocity_dofs".to_string(), serde_json::json!(velocity_dofs));
+        metadata.insert("pressure_dofs".to_string(), serde_json::json!(pressure_dofs));
+        println!("--- StokesSolver Finished ---");
+
+        Ok(super::SolverSolutionData {
+            data: velocity.clone(),
+            time_steps: Vec::new(),
+            fields: HashMap::from([("velocity".to_string(), velocity), ("pressure".to_string(), nodal_pressure)]),
+            metadata,
+            warnings,
+            iterations: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BoundaryCondition, GeometricPrimitive, GeometryDefinition, Material, MaterialProperty, PhysicsDefinition, SolverSettings};
+
+    fn channel_problem(mesh: Mesh, viscosity: f64) -> ProblemDefinition {
+        ProblemDefinition {
+            id: "stokes_unit_test".to_string(),
+            geometry: GeometryDefinition::Primitive(GeometricPrimitive {
+                shape: "box".to_string(),
+                dimensions: vec![2.0, 1.0, 0.5],
+                transform: None,
+            }),
+            physics: PhysicsDefinition {
+                equations: vec![],
+                boundary_conditions: vec![],
+                thermal_boundary_conditions: vec![],
+                material: Material {
+                    youngs_modulus: MaterialProperty::Constant(0.0),
+                    poissons_ratio: MaterialProperty::Constant(0.0),
+                    density: None,
+                    thermal_expansion: None,
+                    reference_temperature: 0.0,
+                    thermal_conductivity: None,
+                    viscosity: Some(MaterialProperty::Constant(viscosity)),
+                },
+                boundary_conditions_file: None,
+                constraints: vec![],
+                processed_equations: None,
+            },
+            solver_settings: SolverSettings {
+                solver_name: "StokesSolver".to_string(),
+                tolerance: 1e-8,
+                max_iterations: 10,
+                ..Default::default()
+            },
+            mesh: Some(mesh),
+            aux_fields: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_channel_flow_matches_poiseuille_profile() {
+        // Flow between plates at y = 0 and y = h, driven by a pressure drop over
+        // a length l, with slip walls at the z faces. The exact solution
+        // u = dp / (2 mu l) y (h - y) is quadratic and the pressure linear, so
+        // Taylor-Hood elements reproduce both.
+        let (l, h, mu, p_in, p_out) = (2.0, 1.0, 0.5, 3.0, 1.0);
+        let mut mesh = crate::meshing::to_quadratic_tetrahedra(&crate::meshing::structured_box_mesh([3, 2, 1], [l, h, 0.5])).unwrap();
+        crate::meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let mut problem = channel_problem(mesh, mu);
+        let condition = |region: &str, condition_type: &str, value: BcValue| BoundaryCondition {
+            region: region.to_string(),
+            condition_type: condition_type.to_string(),
+            value,
+        };
+        problem.physics.boundary_conditions = vec![
+            condition("face_y_neg", "Dirichlet", BcValue::Vector3([0.0; 3])),
+            condition("face_y_pos", "Dirichlet", BcValue::Vector3([0.0; 3])),
+            condition("face_z_neg", "Dirichlet", BcValue::Vector3([f64::NAN, f64::NAN, 0.0])),
+            condition("face_z_pos", "Dirichlet", BcValue::Vector3([f64::NAN, f64::NAN, 0.0])),
+            condition("face_x_neg", "Pressure", BcValue::Scalar(p_in)),
+            condition("face_x_pos", "Pressure", BcValue::Scalar(p_out)),
+        ];
+        StokesSolver.check_compatibility(&problem).unwrap();
+
+        let result = StokesSolver.solve(&problem).unwrap();
+        let mesh = problem.mesh.as_ref().unwrap();
+        let peak = (p_in - p_out) / (2.0 * mu * l) * h * h / 4.0;
+        for (node, &[x, y, _]) in mesh.nodes.iter().enumerate() {
+            let expected = (p_in - p_out) / (2.0 * mu * l) * y * (h - y);
+            let u = &result.data[node * 3..node * 3 + 3];
+            assert!((u[0] - expected).abs() < 1e-9 * peak, "u_x at y = {} is {}, expected {}", y, u[0], expected);
+            assert!(u[1].abs() < 1e-9 * peak && u[2].abs() < 1e-9 * peak, "Cross-flow {:?} at node {}", u, node);
+            let p = result.fields["pressure"][node];
+            let expected_p = p_in + (p_out - p_in) * x / l;
+            assert!((p - expected_p).abs() < 1e-9 * p_in, "Pressure {} at x = {}, expected {}", p, x, expected_p);
+        }
+        assert_eq!(result.fields["velocity"], result.data);
+    }
+
+    #[test]
+    fn test_linear_mesh_is_rejected() {
+        let mut problem = channel_problem(crate::meshing::structured_cube_mesh(1, 1.0), 1.0);
+        assert!(matches!(StokesSolver.check_compatibility(&problem), Err(EngineError::SolverFailed(msg)) if msg.contains("to_quadratic_tetrahedra")));
+        problem.mesh = Some(crate::meshing::to_quadratic_tetrahedra(problem.mesh.as_ref().unwrap()).unwrap());
+        problem.physics.material.viscosity = None;
+        assert!(StokesSolver.check_compatibility(&problem).is_err());
+    }
+}
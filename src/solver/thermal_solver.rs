@@ -0,0 +1,233 @@
+// src/solver/thermal_solver.rs
+
+//! A finite element solver for steady heat conduction on tetrahedral meshes.
+
+use crate::{BcValue, EngineError, Mesh, ProblemDefinition};
+use crate::meshing;
+use crate::solver::Solver;
+use crate::solver::assembly::{self, FieldKind};
+use crate::solver::fdm_solver::conductivity;
+use crate::solver::fem_solver::{integration_points, unmatched_regions};
+use crate::solver::stokes_solver::face_nodes;
+use nalgebra::{DMatrix, DVector, Vector3};
+use std::collections::{HashMap, HashSet};
+
+/// The conduction solves for a temperature at every node.
+const TEMPERATURE: FieldKind = FieldKind::Scalar;
+
+/// Consistent mass matrix of a 6-node triangle of unit area, in the node order
+/// of [`face_nodes`], scaled by 180.
+const QUADRATIC_FACE_MASS: [[f64; 6]; 6] = [
+    [6.0, -1.0, -1.0, 0.0, -4.0, 0.0],
+    [-1.0, 6.0, -1.0, 0.0, 0.0, -4.0],
+    [-1.0, -1.0, 6.0, -4.0, 0.0, 0.0],
+    [0.0, 0.0, -4.0, 32.0, 16.0, 16.0],
+    [-4.0, 0.0, 0.0, 16.0, 32.0, 16.0],
+    [0.0, -4.0, 0.0, 16.0, 16.0, 32.0],
+];
+
+/// A solver for steady heat conduction `div(K grad(T)) = 0` on a `Tetrahedron`
+/// or `Tetrahedron10` mesh.
+///
+/// Conduction uses the material's `thermal_conductivity` `K`, the identity when
+/// it has none. A `Dirichlet` condition with a scalar value prescribes the
+/// temperature on a region, and a `Convective` condition exchanges heat with
+/// the surroundings through `-K grad(T) . n = h (T - t_inf)` on the region's
+/// faces. At least one of them must fix the temperature level.
+///
+/// The solution data and the `"temperature"` field hold the nodal temperatures.
+pub struct ThermalFemSolver;
+
+/// Adds the convective exchange on the boundary faces of `region_nodes` to the system.
+fn add_convection(mesh: &Mesh, region_nodes: &[usize], h: f64, t_inf: f64, k: &mut DMatrix<f64>, f: &mut DVector<f64>) {
+    let in_region: HashSet<usize> = region_nodes.iter().copied().collect();
+    for (e, triangle) in meshing::boundary_faces(mesh) {
+        let element = &mesh.elements[e];
+        // Consistent face mass matrices per unit area.
+        let (nodes, mass): (Vec<usize>, DMatrix<f64>) = if element.len() == 10 {
+            let corner = (0..4).find(|&c| !triangle.contains(&element[c])).expect("a face leaves out one corner");
+            (face_nodes(corner).iter().map(|&i| element[i]).collect(), DMatrix::from_fn(6, 6, |i, j| QUADRATIC_FACE_MASS[i][j] / 180.0))
+        } else {
+            (triangle.to_vec(), DMatrix::from_fn(3, 3, |i, j| if i == j { 2.0 / 12.0 } else { 1.0 / 12.0 }))
+        };
+        if !nodes.iter().all(|n| in_region.contains(n)) {
+            continue;
+        }
+        let x = |n: usize| Vector3::from(mesh.nodes[n]);
+        let area = 0.5 * (x(triangle[1]) - x(triangle[0])).cross(&(x(triangle[2]) - x(triangle[0]))).norm();
+        for (i, &node_i) in nodes.iter().enumerate() {
+            for (j, &node_j) in nodes.iter().enumerate() {
+                k[(node_i, node_j)] += h * area * mass[(i, j)];
+                f[node_i] += h * area * mass[(i, j)] * t_inf;
+            }
+        }
+    }
+}
+
+impl Solver for ThermalFemSolver {
+    fn name(&self) -> &'static str {
+        "ThermalFemSolver"
+    }
+
+    fn capabilities(&self) -> super::SolverCapabilities {
+        super::SolverCapabilities { physics: vec![super::PhysicsKind::HeatConduction], dimensions: vec![3] }
+    }
+
+    fn check_compatibility(&self, problem: &ProblemDefinition) -> Result<(), EngineError> {
+        let mesh = problem.mesh.as_ref().ok_or_else(|| EngineError::SolverFailed("Mesh not found in problem definition".to_string()))?;
+        meshing::validate_mesh(mesh)?;
+        if mesh.element_type != "Tetrahedron" && mesh.element_type != "Tetrahedron10" {
+            return Err(EngineError::SolverFailed(format!("ThermalFemSolver needs Tetrahedron or Tetrahedron10 elements, but found {}", mesh.element_type)));
+        }
+        conductivity(problem)?;
+
+        let mut fixed = false;
+        for bc in &problem.physics.boundary_conditions {
+            match (bc.condition_type.as_str(), &bc.value) {
+                ("Dirichlet", BcValue::Scalar(_)) => fixed = true,
+                ("Convective", BcValue::Convective { h, .. }) => fixed |= *h > 0.0,
+                ("Dirichlet", value) => return Err(EngineError::SolverFailed(format!("Dirichlet condition on {} needs a scalar temperature, got {:?}", bc.region, value))),
+                ("Convective", value) => return Err(EngineError::SolverFailed(format!("Convective condition on {} needs an h and t_inf, got {:?}", bc.region, value))),
+                _ => return Err(EngineError::SolverFailed(format!("Unsupported boundary condition type for heat conduction: {}", bc.condition_type))),
+            }
+        }
+        if !fixed {
+            return Err(EngineError::SolverFailed("ThermalFemSolver needs a Dirichlet or Convective condition to fix the temperature level".to_string()));
+        }
+        Ok(())
+    }
+
+    fn solve(&self, problem: &ProblemDefinition) -> Result<super::SolverSolutionData, EngineError> {
+        println!("--- Running ThermalFemSolver (Steady Heat Conduction) ---");
+
+        let mesh = problem.mesh.as_ref().ok_or_else(|| EngineError::SolverFailed("Mesh not found in problem definition".to_string()))?;
+        let k_material = conductivity(problem)?;
+        let k_dense = DMatrix::from_fn(3, 3, |r, c| k_material[(r, c)]);
+
+        let mut triplets = Vec::new();
+        for element in &mesh.elements {
+            let nodes: Vec<[f64; 3]> = element.iter().map(|&n| mesh.nodes[n]).collect();
+            let mut ke = DMatrix::<f64>::zeros(nodes.len(), nodes.len());
+            for (weight, gradients) in integration_points(&nodes)? {
+                let g = DMatrix::from_fn(3, nodes.len(), |r, c| gradients[c][r]);
+                ke += g.transpose() * &k_dense * g * weight;
+            }
+            triplets.extend(assembly::element_triplets(TEMPERATURE, element, &ke));
+        }
+        let mut k_global = assembly::assemble_triplets::<f64>(TEMPERATURE, mesh.nodes.len(), triplets);
+        let mut f_global = DVector::<f64>::zeros(mesh.nodes.len());
+
+        let warnings = unmatched_regions(problem, mesh);
+        let mut prescribed = Vec::new();
+        for bc in &problem.physics.boundary_conditions {
+            let Some(region_nodes) = mesh.boundary_regions.get(&bc.region) else {
+                continue;
+            };
+            match (bc.condition_type.as_str(), &bc.value) {
+                ("Dirichlet", BcValue::Scalar(t)) => prescribed.extend(region_nodes.iter().map(|&node| (assembly::global_dof(TEMPERATURE, node, 0), *t))),
+                ("Convective", BcValue::Convective { h, t_inf }) => add_convection(mesh, region_nodes, *h, *t_inf, &mut k_global, &mut f_global),
+                (condition_type, value) => return Err(EngineError::SolverFailed(format!("ThermalFemSolver cannot apply a {} condition with value {:?} on {}", condition_type, value, bc.region))),
+            }
+        }
+        assembly::apply_dirichlet(&mut k_global, &mut f_global, &prescribed);
+
+        let temperature: Vec<f64> = k_global.cholesky()
+            .ok_or_else(|| EngineError::SolverFailed("Heat conduction system is singular; check that every part of the mesh has a Dirichlet or Convective condition".to_string()))?
+            .solve(&f_global)
+            .iter()
+            .cloned()
+            .collect();
+
+        println!("--- ThermalFemSolver Finished ---");
+
+        Ok(super::SolverSolutionData {
+            data: temperature.clone(),
+            time_steps: Vec::new(),
+            fields: HashMap::from([("temperature".to_string(), temperature)]),
+            metadata: serde_json::Map::new(),
+            warnings,
+            iterations: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BoundaryCondition, GeometricPrimitive, GeometryDefinition, Material, MaterialProperty, PhysicsDefinition, SolverSettings, ThermalConductivity};
+
+    fn heat_problem(mesh: Mesh, boundary_conditions: Vec<BoundaryCondition>) -> ProblemDefinition {
+        ProblemDefinition {
+            id: "thermal_fem_unit_test".to_string(),
+            geometry: GeometryDefinition::Primitive(GeometricPrimitive {
+                shape: "box".to_string(),
+                dimensions: vec![2.0, 1.0, 1.0],
+                transform: None,
+            }),
+            physics: PhysicsDefinition {
+                equations: vec![],
+                boundary_conditions,
+                thermal_boundary_conditions: vec![],
+                material: Material {
+                    youngs_modulus: MaterialProperty::Constant(0.0),
+                    poissons_ratio: MaterialProperty::Constant(0.0),
+                    density: None,
+                    thermal_expansion: None,
+                    reference_temperature: 0.0,
+                    thermal_conductivity: Some(ThermalConductivity::Isotropic(4.0)),
+                    viscosity: None,
+                },
+                boundary_conditions_file: None,
+                constraints: vec![],
+                processed_equations: None,
+            },
+            solver_settings: SolverSettings {
+                solver_name: "ThermalFemSolver".to_string(),
+                ..Default::default()
+            },
+            mesh: Some(mesh),
+            aux_fields: HashMap::new(),
+        }
+    }
+
+    fn condition(region: &str, condition_type: &str, value: BcValue) -> BoundaryCondition {
+        BoundaryCondition {
+            region: region.to_string(),
+            condition_type: condition_type.to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn test_bar_with_convective_end_matches_linear_profile() {
+        // T(0) = t0 and -k dT/dx = h (T - t_inf) at x = l give the linear
+        // profile T = t0 + a x with a = -h (t0 - t_inf) / (k + h l), which both
+        // linear and quadratic elements reproduce.
+        let (l, k, h, t0, t_inf) = (2.0, 4.0, 3.0, 100.0, 20.0);
+        let slope = -h * (t0 - t_inf) / (k + h * l);
+        let linear = meshing::structured_box_mesh([4, 2, 2], [l, 1.0, 1.0]);
+        for mut mesh in [linear.clone(), meshing::to_quadratic_tetrahedra(&linear).unwrap()] {
+            meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+            let problem = heat_problem(mesh, vec![
+                condition("face_x_neg", "Dirichlet", BcValue::Scalar(t0)),
+                condition("face_x_pos", "Convective", BcValue::Convective { h, t_inf }),
+            ]);
+            ThermalFemSolver.check_compatibility(&problem).unwrap();
+
+            let result = ThermalFemSolver.solve(&problem).unwrap();
+            for (node, &[x, _, _]) in problem.mesh.as_ref().unwrap().nodes.iter().enumerate() {
+                let expected = t0 + slope * x;
+                assert!((result.data[node] - expected).abs() < 1e-9 * t0, "T at x = {} is {}, expected {}", x, result.data[node], expected);
+            }
+            assert_eq!(result.fields["temperature"], result.data);
+        }
+    }
+
+    #[test]
+    fn test_problem_without_fixed_temperature_is_rejected() {
+        let mut mesh = meshing::structured_cube_mesh(1, 1.0);
+        meshing::detect_coordinate_regions(&mut mesh, 1e-9);
+        let problem = heat_problem(mesh, vec![condition("face_x_neg", "Convective", BcValue::Convective { h: 0.0, t_inf: 20.0 })]);
+        assert!(ThermalFemSolver.check_compatibility(&problem).is_err());
+    }
+}
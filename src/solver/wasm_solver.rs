@@ -0,0 +1,175 @@
+// src/solver/wasm_solver.rs
+
+//! A solver plugin that runs a WebAssembly module in the sandbox.
+
+use crate::{ProblemDefinition, EngineError};
+use crate::solver::Solver;
+use std::collections::HashMap;
+
+/// A solver implemented by a sandboxed Wasm module.
+///
+/// The problem, including its mesh, is passed to the guest as JSON and the
+/// guest returns the solution vector; see [`crate::sandbox::run_wasm_solver`]
+/// for the exports the module must provide.
+pub struct WasmSolver {
+    name: &'static str,
+    wasm_bytes: Vec<u8>,
+}
+
+impl WasmSolver {
+    /// Creates a solver registered as `name` from compiled Wasm bytes.
+    pub fn new(name: &'static str, wasm_bytes: Vec<u8>) -> Self {
+        WasmSolver { name, wasm_bytes }
+    }
+}
+
+impl Solver for WasmSolver {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn check_compatibility(&self, problem: &ProblemDefinition) -> Result<(), EngineError> {
+        if problem.mesh.is_none() {
+            return Err(EngineError::SolverFailed(format!("{} requires a mesh", self.name)));
+        }
+        Ok(())
+    }
+
+    fn solve(&self, problem: &ProblemDefinition) -> Result<super::SolverSolutionData, EngineError> {
+        println!("--- Running Wasm solver {} ---", self.name);
+
+        let mesh = problem.mesh.as_ref().ok_or_else(|| EngineError::SolverFailed("Mesh not found in problem definition".to_string()))?;
+        let input = serde_json::to_vec(problem).map_err(|e| EngineError::SolverFailed(format!("Failed to serialize problem for {}: {}", self.name, e)))?;
+        let num_nodes = u32::try_from(mesh.nodes.len()).map_err(|_| EngineError::SolverFailed(format!("Mesh is too large for {}", self.name)))?;
+        let data = crate::sandbox::run_wasm_solver(&self.wasm_bytes, &input, num_nodes).map_err(EngineError::SolverFailed)?;
+
+        println!("--- Wasm solver {} Finished ---", self.name);
+
+        Ok(super::SolverSolutionData {
+            data,
+            time_steps: Vec::new(),
+            fields: HashMap::new(),
+            metadata: serde_json::Map::new(),
+            warnings: Vec::new(),
+            iterations: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GeometricPrimitive, GeometryDefinition, Material, MaterialProperty, PhysicsDefinition, SolverSettings};
+    use std::collections::HashMap;
+
+    // A bump allocator and a `solve` returning `num_nodes` zeros. Memory grown
+    // past the heap is zero-filled, so only the count has to be written.
+    const ZEROS_WAT: &str = r#"
+    (module
+        (memory (export "memory") 1)
+        (global $heap (mut i32) (i32.const 1024))
+        (func $alloc (export "alloc") (param $size i32) (result i32)
+            (local $ptr i32)
+            global.get $heap
+            local.set $ptr
+            global.get $heap
+            local.get $size
+            i32.add
+            global.set $heap
+            (block $done
+                (loop $grow
+                    global.get $heap
+                    memory.size
+                    i32.const 65536
+                    i32.mul
+                    i32.le_u
+                    br_if $done
+                    i32.const 1
+                    memory.grow
+                    drop
+                    br $grow))
+            local.get $ptr)
+        (func (export "solve") (param $ptr i32) (param $len i32) (param $n i32) (result i32)
+            (local $out i32)
+            local.get $n
+            i32.const 8
+            i32.mul
+            i32.const 4
+            i32.add
+            call $alloc
+            local.set $out
+            local.get $out
+            local.get $n
+            i32.store
+            local.get $out))
+    "#;
+
+    // Grows its memory past the plugin limit on every call.
+    const GREEDY_WAT: &str = r#"
+    (module
+        (memory (export "memory") 1)
+        (func (export "alloc") (param $size i32) (result i32)
+            i32.const 0)
+        (func (export "solve") (param $ptr i32) (param $len i32) (param $n i32) (result i32)
+            i32.const 2048
+            memory.grow
+            drop
+            i32.const 0))
+    "#;
+
+    fn problem() -> ProblemDefinition {
+        ProblemDefinition {
+            id: "wasm_plugin".to_string(),
+            geometry: GeometryDefinition::Primitive(GeometricPrimitive {
+                shape: "cube".to_string(),
+                dimensions: vec![1.0, 1.0, 1.0],
+                transform: None,
+            }),
+            physics: PhysicsDefinition {
+                equations: vec![],
+                boundary_conditions: vec![],
+                thermal_boundary_conditions: vec![],
+                material: Material {
+                    youngs_modulus: MaterialProperty::Constant(1.0),
+                    poissons_ratio: MaterialProperty::Constant(0.0),
+                    density: None,
+                    thermal_expansion: None,
+                    reference_temperature: 0.0,
+                    thermal_conductivity: None,
+                    viscosity: None,
+                },
+                boundary_conditions_file: None,
+                constraints: vec![],
+                processed_equations: None,
+            },
+            solver_settings: SolverSettings {
+                solver_name: "ZeroPlugin".to_string(),
+                tolerance: 1e-5,
+                max_iterations: 10,
+                ..Default::default()
+            },
+            mesh: Some(crate::meshing::structured_cube_mesh(2, 1.0)),
+            aux_fields: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_wasm_solver_returns_zeros_for_every_node() {
+        let solver = WasmSolver::new("ZeroPlugin", wasmer::wat2wasm(ZEROS_WAT.as_bytes()).unwrap().into_owned());
+        let problem = problem();
+        solver.check_compatibility(&problem).unwrap();
+
+        let result = solver.solve(&problem).unwrap();
+        assert_eq!(solver.name(), "ZeroPlugin");
+        assert_eq!(result.data, vec![0.0; 27]);
+    }
+
+    #[test]
+    fn test_wasm_solver_memory_limit_is_enforced() {
+        let solver = WasmSolver::new("GreedyPlugin", wasmer::wat2wasm(GREEDY_WAT.as_bytes()).unwrap().into_owned());
+        match solver.solve(&problem()) {
+            Err(EngineError::SolverFailed(msg)) => assert!(msg.contains("memory pages"), "Unexpected message: {}", msg),
+            other => panic!("Expected the memory limit to be enforced, got {:?}", other),
+        }
+    }
+}
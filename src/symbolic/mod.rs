@@ -2,7 +2,10 @@
 
 //! Handles symbolic equation processing by bridging to Python's SymPy library.
 
+pub mod weak_form;
+
 use crate::EngineError;
+use crate::sandbox::SandboxConfig;
 use serde::{Serialize, Deserialize};
 use std::io::Write;
 use std::fs;
@@ -17,11 +20,16 @@ pub struct ProcessedEquations {
 /// Processes a list of equation strings using SymPy in a Docker sandbox.
 ///
 /// This function dynamically creates a Python script, runs it in a Docker container
-/// with SymPy installed, and captures its output.
+/// with SymPy installed, and captures its output. When Docker is unavailable the
+/// script runs with the local Python instead, which then needs SymPy installed.
 pub async fn process_equations_with_sympy(equations: &[String]) -> Result<ProcessedEquations, EngineError> {
+    process_equations_with_sympy_config(equations, &SandboxConfig::default()).await
+}
+
+/// Processes equations like [`process_equations_with_sympy`], using the given sandbox options.
+pub async fn process_equations_with_sympy_config(equations: &[String], sandbox_config: &SandboxConfig) -> Result<ProcessedEquations, EngineError> {
     // Construct the Python script content.
-    let python_script_content = format!(
-        r#"
+    let python_script_content = r#"
 import sympy
 import json
 import sys
@@ -36,11 +44,10 @@ if __name__ == "__main__":
         equations_from_stdin = sys.stdin.read()
         simplify_equations(equations_from_stdin)
     except Exception as e:
-        print(f"Error during symbolic processing: {{e}}", file=sys.stderr)
+        print(f"Error during symbolic processing: {e}", file=sys.stderr)
         import traceback
         traceback.print_exc(file=sys.stderr)
-"#
-    );
+"#;
 
     // Create a temporary file for the Python script.
     let temp_dir = env::temp_dir();
@@ -63,16 +70,27 @@ if __name__ == "__main__":
 
     // Call the Docker sandbox to run the script.
     // We pass the script content and the path to the JSON file.
-    let output = crate::sandbox::run_sandboxed_docker(
+    let output = match crate::sandbox::run_sandboxed_docker_with_config(
         script_file_path.to_str().unwrap(),
         equations_json_path.to_str().unwrap(),
-    ).await.map_err(|e| EngineError::SymbolicFailed(format!("Docker sandbox failed: {}", e)))?;
+        sandbox_config,
+    ).await {
+        Ok(output) => Ok(output),
+        // Fall back to a local Python for users without Docker.
+        Err(docker_error) => {
+            println!("Warning: Docker sandbox failed, running SymPy with the local Python: {}", docker_error);
+            crate::sandbox::run_sandboxed_local_with_config(script_file_path.to_str().unwrap(), &equations_json, sandbox_config)
+                .and_then(crate::sandbox::SandboxOutput::into_stdout)
+                .map_err(|local_error| format!("Docker sandbox failed: {}; local Python failed: {}", docker_error, local_error))
+        }
+    };
 
     // Clean up temporary files.
     fs::remove_file(&script_file_path)
         .map_err(|e| EngineError::SymbolicFailed(format!("Failed to remove script file: {}", e)))?;
     fs::remove_file(&equations_json_path)
         .map_err(|e| EngineError::SymbolicFailed(format!("Failed to remove JSON file: {}", e)))?;
+    let output = output.map_err(EngineError::SymbolicFailed)?;
 
     // Parse the JSON output from the Docker container.
     let simplified_forms: Vec<String> = serde_json::from_str(&output)
@@ -0,0 +1,113 @@
+// src/symbolic/weak_form.rs
+
+//! Recognizes PDE forms in symbolic output and maps them to built-in solvers.
+
+use super::ProcessedEquations;
+use crate::PhysicsDefinition;
+
+/// A recognized partial differential equation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PdeForm {
+    /// `div(grad(field)) = 0`
+    Laplace { field: String },
+    /// `div(grad(field)) = source`, with the source kept as an expression string.
+    Poisson { field: String, source: String },
+}
+
+impl PdeForm {
+    /// Returns the source term as a number if it is a constant.
+    ///
+    /// Laplace equations have a zero source.
+    pub fn constant_source(&self) -> Option<f64> {
+        match self {
+            PdeForm::Laplace { .. } => Some(0.0),
+            PdeForm::Poisson { source, .. } => source.parse().ok(),
+        }
+    }
+}
+
+/// The built-in solver chosen for a recognized equation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolverRoute {
+    pub solver_name: &'static str,
+    pub form: PdeForm,
+}
+
+/// Parses a single equation of the form `div(grad(X)) = RHS` or `laplacian(X) = RHS`.
+///
+/// Whitespace is ignored. Returns `None` for anything else.
+pub fn recognize(equation: &str) -> Option<PdeForm> {
+    let compact: String = equation.chars().filter(|c| !c.is_whitespace()).collect();
+    let (lhs, rhs) = compact.split_once('=')?;
+    if rhs.is_empty() || rhs.contains('=') {
+        return None;
+    }
+
+    let field = lhs
+        .strip_prefix("div(grad(").and_then(|f| f.strip_suffix("))"))
+        .or_else(|| lhs.strip_prefix("laplacian(").and_then(|f| f.strip_suffix(')')))?;
+    if field.is_empty() || !field.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+
+    let field = field.to_string();
+    if rhs.parse::<f64>() == Ok(0.0) {
+        Some(PdeForm::Laplace { field })
+    } else {
+        Some(PdeForm::Poisson { field, source: rhs.to_string() })
+    }
+}
+
+/// Returns the first recognized equation in `forms` together with its solver.
+pub fn route_equations(forms: &[String]) -> Option<SolverRoute> {
+    forms.iter().find_map(|eq| recognize(eq)).map(|form| SolverRoute {
+        // The finite difference solver handles both Laplace and Poisson problems.
+        solver_name: "FdmSolver",
+        form,
+    })
+}
+
+/// Routes the simplified forms of processed symbolic output.
+pub fn route_processed(processed: &ProcessedEquations) -> Option<SolverRoute> {
+    route_equations(&processed.simplified_forms)
+}
+
+/// Routes a physics definition, preferring its processed forms over the raw equations.
+pub fn route_physics(physics: &PhysicsDefinition) -> Option<SolverRoute> {
+    physics.processed_equations
+        .as_ref()
+        .and_then(route_processed)
+        .or_else(|| route_equations(&physics.equations))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poisson_equation_routes_to_poisson_solver() {
+        let processed = ProcessedEquations {
+            simplified_forms: vec!["x + y".to_string(), "div(grad(u)) = f".to_string()],
+        };
+        let route = route_processed(&processed).unwrap();
+        assert_eq!(route.solver_name, "FdmSolver");
+        assert_eq!(route.form, PdeForm::Poisson { field: "u".to_string(), source: "f".to_string() });
+        assert_eq!(route.form.constant_source(), None);
+    }
+
+    #[test]
+    fn test_recognize_laplace_and_constant_source() {
+        assert_eq!(recognize("div(grad(T)) = 0"), Some(PdeForm::Laplace { field: "T".to_string() }));
+        assert_eq!(recognize("laplacian(T)=0.0"), Some(PdeForm::Laplace { field: "T".to_string() }));
+        let poisson = recognize("div(grad(u)) = -2").unwrap();
+        assert_eq!(poisson.constant_source(), Some(-2.0));
+    }
+
+    #[test]
+    fn test_unrecognized_equations() {
+        assert_eq!(recognize("2*x = y"), None);
+        assert_eq!(recognize("div(grad(u))"), None);
+        assert_eq!(recognize("div(grad(u + v)) = 0"), None);
+        assert_eq!(route_equations(&["x + x + y".to_string()]), None);
+    }
+}
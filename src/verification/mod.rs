@@ -0,0 +1,202 @@
+// src/verification/mod.rs
+
+//! Verification helpers for checking solver output against known solutions.
+
+use crate::Solution;
+
+/// Error norms of a nodal solution measured against an exact solution.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MmsError {
+    /// Root-mean-square of the nodal errors.
+    pub l2: f64,
+    /// Largest absolute nodal error.
+    pub linf: f64,
+}
+
+/// Computes the method-of-manufactured-solutions error of a nodal solution.
+///
+/// The exact solution is sampled at every mesh node and compared against the
+/// corresponding entry of `solution.data`. The L2 norm is normalised by the node
+/// count so that results are comparable across mesh refinements.
+///
+/// # Panics
+/// Panics if `solution.data` does not hold exactly one value per mesh node.
+pub fn mms_error(solution: &Solution, exact: impl Fn([f64; 3]) -> f64) -> MmsError {
+    assert_eq!(
+        solution.data.len(),
+        solution.mesh.nodes.len(),
+        "MMS error requires one solution value per mesh node"
+    );
+
+    if solution.data.is_empty() {
+        return MmsError { l2: 0.0, linf: 0.0 };
+    }
+
+    let mut sum_sq = 0.0;
+    let mut linf: f64 = 0.0;
+    for (value, node) in solution.data.iter().zip(&solution.mesh.nodes) {
+        let err = (value - exact(*node)).abs();
+        sum_sq += err * err;
+        linf = linf.max(err);
+    }
+
+    MmsError {
+        l2: (sum_sq / solution.data.len() as f64).sqrt(),
+        linf,
+    }
+}
+
+/// Returns the relative error `|a - b| / |b|` of two fields in the Euclidean norm.
+///
+/// `b` is the reference. When it is all zeros the absolute error `|a - b|` is
+/// returned instead, so the result stays finite.
+///
+/// # Panics
+/// Panics if the fields differ in length.
+pub fn relative_error(a: &[f64], b: &[f64]) -> f64 {
+    assert_eq!(a.len(), b.len(), "relative error requires fields of equal length");
+    let diff = a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum::<f64>().sqrt();
+    let norm = b.iter().map(|y| y * y).sum::<f64>().sqrt();
+    if norm > 0.0 { diff / norm } else { diff }
+}
+
+/// Asserts that two fields agree entry by entry, for use in solver tests.
+///
+/// Entry `i` passes when `|actual[i] - expected[i]| <= tol * max(1, |expected[i]|)`,
+/// an absolute tolerance for small values and a relative one for large values.
+/// NaN entries never pass.
+///
+/// # Panics
+/// Panics if the lengths differ or any entry is out of tolerance, listing the
+/// first mismatches and the overall [`relative_error`].
+#[track_caller]
+pub fn assert_fields_close(actual: &[f64], expected: &[f64], tol: f64) {
+    assert_eq!(actual.len(), expected.len(), "fields differ in length");
+    // Written so that a NaN difference is not within tolerance.
+    let within = |a: f64, e: f64| (a - e).abs() <= tol * e.abs().max(1.0);
+    let mismatches: Vec<String> = actual
+        .iter()
+        .zip(expected)
+        .enumerate()
+        .filter(|(_, (a, e))| !within(**a, **e))
+        .map(|(i, (a, e))| format!("[{}]: {} vs {}", i, a, e))
+        .collect();
+    if !mismatches.is_empty() {
+        panic!(
+            "{} of {} entries differ by more than {:e} (relative error {:e}), first: {}",
+            mismatches.len(),
+            actual.len(),
+            tol,
+            relative_error(actual, expected),
+            mismatches.iter().take(5).cloned().collect::<Vec<_>>().join(", ")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::{fdm_solver::FdmSolver, Solver};
+    use crate::{GeometricPrimitive, GeometryDefinition, Material, MaterialProperty, Mesh, PhysicsDefinition, ProblemDefinition, SolverSettings};
+    use std::collections::HashMap;
+
+    fn line_mesh(num_nodes: usize) -> Mesh {
+        Mesh {
+            nodes: (0..num_nodes).map(|i| [i as f64 / (num_nodes - 1) as f64, 0.0, 0.0]).collect(),
+            elements: (0..num_nodes - 1).map(|i| vec![i, i + 1]).collect(),
+            element_type: "Line".to_string(),
+            boundary_regions: HashMap::new(),
+            source_geometry: None,
+            bounding_box: None,
+            geometry_warnings: Vec::new(),
+        }
+    }
+
+    fn solution_with(mesh: Mesh, data: Vec<f64>) -> Solution {
+        Solution {
+            id: "mms_test".to_string(),
+            mesh,
+            processed_equations: None,
+            data,
+            time_steps: vec![],
+            fields: HashMap::new(),
+            provenance_chain: vec![],
+            metadata: serde_json::Map::new(),
+            warnings: vec![],
+            timings: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_mms_error_for_laplace_solve() {
+        // d^2T/dx^2 = 0 with T(0) = 100, T(1) = 0 has the exact solution T = 100 - 100x.
+        let problem = ProblemDefinition {
+            id: "mms_laplace".to_string(),
+            geometry: GeometryDefinition::Primitive(GeometricPrimitive {
+                shape: "cube".to_string(),
+                dimensions: vec![1.0, 1.0, 1.0],
+                transform: None,
+            }),
+            physics: PhysicsDefinition {
+                equations: vec![],
+                boundary_conditions: vec![],
+                thermal_boundary_conditions: vec![],
+                material: Material {
+                    youngs_modulus: MaterialProperty::Constant(1.0),
+                    poissons_ratio: MaterialProperty::Constant(0.0),
+                    density: None,
+                    thermal_expansion: None,
+                    reference_temperature: 0.0,
+                    thermal_conductivity: None,
+                    viscosity: None,
+                },
+                boundary_conditions_file: None,
+                constraints: vec![],
+                processed_equations: None,
+            },
+            solver_settings: SolverSettings {
+                solver_name: "FdmSolver".to_string(),
+                tolerance: 1e-5,
+                max_iterations: 10,
+                ..Default::default()
+            },
+            mesh: None,
+            aux_fields: HashMap::new(),
+        };
+        let result = FdmSolver.solve(&problem).unwrap();
+        let solution = solution_with(line_mesh(result.data.len()), result.data);
+
+        let error = mms_error(&solution, |p| 100.0 - 100.0 * p[0]);
+        assert!(error.l2 < 1e-9, "L2 error too large: {}", error.l2);
+        assert!(error.linf < 1e-9, "L-infinity error too large: {}", error.linf);
+    }
+
+    #[test]
+    fn test_mms_error_norms() {
+        let solution = solution_with(line_mesh(4), vec![1.0, 0.0, 0.0, -3.0]);
+        let error = mms_error(&solution, |_| 0.0);
+        assert!((error.l2 - (10.0f64 / 4.0).sqrt()).abs() < 1e-12);
+        assert_eq!(error.linf, 3.0);
+    }
+
+    #[test]
+    fn test_field_comparison_helpers() {
+        let expected = [1.0, -2.0, 3e6, 0.0];
+        assert_eq!(relative_error(&expected, &expected), 0.0);
+        assert_fields_close(&expected, &expected, 0.0);
+
+        // Within tolerance: absolute near zero, relative for the large entry.
+        let close = [1.0 + 1e-10, -2.0, 3e6 * (1.0 + 1e-10), 1e-10];
+        assert_fields_close(&close, &expected, 1e-9);
+        assert!(relative_error(&close, &expected) < 1e-9);
+        assert_eq!(relative_error(&[3.0, 4.0], &[0.0, 0.0]), 5.0);
+
+        // Out of tolerance, including NaN.
+        for off in [[1.0, -2.0, 3e6 + 10.0, 0.0], [1.0, -2.0, 3e6, f64::NAN]] {
+            let failure = std::panic::catch_unwind(|| assert_fields_close(&off, &expected, 1e-9)).unwrap_err();
+            let message = failure.downcast_ref::<String>().unwrap();
+            assert!(message.starts_with("1 of 4 entries differ"), "{}", message);
+        }
+        assert!(std::panic::catch_unwind(|| assert_fields_close(&[1.0], &expected, 1.0)).is_err());
+    }
+}